@@ -0,0 +1,182 @@
+use std::path::Path;
+
+use rocket::{
+    Data, State,
+    data::{Limits, ToByteUnit},
+    serde::json::Json,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    admin::{AdminConfig, AdminToken},
+    database::snapshot::Snapshot,
+    json::{ApiError, JsonResult, JsonStatus},
+    logging,
+};
+
+/// What a snapshot or restore copied
+#[derive(Serialize)]
+struct SnapshotSummary {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    types: usize,
+    pokemons: usize,
+    trainers: usize,
+}
+
+impl SnapshotSummary {
+    fn new(path: Option<&str>, snapshot: &Snapshot) -> Self {
+        Self {
+            path: path.map(|path| path.to_string()),
+            types: snapshot.types.len(),
+            pokemons: snapshot.pokemons.len(),
+            trainers: snapshot.trainers.len(),
+        }
+    }
+}
+
+/// Endpoint to write the whole database to the configured snapshot file.
+#[post("/admin/snapshot")]
+pub async fn snapshot<'a>(token: AdminToken, config: &State<AdminConfig>) -> JsonResult<'a> {
+    info!("Request to /api/admin/snapshot");
+
+    config.check_token(&token)?;
+
+    let snapshot = Snapshot::capture().await.map_err(ApiError::from_anyhow)?;
+
+    snapshot
+        .write_to(Path::new(&config.snapshot_path))
+        .map_err(ApiError::from_anyhow)?;
+
+    Ok(JsonStatus::data_owned(SnapshotSummary::new(
+        Some(&config.snapshot_path),
+        &snapshot,
+    )))
+}
+
+/// Endpoint to replace the whole database with the configured snapshot file.
+#[post("/admin/restore")]
+pub async fn restore<'a>(token: AdminToken, config: &State<AdminConfig>) -> JsonResult<'a> {
+    info!("Request to /api/admin/restore");
+
+    config.check_token(&token)?;
+
+    let snapshot =
+        Snapshot::read_from(Path::new(&config.snapshot_path)).map_err(ApiError::from_anyhow)?;
+
+    snapshot.restore().await.map_err(ApiError::from_anyhow)?;
+
+    Ok(JsonStatus::data_owned(SnapshotSummary::new(
+        Some(&config.snapshot_path),
+        &snapshot,
+    )))
+}
+
+/// The largest NDJSON import accepted, unless the `ndjson` limit is set in the Rocket config
+pub const NDJSON_IMPORT_LIMIT_MIB: u64 = 8;
+
+/// Endpoint to replace the whole database with an NDJSON export, in a single transaction.
+#[post("/admin/import.ndjson", data = "<data>")]
+pub async fn import_ndjson<'a>(
+    token: AdminToken,
+    config: &State<AdminConfig>,
+    limits: &Limits,
+    data: Data<'_>,
+) -> JsonResult<'a> {
+    info!("Request to /api/admin/import.ndjson");
+
+    config.check_token(&token)?;
+
+    let limit = limits
+        .get("ndjson")
+        .unwrap_or(NDJSON_IMPORT_LIMIT_MIB.mebibytes());
+
+    let ndjson = data.open(limit).into_string().await;
+    let ndjson = ndjson.map_err(|e| ApiError::from_anyhow(e.into()))?;
+
+    if !ndjson.is_complete() {
+        return Err(ApiError::bad_request(format!(
+            "The import is larger than the {} limit",
+            limit
+        )));
+    }
+
+    let snapshot = Snapshot::from_ndjson(&ndjson).map_err(ApiError::from_anyhow)?;
+
+    snapshot.restore().await.map_err(ApiError::from_anyhow)?;
+
+    Ok(JsonStatus::data_owned(SnapshotSummary::new(None, &snapshot)))
+}
+
+/// Endpoint to remove relationships whose target node can no longer be resolved.
+#[post("/admin/repair")]
+pub async fn repair<'a>(token: AdminToken, config: &State<AdminConfig>) -> JsonResult<'a> {
+    info!("Request to /api/admin/repair");
+
+    config.check_token(&token)?;
+
+    let report = crate::database::repair::repair().await;
+    let report = report.map_err(ApiError::from_anyhow)?;
+
+    Ok(JsonStatus::data_owned(report))
+}
+
+/// The body of an explain request
+#[derive(Deserialize)]
+pub struct ExplainRequest {
+    /// The operation descriptor, e.g. `put pokemon Pikachu`
+    pub operation: String,
+    /// The body the operation's endpoint takes, if it takes one
+    pub body: Option<serde_json::Value>,
+}
+
+/// Endpoint to show the Cypher queries a write operation would execute, without executing them.
+#[post("/admin/explain", data = "<request>")]
+pub async fn explain<'a>(
+    token: AdminToken,
+    config: &State<AdminConfig>,
+    request: Json<ExplainRequest>,
+) -> JsonResult<'a> {
+    info!("Request to /api/admin/explain");
+
+    config.check_token(&token)?;
+
+    let request = request.into_inner();
+
+    let explanation = crate::database::explain::explain(&request.operation, request.body);
+    let explanation = explanation.map_err(ApiError::from_anyhow)?;
+
+    Ok(JsonStatus::data_owned(explanation))
+}
+
+/// A log filter in the `RUST_LOG` format
+#[derive(Serialize, Deserialize)]
+pub struct LogLevelRequest {
+    /// e.g. `info,pokemon_simulator::database=debug`
+    pub filter: String,
+}
+
+/// Endpoint to replace the log filter without restarting the server.
+#[post("/admin/log_level", data = "<request>")]
+pub async fn set_log_level<'a>(
+    token: AdminToken,
+    config: &State<AdminConfig>,
+    request: Json<LogLevelRequest>,
+) -> JsonResult<'a> {
+    info!("Request to /api/admin/log_level");
+
+    config.check_token(&token)?;
+
+    let logger =
+        logging::logger().ok_or_else(|| ApiError::internal("The logger is not installed"))?;
+
+    logger
+        .set_filter(&request.filter)
+        .map_err(ApiError::from_anyhow)?;
+
+    warn!("Log filter set to {}", request.filter);
+
+    Ok(JsonStatus::data_owned(LogLevelRequest {
+        filter: logger.filter(),
+    }))
+}
@@ -0,0 +1,64 @@
+/// Admin HTTP endpoints module
+pub mod endpoints;
+
+use rocket::request::{FromRequest, Outcome, Request};
+use serde::Deserialize;
+
+use crate::json::ApiError;
+
+/// The header admin requests carry their token in
+pub const ADMIN_TOKEN_HEADER: &str = "X-Admin-Token";
+
+/// The admin token sent with a request, if any
+pub struct AdminToken(pub Option<String>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AdminToken {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let token = request
+            .headers()
+            .get_one(ADMIN_TOKEN_HEADER)
+            .map(|token| token.to_string());
+
+        Outcome::Success(AdminToken(token))
+    }
+}
+
+/// Options of the admin endpoints, read from the `[admin]` section of the config file
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct AdminConfig {
+    /// The token admin requests must send, admin endpoints are disabled without one
+    pub token: Option<String>,
+    /// The server-side file snapshots are written to and restored from
+    pub snapshot_path: String,
+}
+
+impl Default for AdminConfig {
+    fn default() -> Self {
+        Self {
+            token: None,
+            snapshot_path: "config/snapshot.json".to_string(),
+        }
+    }
+}
+
+impl AdminConfig {
+    /// Loads the admin config from the config file, or defaults if not configured
+    pub fn load() -> Self {
+        crate::config::read_section_or_default("admin")
+    }
+
+    /// Checks that a request carries the configured admin token
+    pub fn check_token(&self, token: &AdminToken) -> Result<(), ApiError> {
+        match (&self.token, &token.0) {
+            (None, _) => Err(ApiError::bad_request(
+                "Admin endpoints are disabled, set a token in the [admin] config section",
+            )),
+            (Some(expected), Some(token)) if expected == token => Ok(()),
+            _ => Err(ApiError::bad_request("Invalid admin token")),
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use anyhow::{Context, Result};
+use rocket::data::{Limits, ToByteUnit};
+use serde::{Deserialize, Deserializer, Serialize, de::DeserializeOwned};
+use std::{env, fs, path::Path};
+
+use crate::{database::get::MAX_PAGE_LIMIT, json::ApiError};
+
+/// The path of the config file, relative to the working directory
+pub const CONFIG_FILE: &str = "config/config.toml";
+
+/// Reads a section (e.g. `[fight]`) of the config file into `T`
+/// A missing file or section gives `T::default()`
+pub fn read_section<T: DeserializeOwned + Default>(section: &str) -> Result<T> {
+    read_section_at(Path::new(CONFIG_FILE), section)
+}
+
+/// Same as `read_section`, but reads the config file at `path`
+/// Errors name the file by its absolute path, to tell where it was expected (e.g. inside a container)
+pub fn read_section_at<T: DeserializeOwned + Default>(path: &Path, section: &str) -> Result<T> {
+    let resolved = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+
+    let exists = fs::exists(path).with_context(|| {
+        format!(
+            "Could not look for the config file at {}",
+            resolved.display()
+        )
+    })?;
+
+    if !exists {
+        return Ok(T::default());
+    }
+
+    let cfg = fs::read_to_string(path)
+        .with_context(|| format!("Could not read the config file at {}", resolved.display()))?
+        .parse::<toml::Table>()
+        .with_context(|| {
+            format!(
+                "The config file at {} is not valid TOML",
+                resolved.display()
+            )
+        })?;
+
+    match cfg.get(section) {
+        Some(value) => value.clone().try_into().with_context(|| {
+            format!(
+                "Invalid [{section}] section in the config file at {}",
+                resolved.display()
+            )
+        }),
+        None => Ok(T::default()),
+    }
+}
+
+/// Same as `read_section`, but logs errors and falls back to `T::default()`
+pub fn read_section_or_default<T: DeserializeOwned + Default>(section: &str) -> T {
+    read_section(section).unwrap_or_else(|e| {
+        warn!("Could not read the [{section}] config section, using defaults: {e}");
+        T::default()
+    })
+}
+
+/// Connection options of the database, read from the `[database]` section of the config file
+/// Every option can be overridden by an environment variable,
+/// so the precedence is environment variable > config file > default
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct DatabaseConfig {
+    /// The database host, overridden by `POKESIM_DB_HOST`
+    pub host: String,
+    /// The database bolt port, overridden by `POKESIM_DB_PORT`
+    #[serde(deserialize_with = "port_from_int_or_str")]
+    pub port: u16,
+    /// The database user, overridden by `POKESIM_DB_USER`
+    pub username: String,
+    /// The database password, overridden by `POKESIM_DB_PASS`
+    pub password: String,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            host: "neo4j".to_string(),
+            port: 7687,
+            username: "neo4j".to_string(),
+            password: "pass".to_string(),
+        }
+    }
+}
+
+/// Config files generated by older versions store the port as a string
+fn port_from_int_or_str<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u16, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Port {
+        Int(u16),
+        Str(String),
+    }
+
+    match Port::deserialize(deserializer)? {
+        Port::Int(port) => Ok(port),
+        Port::Str(port) => port.parse().map_err(serde::de::Error::custom),
+    }
+}
+
+impl DatabaseConfig {
+    /// The environment variable overriding `host`
+    pub const HOST_VAR: &str = "POKESIM_DB_HOST";
+    /// The environment variable overriding `port`
+    pub const PORT_VAR: &str = "POKESIM_DB_PORT";
+    /// The environment variable overriding `username`
+    pub const USER_VAR: &str = "POKESIM_DB_USER";
+    /// The environment variable overriding `password`
+    pub const PASS_VAR: &str = "POKESIM_DB_PASS";
+
+    /// Loads the effective database config: the config file overlaid with the environment
+    pub fn load() -> Result<Self> {
+        read_section::<Self>("database")?.with_env_overrides()
+    }
+
+    /// Overrides every option whose environment variable is set
+    pub fn with_env_overrides(mut self) -> Result<Self> {
+        if let Ok(host) = env::var(Self::HOST_VAR) {
+            self.host = host;
+        }
+
+        if let Ok(port) = env::var(Self::PORT_VAR) {
+            self.port = port
+                .parse()
+                .with_context(|| format!("{} is not a valid port: {port}", Self::PORT_VAR))?;
+        }
+
+        if let Ok(username) = env::var(Self::USER_VAR) {
+            self.username = username;
+        }
+
+        if let Ok(password) = env::var(Self::PASS_VAR) {
+            self.password = password;
+        }
+
+        Ok(self)
+    }
+}
+
+/// General options of the HTTP API, read from the `[api]` section of the config file
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ApiConfig {
+    /// The maximum number of items any list endpoint returns in one response
+    pub max_list_items: usize,
+    /// The largest JSON request body (in KiB) an endpoint accepts,
+    /// larger bodies are refused with `413 Payload Too Large` before being parsed
+    pub max_json_body_kib: u64,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            max_list_items: 1000,
+            max_json_body_kib: 1024,
+        }
+    }
+}
+
+impl ApiConfig {
+    /// Loads the API config from the config file, or defaults if not configured
+    pub fn load() -> Self {
+        read_section_or_default("api")
+    }
+
+    /// The Rocket data limits, with the `json` limit set to `max_json_body_kib`
+    pub fn limits(&self) -> Limits {
+        Limits::default().limit("json", self.max_json_body_kib.kibibytes())
+    }
+
+    /// Checks that a list of `len` items fits in one response
+    pub fn check_list_len(&self, len: usize) -> Result<(), ApiError> {
+        if len <= self.max_list_items {
+            return Ok(());
+        }
+
+        Err(ApiError::payload_too_large(format!(
+            "Payload too large: {} items exceed the maximum of {} per response",
+            len, self.max_list_items
+        )))
+    }
+
+    /// Checks that a page of `len` items fits in one response,
+    /// the error tells the client how to ask for smaller pages instead
+    pub fn check_page_len(&self, len: usize) -> Result<(), ApiError> {
+        self.check_list_len(len).map_err(|error| {
+            ApiError::payload_too_large(format!(
+                "{}, paginate with ?skip=0&limit={}",
+                error.message(),
+                self.max_list_items.min(MAX_PAGE_LIMIT as usize)
+            ))
+        })
+    }
+}
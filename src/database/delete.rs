@@ -1,28 +1,24 @@
 use anyhow::Result;
 
-use super::{DbHandle, DbRepr};
+use super::{DbHandle, DbQuery, DbRepr};
 
 /// Denotes an ability to delete a node from the database
 pub trait DbDelete: DbRepr {
-    /// Deletes the node from the database with the given identifier
+    /// Deletes the node from the database with the given (raw, unquoted) identifier
     fn delete(database_identifier: &str) -> impl Future<Output = Result<()>> + Send
     where
         Self: Sized,
     {
+        let query = DbQuery::new(format!(
+            "MATCH (n:{}) WHERE n.{} = $id DELETE n;",
+            Self::DB_NODE_KIND,
+            Self::DB_IDENTIFIER_FIELD
+        ))
+        .param("id", database_identifier);
+
         async move {
             let db = DbHandle::connect().await?;
-            let mut q_res = db
-                .inner
-                .execute(
-                    format!(
-                        "MATCH (n:{}) WHERE n.{} = {} DELETE n;",
-                        Self::DB_NODE_KIND,
-                        Self::DB_IDENTIFIER_FIELD,
-                        database_identifier
-                    )
-                    .into(),
-                )
-                .await?;
+            let mut q_res = db.inner.execute(query.into()).await?;
             let _none = q_res.next().await?;
             Ok(())
         }
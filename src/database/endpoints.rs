@@ -0,0 +1,77 @@
+use futures::{Stream, StreamExt};
+use rocket::{http::ContentType, response::stream::TextStream};
+use serde::Serialize;
+
+use super::{
+    get::DbGet,
+    ndjson::{self, Record},
+};
+use crate::{
+    json::{ApiError, JsonResult, JsonStatus},
+    pokemon::{Pokemon, ptype::PokemonType},
+    trainer::Trainer,
+};
+
+/// The number of matches per category when the request doesn't specify a limit
+pub const DEFAULT_SEARCH_LIMIT: usize = 10;
+
+/// The most matches per category a single search returns
+pub const MAX_SEARCH_LIMIT: usize = 100;
+
+/// The names matching a search, by the kind of node they name
+#[derive(Serialize)]
+pub struct SearchResults {
+    /// Matching pokemon names
+    pub pokemons: Vec<String>,
+    /// Matching type names
+    pub types: Vec<String>,
+    /// Matching trainer names
+    pub trainers: Vec<String>,
+}
+
+/// Endpoint streaming every type, pokemon, trainer and relationship as newline-delimited JSON.
+/// A failed export ends with an `error` record.
+#[get("/export.ndjson")]
+pub fn export_ndjson() -> (ContentType, TextStream<impl Stream<Item = String>>) {
+    info!("Request to /api/export.ndjson");
+
+    let lines = ndjson::export().map(|record| {
+        let record = record.unwrap_or_else(|e| Record::Error {
+            message: e.to_string(),
+        });
+        record.to_line()
+    });
+
+    (
+        ContentType::new("application", "x-ndjson"),
+        TextStream(lines),
+    )
+}
+
+/// Endpoint searching pokemon, type and trainer names containing `q`, ignoring case.
+/// At most `limit` (default 10, capped at 100) names are returned per category.
+#[get("/search?<q>&<limit>")]
+pub async fn search<'a>(q: String, limit: Option<usize>) -> JsonResult<'a> {
+    info!("Request to /api/search?q={}", q);
+
+    if q.trim().is_empty() {
+        return Err(ApiError::bad_request("The search term cannot be empty"));
+    }
+
+    let limit = limit
+        .unwrap_or(DEFAULT_SEARCH_LIMIT)
+        .clamp(1, MAX_SEARCH_LIMIT);
+
+    let (pokemons, types, trainers) = futures::try_join!(
+        Pokemon::search_by_name(&q, limit),
+        PokemonType::search_by_name(&q, limit),
+        Trainer::search_by_name(&q, limit),
+    )
+    .map_err(ApiError::from_anyhow)?;
+
+    Ok(JsonStatus::data_owned(SearchResults {
+        pokemons,
+        types,
+        trainers,
+    }))
+}
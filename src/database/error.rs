@@ -0,0 +1,103 @@
+use std::io::ErrorKind as IoErrorKind;
+
+use serde::Serialize;
+
+/// A database node that was looked up does not exist
+#[derive(Debug)]
+pub struct NotFound {
+    /// The kind of the node that was looked up
+    pub kind: String,
+    /// The identifier the node was looked up by
+    pub identifier: String,
+}
+
+impl std::fmt::Display for NotFound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "No {} found with identifier {}",
+            self.kind, self.identifier
+        )
+    }
+}
+
+impl std::error::Error for NotFound {}
+
+/// The category of a database error, which decides how it's reported to clients
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// A temporary failure (e.g. the database is unreachable or a deadlock occurred),
+    /// retrying the same operation may succeed
+    Transient,
+    /// A failure that will happen again on retry (e.g. a syntax error or bad credentials)
+    Permanent,
+    /// The requested node does not exist
+    NotFound,
+    /// The operation conflicts with existing data (e.g. a constraint violation)
+    Conflict,
+}
+
+/// Classifies an error returned from a database operation,
+/// looking through its chain of causes for a known error type
+pub fn classify(error: &anyhow::Error) -> ErrorClass {
+    for cause in error.chain() {
+        if cause.is::<NotFound>() {
+            return ErrorClass::NotFound;
+        }
+
+        if let Some(error) = cause.downcast_ref::<neo4rs::Error>() {
+            return classify_neo4j(error);
+        }
+
+        if let Some(error) = cause.downcast_ref::<std::io::Error>() {
+            return classify_io(error);
+        }
+    }
+
+    ErrorClass::Permanent
+}
+
+/// Classifies an error of the neo4j driver
+pub fn classify_neo4j(error: &neo4rs::Error) -> ErrorClass {
+    match error {
+        neo4rs::Error::IOError { detail } => classify_io(detail),
+        neo4rs::Error::ConnectionError => ErrorClass::Transient,
+        neo4rs::Error::Neo4j(error) => classify_neo4j_code(error.code()),
+        _ => ErrorClass::Permanent,
+    }
+}
+
+/// Classifies an error reported by the neo4j server by its status code,
+/// e.g. `Neo.ClientError.Schema.ConstraintValidationFailed`
+pub fn classify_neo4j_code(code: &str) -> ErrorClass {
+    let mut parts = code.split('.').skip(1);
+
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some("TransientError"), _, _) => ErrorClass::Transient,
+        (Some("ClientError"), Some("Schema"), Some("ConstraintValidationFailed")) => {
+            ErrorClass::Conflict
+        }
+        (Some("ClientError"), Some("Cluster"), Some("NotALeader")) => ErrorClass::Transient,
+        (Some("ClientError"), Some("Security"), Some("AuthorizationExpired")) => {
+            ErrorClass::Transient
+        }
+        // a missing database is a misconfigured server, not a missing node
+        (Some("ClientError"), Some("Database"), Some("DatabaseNotFound")) => ErrorClass::Permanent,
+        _ => ErrorClass::Permanent,
+    }
+}
+
+/// Classifies an IO error, network failures are transient
+fn classify_io(error: &std::io::Error) -> ErrorClass {
+    match error.kind() {
+        IoErrorKind::ConnectionRefused
+        | IoErrorKind::ConnectionReset
+        | IoErrorKind::ConnectionAborted
+        | IoErrorKind::NotConnected
+        | IoErrorKind::BrokenPipe
+        | IoErrorKind::TimedOut
+        | IoErrorKind::Interrupted
+        | IoErrorKind::UnexpectedEof => ErrorClass::Transient,
+        _ => ErrorClass::Permanent,
+    }
+}
@@ -0,0 +1,82 @@
+use anyhow::{Result, anyhow};
+use serde::Serialize;
+
+use super::{DbQuery, put::DbPut};
+use crate::{pokemon::Pokemon, trainer::Trainer};
+
+/// A write operation whose queries can be explained
+pub enum Operation {
+    /// `put pokemon <name>`, what `POST /api/pokemons` runs for the pokemon in the body
+    PutPokemon(Pokemon),
+    /// `put trainer <name>`, what `POST /api/trainer_pokemons/<name>` runs
+    PutTrainer(Trainer),
+}
+
+/// The queries an operation would execute
+#[derive(Serialize, Clone, Debug)]
+pub struct Explanation {
+    /// The operation descriptor, as given
+    pub operation: String,
+    /// The Cypher queries with their parameters, in the order they'd be executed
+    pub queries: Vec<DbQuery>,
+}
+
+impl Operation {
+    /// Parses an operation descriptor, e.g. `put pokemon Pikachu`
+    /// `body` is the request body the operation's endpoint takes, if it takes one
+    pub fn parse(descriptor: &str, body: Option<serde_json::Value>) -> Result<Self> {
+        let mut words = descriptor.splitn(3, ' ');
+
+        let (verb, kind, name) = match (words.next(), words.next(), words.next()) {
+            (Some(verb), Some(kind), Some(name)) if !name.is_empty() => (verb, kind, name),
+            _ => {
+                return Err(anyhow!(
+                    "Invalid operation {descriptor}, expected e.g. put pokemon Pikachu"
+                ));
+            }
+        };
+
+        match (verb, kind) {
+            ("put", "pokemon") => {
+                let body =
+                    body.ok_or_else(|| anyhow!("put pokemon needs the pokemon as the body"))?;
+                let pokemon: Pokemon = serde_json::from_value(body)?;
+
+                if pokemon.name != name {
+                    return Err(anyhow!(
+                        "The operation names {name}, but the body is {}",
+                        pokemon.name
+                    ));
+                }
+
+                Ok(Self::PutPokemon(pokemon))
+            }
+            ("put", "trainer") => Ok(Self::PutTrainer(Trainer {
+                name: name.to_string(),
+                team: vec![],
+                strategy: None,
+            })),
+            _ => Err(anyhow!(
+                "Unsupported operation {verb} {kind}, supported: put pokemon, put trainer"
+            )),
+        }
+    }
+
+    /// The queries the operation would execute, built by the same code that executes them
+    pub fn queries(&self) -> Vec<DbQuery> {
+        match self {
+            Self::PutPokemon(pokemon) => pokemon.put_queries(),
+            Self::PutTrainer(trainer) => vec![trainer.put_query()],
+        }
+    }
+}
+
+/// Explains an operation descriptor without executing anything
+pub fn explain(descriptor: &str, body: Option<serde_json::Value>) -> Result<Explanation> {
+    let operation = Operation::parse(descriptor, body)?;
+
+    Ok(Explanation {
+        operation: descriptor.to_string(),
+        queries: operation.queries(),
+    })
+}
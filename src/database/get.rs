@@ -1,39 +1,142 @@
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use neo4rs::Node;
+use serde::Serialize;
 use std::pin::Pin;
-use log::debug;
+use std::time::{Duration, Instant};
+use log::{debug, warn};
 
-use super::{DbHandle, DbRepr, sanitize};
+use super::{DbHandle, DbQuery, DbRepr, error::NotFound, sanitize};
 
-async fn get_db_node(id_name: &str, kind: &str, database_identifier: &str) -> Result<Node> {
-    let db = DbHandle::connect().await?;
+/// The number of items in a page when the request doesn't specify a limit
+pub const DEFAULT_PAGE_LIMIT: u64 = 50;
+
+/// The largest number of items a single page can have
+pub const MAX_PAGE_LIMIT: u64 = 500;
+
+/// One page of a list of nodes, ordered by identifier
+#[derive(Serialize, Clone, Debug)]
+pub struct Page<T> {
+    /// The number of nodes of this kind in the database, across all pages
+    pub total: u64,
+    /// The number of nodes skipped before this page
+    pub skip: u64,
+    /// The largest number of items the page could have
+    pub limit: u64,
+    /// The nodes of this page, fewer than `limit` on the last page
+    pub items: Vec<T>,
+    /// The nodes of this page that couldn't be read and were left out of `items`
+    pub warnings: Vec<DeserializeError>,
+}
+
+/// A node that was found but couldn't be read, e.g. a pokemon whose primary type link was deleted
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct DeserializeError {
+    /// The identifier of the node, if it has one
+    pub identifier: Option<String>,
+    /// Why the node couldn't be read
+    pub error: String,
+}
+
+/// Reads a node with `T::from_db_node`, a node that can't be read is logged
+/// and returned as a `DeserializeError`, so one bad node doesn't fail a whole list
+async fn from_db_node_checked<T: DbGet>(node: Node) -> Result<T, DeserializeError> {
+    let identifier = node.get::<String>(T::DB_IDENTIFIER_FIELD).ok().or_else(|| {
+        node.get::<i64>(T::DB_IDENTIFIER_FIELD)
+            .ok()
+            .map(|id| id.to_string())
+    });
+
+    T::from_db_node(node).await.map_err(|e| {
+        warn!(
+            "Skipping {} node {:?} that can't be read: {:?}",
+            T::DB_NODE_KIND,
+            identifier,
+            e
+        );
 
-    let mut database_identifier = database_identifier.to_string();
+        DeserializeError {
+            identifier,
+            error: e.to_string(),
+        }
+    })
+}
+
+/// Checks that a page `limit` is between 1 and `MAX_PAGE_LIMIT`
+pub fn check_page_limit(limit: u64) -> Result<()> {
+    if limit == 0 || limit > MAX_PAGE_LIMIT {
+        return Err(anyhow!(
+            "The limit must be between 1 and {MAX_PAGE_LIMIT}, got {limit}"
+        ));
+    }
+
+    Ok(())
+}
+
+/// How a single node lookup went
+#[derive(Serialize, Clone, Debug)]
+pub struct QueryMeta {
+    /// Whether a matching node was found
+    pub found: bool,
+    /// The number of matching nodes the query returned
+    pub rows: usize,
+    /// How long the lookup took, including connecting to the database
+    pub elapsed: Duration,
+}
 
-    // if the identifier is not a number, put it in quotes
+/// Puts an identifier in quotes, unless it's a number, for error messages
+fn quote_identifier(database_identifier: &str) -> String {
     if database_identifier.parse::<u64>().is_err() {
-        database_identifier = format!("'{}'", sanitize(&database_identifier));
+        format!("'{}'", sanitize(database_identifier))
+    } else {
+        database_identifier.to_string()
     }
+}
+
+/// Looks up the first node matching the identifier, `None` if there is none
+async fn get_db_node_meta(
+    id_name: &str,
+    kind: &str,
+    database_identifier: &str,
+) -> Result<(Option<Node>, QueryMeta)> {
+    let start = Instant::now();
+    let db = DbHandle::connect().await?;
+
+    let query = DbQuery::new(format!("MATCH (n:{}) WHERE n.{} = $id RETURN n;", kind, id_name))
+        .param("id", database_identifier);
 
-    let query = format!(
-                "MATCH (n:{}) WHERE n.{} = {} RETURN n;",
-                kind, id_name, &database_identifier
-                );
+    debug!("Getting Node: {:?}", query);
+
+    let mut q_out = db.inner.execute(query.into()).await?;
+
+    let mut node = None;
+    let mut rows = 0;
+
+    while let Some(row) = q_out.next().await? {
+        if node.is_none() {
+            node = Some(row.get::<Node>("n")?);
+        }
+        rows += 1;
+    }
 
-    debug!("Getting Node: {}", query);
+    let meta = QueryMeta {
+        found: node.is_some(),
+        rows,
+        elapsed: start.elapsed(),
+    };
 
-    let mut q_out = db
-        .inner
-        .execute(
-            query
-            .into(),
-        )
-        .await?;
+    Ok((node, meta))
+}
 
-    let row = q_out.next().await?.ok_or(anyhow::anyhow!("No rows"))?;
-    // row should return one or more nodes
+async fn get_db_node(id_name: &str, kind: &str, database_identifier: &str) -> Result<Node> {
+    let (node, _) = get_db_node_meta(id_name, kind, database_identifier).await?;
 
-    row.get::<Node>("n").map_err(|e| e.into())
+    node.ok_or_else(|| {
+        NotFound {
+            kind: kind.to_string(),
+            identifier: quote_identifier(database_identifier),
+        }
+        .into()
+    })
 }
 
 /// Denotes that a type can be retrieved from the database
@@ -61,18 +164,71 @@ pub trait DbGet: DbRepr {
         Self: Sized,
     {
         async move {
-            let node = get_db_node(
+            match Self::get_first_meta(database_identifier).await? {
+                (Some(item), _) => Ok(item),
+                (None, _) => Err(NotFound {
+                    kind: Self::DB_NODE_KIND.to_string(),
+                    identifier: quote_identifier(database_identifier),
+                }
+                .into()),
+            }
+        }
+    }
+
+    /// Whether a node of this type with the identifier exists, without building it
+    fn exists(database_identifier: &str) -> impl Future<Output = Result<bool>> + Send
+    where
+        Self: Sized,
+    {
+        async move {
+            let (node, _) = get_db_node_meta(
                 Self::DB_IDENTIFIER_FIELD,
                 Self::DB_NODE_KIND,
                 database_identifier,
             )
             .await?;
-            Self::from_db_node(node).await
+
+            Ok(node.is_some())
+        }
+    }
+
+    /// Same as `get_first`, but a missing node is `None` instead of an error,
+    /// and the lookup is reported along with the result
+    fn get_first_meta(
+        database_identifier: &str,
+    ) -> impl Future<Output = Result<(Option<Self>, QueryMeta)>> + Send
+    where
+        Self: Sized,
+    {
+        async move {
+            let (node, meta) = get_db_node_meta(
+                Self::DB_IDENTIFIER_FIELD,
+                Self::DB_NODE_KIND,
+                database_identifier,
+            )
+            .await?;
+
+            let item = match node {
+                Some(node) => Some(Self::from_db_node(node).await?),
+                None => None,
+            };
+
+            Ok((item, meta))
         }
     }
 
     /// Get all nodes of this type from the database
+    /// Nodes that can't be read are logged and left out, see `get_all_checked`
     fn get_all() -> impl Future<Output = Result<Vec<Self>>>
+    where
+        Self: Sized,
+    {
+        async move { Ok(Self::get_all_checked().await?.0) }
+    }
+
+    /// Get all nodes of this type from the database,
+    /// along with the nodes that couldn't be read, which are left out of the list
+    fn get_all_checked() -> impl Future<Output = Result<(Vec<Self>, Vec<DeserializeError>)>>
     where
         Self: Sized,
     {
@@ -88,16 +244,154 @@ pub trait DbGet: DbRepr {
             debug!("GetAll Query Finished");
 
             let mut nodes = vec![];
+            let mut warnings = vec![];
 
             while let Some(row) = q_out.next().await? {
                 let node = row.get::<Node>("n")?;
-                nodes.push(Self::from_db_node(node).await?);
+                match from_db_node_checked(node).await {
+                    Ok(node) => nodes.push(node),
+                    Err(warning) => warnings.push(warning),
+                }
                 debug!("Total nodes: {}", nodes.len());
             }
 
             debug!("GetAll Result Count: {}", nodes.len());
 
-            Ok(nodes)
+            Ok((nodes, warnings))
+        }
+    }
+
+    /// Counts the nodes of this type, without fetching them
+    fn count() -> impl Future<Output = Result<u64>> + Send
+    where
+        Self: Sized,
+    {
+        async move {
+            let db = DbHandle::connect().await?;
+
+            let query = format!("MATCH (n:{}) RETURN count(n) AS count;", Self::DB_NODE_KIND);
+
+            debug!("Count Query: {}", query);
+
+            let mut q_out = db.inner.execute(query.into()).await?;
+
+            match q_out.next().await? {
+                Some(row) => Ok(row.get::<i64>("count")?.max(0) as u64),
+                None => Ok(0),
+            }
+        }
+    }
+
+    /// Get at most `limit` nodes of this type, ordered by identifier, after skipping the first `skip`,
+    /// along with the total number of nodes of this type
+    /// Nodes that can't be read are left out of the items and listed in the warnings instead
+    /// `limit` must be between 1 and `MAX_PAGE_LIMIT`
+    fn get_page(skip: u64, limit: u64) -> impl Future<Output = Result<Page<Self>>>
+    where
+        Self: Sized,
+    {
+        async move {
+            check_page_limit(limit)?;
+
+            let total = Self::count().await?;
+
+            let db = DbHandle::connect().await?;
+
+            let query = DbQuery::new(format!(
+                "MATCH (n:{}) RETURN n ORDER BY n.{} SKIP $skip LIMIT $limit;",
+                Self::DB_NODE_KIND,
+                Self::DB_IDENTIFIER_FIELD
+            ))
+            .param("skip", skip)
+            .param("limit", limit);
+
+            debug!("GetPage Query: {:?}", query);
+
+            let mut q_out = db.inner.execute(query.into()).await?;
+
+            let mut items = vec![];
+            let mut warnings = vec![];
+
+            while let Some(row) = q_out.next().await? {
+                let node = row.get::<Node>("n")?;
+                match from_db_node_checked(node).await {
+                    Ok(item) => items.push(item),
+                    Err(warning) => warnings.push(warning),
+                }
+            }
+
+            Ok(Page {
+                total,
+                skip,
+                limit,
+                items,
+                warnings,
+            })
+        }
+    }
+
+    /// Get the identifiers of all nodes of this type, without building the nodes
+    fn get_all_identifiers() -> impl Future<Output = Result<Vec<String>>> + Send
+    where
+        Self: Sized,
+    {
+        async move {
+            let db = DbHandle::connect().await?;
+
+            let query = format!(
+                "MATCH (n:{}) RETURN toString(n.{}) AS id;",
+                Self::DB_NODE_KIND,
+                Self::DB_IDENTIFIER_FIELD
+            );
+
+            debug!("GetAll Identifiers Query: {}", query);
+
+            let mut q_out = db.inner.execute(query.into()).await?;
+
+            let mut identifiers = vec![];
+
+            while let Some(row) = q_out.next().await? {
+                identifiers.push(row.get::<String>("id")?);
+            }
+
+            Ok(identifiers)
+        }
+    }
+
+    /// Get the identifiers of at most `limit` nodes of this type whose identifier contains `term`,
+    /// ignoring case, ordered by identifier
+    /// The term is passed as a query parameter, so it needs no escaping
+    fn search_by_name(term: &str, limit: usize) -> impl Future<Output = Result<Vec<String>>> + Send
+    where
+        Self: Sized,
+    {
+        let term = term.to_string();
+
+        async move {
+            let db = DbHandle::connect().await?;
+
+            let query = format!(
+                "MATCH (n:{kind}) WHERE toLower(toString(n.{id})) CONTAINS toLower($q) \
+                RETURN toString(n.{id}) AS id ORDER BY id LIMIT $limit;",
+                kind = Self::DB_NODE_KIND,
+                id = Self::DB_IDENTIFIER_FIELD
+            );
+
+            debug!("Search Query: {} with q = {:?}", query, term);
+
+            let query = neo4rs::query(&query)
+                .param("q", term)
+                .param("limit", limit as i64);
+
+            let mut q_out = db.inner.execute(query).await?;
+
+            let mut identifiers = vec![];
+
+            while let Some(row) = q_out.next().await? {
+                identifiers.push(row.get::<String>("id")?);
+            }
+
+            Ok(identifiers)
         }
     }
 
@@ -2,7 +2,8 @@ use anyhow::Result;
 use neo4rs::Node;
 
 use super::{
-    AsDbString, DbHandle, DbRepr,
+    AsDbString, DbHandle, DbQuery, DbRepr,
+    error::NotFound,
     get::DbGet,
     promise::{MaybePromise, Promised},
 };
@@ -25,6 +26,24 @@ where
         relationship_type: &Self::RelationshipType,
     ) -> Result<()>;
 
+    /// The query `link_to` executes, without executing it
+    fn link_query(
+        &self,
+        other: &MaybePromise<T>,
+        relationship_type: &Self::RelationshipType,
+    ) -> DbQuery {
+        DbQuery::new(format!(
+            "MATCH (a:{}), (b:{}) WHERE a.{} = $a AND b.{} = $b MERGE (a)-[:{}]->(b);",
+            Self::DB_NODE_KIND,
+            T::DB_NODE_KIND,
+            Self::DB_IDENTIFIER_FIELD,
+            T::DB_IDENTIFIER_FIELD,
+            relationship_type.as_db_string()
+        ))
+        .param("a", self.get_raw_identifier())
+        .param("b", other.ident())
+    }
+
     /// Adds a new link (does nothing if the link already exists) from 'self' to 'other'
     fn link_to(
         &mut self,
@@ -34,18 +53,9 @@ where
         async move {
             let db = DbHandle::connect().await?;
 
-            let query = format!(
-                "MATCH (a:{}), (b:{}) WHERE a.{} = {} AND b.{} = {} MERGE (a)-[:{}]->(b);",
-                Self::DB_NODE_KIND,
-                T::DB_NODE_KIND,
-                Self::DB_IDENTIFIER_FIELD,
-                &self.get_db_identifier(),
-                T::DB_IDENTIFIER_FIELD,
-                &other.ident_db(),
-                relationship_type.as_db_string()
-            );
+            let query = self.link_query(other, relationship_type);
 
-            debug!("Linking query: {}", query);
+            debug!("Linking query: {:?}", query);
 
             let mut q_res = db.inner.execute(query.into()).await?;
 
@@ -76,22 +86,18 @@ where
         async move {
             let db = DbHandle::connect().await?;
 
-            let mut q_res = db
-                .inner
-                .execute(
-                    format!(
-                        "MATCH (a:{}), (b:{}) WHERE a.{} = {} AND b.{} = {} MATCH (a)-[r:{}]->(b) DELETE r;",
-                        Self::DB_NODE_KIND,
-                        T::DB_NODE_KIND,
-                        Self::DB_IDENTIFIER_FIELD,
-                        &self.get_db_identifier(),
-                        T::DB_IDENTIFIER_FIELD,
-                        &other.ident_db(),
-                        relationship_type.as_db_string()
-                    )
-                    .into(),
-                )
-                .await?;
+            let query = DbQuery::new(format!(
+                "MATCH (a:{}), (b:{}) WHERE a.{} = $a AND b.{} = $b MATCH (a)-[r:{}]->(b) DELETE r;",
+                Self::DB_NODE_KIND,
+                T::DB_NODE_KIND,
+                Self::DB_IDENTIFIER_FIELD,
+                T::DB_IDENTIFIER_FIELD,
+                relationship_type.as_db_string()
+            ))
+            .param("a", self.get_raw_identifier())
+            .param("b", other.ident());
+
+            let mut q_res = db.inner.execute(query.into()).await?;
 
             let _none = q_res.next().await?;
 
@@ -102,44 +108,15 @@ where
         }
     }
 
-    /// Checks whether a link exists from 'self' to 'other' with the given relationship name
-    fn is_linked_by(
-        &self,
-        other: &MaybePromise<T>,
-        relationship_name: &str,
-    ) -> impl Future<Output = Result<bool>> {
-        async move {
-            let db = DbHandle::connect().await?;
-
-            let mut q_res = db
-                .inner
-                .execute(
-                    format!(
-                        "MATCH (a:{}), (b:{}) WHERE a.{} = {} AND b.{} = {} RETURN exists((a)-[:{}]->(b));",
-                        Self::DB_NODE_KIND,
-                        T::DB_NODE_KIND,
-                        Self::DB_IDENTIFIER_FIELD,
-                        &self.get_db_identifier(),
-                        T::DB_IDENTIFIER_FIELD,
-                        &other.ident_db(),
-                        relationship_name
-                    )
-                    .into(),
-                )
-                .await?;
-
-            // One row if successful
-            Ok(q_res.next().await?.is_some())
-        }
-    }
-
     /// Returns the representations of nodes linked to this node via the given relationship name
     /// with the given identifier
+    /// Each linked node is returned once (even if it's linked more than once),
+    /// ordered by its identifier
     ///
     /// # Arguments
     ///
     /// * `relationship_name` - The name of the relationship to follow
-    /// * `database_identifier` - The identifier of the node to get linked nodes from
+    /// * `database_identifier` - The (raw, unquoted) identifier of the node to get linked nodes from
     ///
     /// # Returns
     ///
@@ -153,30 +130,27 @@ where
     /// use crate::pokemon::PokemonType;
     ///
     /// let water = PokemonType::get_first("Water").await.unwrap();
-    /// let strong_against = water.get_linked_by_id("strong_against", water.get_identifier()).await.unwrap();
+    /// let strong_against = PokemonType::get_linked_by_id(&Relationship::StrongAgainst, water.get_raw_identifier()).await.unwrap();
     ///
     /// ```
     fn get_linked_by_id(
         relationship_type: &Self::RelationshipType,
-        database_identifier: String,
+        database_identifier: &str,
     ) -> impl Future<Output = Result<Vec<MaybePromise<T>>>> {
+        let query = DbQuery::new(format!(
+            "MATCH (a:{})-[:{}]->(b:{}) WHERE a.{} = $a RETURN DISTINCT b ORDER BY b.{};",
+            Self::DB_NODE_KIND,
+            relationship_type.as_db_string(),
+            T::DB_NODE_KIND,
+            Self::DB_IDENTIFIER_FIELD,
+            T::DB_IDENTIFIER_FIELD
+        ))
+        .param("a", database_identifier);
+
         async move {
             let db = DbHandle::connect().await?;
 
-            let mut q_res = db
-                .inner
-                .execute(
-                    format!(
-                        "MATCH (a:{} {{ {} : {} }})-[:{}]->(b:{}) RETURN b;",
-                        Self::DB_NODE_KIND,
-                        Self::DB_IDENTIFIER_FIELD,
-                        &database_identifier,
-                        relationship_type.as_db_string(),
-                        T::DB_NODE_KIND
-                    )
-                    .into(),
-                )
-                .await?;
+            let mut q_res = db.inner.execute(query.into()).await?;
 
             let mut nodes = vec![];
 
@@ -189,12 +163,59 @@ where
         }
     }
 
+    /// Counts the links from this node via the given relationship,
+    /// without fetching or resolving the linked nodes
+    fn link_count(
+        &self,
+        relationship_type: &Self::RelationshipType,
+    ) -> impl Future<Output = Result<u64>> {
+        Self::count_linked_by_id(relationship_type, self.get_raw_identifier())
+    }
+
+    /// Counts the links via the given relationship from the node with the given identifier,
+    /// without fetching or resolving the linked nodes, nor the node itself
+    /// Fails with `NotFound` if there is no such node, rather than counting `0`
+    fn count_linked_by_id(
+        relationship_type: &Self::RelationshipType,
+        database_identifier: &str,
+    ) -> impl Future<Output = Result<u64>> {
+        async move {
+            let db = DbHandle::connect().await?;
+
+            // grouping by the node leaves no row when it doesn't exist
+            let query = DbQuery::new(format!(
+                "MATCH (a:{}) WHERE a.{} = $a OPTIONAL MATCH (a)-[r:{}]->(:{}) \
+                RETURN a.{} AS id, count(r) AS count;",
+                Self::DB_NODE_KIND,
+                Self::DB_IDENTIFIER_FIELD,
+                relationship_type.as_db_string(),
+                T::DB_NODE_KIND,
+                Self::DB_IDENTIFIER_FIELD
+            ))
+            .param("a", database_identifier);
+
+            debug!("Link Count Query: {:?}", query);
+
+            let mut q_res = db.inner.execute(query.into()).await?;
+
+            match q_res.next().await? {
+                Some(row) => Ok(row.get::<i64>("count")?.max(0) as u64),
+                None => Err(NotFound {
+                    kind: Self::DB_NODE_KIND.to_string(),
+                    identifier: format!("'{}'", database_identifier),
+                }
+                .into()),
+            }
+        }
+    }
+
     /// Returns the representations of nodes this node is linked to via the
     /// given relationship name
     fn get_linked_to(
         &self,
         relationship_type: &Self::RelationshipType,
     ) -> impl Future<Output = Result<Vec<MaybePromise<T>>>> {
-        Self::get_linked_by_id(relationship_type, self.get_db_identifier())
+        Self::get_linked_by_id(relationship_type, self.get_raw_identifier())
     }
 }
+
@@ -1,22 +1,46 @@
-use anyhow::Result;
-use neo4rs::Graph;
-use std::fs;
+use anyhow::{Result, anyhow};
+use neo4rs::{BoltType, Graph, Query, Row};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::config::DatabaseConfig;
 
 /// The delete module contains traits to allow a type to be deleted from database
 pub mod delete;
 
+/// Database export HTTP endpoints module
+pub mod endpoints;
+
+/// The error module classifies database errors (transient, not found, etc.)
+pub mod error;
+
+/// The explain module shows the queries a write operation would execute, without executing them
+pub mod explain;
+
 /// The get module contains traits to allow a type to be retrieved from the database
 pub mod get;
 
 /// The link module contains traits to allow a type to be linked to another type in the database
 pub mod link;
 
+/// The ndjson module streams the database as newline-delimited JSON records and reads them back
+pub mod ndjson;
+
 /// The promise module contains traits to allow a type to be promised to be available from database
 pub mod promise;
 
 /// The put module contains traits to allow a type to be inserted into the database
 pub mod put;
 
+/// The repair module removes relationships that point at nodes which can't be resolved
+pub mod repair;
+
+/// The snapshot module copies the whole database to and from a file
+pub mod snapshot;
+
+/// The suggest module finds existing identifiers close to a misspelled one
+pub mod suggest;
+
 /// The update module contains traits to allow a type to be updated in the database
 pub mod update;
 
@@ -27,35 +51,59 @@ pub struct DbHandle {
 }
 
 impl DbHandle {
-    /// Connects to the database using the configuration in `config.toml`
+    /// Connects to the database using the effective `DatabaseConfig`
+    /// (environment variables over the config file, `config::CONFIG_FILE`, over defaults)
     pub async fn connect() -> Result<Self> {
-        let cfg = fs::read_to_string("../../config/config.toml")?.parse::<toml::Table>()?;
-        let url = format!(
-            "neo4j://{}:{}",
-            cfg["database"]["host"]
-                .as_str()
-                .ok_or(anyhow::anyhow!("No host"))?,
-            cfg["database"]["port"]
-        );
-
-        let uname = cfg["database"]["username"]
-            .as_str()
-            .ok_or(anyhow::anyhow!("No username"))?;
-        let pass = cfg["database"]["password"]
-            .as_str()
-            .ok_or(anyhow::anyhow!("No password"))?;
+        let cfg = DatabaseConfig::load()?;
+        let url = format!("neo4j://{}:{}", cfg.host, cfg.port);
 
         let dbconfig = neo4rs::ConfigBuilder::new()
             .fetch_size(1000)
             .uri(url)
-            .user(uname)
-            .password(pass)
+            .user(cfg.username)
+            .password(cfg.password)
             .build()?;
 
         let graph = Graph::connect(dbconfig).await?;
 
         Ok(Self { inner: graph })
     }
+
+    /// Connects to the database and runs a trivial query,
+    /// to check that the database can serve requests
+    pub async fn ping() -> Result<()> {
+        let db = Self::connect().await?;
+        let mut q_res = db.inner.execute(DbQuery::new("RETURN 1;").into()).await?;
+
+        q_res
+            .next()
+            .await?
+            .ok_or_else(|| anyhow!("The database returned no row for RETURN 1"))?;
+
+        Ok(())
+    }
+}
+
+/// Runs queries and collects their rows
+/// Code that takes an executor rather than connecting on its own can be run,
+/// and have its queries counted, without a database
+pub trait QueryExecutor: Sync {
+    /// Executes the query, returning every row it produced
+    fn fetch_all(&self, query: DbQuery) -> impl Future<Output = Result<Vec<Row>>> + Send;
+}
+
+impl QueryExecutor for DbHandle {
+    async fn fetch_all(&self, query: DbQuery) -> Result<Vec<Row>> {
+        let mut q_out = self.inner.execute(query.into()).await?;
+
+        let mut rows = vec![];
+
+        while let Some(row) = q_out.next().await? {
+            rows.push(row);
+        }
+
+        Ok(rows)
+    }
 }
 
 /// Represents a string that can be used in a database query
@@ -80,6 +128,84 @@ pub trait DbRepr {
     fn get_raw_identifier(&self) -> &str;
 }
 
+/// A value bound to a query parameter, never written into the query text
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum DbValue {
+    /// An integer property
+    Integer(i64),
+    /// A string property, e.g. a name
+    String(String),
+    /// A property without a value, which removes it from the node
+    Null,
+}
+
+impl From<u32> for DbValue {
+    fn from(value: u32) -> Self {
+        DbValue::Integer(value.into())
+    }
+}
+
+impl From<u64> for DbValue {
+    fn from(value: u64) -> Self {
+        DbValue::Integer(i64::try_from(value).unwrap_or(i64::MAX))
+    }
+}
+
+impl From<&str> for DbValue {
+    fn from(value: &str) -> Self {
+        DbValue::String(value.to_string())
+    }
+}
+
+impl<T: Into<DbValue>> From<Option<T>> for DbValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(DbValue::Null, Into::into)
+    }
+}
+
+impl From<DbValue> for BoltType {
+    fn from(value: DbValue) -> Self {
+        match value {
+            DbValue::Integer(value) => value.into(),
+            DbValue::String(value) => value.into(),
+            DbValue::Null => BoltType::Null(neo4rs::BoltNull),
+        }
+    }
+}
+
+/// A Cypher query whose user supplied values are bound as parameters (`$name`),
+/// so names can't change what the query does whatever characters they hold
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct DbQuery {
+    /// The query text, only node kinds, fields and relationships are written into it
+    pub text: String,
+    /// The values of the query's parameters, by name
+    pub params: BTreeMap<String, DbValue>,
+}
+
+impl DbQuery {
+    /// A query without parameters
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            params: BTreeMap::new(),
+        }
+    }
+
+    /// Binds `value` to the parameter `$name`
+    pub fn param(mut self, name: &str, value: impl Into<DbValue>) -> Self {
+        self.params.insert(name.to_string(), value.into());
+        self
+    }
+}
+
+impl From<DbQuery> for Query {
+    fn from(query: DbQuery) -> Self {
+        Query::new(query.text).params(query.params)
+    }
+}
+
 /// Sanitize a string for use in a cypher query
 pub fn sanitize(s: &str) -> String {
     // 1. escape all backslashes
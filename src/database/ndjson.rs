@@ -0,0 +1,411 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, Result, anyhow};
+use futures::{Stream, StreamExt, TryStreamExt, stream};
+use neo4rs::Row;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    AsDbString, DbHandle, DbRepr,
+    promise::MaybePromise,
+    snapshot::{SNAPSHOT_VERSION, Snapshot},
+};
+use crate::{
+    pokemon::{
+        Pokemon, Relationship as PokemonRelationship,
+        ptype::{PokemonType, Relationship as TypeRelationship},
+        stats::PokemonStats,
+    },
+    trainer::{Relationship as TrainerRelationship, Trainer},
+};
+
+/// One line of an NDJSON export
+/// Relationships refer to their nodes by name, the kinds of the nodes follow from the relationship:
+/// `Owns` goes from a trainer to a pokemon, `PrimaryType` and `SecondaryType` from a pokemon to a type,
+/// and `StrongAgainst` and `WeakAgainst` from a type to a type
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Record {
+    /// The first line of every export
+    Header {
+        /// The format version, same as the snapshot version
+        version: u32,
+    },
+    /// A pokemon type
+    Type {
+        /// The name of the type
+        name: String,
+    },
+    /// A pokemon, without its types
+    Pokemon {
+        /// The name of the pokemon
+        name: String,
+        /// The base stats of the pokemon
+        #[serde(flatten)]
+        stats: PokemonStats,
+    },
+    /// A trainer, without their team
+    Trainer {
+        /// The name of the trainer
+        name: String,
+    },
+    /// A relationship between two nodes
+    Relationship {
+        /// The kind of the relationship, e.g. `Owns`
+        relationship: String,
+        /// The name of the node the relationship starts from
+        source: String,
+        /// The name of the node the relationship points at
+        target: String,
+    },
+    /// The export failed, this is always the last line
+    Error {
+        /// What went wrong
+        message: String,
+    },
+}
+
+impl Record {
+    fn relationship(relationship: &impl AsDbString, source: &str, target: &str) -> Self {
+        Self::Relationship {
+            relationship: relationship.as_db_string().to_string(),
+            source: source.to_string(),
+            target: target.to_string(),
+        }
+    }
+
+    /// The record as a single line of JSON, including the newline
+    pub fn to_line(&self) -> String {
+        // a record only holds strings and numbers, which always serialize
+        format!("{}\n", serde_json::to_string(self).unwrap())
+    }
+}
+
+/// The queries of an export, in the order they run
+#[derive(Clone, Copy)]
+enum ExportQuery {
+    Types,
+    Pokemons,
+    Trainers,
+    Relationships,
+}
+
+/// The query returning every relationship of one kind as `relationship`, `source` and `target`
+fn relationship_query<A: DbRepr, B: DbRepr>(relationship: &impl AsDbString) -> String {
+    format!(
+        "MATCH (a:{})-[:{}]->(b:{}) RETURN '{}' AS relationship, a.{} AS source, b.{} AS target",
+        A::DB_NODE_KIND,
+        relationship.as_db_string(),
+        B::DB_NODE_KIND,
+        relationship.as_db_string(),
+        A::DB_IDENTIFIER_FIELD,
+        B::DB_IDENTIFIER_FIELD
+    )
+}
+
+impl ExportQuery {
+    const ALL: [ExportQuery; 4] = [
+        ExportQuery::Types,
+        ExportQuery::Pokemons,
+        ExportQuery::Trainers,
+        ExportQuery::Relationships,
+    ];
+
+    fn query(&self) -> String {
+        match self {
+            ExportQuery::Types => format!("MATCH (n:{}) RETURN n;", PokemonType::DB_NODE_KIND),
+            ExportQuery::Pokemons => format!("MATCH (n:{}) RETURN n;", Pokemon::DB_NODE_KIND),
+            ExportQuery::Trainers => format!("MATCH (n:{}) RETURN n;", Trainer::DB_NODE_KIND),
+            ExportQuery::Relationships => {
+                let queries = [
+                    relationship_query::<Trainer, Pokemon>(&TrainerRelationship::Owns),
+                    relationship_query::<Pokemon, PokemonType>(&PokemonRelationship::PrimaryType),
+                    relationship_query::<Pokemon, PokemonType>(&PokemonRelationship::SecondaryType),
+                    relationship_query::<PokemonType, PokemonType>(
+                        &TypeRelationship::StrongAgainst,
+                    ),
+                    relationship_query::<PokemonType, PokemonType>(&TypeRelationship::WeakAgainst),
+                ];
+
+                format!("{};", queries.join(" UNION ALL "))
+            }
+        }
+    }
+
+    fn record(&self, row: &Row) -> Result<Record> {
+        Ok(match self {
+            ExportQuery::Types => Record::Type {
+                name: row.get::<neo4rs::Node>("n")?.get("name")?,
+            },
+            ExportQuery::Pokemons => {
+                let node = row.get::<neo4rs::Node>("n")?;
+                Record::Pokemon {
+                    name: node.get("name")?,
+                    stats: PokemonStats::from_db_node(&node)?,
+                }
+            }
+            ExportQuery::Trainers => Record::Trainer {
+                name: row.get::<neo4rs::Node>("n")?.get("name")?,
+            },
+            ExportQuery::Relationships => Record::Relationship {
+                relationship: row.get("relationship")?,
+                source: row.get("source")?,
+                target: row.get("target")?,
+            },
+        })
+    }
+
+    /// The records of this query, read one row at a time
+    fn records(self) -> impl Stream<Item = Result<Record>> + Send {
+        stream::once(async move {
+            let db = DbHandle::connect().await?;
+
+            debug!("Export Query: {}", self.query());
+
+            let rows = db.inner.execute(self.query().into()).await?;
+
+            // the handle is kept alongside the rows until they're all read
+            Ok::<_, anyhow::Error>(stream::try_unfold(
+                (db, rows),
+                move |(db, mut rows)| async move {
+                    match rows.next().await? {
+                        Some(row) => Ok(Some((self.record(&row)?, (db, rows)))),
+                        None => Ok(None),
+                    }
+                },
+            ))
+        })
+        .try_flatten()
+    }
+}
+
+/// Streams every type, pokemon, trainer and relationship in the database as records,
+/// reading them from the database as the stream is polled, so they're never all in memory
+/// The stream ends after the first error
+pub fn export() -> impl Stream<Item = Result<Record>> + Send {
+    let header = Record::Header {
+        version: SNAPSHOT_VERSION,
+    };
+
+    stream::once(async move { Ok(header) })
+        .chain(stream::iter(ExportQuery::ALL).flat_map(ExportQuery::records))
+        .scan(false, |failed, record| {
+            let record = (!*failed).then(|| {
+                *failed = record.is_err();
+                record
+            });
+            async move { record }
+        })
+}
+
+impl Snapshot {
+    /// The snapshot as the records of an NDJSON export, in the same order as `export`
+    pub fn to_records(&self) -> Vec<Record> {
+        let mut records = vec![Record::Header {
+            version: self.version,
+        }];
+
+        records.extend(self.types.iter().map(|t| Record::Type {
+            name: t.name.clone(),
+        }));
+        records.extend(self.pokemons.iter().map(|p| Record::Pokemon {
+            name: p.name.clone(),
+            stats: p.stats.clone(),
+        }));
+        records.extend(self.trainers.iter().map(|t| Record::Trainer {
+            name: t.name.clone(),
+        }));
+
+        for trainer in &self.trainers {
+            for pokemon in &trainer.team {
+                records.push(Record::relationship(
+                    &TrainerRelationship::Owns,
+                    &trainer.name,
+                    pokemon.ident(),
+                ));
+            }
+        }
+
+        for pokemon in &self.pokemons {
+            records.push(Record::relationship(
+                &PokemonRelationship::PrimaryType,
+                &pokemon.name,
+                pokemon.primary_type().ident(),
+            ));
+
+            if let Some(secondary_type) = pokemon.secondary_type() {
+                records.push(Record::relationship(
+                    &PokemonRelationship::SecondaryType,
+                    &pokemon.name,
+                    secondary_type.ident(),
+                ));
+            }
+        }
+
+        for ptype in &self.types {
+            for other in ptype.strong_against() {
+                records.push(Record::relationship(
+                    &TypeRelationship::StrongAgainst,
+                    &ptype.name,
+                    other.ident(),
+                ));
+            }
+
+            for other in ptype.weak_against() {
+                records.push(Record::relationship(
+                    &TypeRelationship::WeakAgainst,
+                    &ptype.name,
+                    other.ident(),
+                ));
+            }
+        }
+
+        records
+    }
+
+    /// The snapshot as NDJSON, one record per line
+    pub fn to_ndjson(&self) -> String {
+        self.to_records().iter().map(Record::to_line).collect()
+    }
+
+    /// Builds a snapshot from the records of an NDJSON export
+    /// The first record must be the header, the other records may come in any order
+    /// Relationships are only checked to start from a known node,
+    /// use `validate` (or `restore`) to check their targets
+    pub fn from_records(records: impl IntoIterator<Item = Record>) -> Result<Self> {
+        let mut records = records.into_iter();
+
+        let version = match records.next() {
+            Some(Record::Header { version }) => version,
+            _ => return Err(anyhow!("The first record must be the header")),
+        };
+
+        let mut types: Vec<(String, Vec<String>, Vec<String>)> = vec![];
+        let mut pokemons: Vec<(String, PokemonStats, Option<String>, Option<String>)> = vec![];
+        let mut trainers: Vec<Trainer> = vec![];
+        let mut relationships = vec![];
+
+        for record in records {
+            match record {
+                Record::Header { .. } => {
+                    return Err(anyhow!("Only the first record can be a header"));
+                }
+                Record::Type { name } => types.push((name, vec![], vec![])),
+                Record::Pokemon { name, stats } => pokemons.push((name, stats, None, None)),
+                Record::Trainer { name } => trainers.push(Trainer {
+                    name,
+                    team: vec![],
+                    strategy: None,
+                }),
+                Record::Relationship {
+                    relationship,
+                    source,
+                    target,
+                } => relationships.push((relationship, source, target)),
+                Record::Error { message } => {
+                    return Err(anyhow!("The export is incomplete: {message}"));
+                }
+            }
+        }
+
+        let type_index: HashMap<String, usize> = types
+            .iter()
+            .enumerate()
+            .map(|(i, t)| (t.0.clone(), i))
+            .collect();
+        let pokemon_index: HashMap<String, usize> = pokemons
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (p.0.clone(), i))
+            .collect();
+        let trainer_index: HashMap<String, usize> = trainers
+            .iter()
+            .enumerate()
+            .map(|(i, t)| (t.name.clone(), i))
+            .collect();
+
+        let find = |index: &HashMap<String, usize>, kind: &str, name: &str| {
+            index
+                .get(name)
+                .copied()
+                .ok_or_else(|| anyhow!("A relationship starts from unknown {kind} {name}"))
+        };
+
+        for (relationship, source, target) in relationships {
+            match relationship.as_str() {
+                r if r == TrainerRelationship::Owns.as_db_string() => {
+                    let i = find(&trainer_index, "trainer", &source)?;
+                    trainers[i]
+                        .team
+                        .push(MaybePromise::from_ident_unchecked(target));
+                }
+                r if r == PokemonRelationship::PrimaryType.as_db_string() => {
+                    let i = find(&pokemon_index, "pokemon", &source)?;
+                    pokemons[i].2 = Some(target);
+                }
+                r if r == PokemonRelationship::SecondaryType.as_db_string() => {
+                    let i = find(&pokemon_index, "pokemon", &source)?;
+                    pokemons[i].3 = Some(target);
+                }
+                r if r == TypeRelationship::StrongAgainst.as_db_string() => {
+                    let i = find(&type_index, "type", &source)?;
+                    types[i].1.push(target);
+                }
+                r if r == TypeRelationship::WeakAgainst.as_db_string() => {
+                    let i = find(&type_index, "type", &source)?;
+                    types[i].2.push(target);
+                }
+                _ => return Err(anyhow!("Unknown relationship {relationship}")),
+            }
+        }
+
+        let promises = |names: Vec<String>| {
+            names
+                .into_iter()
+                .map(MaybePromise::from_ident_unchecked)
+                .collect()
+        };
+
+        let types = types
+            .into_iter()
+            .map(|(name, strong, weak)| PokemonType::new(name, promises(strong), promises(weak)))
+            .collect();
+
+        let pokemons = pokemons
+            .into_iter()
+            .map(|(name, stats, primary_type, secondary_type)| {
+                let primary_type =
+                    primary_type.ok_or_else(|| anyhow!("Pokemon {name} has no primary type"))?;
+
+                Ok(Pokemon::new(
+                    name,
+                    MaybePromise::from_ident_unchecked(primary_type),
+                    secondary_type.map(MaybePromise::from_ident_unchecked),
+                    stats,
+                ))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            version,
+            types,
+            pokemons,
+            trainers,
+        })
+    }
+
+    /// Builds a snapshot from NDJSON, one record per line, blank lines are skipped
+    pub fn from_ndjson(ndjson: &str) -> Result<Self> {
+        let records = ndjson
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                serde_json::from_str(line)
+                    .with_context(|| format!("Invalid record on line {}", i + 1))
+            })
+            .collect::<Result<Vec<Record>>>()?;
+
+        Self::from_records(records)
+    }
+}
@@ -1,12 +1,32 @@
 use anyhow::Result;
 
-use super::{DbHandle, DbRepr};
+use super::{DbHandle, DbQuery, DbRepr, DbValue};
 
 /// Denotes that a type can be inserted into the database
 pub trait DbPut: DbRepr {
-    /// Arguments for database insertion query (properties, so)
-    /// e.g. "{name: 'John', age: 30}"
-    fn put_args(&self) -> String;
+    /// The node's properties, each bound as the query parameter of the same name
+    /// e.g. `[("name", "John".into()), ("age", 30.into())]`
+    fn put_args(&self) -> Vec<(&'static str, DbValue)>;
+
+    /// The query `put_self_only` executes, without executing it
+    fn put_query(&self) -> DbQuery {
+        let args = self.put_args();
+
+        let properties = args
+            .iter()
+            .map(|(name, _)| format!("{name}: ${name}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        args.into_iter().fold(
+            DbQuery::new(format!(
+                "MERGE (n:{} {{ {} }})",
+                Self::DB_NODE_KIND,
+                properties
+            )),
+            |query, (name, value)| query.param(name, value),
+        )
+    }
 
     /// Inserts a new node into the database, holding the contents 'self'
     /// Does not duplicate nodes
@@ -15,9 +35,8 @@ pub trait DbPut: DbRepr {
     where
         Self: Sized,
     {
-        debug!("PutSelfOnly: {}", self.put_args());
-        let query = format!("MERGE (n:{} {})", Self::DB_NODE_KIND, self.put_args());
-        debug!("PutSelfQuery: {query}");
+        let query = self.put_query();
+        debug!("PutSelfQuery: {query:?}");
         async move {
             let db = DbHandle::connect().await?;
             let mut q_res = db.inner.execute(query.into()).await?;
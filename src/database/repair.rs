@@ -0,0 +1,92 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use super::{AsDbString, DbHandle, DbRepr};
+use crate::{
+    pokemon::{
+        Pokemon, Relationship as PokemonRelationship,
+        ptype::{PokemonType, Relationship as TypeRelationship},
+    },
+    trainer::{Relationship as TrainerRelationship, Trainer},
+};
+
+/// A relationship removed by `repair`
+#[derive(Serialize, Clone, Debug)]
+pub struct RemovedRelationship {
+    /// The kind of the relationship, e.g. `Owns`
+    pub relationship: String,
+    /// The identifier of the node the relationship started from
+    pub source: String,
+    /// The identifier of the node the relationship pointed at, if it still had one
+    pub target: Option<String>,
+}
+
+/// What `repair` cleaned up
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct RepairReport {
+    /// Every relationship removed, grouped by kind
+    pub removed: Vec<RemovedRelationship>,
+}
+
+/// Removes the `relationship` edges from an `A` node whose target is not a valid `B` node
+async fn repair_relationship<A: DbRepr, B: DbRepr>(
+    db: &DbHandle,
+    relationship: &impl AsDbString,
+    report: &mut RepairReport,
+) -> Result<()> {
+    let query = format!(
+        "MATCH (a:{})-[r:{}]->(b) WHERE NOT b:{} OR b.{} IS NULL \
+        DELETE r RETURN toString(a.{}) AS source, toString(b.{}) AS target;",
+        A::DB_NODE_KIND,
+        relationship.as_db_string(),
+        B::DB_NODE_KIND,
+        B::DB_IDENTIFIER_FIELD,
+        A::DB_IDENTIFIER_FIELD,
+        B::DB_IDENTIFIER_FIELD
+    );
+
+    debug!("Repair Query: {}", query);
+
+    let mut q_out = db.inner.execute(query.into()).await?;
+
+    while let Some(row) = q_out.next().await? {
+        report.removed.push(RemovedRelationship {
+            relationship: relationship.as_db_string().to_string(),
+            source: row.get::<String>("source")?,
+            target: row.get::<Option<String>>("target")?,
+        });
+    }
+
+    Ok(())
+}
+
+/// Removes every relationship whose target is no longer a node the relationship can point at
+/// Neo4j never keeps a relationship to a deleted node, but a manual edit can remove
+/// the target's label or identifier (e.g. `REMOVE p:Pokemon`), after which it can't be resolved
+pub async fn repair() -> Result<RepairReport> {
+    let db = DbHandle::connect().await?;
+    let mut report = RepairReport::default();
+
+    repair_relationship::<Trainer, Pokemon>(&db, &TrainerRelationship::Owns, &mut report).await?;
+
+    for relationship in [
+        PokemonRelationship::PrimaryType,
+        PokemonRelationship::SecondaryType,
+    ] {
+        repair_relationship::<Pokemon, PokemonType>(&db, &relationship, &mut report).await?;
+    }
+
+    for relationship in [
+        TypeRelationship::StrongAgainst,
+        TypeRelationship::WeakAgainst,
+    ] {
+        repair_relationship::<PokemonType, PokemonType>(&db, &relationship, &mut report).await?;
+    }
+
+    info!(
+        "Repair removed {} dangling relationships",
+        report.removed.len()
+    );
+
+    Ok(report)
+}
@@ -0,0 +1,232 @@
+use std::{collections::HashSet, fs, path::Path};
+
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    AsDbString, DbHandle, DbQuery, DbRepr,
+    get::DbGet,
+    promise::{MaybePromise, Promised},
+    put::DbPut,
+};
+use crate::{
+    pokemon::{
+        Pokemon, Relationship as PokemonRelationship,
+        ptype::{PokemonType, Relationship as TypeRelationship},
+    },
+    trainer::{Relationship as TrainerRelationship, Trainer},
+};
+
+/// The format version written into snapshots, restoring any other version is refused
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// A copy of every type, pokemon and trainer in the database, with their relationships
+/// Relationships are stored as the names of the linked nodes
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Snapshot {
+    /// The format version of the snapshot
+    pub version: u32,
+    /// Every pokemon type
+    pub types: Vec<PokemonType>,
+    /// Every pokemon
+    pub pokemons: Vec<Pokemon>,
+    /// Every trainer
+    pub trainers: Vec<Trainer>,
+}
+
+/// A query merging a relationship between two existing nodes
+fn link_query<A: DbRepr, B: DbRepr + Promised>(
+    a: &str,
+    relationship: &impl AsDbString,
+    b: &MaybePromise<B>,
+) -> DbQuery {
+    DbQuery::new(format!(
+        "MATCH (a:{}), (b:{}) WHERE a.{} = $a AND b.{} = $b MERGE (a)-[:{}]->(b);",
+        A::DB_NODE_KIND,
+        B::DB_NODE_KIND,
+        A::DB_IDENTIFIER_FIELD,
+        B::DB_IDENTIFIER_FIELD,
+        relationship.as_db_string()
+    ))
+    .param("a", a)
+    .param("b", b.ident())
+}
+
+/// Checks that no two items share a name, and returns the set of names
+fn unique_names<'a>(kind: &str, names: impl Iterator<Item = &'a str>) -> Result<HashSet<&'a str>> {
+    let mut seen = HashSet::new();
+
+    for name in names {
+        if !seen.insert(name) {
+            return Err(anyhow!(
+                "The snapshot contains the {kind} {name} more than once"
+            ));
+        }
+    }
+
+    Ok(seen)
+}
+
+impl Snapshot {
+    /// Reads every type, pokemon and trainer from the database
+    pub async fn capture() -> Result<Self> {
+        Ok(Self {
+            version: SNAPSHOT_VERSION,
+            types: PokemonType::get_all().await?,
+            pokemons: Pokemon::get_all().await?,
+            trainers: Trainer::get_all().await?,
+        })
+    }
+
+    /// Checks the snapshot's version, and that every relationship points at a node in the snapshot
+    pub fn validate(&self) -> Result<()> {
+        if self.version != SNAPSHOT_VERSION {
+            return Err(anyhow!(
+                "Unsupported snapshot version {}, expected {}",
+                self.version,
+                SNAPSHOT_VERSION
+            ));
+        }
+
+        let types = unique_names("type", self.types.iter().map(|t| t.name.as_str()))?;
+        let pokemons = unique_names("pokemon", self.pokemons.iter().map(|p| p.name.as_str()))?;
+        unique_names("trainer", self.trainers.iter().map(|t| t.name.as_str()))?;
+
+        for ptype in &self.types {
+            for other in ptype.strong_against().iter().chain(ptype.weak_against()) {
+                if !types.contains(other.ident()) {
+                    return Err(anyhow!(
+                        "Type {} refers to unknown type {}",
+                        ptype.name,
+                        other.ident()
+                    ));
+                }
+            }
+        }
+
+        for pokemon in &self.pokemons {
+            for ptype in std::iter::once(pokemon.primary_type()).chain(pokemon.secondary_type()) {
+                if !types.contains(ptype.ident()) {
+                    return Err(anyhow!(
+                        "Pokemon {} has unknown type {}",
+                        pokemon.name,
+                        ptype.ident()
+                    ));
+                }
+            }
+        }
+
+        for trainer in &self.trainers {
+            for pokemon in &trainer.team {
+                if !pokemons.contains(pokemon.ident()) {
+                    return Err(anyhow!(
+                        "Trainer {} owns unknown pokemon {}",
+                        trainer.name,
+                        pokemon.ident()
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The queries that replace the database contents with the snapshot
+    pub fn restore_queries(&self) -> Vec<DbQuery> {
+        let mut queries = vec![DbQuery::new(format!(
+            "MATCH (n) WHERE n:{} OR n:{} OR n:{} DETACH DELETE n;",
+            PokemonType::DB_NODE_KIND,
+            Pokemon::DB_NODE_KIND,
+            Trainer::DB_NODE_KIND
+        ))];
+
+        // nodes first, so every relationship has both of its ends
+        queries.extend(self.types.iter().map(|t| t.put_query()));
+        queries.extend(self.pokemons.iter().map(|p| p.put_query()));
+        queries.extend(self.trainers.iter().map(|t| t.put_query()));
+
+        for ptype in &self.types {
+            let ident = ptype.get_raw_identifier();
+
+            for other in ptype.strong_against() {
+                queries.push(link_query::<PokemonType, PokemonType>(
+                    ident,
+                    &TypeRelationship::StrongAgainst,
+                    other,
+                ));
+            }
+
+            for other in ptype.weak_against() {
+                queries.push(link_query::<PokemonType, PokemonType>(
+                    ident,
+                    &TypeRelationship::WeakAgainst,
+                    other,
+                ));
+            }
+        }
+
+        for pokemon in &self.pokemons {
+            let ident = pokemon.get_raw_identifier();
+
+            queries.push(link_query::<Pokemon, PokemonType>(
+                ident,
+                &PokemonRelationship::PrimaryType,
+                pokemon.primary_type(),
+            ));
+
+            if let Some(secondary_type) = pokemon.secondary_type() {
+                queries.push(link_query::<Pokemon, PokemonType>(
+                    ident,
+                    &PokemonRelationship::SecondaryType,
+                    secondary_type,
+                ));
+            }
+        }
+
+        for trainer in &self.trainers {
+            let ident = trainer.get_raw_identifier();
+
+            for pokemon in &trainer.team {
+                queries.push(link_query::<Trainer, Pokemon>(
+                    ident,
+                    &TrainerRelationship::Owns,
+                    pokemon,
+                ));
+            }
+        }
+
+        queries
+    }
+
+    /// Replaces the database contents with the snapshot, in a single transaction
+    /// The snapshot is validated first, and nothing changes if any query fails
+    pub async fn restore(&self) -> Result<()> {
+        self.validate()?;
+
+        let db = DbHandle::connect().await?;
+        let mut txn = db.inner.start_txn().await?;
+
+        if let Err(e) = txn.run_queries(self.restore_queries()).await {
+            txn.rollback().await?;
+            return Err(e.into());
+        }
+
+        txn.commit().await?;
+
+        Ok(())
+    }
+
+    /// Writes the snapshot to a JSON file
+    pub fn write_to(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json)
+            .with_context(|| format!("Could not write snapshot {}", path.display()))
+    }
+
+    /// Reads a snapshot from a JSON file
+    pub fn read_from(path: &Path) -> Result<Self> {
+        let json = fs::read_to_string(path)
+            .with_context(|| format!("Could not read snapshot {}", path.display()))?;
+        Ok(serde_json::from_str(&json)?)
+    }
+}
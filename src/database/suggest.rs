@@ -0,0 +1,84 @@
+use super::{
+    error::{ErrorClass, classify},
+    get::DbGet,
+};
+use crate::json::ApiError;
+
+/// The most names suggested for a missing name
+pub const MAX_SUGGESTIONS: usize = 3;
+
+/// The number of single character edits (insertions, deletions or substitutions)
+/// needed to turn `a` into `b`
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+
+    // distances from the current prefix of `a` to every prefix of `b`
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a_char != *b_char);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
+/// The candidates closest to `name`, closest first, at most `MAX_SUGGESTIONS` of them
+/// Case is ignored, ties go to the alphabetically first candidate,
+/// and candidates needing more edits than a third of the name are left out
+pub fn closest<'a>(name: &str, candidates: &'a [String]) -> Vec<&'a str> {
+    let name = name.to_lowercase();
+    let max_distance = (name.chars().count() / 3).max(1);
+
+    let mut close: Vec<(usize, &str)> = candidates
+        .iter()
+        .map(|c| (levenshtein(&name, &c.to_lowercase()), c.as_str()))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+
+    close.sort();
+    close.truncate(MAX_SUGGESTIONS);
+
+    close.into_iter().map(|(_, c)| c).collect()
+}
+
+/// The error message for a `label` (e.g. "Pokemon") named `name` that doesn't exist,
+/// suggesting the closest `candidates` if any are close enough
+pub fn not_found_message(label: &str, name: &str, candidates: &[String]) -> String {
+    let suggestions = closest(name, candidates);
+
+    if suggestions.is_empty() {
+        format!("{label} '{name}' not found")
+    } else {
+        format!(
+            "{label} '{name}' not found; did you mean: {}?",
+            suggestions.join(", ")
+        )
+    }
+}
+
+/// Same as `not_found_message`, with every identifier of `T` in the database as the candidates
+/// Suggestions are skipped if the identifiers can't be fetched
+pub async fn not_found<T: DbGet>(label: &str, name: &str) -> String {
+    let candidates = T::get_all_identifiers().await.unwrap_or_default();
+    not_found_message(label, name, &candidates)
+}
+
+/// Gets the `T` with the identifier `name`, `label` (e.g. "Contender") names it in the error message
+/// A missing node is `ApiError::NotFound`, suggesting the closest existing names,
+/// any other failure (e.g. the database being down) keeps its own kind, see `ApiError::from_anyhow`
+pub async fn find<T: DbGet>(label: &str, name: &str) -> Result<T, ApiError> {
+    match T::get_first(name).await {
+        Ok(found) => Ok(found),
+        Err(e) if classify(&e) == ErrorClass::NotFound => {
+            Err(ApiError::not_found(not_found::<T>(label, name).await))
+        }
+        Err(e) => Err(ApiError::from_anyhow(e)),
+    }
+}
@@ -1,44 +1,46 @@
 use anyhow::Result;
 
-use super::{DbHandle, DbRepr};
+use super::{DbHandle, DbQuery, DbRepr, DbValue};
 
 /// Denotes an ability to update a node in the database
 pub trait DbUpdate: DbRepr {
-    /// Give the string representation of the update query
-    /// e.g. "n.name = 'John', n.age = 30", the node is always 'n'
-    fn update_args(&self) -> String
-    where
-        Self: Sized;
+    /// The properties to set, each bound as the query parameter of the same name,
+    /// a `DbValue::Null` removes the property
+    /// e.g. `[("age", 30.into()), ("nickname", DbValue::Null)]`
+    fn update_args(&self) -> Vec<(&'static str, DbValue)>;
+
+    /// The query `update` executes, without executing it
+    fn update_query(&self, database_identifier: &str) -> DbQuery {
+        let args = self.update_args();
+
+        let assignments = args
+            .iter()
+            .map(|(name, _)| format!("n.{name} = ${name}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        args.into_iter().fold(
+            DbQuery::new(format!(
+                "MATCH (n:{}) WHERE n.{} = $id SET {}",
+                Self::DB_NODE_KIND,
+                Self::DB_IDENTIFIER_FIELD,
+                assignments
+            ))
+            .param("id", database_identifier),
+            |query, (name, value)| query.param(name, value),
+        )
+    }
 
-    /// Update database node at given identifier with contents of 'self'
-    fn update(
-        &self,
-        database_identifier: &str,
-    ) -> impl Future<Output = Result<()>> + Send
+    /// Update the database node with the given (raw, unquoted) identifier with the contents of 'self'
+    fn update(&self, database_identifier: &str) -> impl Future<Output = Result<()>> + Send
     where
         Self: Sized,
     {
-        let update_args = self.update_args();
+        let query = self.update_query(database_identifier);
         async move {
-            // first get old database node
             let db = DbHandle::connect().await?;
-
-            let mut q_res = db
-                .inner
-                .execute(
-                    format!(
-                        "MATCH (n:{}) WHERE n.{} = {} SET {}",
-                        Self::DB_NODE_KIND,
-                        Self::DB_IDENTIFIER_FIELD,
-                        database_identifier,
-                        update_args
-                    )
-                    .into(),
-                )
-                .await?;
-
+            let mut q_res = db.inner.execute(query.into()).await?;
             let _none = q_res.next().await?;
-
             Ok(())
         }
     }
@@ -0,0 +1,174 @@
+use std::marker::PhantomData;
+
+use rocket::{
+    http::Status,
+    request::{FromRequest, Outcome, Request},
+};
+use serde::Deserialize;
+
+use crate::json::JsonStatus;
+
+/// Which of the expensive endpoints are enabled, read once at startup
+/// from the `[features]` section of the config file. Every feature is enabled by default
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct FeatureConfig {
+    /// The trainer fight odds and fairness endpoints
+    pub odds: bool,
+    /// The fight length distribution endpoint
+    pub length_distribution: bool,
+    /// The pokemon fight batch endpoint
+    pub batch: bool,
+    /// The gauntlet odds endpoint
+    pub gauntlet: bool,
+    /// The stat sensitivity endpoint
+    pub sensitivity: bool,
+    /// The team solver endpoint
+    pub solve_team: bool,
+    /// The round robin and tournament endpoints
+    pub round_robin: bool,
+}
+
+impl Default for FeatureConfig {
+    fn default() -> Self {
+        Self {
+            odds: true,
+            length_distribution: true,
+            batch: true,
+            gauntlet: true,
+            sensitivity: true,
+            solve_team: true,
+            round_robin: true,
+        }
+    }
+}
+
+impl FeatureConfig {
+    /// Loads the feature flags from the config file, or defaults if not configured
+    pub fn load() -> Self {
+        crate::config::read_section_or_default("features")
+    }
+}
+
+/// A feature that can be switched off in the `[features]` config section
+pub trait Feature: Send {
+    /// The name of the feature's flag in the config section
+    const NAME: &'static str;
+
+    /// Whether the feature is enabled in `config`
+    fn enabled(config: &FeatureConfig) -> bool;
+}
+
+/// The trainer fight odds feature
+pub struct Odds;
+/// The fight length distribution feature
+pub struct LengthDistribution;
+/// The pokemon fight batch feature
+pub struct Batch;
+/// The gauntlet odds feature
+pub struct Gauntlet;
+/// The stat sensitivity feature
+pub struct Sensitivity;
+/// The team solver feature
+pub struct SolveTeam;
+/// The round robin feature
+pub struct RoundRobin;
+
+impl Feature for Odds {
+    const NAME: &'static str = "odds";
+
+    fn enabled(config: &FeatureConfig) -> bool {
+        config.odds
+    }
+}
+
+impl Feature for LengthDistribution {
+    const NAME: &'static str = "length_distribution";
+
+    fn enabled(config: &FeatureConfig) -> bool {
+        config.length_distribution
+    }
+}
+
+impl Feature for Batch {
+    const NAME: &'static str = "batch";
+
+    fn enabled(config: &FeatureConfig) -> bool {
+        config.batch
+    }
+}
+
+impl Feature for Gauntlet {
+    const NAME: &'static str = "gauntlet";
+
+    fn enabled(config: &FeatureConfig) -> bool {
+        config.gauntlet
+    }
+}
+
+impl Feature for Sensitivity {
+    const NAME: &'static str = "sensitivity";
+
+    fn enabled(config: &FeatureConfig) -> bool {
+        config.sensitivity
+    }
+}
+
+impl Feature for SolveTeam {
+    const NAME: &'static str = "solve_team";
+
+    fn enabled(config: &FeatureConfig) -> bool {
+        config.solve_team
+    }
+}
+
+impl Feature for RoundRobin {
+    const NAME: &'static str = "round_robin";
+
+    fn enabled(config: &FeatureConfig) -> bool {
+        config.round_robin
+    }
+}
+
+/// The name of the disabled feature that refused a request, for the `feature_disabled` catcher
+struct DisabledFeature(Option<&'static str>);
+
+/// A request guard that only lets a request through if feature `F` is enabled,
+/// otherwise the request is refused with `403 Forbidden`
+/// Without a managed `FeatureConfig` every feature counts as enabled
+pub struct Enabled<F: Feature>(PhantomData<F>);
+
+#[rocket::async_trait]
+impl<'r, F: Feature> FromRequest<'r> for Enabled<F> {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let enabled = request
+            .rocket()
+            .state::<FeatureConfig>()
+            .is_none_or(F::enabled);
+
+        if enabled {
+            return Outcome::Success(Enabled(PhantomData));
+        }
+
+        request.local_cache(|| DisabledFeature(Some(F::NAME)));
+
+        Outcome::Error((Status::Forbidden, ()))
+    }
+}
+
+/// Catches requests refused with `403 Forbidden`, e.g. by a disabled feature.
+/// # Returns
+/// A `403 Forbidden` JSON error response naming the disabled feature.
+#[catch(403)]
+pub fn feature_disabled(request: &Request<'_>) -> (Status, JsonStatus<'static>) {
+    let message = match request.local_cache(|| DisabledFeature(None)).0 {
+        Some(name) => format!(
+            "Feature disabled: '{name}' is turned off in the [features] config section"
+        ),
+        None => "Forbidden".to_string(),
+    };
+
+    (Status::Forbidden, JsonStatus::error(message))
+}
@@ -0,0 +1,36 @@
+use anyhow::Result;
+use futures::{StreamExt, TryStreamExt, stream};
+
+/// Tags the result of a fight with its position in the batch
+async fn indexed<F, T>(i: usize, fight: F) -> Result<(usize, T)>
+where
+    F: Future<Output = Result<T>>,
+{
+    Ok((i, fight.await?))
+}
+
+/// Runs `fights` concurrently, at most `limit` at a time (at least one),
+/// so a large batch can't take every database connection at once
+/// Fights finish in any order, but the results are returned in the order of `fights`
+/// The first error stops the batch
+pub async fn run_bounded<F, T>(fights: impl IntoIterator<Item = F>, limit: usize) -> Result<Vec<T>>
+where
+    F: Future<Output = Result<T>>,
+{
+    // the futures are tagged before they become a stream, mapping the stream instead
+    // makes the caller's future lose `Send` (rust-lang/rust#102211)
+    let fights: Vec<_> = fights
+        .into_iter()
+        .enumerate()
+        .map(|(i, fight)| indexed(i, fight))
+        .collect();
+
+    let mut results: Vec<(usize, T)> = stream::iter(fights)
+        .buffer_unordered(limit.max(1))
+        .try_collect()
+        .await?;
+
+    results.sort_by_key(|(i, _)| *i);
+
+    Ok(results.into_iter().map(|(_, result)| result).collect())
+}
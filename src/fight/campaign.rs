@@ -0,0 +1,193 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Result, anyhow};
+use rand::{SeedableRng, rngs::StdRng};
+use serde::Serialize;
+
+use super::{
+    FightEvent, FightLog, FightStrategy,
+    config::FightConfig,
+    pokemon_fight::ResolvedPokemon,
+    trainer_fight::{self, CarriedHp},
+};
+use crate::{database::promise::MaybePromise, pokemon::Pokemon, trainer::Trainer};
+
+/// A step of a campaign, in the order the trainer takes them
+#[derive(Clone, Debug)]
+pub enum Stage {
+    /// A trainer fight against `opponent`, who picks their pokemon first
+    Fight {
+        /// The opposing trainer
+        opponent: Trainer,
+        /// The strategy the opponent fights with
+        strategy: FightStrategy,
+    },
+    /// Restores the whole team, fainted pokemon included, to full HP and removes all fatigue
+    Heal,
+}
+
+/// What happened at a step of a campaign
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum StageLog {
+    /// A trainer fight
+    Fight {
+        /// The name of the opposing trainer
+        opponent: String,
+        /// The name of the winning trainer
+        winner: String,
+        /// The HP each of the trainer's pokemon had left after the fight, `0` for fainted pokemon
+        hp_left: BTreeMap<String, u32>,
+        /// The log of the trainer fight
+        log: FightLog,
+    },
+    /// The team was healed
+    Heal,
+}
+
+/// The outcome of a whole campaign
+#[derive(Serialize, Clone, Debug)]
+pub struct Campaign {
+    /// The seed the campaign's fights were simulated with
+    pub seed: u64,
+    /// The name of the trainer who took on the campaign
+    pub trainer: String,
+    /// Whether the trainer won every fight of the campaign
+    pub survived: bool,
+    /// The number of fights the trainer won
+    pub fights_won: usize,
+    /// Every step taken, in order, ending with the lost fight if the trainer didn't survive
+    pub stages: Vec<StageLog>,
+}
+
+/// Scales a stat down by `fatigue`, without letting it drop to zero
+fn tire(stat: u32, fatigue: f32) -> u32 {
+    ((stat as f32 * (1.0 - fatigue)).round() as u32).max(1)
+}
+
+/// Takes `trainer` through `stages` in order, fighting with `strategy`. The trainer is the challenger of every fight
+/// Their pokemon carry their HP from one fight to the next, and fainted pokemon stay out, until a `Heal` stage.
+/// After every won fight, each pokemon still standing loses a `fatigue` share (from `0.0` to below `1.0`)
+/// of its attack, defense and agility, which also lasts until a `Heal` stage
+/// The campaign ends at the first lost fight. The same `seed` gives the same campaign
+pub async fn run_campaign(
+    trainer: &Trainer,
+    strategy: FightStrategy,
+    stages: &[Stage],
+    fatigue: f32,
+    seed: u64,
+    config: &FightConfig,
+) -> Result<Campaign> {
+    if !(0.0..1.0).contains(&fatigue) {
+        return Err(anyhow!(
+            "The fatigue must be at least 0.0 and below 1.0, got {fatigue}"
+        ));
+    }
+
+    if !stages.iter().any(|s| matches!(s, Stage::Fight { .. })) {
+        return Err(anyhow!("A campaign needs at least one fight"));
+    }
+
+    for stage in stages {
+        if let Stage::Fight { opponent, .. } = stage
+            && opponent.name == trainer.name
+        {
+            return Err(anyhow!(
+                "Trainer {} can't be their own opponent in a campaign",
+                trainer.name
+            ));
+        }
+    }
+
+    // resolved once along with their types, fatigue then changes the stats of these copies
+    let mut rested: Vec<Pokemon> = vec![];
+    for member in &trainer.team {
+        let pokemon = member.clone().resolve().await?;
+        rested.push(ResolvedPokemon::resolve(&pokemon).await?.into_concrete());
+    }
+
+    let mut team = rested.clone();
+    let mut hp = CarriedHp::new();
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let mut campaign = Campaign {
+        seed,
+        trainer: trainer.name.clone(),
+        survived: true,
+        fights_won: 0,
+        stages: vec![],
+    };
+
+    for stage in stages {
+        let (opponent, opponent_strategy) = match stage {
+            Stage::Heal => {
+                team = rested.clone();
+                hp.clear();
+                campaign.stages.push(StageLog::Heal);
+                continue;
+            }
+            Stage::Fight { opponent, strategy } => (opponent, strategy),
+        };
+
+        let challenger = Trainer {
+            name: trainer.name.clone(),
+            team: team
+                .iter()
+                .cloned()
+                .map(MaybePromise::from_concrete)
+                .collect(),
+            strategy: trainer.strategy.clone(),
+        };
+
+        let log = trainer_fight::process_fight_carrying_hp(
+            &challenger,
+            opponent,
+            strategy.clone(),
+            opponent_strategy.clone(),
+            config,
+            &mut rng,
+            &mut hp,
+        )
+        .await?;
+
+        let winner = match log.log.last() {
+            Some(FightEvent::Winner { trainer, .. }) => trainer.clone(),
+            _ => return Err(anyhow!("The trainer fight log has no winner")),
+        };
+
+        // fainted pokemon sit out the rest of the campaign, or until a heal
+        team.retain(|p| hp.get(&p.name) != Some(&0));
+
+        let hp_left = rested
+            .iter()
+            .map(|p| {
+                let left = hp.get(&p.name).copied().unwrap_or(p.stats.hp);
+                (p.name.clone(), left)
+            })
+            .collect();
+
+        let won = winner == trainer.name;
+
+        campaign.stages.push(StageLog::Fight {
+            opponent: opponent.name.clone(),
+            winner,
+            hp_left,
+            log,
+        });
+
+        if !won {
+            campaign.survived = false;
+            break;
+        }
+
+        campaign.fights_won += 1;
+
+        for pokemon in &mut team {
+            pokemon.stats.attack = tire(pokemon.stats.attack, fatigue);
+            pokemon.stats.defense = tire(pokemon.stats.defense, fatigue);
+            pokemon.stats.agility = tire(pokemon.stats.agility, fatigue);
+        }
+    }
+
+    Ok(campaign)
+}
@@ -0,0 +1,65 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    database::promise::MaybePromise,
+    pokemon::{Pokemon, ptype::PokemonType},
+};
+
+/// The relationships of a single type in a type chart override
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct TypeRelations {
+    /// The names of the types this type is strong against
+    pub strong_against: Vec<String>,
+    /// The names of the types this type is weak against
+    pub weak_against: Vec<String>,
+}
+
+/// An inline type chart, keyed by type name, which replaces the stored relationships
+/// of the listed types for a single fight without touching the database
+/// Types that aren't listed keep their stored relationships
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(transparent)]
+pub struct TypeChartOverride(pub HashMap<String, TypeRelations>);
+
+impl TypeChartOverride {
+    /// Returns the type with its relationships overridden, if the chart lists it
+    fn apply_to_type(&self, ptype: PokemonType) -> PokemonType {
+        let promises = |names: &[String]| {
+            names
+                .iter()
+                .map(|n| MaybePromise::from_ident_unchecked(n.clone()))
+                .collect()
+        };
+
+        match self.0.get(&ptype.name) {
+            Some(relations) => PokemonType::new(
+                ptype.name,
+                promises(&relations.strong_against),
+                promises(&relations.weak_against),
+            ),
+            None => ptype,
+        }
+    }
+
+    /// Resolves the types of a pokemon and applies the override to them,
+    /// the returned pokemon holds concrete types so the fight engine uses them as-is
+    pub async fn apply(&self, pokemon: &Pokemon) -> Result<Pokemon> {
+        let primary_type = pokemon.primary_type().clone().resolve().await?;
+
+        let secondary_type = match pokemon.secondary_type() {
+            Some(t) => Some(t.clone().resolve().await?),
+            None => None,
+        };
+
+        Ok(Pokemon::new(
+            pokemon.name.clone(),
+            MaybePromise::from_concrete(self.apply_to_type(primary_type)),
+            secondary_type.map(|t| MaybePromise::from_concrete(self.apply_to_type(t))),
+            pokemon.stats.clone(),
+        ))
+    }
+}
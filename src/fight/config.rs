@@ -0,0 +1,129 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+use super::weather::Weather;
+
+/// Tunable options of the fight engine,
+/// read from the `[fight]` section of the config file
+/// The defaults reproduce the documented fight algorithm
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct FightConfig {
+    /// Caps the damage of a single hit to this fraction of the defender's max HP
+    /// e.g. `0.5` means no hit can take more than half of the defender's max HP
+    /// `None` leaves damage uncapped
+    pub max_hit_fraction: Option<f32>,
+    /// In trainer fights, the pokemon chosen after an ally faints
+    /// enters with its attack raised by 10%
+    pub revenge_boost: bool,
+    /// How many fights of a batch (e.g. a round robin) are simulated at the same time
+    pub max_concurrent_fights: usize,
+    /// The chance that a hit with a type advantage (a type damage multiplier above `1`)
+    /// inflicts the attacker's status on a defender that has none
+    /// `0.0` turns status conditions off
+    pub status_chance: f32,
+    /// The chance that a hit is critical, dealing `1.5` times the damage
+    /// `0.0` turns critical hits off
+    pub crit_chance: f32,
+    /// The types whose attacks burn, by the attacker's primary type
+    pub burn_types: Vec<String>,
+    /// The types whose attacks poison, by the attacker's primary type
+    pub poison_types: Vec<String>,
+    /// The types whose attacks paralyze, by the attacker's primary type
+    pub paralysis_types: Vec<String>,
+    /// Scales the damage of every hit of every pokemon, whatever either pokemon's types
+    /// `None` leaves the damage as it is
+    pub damage_scale: Option<f32>,
+    /// How much of the damage scale a pokemon with a secondary type gets
+    pub dual_type_scale: DualTypeScale,
+    /// Who attacks first in each round of a pokemon fight
+    pub turn_order: TurnOrder,
+    /// Lets a pokemon or a trainer fight itself, which is usually a mistake,
+    /// so by default such fights are refused
+    pub allow_self_fights: bool,
+    /// The weather every pokemon fight takes place in, unless a request picks another one
+    pub weather: Weather,
+}
+
+/// Who attacks first in each round (one turn of each pokemon) of a pokemon fight
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum TurnOrder {
+    /// The pokemon with the highest agility attacks first, then the pokemon take turns
+    #[default]
+    Alternate,
+    /// Every round, the pokemon that attacks first is rolled with a chance proportional to its agility
+    /// (e.g. `70` agility against `30` attacks first 70% of the time),
+    /// so the faster pokemon usually, but not always, strikes first
+    AgilityWeighted,
+}
+
+/// How a secondary type affects the damage scale
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum DualTypeScale {
+    /// Dual-typed pokemon get the whole scale, like single-typed ones
+    #[default]
+    Full,
+    /// Dual-typed pokemon get half of the scale's change (e.g. `1.25` for a `1.5` scale)
+    Split,
+}
+
+impl Default for FightConfig {
+    fn default() -> Self {
+        Self {
+            max_hit_fraction: None,
+            revenge_boost: false,
+            max_concurrent_fights: 4,
+            status_chance: 0.0,
+            crit_chance: 0.0625,
+            burn_types: vec!["Fire".to_string()],
+            poison_types: vec!["Poison".to_string()],
+            paralysis_types: vec!["Electric".to_string()],
+            damage_scale: None,
+            dual_type_scale: DualTypeScale::default(),
+            turn_order: TurnOrder::default(),
+            allow_self_fights: false,
+            weather: Weather::default(),
+        }
+    }
+}
+
+impl FightConfig {
+    /// Loads the fight config from the config file, or defaults if not configured
+    pub fn load() -> Self {
+        crate::config::read_section_or_default("fight")
+    }
+
+    /// The damage scale of an attacker, `1.0` while `damage_scale` is not set
+    /// The same for every attacker with as many types, whichever types they are
+    pub fn damage_scale(&self, dual_typed: bool) -> f32 {
+        let Some(scale) = self.damage_scale else {
+            return 1.0;
+        };
+
+        match (dual_typed, self.dual_type_scale) {
+            (true, DualTypeScale::Split) => 1.0 + (scale - 1.0) / 2.0,
+            _ => scale,
+        }
+    }
+
+    /// Refuses a fight between two sides of the same name, unless `allow_self_fights` is set
+    /// `kind` names what is fighting, such as `Pokemon` or `Trainer`
+    pub fn check_self_fight(&self, kind: &str, contender: &str, challenger: &str) -> Result<()> {
+        if contender != challenger || self.allow_self_fights {
+            return Ok(());
+        }
+
+        Err(anyhow!(
+            "{kind} '{contender}' can't fight itself, pick two different names \
+            or set allow_self_fights = true in the [fight] config section"
+        ))
+    }
+
+    /// Applies the per-hit damage cap (if any) to a hit against a defender with `max_hp`
+    pub fn cap_damage(&self, damage: f32, max_hp: u32) -> f32 {
+        match self.max_hit_fraction {
+            Some(fraction) => damage.min(max_hp as f32 * fraction.max(0.0)),
+            None => damage,
+        }
+    }
+}
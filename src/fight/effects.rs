@@ -0,0 +1,60 @@
+use super::{FightEvent, config::FightConfig};
+use crate::pokemon::Pokemon;
+
+/// The attack multiplier `RevengeBoost` gives the next entrant after a faint
+pub const REVENGE_BOOST_MULTIPLIER: f32 = 1.1;
+
+/// An effect that triggers in a trainer fight when a pokemon faints
+pub trait FaintEffect: Send + Sync {
+    /// Called when `fainted` has fainted and `next` was chosen to replace it,
+    /// before `next` fights
+    /// The effect may modify `next`, and returns the events it wants to add to the log
+    fn on_faint(&self, fainted: &Pokemon, next: &mut Pokemon) -> Vec<FightEvent>;
+}
+
+/// The pokemon chosen after an ally faints enters with boosted attack
+pub struct RevengeBoost;
+
+impl FaintEffect for RevengeBoost {
+    fn on_faint(&self, fainted: &Pokemon, next: &mut Pokemon) -> Vec<FightEvent> {
+        next.stats.attack = (next.stats.attack as f32 * REVENGE_BOOST_MULTIPLIER).round() as u32;
+
+        vec![FightEvent::StatBoost {
+            pokemon: next.name.clone(),
+            cause: fainted.name.clone(),
+            attack: next.stats.attack,
+        }]
+    }
+}
+
+/// The effects active in a trainer fight
+#[derive(Default)]
+pub struct EffectRegistry {
+    faint_effects: Vec<Box<dyn FaintEffect>>,
+}
+
+impl EffectRegistry {
+    /// The effects enabled in the fight config
+    pub fn from_config(config: &FightConfig) -> Self {
+        let mut registry = Self::default();
+
+        if config.revenge_boost {
+            registry.register_faint_effect(RevengeBoost);
+        }
+
+        registry
+    }
+
+    /// Adds an effect that triggers when a pokemon faints
+    pub fn register_faint_effect(&mut self, effect: impl FaintEffect + 'static) {
+        self.faint_effects.push(Box::new(effect));
+    }
+
+    /// Runs every faint effect, in registration order, on the pokemon replacing `fainted`
+    pub fn on_faint(&self, fainted: &Pokemon, next: &mut Pokemon) -> Vec<FightEvent> {
+        self.faint_effects
+            .iter()
+            .flat_map(|effect| effect.on_faint(fainted, next))
+            .collect()
+    }
+}
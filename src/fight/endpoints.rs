@@ -1,77 +1,1002 @@
+use std::collections::BTreeMap;
+
+use rocket::{
+    State,
+    response::stream::{Event, EventStream},
+    serde::json::Json,
+};
+use serde::{Deserialize, Serialize};
+
 use crate::{
-    database::get::DbGet,
-    fight::{pokemon_fight, trainer_fight},
-    json::{JsonResult, JsonStatus},
-    pokemon::Pokemon,
-    trainer::Trainer,
+    config::ApiConfig,
+    database::{get::DbGet, suggest},
+    features::{Batch, Enabled, Gauntlet, LengthDistribution, Odds, RoundRobin, SolveTeam},
+    fight::{
+        FightLog, FightStrategy, campaign, chart::TypeChartOverride, config::FightConfig, gauntlet, odds, pokemon_fight,
+        pokemon_fight::{CombatantState, ResolvedPokemon}, record::FightRecord, replay::ReplayToken, round_robin, solver, trainer_fight, weather::Weather,
+    },
+    json::{ApiError, JsonResult, JsonStatus},
+    pokemon::{Pokemon, stats::MAX_LEVEL},
+    trainer::{self, Trainer},
 };
 
+/// A replay token along with the seed it encodes
+#[derive(Serialize)]
+struct ReplayTokenResponse {
+    token: String,
+    seed: u64,
+}
+
+/// The running damage totals of a seeded fight, along with the seed
+#[derive(Serialize)]
+struct DamageTimelineResponse {
+    seed: u64,
+    turns: usize,
+    damage: BTreeMap<String, Vec<u32>>,
+}
+
+/// The last event of a streamed fight, once a pokemon has won
+#[derive(Serialize)]
+struct FightStreamEnd {
+    contender_name: String,
+    challenger_name: String,
+    seed: u64,
+}
+
+/// A stored fight's identifier along with its log
+#[derive(Serialize)]
+struct SavedFightResponse {
+    fight_id: String,
+    log: FightLog,
+}
+
+/// The optional flags of a simulated pokemon fight, all off by default
+#[derive(FromForm)]
+pub struct FightFlags {
+    token: Option<bool>,
+    normalized: Option<bool>,
+    save: Option<bool>,
+    deterministic: Option<bool>,
+    track: Option<bool>,
+    level: Option<u32>,
+    weather: Option<String>,
+}
+
 /// Endpoint to simulate a fight between two Pokemon.
-#[get("/simulate_fight/<contender_name>/<challenger_name>")]
-pub async fn simulate_fight<'a>(contender_name: String, challenger_name: String) -> JsonResult<'a> {
+/// Passing a `seed` makes the fight reproducible, and `token=true` returns
+/// a replay token for the fight instead of its log
+/// `normalized=true` adds the defender's remaining share of its max HP to every hit
+/// `save=true` stores the fight, linked to both pokemon, and returns its id along with the log
+/// `deterministic=true` fixes the random multiplier to `1.0`, so the log only depends on stats and types
+/// `track=true` adds one to the fight count of both pokemon
+/// `level` makes both pokemon fight at that level instead of their own, without changing the stored pokemon
+/// `weather` makes the fight take place in that weather instead of the configured one (`Clear` by default)
+#[get(
+    "/simulate_fight/<contender_name>/<challenger_name>?<seed>&<flags..>"
+)]
+pub async fn simulate_fight<'a>(
+    contender_name: String,
+    challenger_name: String,
+    seed: Option<u64>,
+    flags: FightFlags,
+    config: &State<FightConfig>,
+) -> JsonResult<'a> {
     info!(
         "Request to /api/simulate_fight/{}/{}",
         contender_name, challenger_name
     );
 
-    let contender = match Pokemon::get_first(&contender_name).await {
-        Ok(pokemon) => pokemon,
-        Err(_) => return Err(JsonStatus::error("Contender not found")),
+    config
+        .check_self_fight("Pokemon", &contender_name, &challenger_name)
+        .map_err(ApiError::from_anyhow)?;
+
+    let contender = suggest::find::<Pokemon>("Contender", &contender_name).await?;
+
+    let challenger = suggest::find::<Pokemon>("Challenger", &challenger_name).await?;
+
+    let deterministic = flags.deterministic.unwrap_or(false);
+
+    if let Some(level) = flags.level
+        && !(1..=MAX_LEVEL).contains(&level)
+    {
+        return Err(ApiError::bad_request(format!(
+            "The level must be between 1 and {MAX_LEVEL}, got {level}"
+        )));
+    }
+
+    let weather = match flags.weather.as_deref() {
+        Some(weather) => Some(
+            weather
+                .parse::<Weather>()
+                .map_err(|_| ApiError::bad_request("Invalid weather"))?,
+        ),
+        None => None,
     };
 
-    let challenger = match Pokemon::get_first(&challenger_name).await {
-        Ok(pokemon) => pokemon,
-        Err(_) => return Err(JsonStatus::error("Challenger not found")),
+    if flags.token.unwrap_or(false) {
+        if deterministic {
+            return Err(ApiError::bad_request(
+                "A deterministic fight has no seed to make a replay token from",
+            ));
+        }
+
+        if flags.level.is_some() {
+            return Err(ApiError::bad_request(
+                "A replay token doesn't hold the level, it can't be combined with level",
+            ));
+        }
+
+        if weather.is_some() {
+            return Err(ApiError::bad_request(
+                "A replay token doesn't hold the weather, it can't be combined with weather",
+            ));
+        }
+
+        let seed = seed.unwrap_or_else(rand::random);
+        let token = ReplayToken::new(contender.name, challenger.name, seed);
+
+        return Ok(JsonStatus::data_owned(ReplayTokenResponse {
+            token: token.encode(),
+            seed,
+        }));
+    }
+
+    // copies fight at the overridden level, the stored pokemon keep theirs when their fights are counted
+    let at_level = |pokemon: &Pokemon| {
+        let mut pokemon = pokemon.clone();
+        if let Some(level) = flags.level {
+            pokemon.stats.level = level;
+        }
+        pokemon
     };
+    let (fighting_contender, fighting_challenger) = (at_level(&contender), at_level(&challenger));
+
+    let config = &FightConfig {
+        weather: weather.unwrap_or(config.weather),
+        ..config.inner().clone()
+    };
+
+    let log = match seed {
+        _ if deterministic => {
+            pokemon_fight::process_fight_deterministic(&fighting_contender, &fighting_challenger, config).await
+        }
+        Some(seed) => {
+            pokemon_fight::process_fight_seeded(&fighting_contender, &fighting_challenger, seed, config).await
+        }
+        None => pokemon_fight::process_fight(&fighting_contender, &fighting_challenger, config).await,
+    }
+    .map_err(ApiError::from_anyhow)?;
+
+    if flags.track.unwrap_or(false) {
+        contender
+            .count_fight()
+            .await
+            .map_err(ApiError::from_anyhow)?;
+
+        // a pokemon fighting itself took part in one fight
+        if challenger.name != contender.name {
+            challenger
+                .count_fight()
+                .await
+                .map_err(ApiError::from_anyhow)?;
+        }
+    }
+
+    let log = match flags.normalized {
+        Some(true) => log,
+        _ => log.without_hp_fractions(),
+    };
+
+    if flags.save.unwrap_or(false) {
+        let record = FightRecord::from_log(&log);
+        record.save().await.map_err(ApiError::from_anyhow)?;
+
+        return Ok(JsonStatus::data_owned(SavedFightResponse {
+            fight_id: record.id,
+            log,
+        }));
+    }
+
+    Ok(JsonStatus::data_owned(log))
+}
+
+/// Endpoint to list the stored fights, filtered by the winner's name and by the number of turns.
+/// Every filter is optional, `min_turns` and `max_turns` are inclusive
+#[get("/fights?<winner>&<min_turns>&<max_turns>")]
+pub async fn get_fights<'a>(
+    winner: Option<String>,
+    min_turns: Option<u32>,
+    max_turns: Option<u32>,
+    api_config: &State<ApiConfig>,
+) -> JsonResult<'a> {
+    info!("Request to /api/fights");
+
+    if let (Some(min), Some(max)) = (min_turns, max_turns)
+        && min > max
+    {
+        return Err(ApiError::bad_request(format!(
+            "min_turns ({min}) can't be greater than max_turns ({max})"
+        )));
+    }
+
+    let fights = FightRecord::filter(winner.as_deref(), min_turns, max_turns)
+        .await
+        .map_err(ApiError::from_anyhow)?;
+    api_config.check_list_len(fights.len())?;
+
+    Ok(JsonStatus::data_owned(fights))
+}
+
+/// Endpoint to explain the type damage multiplier of one pokemon attacking another,
+/// step by step as in the fight algorithm.
+#[get("/matchup/<attacker_name>/<defender_name>/type_breakdown")]
+pub async fn type_breakdown<'a>(attacker_name: String, defender_name: String) -> JsonResult<'a> {
+    info!(
+        "Request to /api/matchup/{}/{}/type_breakdown",
+        attacker_name, defender_name
+    );
+
+    let attacker = suggest::find::<Pokemon>("Attacker", &attacker_name).await?;
+
+    let defender = suggest::find::<Pokemon>("Defender", &defender_name).await?;
+
+    let attacker = ResolvedPokemon::resolve(&attacker).await;
+    let attacker = attacker.map_err(ApiError::from_anyhow)?;
+    let defender = ResolvedPokemon::resolve(&defender).await;
+    let defender = defender.map_err(ApiError::from_anyhow)?;
+
+    Ok(JsonStatus::data_owned(pokemon_fight::type_damage_multiplier(
+        &attacker.primary_type,
+        attacker.secondary_type.as_ref(),
+        &defender.primary_type,
+        defender.secondary_type.as_ref(),
+    )))
+}
+
+/// The body of a fight simulated with a custom type chart
+#[derive(Deserialize)]
+pub struct ChartFightRequest {
+    /// The name of the contender pokemon
+    pub contender: String,
+    /// The name of the challenger pokemon
+    pub challenger: String,
+    /// The type chart override, see `TypeChartOverride`
+    #[serde(default)]
+    pub chart: TypeChartOverride,
+}
+
+/// Endpoint to simulate a fight between two Pokemon using an inline type chart override,
+/// for what-if balance testing without changing the stored chart.
+#[post("/simulate_fight_with_chart", data = "<request>")]
+pub async fn simulate_fight_with_chart<'a>(
+    request: Json<ChartFightRequest>,
+    config: &State<FightConfig>,
+) -> JsonResult<'a> {
+    info!(
+        "Request to /api/simulate_fight_with_chart ({} vs {})",
+        request.contender, request.challenger
+    );
+
+    let contender = suggest::find::<Pokemon>("Contender", &request.contender).await?;
+
+    let challenger = suggest::find::<Pokemon>("Challenger", &request.challenger).await?;
+
+    let contender = request
+        .chart
+        .apply(&contender)
+        .await
+        .map_err(ApiError::from_anyhow)?;
+
+    let challenger = request
+        .chart
+        .apply(&challenger)
+        .await
+        .map_err(ApiError::from_anyhow)?;
+
+    let log = pokemon_fight::process_fight(&contender, &challenger, config)
+        .await
+        .map_err(ApiError::from_anyhow)?;
+
+    Ok(JsonStatus::data_owned(log))
+}
+
+/// Endpoint to re-run a fight from a replay token, reproducing its exact log.
+#[get("/replay/<token>")]
+pub async fn replay_fight<'a>(token: String, config: &State<FightConfig>) -> JsonResult<'a> {
+    info!("Request to /api/replay/{}", token);
+
+    let token = ReplayToken::decode(&token).map_err(ApiError::bad_request)?;
+
+    let contender = suggest::find::<Pokemon>("Contender", &token.contender).await?;
+
+    let challenger = suggest::find::<Pokemon>("Challenger", &token.challenger).await?;
 
-    let log = pokemon_fight::process_fight(&contender, &challenger)
+    let log = pokemon_fight::process_fight_seeded(&contender, &challenger, token.seed, config)
         .await
-        .map_err(JsonStatus::from_anyhow)?;
+        .map_err(ApiError::from_anyhow)?;
 
     Ok(JsonStatus::data_owned(log))
 }
 
+/// Endpoint to simulate a fight between two Pokemon many times and report how many turns the fights took.
+/// `trials` defaults to 200 and is capped, passing a `seed` makes the distribution reproducible
+#[get("/simulate_fight/<contender_name>/<challenger_name>/length_distribution?<trials>&<seed>")]
+pub async fn fight_length_distribution<'a>(
+    _enabled: Enabled<LengthDistribution>,
+    contender_name: String,
+    challenger_name: String,
+    trials: Option<u32>,
+    seed: Option<u64>,
+    config: &State<FightConfig>,
+) -> JsonResult<'a> {
+    info!(
+        "Request to /api/simulate_fight/{}/{}/length_distribution",
+        contender_name, challenger_name
+    );
+
+    config
+        .check_self_fight("Pokemon", &contender_name, &challenger_name)
+        .map_err(ApiError::from_anyhow)?;
+
+    let contender = suggest::find::<Pokemon>("Contender", &contender_name).await?;
+
+    let challenger = suggest::find::<Pokemon>("Challenger", &challenger_name).await?;
+
+    let distribution = odds::fight_length_distribution(
+        &contender,
+        &challenger,
+        trials.unwrap_or(odds::DEFAULT_LENGTH_TRIALS),
+        seed.unwrap_or_else(rand::random),
+        config,
+    )
+    .await
+    .map_err(ApiError::from_anyhow)?;
+
+    Ok(JsonStatus::data_owned(distribution))
+}
+
+/// Endpoint to simulate a fight between two Pokemon, sending every event of the fight
+/// as a server-sent event while the fight goes on, and an `end` event once a pokemon has won.
+/// Without a `seed` a random one is used, it's sent along with the `end` event
+#[get("/simulate_fight_stream/<contender_name>/<challenger_name>?<seed>")]
+pub async fn simulate_fight_stream(
+    contender_name: String,
+    challenger_name: String,
+    seed: Option<u64>,
+    config: &State<FightConfig>,
+) -> Result<EventStream![], ApiError> {
+    info!(
+        "Request to /api/simulate_fight_stream/{}/{}",
+        contender_name, challenger_name
+    );
+
+    config
+        .check_self_fight("Pokemon", &contender_name, &challenger_name)
+        .map_err(ApiError::from_anyhow)?;
+
+    let contender = suggest::find::<Pokemon>("Contender", &contender_name).await?;
+
+    let challenger = suggest::find::<Pokemon>("Challenger", &challenger_name).await?;
+
+    let seed = seed.unwrap_or_else(rand::random);
+
+    let mut events = pokemon_fight::stream_fight(
+        &CombatantState::full(contender.clone()),
+        &CombatantState::full(challenger.clone()),
+        seed,
+        config,
+    )
+    .await
+    .map_err(ApiError::from_anyhow)?;
+
+    let end = FightStreamEnd {
+        contender_name: contender.name,
+        challenger_name: challenger.name,
+        seed,
+    };
+
+    Ok(EventStream! {
+        while let Some(event) = events.recv().await {
+            yield Event::json(&event.without_hp_fraction());
+        }
+
+        yield Event::json(&end).event("end");
+    })
+}
+
+/// Endpoint to simulate a fight between two Pokemon many times and report who won how often, without the logs.
+/// `runs` defaults to 1000 and is capped, passing a `seed` makes the summary reproducible
+#[get("/simulate_fight/<contender_name>/<challenger_name>/batch?<runs>&<seed>")]
+pub async fn fight_batch<'a>(
+    _enabled: Enabled<Batch>,
+    contender_name: String,
+    challenger_name: String,
+    runs: Option<u32>,
+    seed: Option<u64>,
+    config: &State<FightConfig>,
+) -> JsonResult<'a> {
+    info!(
+        "Request to /api/simulate_fight/{}/{}/batch",
+        contender_name, challenger_name
+    );
+
+    config
+        .check_self_fight("Pokemon", &contender_name, &challenger_name)
+        .map_err(ApiError::from_anyhow)?;
+
+    let contender = suggest::find::<Pokemon>("Contender", &contender_name).await?;
+
+    let challenger = suggest::find::<Pokemon>("Challenger", &challenger_name).await?;
+
+    let summary = odds::fight_batch(
+        &contender,
+        &challenger,
+        runs.unwrap_or(odds::DEFAULT_BATCH_RUNS),
+        seed.unwrap_or_else(rand::random),
+        config,
+    )
+    .await
+    .map_err(ApiError::from_anyhow)?;
+
+    Ok(JsonStatus::data_owned(summary))
+}
+
+/// Endpoint to chart a seeded fight between two Pokemon: the running total of the damage each has dealt, after every turn.
+/// Without a `seed` a random one is used, it's returned along with the series
+#[get("/simulate_fight/<contender_name>/<challenger_name>/timeline?<seed>")]
+pub async fn fight_timeline<'a>(
+    contender_name: String,
+    challenger_name: String,
+    seed: Option<u64>,
+    config: &State<FightConfig>,
+) -> JsonResult<'a> {
+    info!(
+        "Request to /api/simulate_fight/{}/{}/timeline",
+        contender_name, challenger_name
+    );
+
+    config
+        .check_self_fight("Pokemon", &contender_name, &challenger_name)
+        .map_err(ApiError::from_anyhow)?;
+
+    let contender = suggest::find::<Pokemon>("Contender", &contender_name).await?;
+
+    let challenger = suggest::find::<Pokemon>("Challenger", &challenger_name).await?;
+
+    let seed = seed.unwrap_or_else(rand::random);
+
+    let log = pokemon_fight::process_fight_seeded(&contender, &challenger, seed, config)
+        .await
+        .map_err(ApiError::from_anyhow)?;
+
+    Ok(JsonStatus::data_owned(DamageTimelineResponse {
+        seed,
+        turns: log.turns(),
+        damage: log.damage_timeline(),
+    }))
+}
+
+/// Looks up two trainers, checks both teams and simulates a fight between them with the given strategies
+/// `normalized` keeps the defender's remaining share of its max HP on every hit
+async fn trainer_fight_log<'a>(
+    challenger_name: &str,
+    challenger_strategy: FightStrategy,
+    contender_name: &str,
+    contender_strategy: FightStrategy,
+    normalized: bool,
+    config: &FightConfig,
+) -> JsonResult<'a> {
+    config
+        .check_self_fight("Trainer", contender_name, challenger_name)
+        .map_err(ApiError::from_anyhow)?;
+
+    let challenger = suggest::find::<Trainer>("Challenger", challenger_name).await?;
+
+    let contender = suggest::find::<Trainer>("Contender", contender_name).await?;
+
+    trainer_fight::check_fight_ready("Challenger", &challenger)
+        .await
+        .map_err(ApiError::from_anyhow)?;
+    trainer_fight::check_fight_ready("Contender", &contender)
+        .await
+        .map_err(ApiError::from_anyhow)?;
+
+    let log = trainer_fight::process_fight(
+        &challenger,
+        &contender,
+        challenger_strategy,
+        contender_strategy,
+        config,
+    )
+    .await
+    .map_err(ApiError::from_anyhow)?;
+
+    if normalized {
+        return Ok(JsonStatus::data_owned(log));
+    }
+
+    Ok(JsonStatus::data_owned(log.without_hp_fractions()))
+}
+
 /// Endpoint to simulate a fight between two trainers.
+/// `normalized=true` adds the defender's remaining share of its max HP to every hit
+/// Both teams are checked before the fight starts, an empty team or a pokemon that fails to resolve is reported by name
 #[get(
-    "/simulate_trainer_fight/<challenger_name>/<challenger_strategy>/<contender_name>/<contender_strategy>"
+    "/simulate_trainer_fight/<challenger_name>/<challenger_strategy>/<contender_name>/<contender_strategy>?<normalized>"
 )]
 pub async fn simulate_trainer_fight<'a>(
     challenger_name: String,
     challenger_strategy: String,
     contender_name: String,
     contender_strategy: String,
+    normalized: Option<bool>,
+    config: &State<FightConfig>,
 ) -> JsonResult<'a> {
     info!(
         "Request to /api/simulate_trainer_fight/{}/{}",
         challenger_name, contender_name
     );
 
-    let challenger = match Trainer::get_first(&challenger_name).await {
-        Ok(trainer) => trainer,
-        Err(_) => return Err(JsonStatus::error("Challenger not found")),
-    };
+    let challenger_strategy = challenger_strategy
+        .parse()
+        .map_err(|_| ApiError::bad_request("Invalid strategy"))?;
 
-    let contender = match Trainer::get_first(&contender_name).await {
-        Ok(trainer) => trainer,
-        Err(_) => return Err(JsonStatus::error("Contender not found")),
-    };
+    let contender_strategy = contender_strategy
+        .parse()
+        .map_err(|_| ApiError::bad_request("Invalid strategy"))?;
+
+    trainer_fight_log(
+        &challenger_name,
+        challenger_strategy,
+        &contender_name,
+        contender_strategy,
+        normalized.unwrap_or(false),
+        config,
+    )
+    .await
+}
+
+/// The body of a trainer fight request
+#[derive(Deserialize)]
+pub struct TrainerFightRequest {
+    /// The name of the trainer who picks first
+    pub challenger: String,
+    /// The strategy the challenger fights with
+    pub challenger_strategy: FightStrategy,
+    /// The name of the other trainer
+    pub contender: String,
+    /// The strategy the contender fights with
+    pub contender_strategy: FightStrategy,
+}
+
+/// Endpoint to simulate a fight between two trainers given in the body,
+/// so trainer names never have to be percent-encoded into the path
+#[post("/simulate_trainer_fight?<normalized>", data = "<request>")]
+pub async fn simulate_trainer_fight_body<'a>(
+    request: Json<TrainerFightRequest>,
+    normalized: Option<bool>,
+    config: &State<FightConfig>,
+) -> JsonResult<'a> {
+    info!(
+        "Request to /api/simulate_trainer_fight for {} and {}",
+        request.challenger, request.contender
+    );
+
+    let request = request.into_inner();
+
+    trainer_fight_log(
+        &request.challenger,
+        request.challenger_strategy,
+        &request.contender,
+        request.contender_strategy,
+        normalized.unwrap_or(false),
+        config,
+    )
+    .await
+}
+
+/// Endpoint to simulate a fight between two trainers, each fighting with their own stored strategy.
+#[get("/simulate_trainer_fight_auto/<challenger_name>/<contender_name>?<normalized>")]
+pub async fn simulate_trainer_fight_auto<'a>(
+    challenger_name: String,
+    contender_name: String,
+    normalized: Option<bool>,
+    config: &State<FightConfig>,
+) -> JsonResult<'a> {
+    info!(
+        "Request to /api/simulate_trainer_fight_auto/{}/{}",
+        challenger_name, contender_name
+    );
+
+    config
+        .check_self_fight("Trainer", &contender_name, &challenger_name)
+        .map_err(ApiError::from_anyhow)?;
+
+    let challenger = suggest::find::<Trainer>("Challenger", &challenger_name).await?;
+
+    let contender = suggest::find::<Trainer>("Contender", &contender_name).await?;
+
+    trainer_fight::check_fight_ready("Challenger", &challenger)
+        .await
+        .map_err(ApiError::from_anyhow)?;
+    trainer_fight::check_fight_ready("Contender", &contender)
+        .await
+        .map_err(ApiError::from_anyhow)?;
+
+    let log = trainer_fight::process_fight(
+        &challenger,
+        &contender,
+        challenger.strategy(),
+        contender.strategy(),
+        config,
+    )
+    .await
+    .map_err(ApiError::from_anyhow)?;
+
+    if normalized.unwrap_or(false) {
+        return Ok(JsonStatus::data_owned(log));
+    }
+
+    Ok(JsonStatus::data_owned(log.without_hp_fractions()))
+}
+
+/// The number of trials and the first seed of a repeated simulation, both optional
+#[derive(FromForm)]
+pub struct TrialParams {
+    trials: Option<u32>,
+    seed: Option<u64>,
+}
+
+/// Endpoint to simulate a fight between two trainers many times and report each trainer's odds.
+/// `trials` defaults to 50 and is capped, passing a `seed` makes the odds reproducible
+#[get(
+    "/simulate_trainer_fight/<challenger_name>/<challenger_strategy>/<contender_name>/<contender_strategy>/odds?<params..>"
+)]
+pub async fn simulate_trainer_fight_odds<'a>(
+    _enabled: Enabled<Odds>,
+    challenger_name: String,
+    challenger_strategy: String,
+    contender_name: String,
+    contender_strategy: String,
+    params: TrialParams,
+    config: &State<FightConfig>,
+) -> JsonResult<'a> {
+    info!(
+        "Request to /api/simulate_trainer_fight/{}/{}/odds",
+        challenger_name, contender_name
+    );
+
+    config
+        .check_self_fight("Trainer", &contender_name, &challenger_name)
+        .map_err(ApiError::from_anyhow)?;
+
+    let challenger = suggest::find::<Trainer>("Challenger", &challenger_name).await?;
+
+    let contender = suggest::find::<Trainer>("Contender", &contender_name).await?;
 
     let challenger_strategy = challenger_strategy
         .parse()
-        .map_err(|_| JsonStatus::error("Invalid strategy"))?;
+        .map_err(|_| ApiError::bad_request("Invalid strategy"))?;
 
     let contender_strategy = contender_strategy
         .parse()
-        .map_err(|_| JsonStatus::error("Invalid strategy"))?;
+        .map_err(|_| ApiError::bad_request("Invalid strategy"))?;
 
-    let log = trainer_fight::process_fight(
+    let odds = odds::trainer_fight_odds(
         &challenger,
         &contender,
         challenger_strategy,
         contender_strategy,
+        params.trials.unwrap_or(odds::DEFAULT_TRIALS),
+        params.seed.unwrap_or_else(rand::random),
+        config,
     )
     .await
-    .map_err(JsonStatus::from_anyhow)?;
+    .map_err(ApiError::from_anyhow)?;
 
-    Ok(JsonStatus::data_owned(log))
+    Ok(JsonStatus::data_owned(odds))
+}
+
+/// Endpoint to measure how much picking second is worth in a fight between two trainers.
+/// Runs the fight many times in both orderings, both trainers using `strategy` (`StrongestType` by default).
+/// `trials` defaults to 50 per ordering and is capped, passing a `seed` makes the result reproducible
+#[get("/trainers/<a>/<b>/fairness?<strategy>&<params..>")]
+pub async fn trainer_fight_fairness<'a>(
+    _enabled: Enabled<Odds>,
+    a: String,
+    b: String,
+    strategy: Option<String>,
+    params: TrialParams,
+    config: &State<FightConfig>,
+) -> JsonResult<'a> {
+    info!("Request to /api/trainers/{}/{}/fairness", a, b);
+
+    let mut trainers = vec![];
+
+    for name in [&a, &b] {
+        trainers.push(suggest::find::<Trainer>("Trainer", name).await?);
+    }
+
+    let strategy = match strategy {
+        Some(strategy) => strategy
+            .parse()
+            .map_err(|_| ApiError::bad_request("Invalid strategy"))?,
+        None => FightStrategy::StrongestType,
+    };
+
+    let fairness = odds::order_fairness(
+        &trainers[0],
+        &trainers[1],
+        strategy,
+        params.trials.unwrap_or(odds::DEFAULT_TRIALS),
+        params.seed.unwrap_or_else(rand::random),
+        config,
+    )
+    .await
+    .map_err(ApiError::from_anyhow)?;
+
+    Ok(JsonStatus::data_owned(fairness))
+}
+
+/// Endpoint to run a lone pokemon through a trainer's team many times and report how far it gets.
+/// `trials` defaults to 100 and is capped, passing a `seed` makes the odds reproducible
+#[get("/gauntlet/<pokemon_name>/<trainer_name>/odds?<trials>&<seed>")]
+pub async fn gauntlet_odds<'a>(
+    _enabled: Enabled<Gauntlet>,
+    pokemon_name: String,
+    trainer_name: String,
+    trials: Option<u32>,
+    seed: Option<u64>,
+    config: &State<FightConfig>,
+) -> JsonResult<'a> {
+    info!("Request to /api/gauntlet/{}/{}/odds", pokemon_name, trainer_name);
+
+    let pokemon = suggest::find::<Pokemon>("Pokemon", &pokemon_name).await?;
+
+    let trainer = suggest::find::<Trainer>("Trainer", &trainer_name).await?;
+
+    let odds = gauntlet::gauntlet_odds(
+        &pokemon,
+        &trainer,
+        trials.unwrap_or(gauntlet::DEFAULT_TRIALS),
+        seed.unwrap_or_else(rand::random),
+        config,
+    )
+    .await
+    .map_err(ApiError::from_anyhow)?;
+
+    Ok(JsonStatus::data_owned(odds))
+}
+
+/// The body of a team solving request
+#[derive(Deserialize)]
+pub struct SolveTeamRequest {
+    /// The strategy the solved team fights with
+    pub strategy: FightStrategy,
+    /// The strategy the target trainer is expected to fight with
+    pub target_strategy: FightStrategy,
+    /// The largest team to try, capped to the trainer team limit
+    pub max_team_size: Option<usize>,
+}
+
+/// Endpoint to find the smallest team of existing pokemon predicted to beat a trainer.
+#[post("/solve_team/<target_trainer>", data = "<request>")]
+pub async fn solve_team<'a>(
+    _enabled: Enabled<SolveTeam>,
+    target_trainer: String,
+    request: Json<SolveTeamRequest>,
+    config: &State<FightConfig>,
+) -> JsonResult<'a> {
+    info!("Request to /api/solve_team/{}", target_trainer);
+
+    let target = suggest::find::<Trainer>("Trainer", &target_trainer).await?;
+
+    let max_team_size = request
+        .max_team_size
+        .unwrap_or(trainer::MAX_TEAM_SIZE)
+        .min(trainer::MAX_TEAM_SIZE);
+
+    let pokemons = Pokemon::get_all().await.map_err(ApiError::from_anyhow)?;
+
+    // resolve every pokemon's types once, instead of once per predicted fight
+    let resolved = futures::future::try_join_all(pokemons.iter().map(ResolvedPokemon::resolve))
+        .await
+        .map_err(ApiError::from_anyhow)?;
+
+    let available: Vec<Pokemon> = resolved
+        .into_iter()
+        .map(ResolvedPokemon::into_concrete)
+        .collect();
+
+    let solved = solver::solve_team(
+        &target,
+        &available,
+        request.strategy.clone(),
+        request.target_strategy.clone(),
+        max_team_size,
+        config,
+    )
+    .await
+    .map_err(ApiError::from_anyhow)?;
+
+    Ok(JsonStatus::data_owned(solved))
+}
+
+/// A trainer entered into a round robin
+#[derive(Deserialize)]
+pub struct RoundRobinEntry {
+    /// The name of the trainer
+    pub trainer: String,
+    /// The strategy the trainer fights with
+    pub strategy: FightStrategy,
+}
+
+/// The body of a round robin request
+#[derive(Deserialize)]
+pub struct RoundRobinRequest {
+    /// The trainers playing the round robin
+    pub trainers: Vec<RoundRobinEntry>,
+    /// Whether every pairing is played twice, with each trainer picking first once
+    #[serde(default)]
+    pub home_and_away: bool,
+}
+
+/// Looks up the trainers entered into a round robin, along with their strategies
+async fn round_robin_entries<'n>(
+    entries: impl Iterator<Item = (&'n str, FightStrategy)>,
+) -> Result<Vec<(Trainer, FightStrategy)>, ApiError> {
+    let mut found = vec![];
+
+    for (name, strategy) in entries {
+        let trainer = suggest::find::<Trainer>("Trainer", name).await?;
+
+        found.push((trainer, strategy));
+    }
+
+    Ok(found)
+}
+
+/// Endpoint to play every pairing of a list of trainers and return the standings.
+#[post("/round_robin", data = "<request>")]
+pub async fn play_round_robin<'a>(
+    _enabled: Enabled<RoundRobin>,
+    request: Json<RoundRobinRequest>,
+    config: &State<FightConfig>,
+) -> JsonResult<'a> {
+    info!("Request to /api/round_robin");
+
+    let entries = round_robin_entries(
+        request
+            .trainers
+            .iter()
+            .map(|entry| (entry.trainer.as_str(), entry.strategy.clone())),
+    )
+    .await?;
+
+    let league = round_robin::round_robin(&entries, request.home_and_away, config)
+        .await
+        .map_err(ApiError::from_anyhow)?;
+
+    Ok(JsonStatus::data_owned(league))
+}
+
+/// The body of a tournament request
+#[derive(Deserialize)]
+pub struct TournamentRequest {
+    /// The names of the trainers playing the tournament
+    pub trainers: Vec<String>,
+    /// The strategy every trainer fights with
+    pub strategy: FightStrategy,
+}
+
+/// Endpoint to play every pairing of a list of trainers, all fighting with the same strategy,
+/// and return only the standings.
+#[post("/tournament", data = "<request>")]
+pub async fn play_tournament<'a>(
+    _enabled: Enabled<RoundRobin>,
+    request: Json<TournamentRequest>,
+    config: &State<FightConfig>,
+) -> JsonResult<'a> {
+    info!("Request to /api/tournament");
+
+    let entries = round_robin_entries(
+        request
+            .trainers
+            .iter()
+            .map(|name| (name.as_str(), request.strategy.clone())),
+    )
+    .await?;
+
+    let league = round_robin::round_robin(&entries, false, config)
+        .await
+        .map_err(ApiError::from_anyhow)?;
+
+    Ok(JsonStatus::data_owned(league.table))
+}
+
+/// An opposing trainer in a campaign
+#[derive(Deserialize)]
+pub struct CampaignOpponent {
+    /// The name of the trainer
+    pub trainer: String,
+    /// The strategy the trainer fights with
+    pub strategy: FightStrategy,
+}
+
+/// A step of a campaign request, either `{"fight": {...}}` or `"heal"`
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CampaignStep {
+    /// A trainer fight against the opponent
+    Fight(CampaignOpponent),
+    /// Restores the whole team to full HP and removes all fatigue
+    Heal,
+}
+
+/// The body of a campaign request
+#[derive(Deserialize)]
+pub struct CampaignRequest {
+    /// The name of the trainer taking on the campaign
+    pub trainer: String,
+    /// The strategy the trainer fights with
+    pub strategy: FightStrategy,
+    /// The steps of the campaign, in order
+    pub steps: Vec<CampaignStep>,
+    /// The share of attack, defense and agility the trainer's pokemon lose after every won fight, none by default
+    #[serde(default)]
+    pub fatigue: f32,
+    /// Makes the campaign reproducible
+    pub seed: Option<u64>,
+}
+
+/// Endpoint to take a trainer through a sequence of fights, carrying their pokemon's HP from one to the next.
+/// Returns every step's log and whether the trainer survived the campaign
+#[post("/campaign", data = "<request>")]
+pub async fn play_campaign<'a>(
+    request: Json<CampaignRequest>,
+    config: &State<FightConfig>,
+) -> JsonResult<'a> {
+    info!("Request to /api/campaign ({})", request.trainer);
+
+    let trainer = suggest::find::<Trainer>("Trainer", &request.trainer).await?;
+
+    trainer_fight::check_fight_ready("Trainer", &trainer)
+        .await
+        .map_err(ApiError::from_anyhow)?;
+
+    let mut stages = vec![];
+
+    for step in &request.steps {
+        let opponent = match step {
+            CampaignStep::Heal => {
+                stages.push(campaign::Stage::Heal);
+                continue;
+            }
+            CampaignStep::Fight(opponent) => opponent,
+        };
+
+        let trainer = suggest::find::<Trainer>("Trainer", &opponent.trainer).await?;
+
+        trainer_fight::check_fight_ready("Opponent", &trainer)
+            .await
+            .map_err(ApiError::from_anyhow)?;
+
+        stages.push(campaign::Stage::Fight {
+            opponent: trainer,
+            strategy: opponent.strategy.clone(),
+        });
+    }
+
+    let campaign = campaign::run_campaign(
+        &trainer,
+        request.strategy.clone(),
+        &stages,
+        request.fatigue,
+        request.seed.unwrap_or_else(rand::random),
+        config,
+    )
+    .await
+    .map_err(ApiError::from_anyhow)?;
+
+    Ok(JsonStatus::data_owned(campaign))
 }
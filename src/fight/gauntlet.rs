@@ -0,0 +1,139 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Result, anyhow};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use serde::Serialize;
+
+use super::{
+    FightEvent,
+    config::FightConfig,
+    odds::MAX_TRIALS,
+    pokemon_fight::{self, ResolvedPokemon},
+};
+use crate::{pokemon::Pokemon, trainer::Trainer};
+
+/// The number of trials used when the request doesn't specify one
+pub const DEFAULT_TRIALS: u32 = 100;
+
+/// Runs a lone pokemon through a trainer's whole team, in team order
+/// The lone pokemon carries its HP from one fight to the next, while every opponent enters with full HP
+/// Returns how many opponents the lone pokemon defeated before fainting,
+/// which is the size of the team if it cleared the gauntlet
+pub fn clear_depth<R: Rng>(
+    lone: &ResolvedPokemon,
+    team: &[ResolvedPokemon],
+    config: &FightConfig,
+    rng: &mut R,
+) -> Result<usize> {
+    let mut hp = lone.pokemon.stats.hp;
+
+    for (defeated, opponent) in team.iter().enumerate() {
+        if hp == 0 {
+            return Ok(defeated);
+        }
+
+        let log = pokemon_fight::process_fight_resolved(
+            lone,
+            opponent,
+            hp,
+            opponent.pokemon.stats.hp,
+            config,
+            rng,
+        );
+
+        hp = match log.log.last() {
+            Some(FightEvent::PokemonWinner { pokemon, hp_left })
+                if *pokemon == lone.pokemon.name =>
+            {
+                *hp_left
+            }
+            Some(FightEvent::PokemonWinner { .. }) => return Ok(defeated),
+            _ => return Err(anyhow!("The fight log has no winner")),
+        };
+    }
+
+    Ok(team.len())
+}
+
+/// How deep into a trainer's team a lone pokemon got over many trials
+#[derive(Serialize, Clone, Debug)]
+pub struct GauntletOdds {
+    /// The number of gauntlets simulated
+    pub trials: u32,
+    /// The seed of the first trial, trial `i` is seeded with `seed + i`
+    pub seed: u64,
+    /// The name of the lone pokemon
+    pub pokemon: String,
+    /// The name of the trainer whose team makes up the gauntlet
+    pub trainer: String,
+    /// The number of opponents in the gauntlet
+    pub team_size: usize,
+    /// The average number of opponents defeated
+    pub mean: f32,
+    /// The median number of opponents defeated
+    pub median: f32,
+    /// The most opponents defeated in a single trial
+    pub max: usize,
+    /// How many trials ended with each number of opponents defeated,
+    /// `team_size` meaning the whole team was cleared
+    pub clear_depth: BTreeMap<usize, u32>,
+}
+
+/// Runs the gauntlet `trials` times, each with its own seed, and tallies how far the pokemon got
+/// The same `seed` gives the same odds
+pub async fn gauntlet_odds(
+    pokemon: &Pokemon,
+    trainer: &Trainer,
+    trials: u32,
+    seed: u64,
+    config: &FightConfig,
+) -> Result<GauntletOdds> {
+    if trials == 0 || trials > MAX_TRIALS {
+        return Err(anyhow!(
+            "The number of trials must be between 1 and {MAX_TRIALS}, got {trials}"
+        ));
+    }
+
+    if trainer.team.is_empty() {
+        return Err(anyhow!("Trainer {} has no pokemon", trainer.name));
+    }
+
+    // resolved once, so the trials never query the database
+    let lone = ResolvedPokemon::resolve(pokemon).await?;
+    let mut team = vec![];
+    for member in &trainer.team {
+        team.push(ResolvedPokemon::resolve(&member.clone().resolve().await?).await?);
+    }
+
+    let mut depths = vec![];
+    for trial in 0..trials {
+        let mut rng = StdRng::seed_from_u64(seed.wrapping_add(trial as u64));
+        depths.push(clear_depth(&lone, &team, config, &mut rng)?);
+    }
+
+    depths.sort_unstable();
+
+    let mut histogram = BTreeMap::new();
+    for depth in &depths {
+        *histogram.entry(*depth).or_default() += 1;
+    }
+
+    let middle = depths.len() / 2;
+    let median = if depths.len() % 2 == 0 {
+        (depths[middle - 1] + depths[middle]) as f32 / 2.0
+    } else {
+        depths[middle] as f32
+    };
+
+    Ok(GauntletOdds {
+        trials,
+        seed,
+        pokemon: pokemon.name.clone(),
+        trainer: trainer.name.clone(),
+        team_size: team.len(),
+        mean: depths.iter().sum::<usize>() as f32 / trials as f32,
+        median,
+        max: depths.last().copied().unwrap_or(0),
+        clear_depth: histogram,
+    })
+}
@@ -1,19 +1,67 @@
 use core::str;
-use std::str::FromStr;
+use std::{collections::BTreeMap, str::FromStr};
 
+use rand::{Rng, SeedableRng, rngs::StdRng};
 use serde::{Deserialize, Serialize};
 
-use crate::pokemon::Pokemon;
+use crate::pokemon::{Pokemon, ptype::PokemonType};
+use status::StatusCondition;
+use weather::Weather;
+
+/// Bounded concurrent simulation of many fights
+pub mod batch;
+
+/// A trainer's run through a sequence of fights, carrying HP and fatigue between them
+pub mod campaign;
+
+/// Inline type chart overrides for what-if fights
+pub mod chart;
+
+/// Tunable options of the fight engine
+pub mod config;
+
+/// Pluggable effects that trigger during trainer fights
+pub mod effects;
 
 /// HTTP Enpoints for simulating pokemon and trainer fights
 pub mod endpoints;
 
+/// A lone pokemon fighting its way through a trainer's whole team
+pub mod gauntlet;
+
+/// Win rates of trainer fights over many seeded trials
+pub mod odds;
+
 /// A module for simulating a fight between two pokemon
 pub mod pokemon_fight;
 
+/// Deterministic predictions of fight outcomes
+pub mod predictor;
+
+/// Pokemon fights stored in the database with links to their participants
+pub mod record;
+
+/// A module for encoding seeded fights as shareable replay tokens
+pub mod replay;
+
+/// A module for playing round robin leagues between trainers
+pub mod round_robin;
+
+/// How the win rate of a matchup changes with each stat
+pub mod sensitivity;
+
+/// A module for assembling teams that are predicted to beat a trainer
+pub mod solver;
+
+/// Status conditions that chip HP at the end of every turn
+pub mod status;
+
 /// A module for simulating a fight between trainers
 pub mod trainer_fight;
 
+/// Weather that boosts some types and chips HP every turn
+pub mod weather;
+
 /// Represents a fight event that can occur during a Pokemon battle
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(tag = "event_name", content = "event_data")]
@@ -35,14 +83,92 @@ pub enum FightEvent {
         damage: u32,
         /// The amount of HP left on the defending Pokemon
         hp_left: u32,
-        /// Effectiveness
-        effectiveness: Effectiveness
+        /// The current HP of the attacking Pokemon,
+        /// together with `hp_left` this gives both health bars after the hit
+        attacker_hp: u32,
+        /// How effective the attack was by its type damage multiplier
+        /// (step 4 of the fight algorithm), e.g. for clients to highlight "super effective" hits
+        effectiveness: Effectiveness,
+        /// Whether the hit was critical, dealing `1.5` times the damage
+        #[serde(default)]
+        crit: bool,
+        /// `hp_left` as a share (`0.0` to `1.0`) of the defending Pokemon's max HP,
+        /// comparable between Pokemon with different max HP
+        /// Only kept in responses that ask for it, see `FightLog::without_hp_fractions`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        hp_fraction: Option<f32>,
+    },
+    /// A Pokemon's attack applied a status condition to its defender
+    StatusApplied {
+        /// The name of the Pokemon that got the status condition
+        pokemon: String,
+        /// The applied status condition
+        condition: StatusCondition,
+    },
+    /// A Pokemon lost HP to its status condition at the end of its turn
+    StatusDamage {
+        /// The name of the Pokemon that took the damage
+        pokemon: String,
+        /// The status condition that caused the damage
+        condition: StatusCondition,
+        /// The amount of damage taken
+        damage: u32,
+        /// The amount of HP left on the Pokemon
+        hp_left: u32,
+    },
+    /// A Pokemon healed by its held item at the end of its turn
+    ItemHealed {
+        /// The name of the Pokemon that healed
+        pokemon: String,
+        /// The amount of HP healed, never past the Pokemon's max HP
+        amount: u32,
+        /// The amount of HP left on the Pokemon after healing
+        hp_left: u32,
+    },
+    /// A Pokemon hurt by the weather at the end of its turn
+    WeatherDamage {
+        /// The name of the Pokemon that took the damage
+        pokemon: String,
+        /// The weather that caused the damage
+        weather: Weather,
+        /// The amount of damage taken
+        damage: u32,
+        /// The amount of HP left on the Pokemon
+        hp_left: u32,
+    },
+    /// A paralyzed Pokemon can't move and skips its turn
+    Paralyzed {
+        /// The name of the paralyzed Pokemon
+        pokemon: String,
+    },
+    /// A Pokemon's attack misses, or its defender dodges it, and no damage is dealt
+    Missed {
+        /// The name of the attacking Pokemon
+        attacker: String,
+        /// The name of the Pokemon that dodged the attack
+        defender: String,
+    },
+    /// A Pokemon is too tired to attack and skips its turn
+    Rest {
+        /// The name of the resting Pokemon
+        pokemon: String,
+        /// The stamina of the Pokemon after resting
+        stamina: u32,
     },
     /// A Pokemon faints
     Fainted {
         /// The name of the Pokemon that fainted
         pokemon: String,
     },
+    /// An effect raised a Pokemon's stats
+    StatBoost {
+        /// The name of the boosted Pokemon
+        pokemon: String,
+        /// The name of the Pokemon whose fainting triggered the effect
+        cause: String,
+        /// The boosted attack stat of the Pokemon
+        attack: u32,
+    },
     /// A Pokemon wins the battle
     PokemonWinner {
         /// The name of the winning Pokemon
@@ -50,6 +176,12 @@ pub enum FightEvent {
         /// The amount of HP left on the winning Pokemon
         hp_left: u32,
     },
+    /// A pokemon fight reached `pokemon_fight::MAX_FIGHT_TURNS` turns with both pokemon standing,
+    /// it ends in a draw without a `PokemonWinner`
+    TurnLimit {
+        /// The number of turns taken
+        turns: u32,
+    },
     /// A trainer wins the battle
     Winner {
         /// The name of the winning trainer
@@ -62,9 +194,127 @@ pub enum FightEvent {
 /// Represents a log of a Pokemon battle
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct FightLog {
-    contender_name: String,
-    challenger_name: String,
-    log: Vec<FightEvent>,
+    /// The name of the contender (pokemon or trainer)
+    pub contender_name: String,
+    /// The name of the challenger (pokemon or trainer)
+    pub challenger_name: String,
+    /// The events of the fight, in order
+    pub log: Vec<FightEvent>,
+    /// The seed the fight's random rolls were drawn from, passing it as `?seed=` replays the fight
+    /// Only pokemon fights drawn from a seeded generator have one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// The name of the winner (pokemon or trainer), `None` until the fight is over
+    #[serde(default)]
+    pub winner: Option<String>,
+    /// The number of rounds the fight took, one per pokemon turn (see `turns`)
+    #[serde(default)]
+    pub total_rounds: u32,
+    /// The HP the winning pokemon had left, in a trainer fight that of the pokemon that won the last pokemon fight
+    #[serde(default)]
+    pub winner_hp_left: Option<u32>,
+}
+
+impl FightLog {
+    /// An empty log of a fight between `contender_name` and `challenger_name`
+    pub fn new(contender_name: String, challenger_name: String) -> Self {
+        FightLog {
+            contender_name,
+            challenger_name,
+            log: vec![],
+            seed: None,
+            winner: None,
+            total_rounds: 0,
+            winner_hp_left: None,
+        }
+    }
+
+    /// Fills in the outcome fields (`winner`, `total_rounds` and `winner_hp_left`) from the events of the log,
+    /// so clients don't have to scan the events for them
+    pub fn summarize(&mut self) {
+        let winning_pokemon = self.log.iter().rev().find_map(|event| match event {
+            FightEvent::PokemonWinner { pokemon, hp_left } => Some((pokemon, *hp_left)),
+            _ => None,
+        });
+        let winning_trainer = self.log.iter().rev().find_map(|event| match event {
+            FightEvent::Winner { trainer, .. } => Some(trainer),
+            _ => None,
+        });
+
+        self.winner = winning_trainer
+            .or(winning_pokemon.map(|(pokemon, _)| pokemon))
+            .cloned();
+        self.winner_hp_left = winning_pokemon.map(|(_, hp_left)| hp_left);
+        self.total_rounds = self.turns() as u32;
+    }
+
+    /// The number of turns taken in the fight: every hit, every miss, every rest and every turn skipped to paralysis is one pokemon's turn
+    pub fn turns(&self) -> usize {
+        self.log
+            .iter()
+            .filter(|event| {
+                matches!(
+                    event,
+                    FightEvent::Hit { .. }
+                        | FightEvent::Missed { .. }
+                        | FightEvent::Rest { .. }
+                        | FightEvent::Paralyzed { .. }
+                )
+            })
+            .count()
+    }
+
+    /// The running total of the damage each pokemon has dealt, by pokemon name, after every turn
+    /// Entry `i` of a series is the total after turn `i + 1` (see `turns`),
+    /// so every series has one entry per turn. Only hits count, status damage isn't dealt by a pokemon
+    pub fn damage_timeline(&self) -> BTreeMap<String, Vec<u32>> {
+        let mut totals: BTreeMap<String, u32> = [&self.contender_name, &self.challenger_name]
+            .into_iter()
+            .map(|name| (name.clone(), 0))
+            .collect();
+        let mut timeline: BTreeMap<String, Vec<u32>> =
+            totals.keys().map(|name| (name.clone(), vec![])).collect();
+
+        for event in &self.log {
+            match event {
+                FightEvent::Hit {
+                    attacker, damage, ..
+                } => *totals.entry(attacker.clone()).or_default() += damage,
+                FightEvent::Missed { .. }
+                | FightEvent::Rest { .. }
+                | FightEvent::Paralyzed { .. } => {}
+                _ => continue,
+            }
+
+            for (name, total) in &totals {
+                timeline.entry(name.clone()).or_default().push(*total);
+            }
+        }
+
+        timeline
+    }
+
+    /// Removes the `hp_fraction` of every `Hit`, to keep the log lean
+    pub fn without_hp_fractions(mut self) -> Self {
+        self.log = self
+            .log
+            .into_iter()
+            .map(FightEvent::without_hp_fraction)
+            .collect();
+
+        self
+    }
+}
+
+impl FightEvent {
+    /// The same event, without the defender's HP fraction if it's a `Hit`
+    pub fn without_hp_fraction(mut self) -> Self {
+        if let FightEvent::Hit { hp_fraction, .. } = &mut self {
+            *hp_fraction = None;
+        }
+
+        self
+    }
 }
 
 /// Represents a trainer's strategy during a fight
@@ -81,21 +331,40 @@ pub enum FightStrategy {
 
     /// Choose the pokemon that'll have a type advantage
     /// or if not possible, choose the strongest-sum pokemon
+    /// See `choose_pokemon` for the exact preference order
     StrongestType,
 
+    /// Choose a pokemon that is safe against the enemy's current pokemon,
+    /// saving the best counters for the enemy's upcoming pokemon
+    /// or if none are safe, choose like `StrongestType`
+    CounterLead,
+
+    /// Choose the pokemon that is "weak against" the fewest of the enemy's types,
+    /// ties go to the highest attack+defense sum
+    /// or on the first pick, choose the strongest-sum pokemon
+    SafestSwitch,
+
+    /// Choose the pokemon that has the lowest HP stat,
+    /// sacrificing the weakest pokemon first to preserve the strong ones
+    LowestHp,
+
+    /// Choose the pokemon that has the highest HP+defense sum
+    MostDurable,
+
     /// Choose a random pokemon
     Random,
 }
 
 /// Represents the effectiveness of a move in a Pokemon battle
-#[derive(Serialize, Deserialize, Clone, Debug)]
+/// Serialized by its variant name, e.g. `"SuperEffective"`
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Effectiveness {
-    /// The move is super effective
+    /// The move is super effective, its type damage multiplier is above `1.8`
     SuperEffective,
-    /// The move is not very effective
-    NotVeryEffective,
     /// The move is normally effective
     Normal,
+    /// The move is not very effective, its type damage multiplier is below `0.6`
+    NotVeryEffective,
 }
 
 impl FromStr for FightStrategy {
@@ -107,132 +376,349 @@ impl FromStr for FightStrategy {
             "StrongestDef" => Ok(FightStrategy::StrongestDef),
             "StrongestSum" => Ok(FightStrategy::StrongestSum),
             "StrongestType" => Ok(FightStrategy::StrongestType),
+            "CounterLead" => Ok(FightStrategy::CounterLead),
+            "SafestSwitch" => Ok(FightStrategy::SafestSwitch),
+            "LowestHp" => Ok(FightStrategy::LowestHp),
+            "MostDurable" => Ok(FightStrategy::MostDurable),
             "Random" => Ok(FightStrategy::Random),
             _ => Err(()),
         }
     }
 }
 
+/// The tier at which `StrongestType` gives up and falls back to `StrongestSum`
+const FALLBACK_TIER: u8 = 6;
+
+/// Chooses the pokemon with the highest `key`
+/// Ties go to the highest stat total, then to the alphabetically first name,
+/// so the pick doesn't depend on the order of the team
+fn strongest_by(team: &[Pokemon], key: impl Fn(&Pokemon) -> u32) -> Option<Pokemon> {
+    team.iter()
+        .max_by(|a, b| {
+            key(a)
+                .cmp(&key(b))
+                .then_with(|| a.stats.total().cmp(&b.stats.total()))
+                .then_with(|| b.name.cmp(&a.name))
+        })
+        .cloned()
+}
+
+/// Chooses the pokemon with the lowest `key`
+/// Ties go to the lowest stat total, then to the alphabetically first name,
+/// so the pick doesn't depend on the order of the team
+fn weakest_by(team: &[Pokemon], key: impl Fn(&Pokemon) -> u32) -> Option<Pokemon> {
+    team.iter()
+        .min_by(|a, b| {
+            key(a)
+                .cmp(&key(b))
+                .then_with(|| a.stats.total().cmp(&b.stats.total()))
+                .then_with(|| a.name.cmp(&b.name))
+        })
+        .cloned()
+}
+
+/// Chooses the pokemon with the highest attack+defense sum
+fn strongest_sum(team: &[Pokemon]) -> Option<Pokemon> {
+    strongest_by(team, |p| p.stats.attack + p.stats.defense)
+}
+
+/// Resolves the primary and secondary types of a pokemon, if possible
+async fn resolve_types(pokemon: &Pokemon) -> (Option<PokemonType>, Option<PokemonType>) {
+    let primary = pokemon.primary_type().clone().resolve().await.ok();
+
+    let secondary = match pokemon.secondary_type() {
+        Some(t) => t.clone().resolve().await.ok(),
+        None => None,
+    };
+
+    (primary, secondary)
+}
+
+/// The types of an enemy pokemon, skipping any that can't be resolved
+async fn enemy_types(pokemon: &Pokemon) -> Vec<PokemonType> {
+    let (primary, secondary) = resolve_types(pokemon).await;
+    primary.into_iter().chain(secondary).collect()
+}
+
+/// The types of one of our own pokemon,
+/// `None` if its primary type can't be resolved
+async fn own_types(pokemon: &Pokemon) -> Option<Vec<PokemonType>> {
+    let (primary, secondary) = resolve_types(pokemon).await;
+    Some(std::iter::once(primary?).chain(secondary).collect())
+}
+
+/// Rates how well our types match up against the enemy's types,
+/// following the `StrongestType` preference order (lower is better)
+/// Returns the tier and the number of weaknesses
+fn type_matchup(own_types: &[PokemonType], enemy_types: &[PokemonType]) -> (u8, usize) {
+    // how many enemy types at least one of our types is strong against
+    let covered = enemy_types
+        .iter()
+        .filter(|e| own_types.iter().any(|o| o.is_strong_against(e)))
+        .count();
+
+    // how many of our types are weak against an enemy type, counted per pair
+    let weaknesses: usize = enemy_types
+        .iter()
+        .map(|e| own_types.iter().filter(|o| o.is_weak_against(e)).count())
+        .sum();
+
+    let tier = match (covered, weaknesses) {
+        (c, _) if c > 0 && c == enemy_types.len() => 1,
+        (c, 0) if c > 0 => 2,
+        (c, _) if c > 0 => 3,
+        (_, 0) => 4,
+        (_, 1) => 5,
+        _ => FALLBACK_TIER,
+    };
+
+    (tier, weaknesses)
+}
+
+/// The `StrongestType` strategy
+async fn strongest_type(team: &[Pokemon], enemy_pokemon: Option<&Pokemon>) -> Option<Pokemon> {
+    // Preference order, best first:
+    // 1. A pokemon that is "strong against" every enemy type
+    // 2. A pokemon that is "strong against" one enemy type, with no "weak against"
+    // 3. A pokemon that is "strong against" one enemy type
+    // 4. A pokemon that has no "weak against" enemy types
+    // 5. A pokemon that has only one "weak against" enemy type
+    // 6. The strongest-sum pokemon
+    // Within a tier, the pokemon with the highest attack+defense sum is chosen
+
+    // If enemy types can't be determined we use the strongest-sum strategy
+
+    let enemy_pokemon = match enemy_pokemon {
+        Some(p) => p,
+        None => return strongest_sum(team),
+    };
+
+    let enemy_types = enemy_types(enemy_pokemon).await;
+
+    let mut best: Option<(u8, &Pokemon)> = None;
+
+    for pokemon in team.iter() {
+        let tier = match own_types(pokemon).await {
+            Some(own) => type_matchup(&own, &enemy_types).0,
+            None => continue,
+        };
+
+        let sum = pokemon.stats.attack + pokemon.stats.defense;
+
+        let is_better = match best {
+            Some((best_tier, best_pokemon)) => {
+                tier < best_tier
+                    || (tier == best_tier
+                        && sum > best_pokemon.stats.attack + best_pokemon.stats.defense)
+            }
+            None => true,
+        };
+
+        if is_better {
+            best = Some((tier, pokemon));
+        }
+    }
+
+    match best {
+        Some((tier, pokemon)) if tier < FALLBACK_TIER => Some(pokemon.clone()),
+        _ => strongest_sum(team),
+    }
+}
+
+/// The `SafestSwitch` strategy
+async fn safest_switch(team: &[Pokemon], enemy_pokemon: Option<&Pokemon>) -> Option<Pokemon> {
+    // 1. Count the enemy types each pokemon has at least one "weak against"
+    // 2. Among the pokemon with the lowest count, choose the strongest-sum one
+    // Pokemon whose types can't be determined are skipped,
+    // and if that's all of them, or there is no enemy yet, we use the strongest-sum strategy
+
+    let enemy_pokemon = match enemy_pokemon {
+        Some(p) => p,
+        None => return strongest_sum(team),
+    };
+
+    let enemy_types = enemy_types(enemy_pokemon).await;
+
+    let mut rated = vec![];
+    for pokemon in team.iter() {
+        if let Some(own) = own_types(pokemon).await {
+            let weak_to = enemy_types
+                .iter()
+                .filter(|e| own.iter().any(|o| o.is_weak_against(e)))
+                .count();
+            rated.push((weak_to, pokemon));
+        }
+    }
+
+    let safest: Vec<Pokemon> = match rated.iter().map(|(weak_to, _)| *weak_to).min() {
+        Some(fewest) => rated
+            .into_iter()
+            .filter(|(weak_to, _)| *weak_to == fewest)
+            .map(|(_, pokemon)| pokemon.clone())
+            .collect(),
+        None => return strongest_sum(team),
+    };
+
+    strongest_sum(&safest)
+}
+
+/// The `CounterLead` strategy
+async fn counter_lead(
+    team: &[Pokemon],
+    enemy_pokemon: Option<&Pokemon>,
+    enemy_team: &[Pokemon],
+) -> Option<Pokemon> {
+    // 1. Only pokemon with no "weak against" the current enemy are considered safe
+    // 2. Among the safe pokemon, choose the one whose absence hurts our matchups
+    //    against the enemy's upcoming pokemon the least (the "reserve" cost,
+    //    the sum of the best `StrongestType` tier left against each upcoming enemy)
+    // 3. Ties go to the better tier against the current enemy, then to the strongest sum
+    // 4. If no pokemon is safe, choose like `StrongestType`
+
+    let enemy_pokemon = match enemy_pokemon {
+        Some(p) => p,
+        None => return strongest_sum(team),
+    };
+
+    let current_types = enemy_types(enemy_pokemon).await;
+
+    let mut upcoming_types = vec![];
+    for enemy in enemy_team.iter().filter(|p| p.name != enemy_pokemon.name) {
+        upcoming_types.push(enemy_types(enemy).await);
+    }
+
+    // (tier vs current enemy, weaknesses vs current enemy, tiers vs upcoming enemies)
+    let mut matchups = vec![];
+    for pokemon in team.iter() {
+        matchups.push(own_types(pokemon).await.map(|own| {
+            let (tier, weaknesses) = type_matchup(&own, &current_types);
+            let upcoming: Vec<u8> = upcoming_types
+                .iter()
+                .map(|enemy| type_matchup(&own, enemy).0)
+                .collect();
+            (tier, weaknesses, upcoming)
+        }));
+    }
+
+    let reserve_cost = |i: usize| -> u32 {
+        (0..upcoming_types.len())
+            .map(|u| {
+                matchups
+                    .iter()
+                    .enumerate()
+                    .filter(|(j, _)| *j != i)
+                    .filter_map(|(_, m)| m.as_ref().map(|(_, _, upcoming)| upcoming[u]))
+                    .min()
+                    .unwrap_or(FALLBACK_TIER) as u32
+            })
+            .sum()
+    };
+
+    let best = team
+        .iter()
+        .enumerate()
+        .filter_map(|(i, pokemon)| match &matchups[i] {
+            Some((tier, 0, _)) => Some((i, *tier, pokemon)),
+            _ => None,
+        })
+        .min_by_key(|(i, tier, pokemon)| {
+            (
+                reserve_cost(*i),
+                *tier,
+                std::cmp::Reverse(pokemon.stats.attack + pokemon.stats.defense),
+            )
+        });
+
+    match best {
+        Some((_, _, pokemon)) => Some(pokemon.clone()),
+        None => strongest_type(team, Some(enemy_pokemon)).await,
+    }
+}
+
+/// The pokemon a strategy picked, see `strategy_picks`
+#[derive(Serialize, Clone, Debug)]
+pub struct StrategyPick {
+    /// The strategy that made the pick
+    pub strategy: FightStrategy,
+    /// The name of the picked pokemon, `None` if the strategy picked nothing
+    pub pokemon: Option<String>,
+}
+
+/// Lets every strategy pick from the same `team` against the same `enemy`,
+/// without an enemy each strategy makes its opening pick
+/// The enemy is the only pokemon of the enemy's team
+pub async fn strategy_picks(team: &[Pokemon], enemy: Option<&Pokemon>) -> Vec<StrategyPick> {
+    let enemy_team: Vec<Pokemon> = enemy.into_iter().cloned().collect();
+    let mut rng = StdRng::from_entropy();
+    let mut picks = vec![];
+
+    for strategy in FightStrategy::ALL {
+        let pokemon = strategy
+            .choose_pokemon(team, enemy, &enemy_team, &mut rng)
+            .await
+            .map(|p| p.name);
+
+        picks.push(StrategyPick { strategy, pokemon });
+    }
+
+    picks
+}
+
 impl FightStrategy {
+    /// Every strategy, in declaration order
+    pub const ALL: [FightStrategy; 9] = [
+        FightStrategy::StrongestAtk,
+        FightStrategy::StrongestDef,
+        FightStrategy::StrongestSum,
+        FightStrategy::StrongestType,
+        FightStrategy::CounterLead,
+        FightStrategy::SafestSwitch,
+        FightStrategy::LowestHp,
+        FightStrategy::MostDurable,
+        FightStrategy::Random,
+    ];
+
+    /// The name of the strategy, the same one `from_str` parses
+    pub fn name(&self) -> &'static str {
+        match self {
+            FightStrategy::StrongestAtk => "StrongestAtk",
+            FightStrategy::StrongestDef => "StrongestDef",
+            FightStrategy::StrongestSum => "StrongestSum",
+            FightStrategy::StrongestType => "StrongestType",
+            FightStrategy::CounterLead => "CounterLead",
+            FightStrategy::SafestSwitch => "SafestSwitch",
+            FightStrategy::LowestHp => "LowestHp",
+            FightStrategy::MostDurable => "MostDurable",
+            FightStrategy::Random => "Random",
+        }
+    }
+
     /// Chooses a pokemon from a team based on the strategy
-    pub async fn choose_pokemon(
+    /// `enemy_pokemon` is the enemy's current pokemon, if it has chosen one,
+    /// and `enemy_team` is the enemy's remaining team (including `enemy_pokemon`)
+    /// Every strategy returns `None` for an empty team
+    /// Only `Random` draws from `rng`, so a fight with a seeded `rng` always picks the same pokemon
+    pub async fn choose_pokemon<R: Rng + Send>(
         &self,
         team: &[Pokemon],
         enemy_pokemon: Option<&Pokemon>,
+        enemy_team: &[Pokemon],
+        rng: &mut R,
     ) -> Option<Pokemon> {
+        if team.is_empty() {
+            return None;
+        }
+
         match self {
-            FightStrategy::StrongestAtk => team.iter().max_by_key(|p| p.stats.attack).cloned(),
-            FightStrategy::StrongestDef => team.iter().max_by_key(|p| p.stats.defense).cloned(),
-            FightStrategy::StrongestSum => team
-                .iter()
-                .max_by_key(|p| p.stats.attack + p.stats.defense)
-                .cloned(),
-            FightStrategy::StrongestType => {
-                // 1. Find out if we have a pokemon that has "strong against" both enemy types
-                // 2. Find out if we have a pokemon that has "strong against" one enemy type but no weak against
-                // 3. Find out if we have a pokemon that has "strong against" one enemy type
-                // 4. Find out if we have a pokemon that has no "weak against" enemy types
-                // 5. Find out if we have a pokemon that has only one "weak_against type"
-                // 6. Choose the strongest sum pokemon
-
-                // If enemy types can't be determined we use the strongest-sum strategy
-
-                let enemy_pokemon = match enemy_pokemon {
-                    Some(p) => p,
-                    None => return team.iter().max_by_key(|p| p.stats.attack + p.stats.defense).cloned(),
-                };
-
-                let enemy_primary_type = match enemy_pokemon.primary_type().clone().resolve().await
-                {
-                    Ok(t) => Some(t),
-                    Err(_) => None,
-                };
-
-                let enemy_secondary_type = match enemy_pokemon.secondary_type() {
-                    Some(t) => t.clone().resolve().await.ok(),
-                    None => None,
-                };
-
-                for pokemon in team.iter() {
-                    let own_primary_type = match pokemon.primary_type().clone().resolve().await {
-                        Ok(t) => t,
-                        Err(_) => continue,
-                    };
-
-                    let own_secondary_type = match pokemon.secondary_type() {
-                        Some(t) => t.clone().resolve().await.ok(),
-                        None => None,
-                    };
-
-                    if let Some(enemy_primary_type) = &enemy_primary_type {
-                        if own_primary_type.is_strong_against(enemy_primary_type) {
-                            if let Some(enemy_secondary_type) = &enemy_secondary_type {
-                                if own_primary_type.is_strong_against(enemy_secondary_type) {
-                                    return Some(pokemon.clone());
-                                }
-                            } else {
-                                return Some(pokemon.clone());
-                            }
-                        }
-                    }
-
-                    if let Some(enemy_secondary_type) = &enemy_secondary_type {
-                        if own_primary_type.is_strong_against(enemy_secondary_type) {
-                            return Some(pokemon.clone());
-                        }
-                    }
-
-                    if let Some(own_secondary_type) = &own_secondary_type {
-                        if let Some(enemy_primary_type) = &enemy_primary_type {
-                            if own_secondary_type.is_strong_against(enemy_primary_type) {
-                                return Some(pokemon.clone());
-                            }
-                        }
-
-                        if let Some(enemy_secondary_type) = &enemy_secondary_type {
-                            if own_secondary_type.is_strong_against(enemy_secondary_type) {
-                                return Some(pokemon.clone());
-                            }
-                        }
-                    }
-
-                    if let Some(enemy_primary_type) = &enemy_primary_type {
-                        if own_primary_type.is_weak_against(enemy_primary_type) {
-                            continue;
-                        }
-                    }
-
-                    if let Some(enemy_secondary_type) = &enemy_secondary_type {
-                        if own_primary_type.is_weak_against(enemy_secondary_type) {
-                            continue;
-                        }
-                    }
-
-                    if let Some(own_secondary_type) = own_secondary_type {
-                        if let Some(enemy_primary_type) = &enemy_primary_type {
-                            if own_secondary_type.is_weak_against(enemy_primary_type) {
-                                continue;
-                            }
-                        }
-
-                        if let Some(enemy_secondary_type) = &enemy_secondary_type {
-                            if own_secondary_type.is_weak_against(enemy_secondary_type) {
-                                continue;
-                            }
-                        }
-                    }
-
-                    return Some(pokemon.clone());
-                }
-
-                team.iter()
-                    .max_by_key(|p| p.stats.attack + p.stats.defense)
-                    .cloned()
-            }
-            FightStrategy::Random => {
-                let idx = rand::random::<usize>() % team.len();
-                team.get(idx).cloned()
-            }
+            FightStrategy::StrongestAtk => strongest_by(team, |p| p.stats.attack),
+            FightStrategy::StrongestDef => strongest_by(team, |p| p.stats.defense),
+            FightStrategy::StrongestSum => strongest_sum(team),
+            FightStrategy::StrongestType => strongest_type(team, enemy_pokemon).await,
+            FightStrategy::CounterLead => counter_lead(team, enemy_pokemon, enemy_team).await,
+            FightStrategy::SafestSwitch => safest_switch(team, enemy_pokemon).await,
+            FightStrategy::LowestHp => weakest_by(team, |p| p.stats.hp),
+            FightStrategy::MostDurable => strongest_by(team, |p| p.stats.hp + p.stats.defense),
+            FightStrategy::Random => team.get(rng.gen_range(0..team.len())).cloned(),
         }
     }
 }
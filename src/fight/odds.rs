@@ -0,0 +1,386 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Result, anyhow};
+use rand::{SeedableRng, rngs::StdRng};
+use serde::Serialize;
+
+use super::{
+    FightEvent, FightStrategy,
+    config::FightConfig,
+    pokemon_fight::{self, ResolvedPokemon},
+    trainer_fight,
+};
+use crate::{database::promise::MaybePromise, pokemon::Pokemon, trainer::Trainer};
+
+/// The number of trials used when the request doesn't specify one
+pub const DEFAULT_TRIALS: u32 = 50;
+
+/// The largest number of trials a single request can run
+pub const MAX_TRIALS: u32 = 1000;
+
+/// The number of trials of a fight length distribution when the request doesn't specify one
+pub const DEFAULT_LENGTH_TRIALS: u32 = 200;
+
+/// The number of runs of a fight batch when the request doesn't specify one
+pub const DEFAULT_BATCH_RUNS: u32 = 1000;
+
+/// The largest number of runs a single fight batch can have, a run is much cheaper than a trainer fight trial
+pub const MAX_BATCH_RUNS: u32 = 10000;
+
+/// How one trainer did over every trial
+#[derive(Serialize, Clone, Debug)]
+pub struct TrainerOdds {
+    /// The name of the trainer
+    pub trainer: String,
+    /// The number of trials the trainer won
+    pub wins: u32,
+    /// `wins` divided by the number of trials
+    pub win_rate: f32,
+    /// The average number of the trainer's pokemon left standing at the end of a trial,
+    /// a lost trial counts as zero
+    pub average_surviving: f32,
+    /// How many trials ended with each number of the trainer's pokemon left standing
+    pub surviving_distribution: BTreeMap<usize, u32>,
+}
+
+impl TrainerOdds {
+    fn new(trainer: &Trainer) -> Self {
+        Self {
+            trainer: trainer.name.clone(),
+            wins: 0,
+            win_rate: 0.0,
+            average_surviving: 0.0,
+            surviving_distribution: BTreeMap::new(),
+        }
+    }
+
+    fn record(&mut self, won: bool, surviving: usize) {
+        if won {
+            self.wins += 1;
+        }
+
+        *self.surviving_distribution.entry(surviving).or_default() += 1;
+    }
+
+    fn finish(&mut self, trials: u32) {
+        let surviving: usize = self
+            .surviving_distribution
+            .iter()
+            .map(|(surviving, count)| surviving * *count as usize)
+            .sum();
+
+        self.win_rate = self.wins as f32 / trials as f32;
+        self.average_surviving = surviving as f32 / trials as f32;
+    }
+}
+
+/// The outcome of running the same trainer fight many times
+#[derive(Serialize, Clone, Debug)]
+pub struct FightOdds {
+    /// The number of fights simulated
+    pub trials: u32,
+    /// The seed of the first trial, trial `i` is seeded with `seed + i`
+    pub seed: u64,
+    /// How the challenger did
+    pub challenger: TrainerOdds,
+    /// How the contender did
+    pub contender: TrainerOdds,
+}
+
+/// A copy of `trainer` whose pokemon and their types are all concrete values
+async fn resolve_team(trainer: &Trainer) -> Result<Trainer> {
+    let mut team = vec![];
+    for member in &trainer.team {
+        let pokemon = member.clone().resolve().await?;
+        team.push(MaybePromise::from_concrete(
+            ResolvedPokemon::resolve(&pokemon).await?.into_concrete(),
+        ));
+    }
+
+    Ok(Trainer {
+        team,
+        ..trainer.clone()
+    })
+}
+
+/// Simulates the trainer fight `trials` times, each with its own seed, and tallies the results
+/// The same `seed` gives the same odds, whatever strategy each trainer uses
+pub async fn trainer_fight_odds(
+    challenger: &Trainer,
+    contender: &Trainer,
+    challenger_strat: FightStrategy,
+    contender_strat: FightStrategy,
+    trials: u32,
+    seed: u64,
+    config: &FightConfig,
+) -> Result<FightOdds> {
+    if trials == 0 || trials > MAX_TRIALS {
+        return Err(anyhow!(
+            "The number of trials must be between 1 and {MAX_TRIALS}, got {trials}"
+        ));
+    }
+
+    let mut odds = FightOdds {
+        trials,
+        seed,
+        challenger: TrainerOdds::new(challenger),
+        contender: TrainerOdds::new(contender),
+    };
+
+    // resolved once, so the trials never query the database
+    let challenger = resolve_team(challenger).await?;
+    let contender = resolve_team(contender).await?;
+
+    for trial in 0..trials {
+        let mut rng = StdRng::seed_from_u64(seed.wrapping_add(trial as u64));
+
+        let log = trainer_fight::process_fight_with_rng(
+            &challenger,
+            &contender,
+            challenger_strat.clone(),
+            contender_strat.clone(),
+            config,
+            &mut rng,
+        )
+        .await?;
+
+        let (winner, surviving) = match log.log.last() {
+            Some(FightEvent::Winner {
+                trainer,
+                pokemon_left,
+            }) => (trainer.clone(), pokemon_left.len()),
+            _ => return Err(anyhow!("The fight log has no winner")),
+        };
+
+        if winner == challenger.name {
+            odds.challenger.record(true, surviving);
+            odds.contender.record(false, 0);
+        } else {
+            odds.challenger.record(false, 0);
+            odds.contender.record(true, surviving);
+        }
+    }
+
+    odds.challenger.finish(trials);
+    odds.contender.finish(trials);
+
+    Ok(odds)
+}
+
+/// How long the same pokemon fight lasts over many trials
+#[derive(Serialize, Clone, Debug)]
+pub struct LengthDistribution {
+    /// The number of fights simulated
+    pub trials: u32,
+    /// The seed of the first trial, trial `i` is seeded with `seed + i`
+    pub seed: u64,
+    /// The fewest turns a fight took
+    pub min: usize,
+    /// The most turns a fight took
+    pub max: usize,
+    /// The average number of turns
+    pub mean: f32,
+    /// How many fights took each number of turns
+    pub turns: BTreeMap<usize, u32>,
+}
+
+/// Simulates the pokemon fight `trials` times, each with its own seed, and counts how many turns each took
+/// The same `seed` gives the same distribution
+pub async fn fight_length_distribution(
+    contender: &Pokemon,
+    challenger: &Pokemon,
+    trials: u32,
+    seed: u64,
+    config: &FightConfig,
+) -> Result<LengthDistribution> {
+    if trials == 0 || trials > MAX_TRIALS {
+        return Err(anyhow!(
+            "The number of trials must be between 1 and {MAX_TRIALS}, got {trials}"
+        ));
+    }
+
+    // resolved once, so the trials never query the database
+    let contender = ResolvedPokemon::resolve(contender).await?;
+    let challenger = ResolvedPokemon::resolve(challenger).await?;
+
+    let mut turns = BTreeMap::new();
+    let mut total = 0;
+
+    for trial in 0..trials {
+        let mut rng = StdRng::seed_from_u64(seed.wrapping_add(trial as u64));
+
+        let log = pokemon_fight::process_fight_resolved(
+            &contender,
+            &challenger,
+            contender.pokemon.stats.hp,
+            challenger.pokemon.stats.hp,
+            config,
+            &mut rng,
+        );
+
+        total += log.turns();
+        *turns.entry(log.turns()).or_default() += 1;
+    }
+
+    Ok(LengthDistribution {
+        trials,
+        seed,
+        min: turns.keys().next().copied().unwrap_or(0),
+        max: turns.keys().next_back().copied().unwrap_or(0),
+        mean: total as f32 / trials as f32,
+        turns,
+    })
+}
+
+/// Who won the same pokemon fight over many runs, without the logs
+#[derive(Serialize, Clone, Debug)]
+pub struct BatchSummary {
+    /// The number of fights simulated
+    pub runs: u32,
+    /// The seed of the first run, run `i` is seeded with `seed + i`
+    pub seed: u64,
+    /// The number of runs the contender won
+    pub contender_wins: u32,
+    /// The number of runs the challenger won
+    pub challenger_wins: u32,
+    /// The share of runs the contender won, in percent
+    pub contender_win_percentage: f32,
+    /// The average number of turns a run took, see `FightLog::turns`
+    pub average_turns: f32,
+    /// The average HP the winner had left at the end of a run
+    pub average_winner_hp: f32,
+}
+
+/// Simulates the pokemon fight `runs` times, each with its own seed, and tallies who won
+/// In a fight of a pokemon against itself every run counts as a contender win
+/// The same `seed` gives the same summary
+pub async fn fight_batch(
+    contender: &Pokemon,
+    challenger: &Pokemon,
+    runs: u32,
+    seed: u64,
+    config: &FightConfig,
+) -> Result<BatchSummary> {
+    if runs == 0 || runs > MAX_BATCH_RUNS {
+        return Err(anyhow!(
+            "The number of runs must be between 1 and {MAX_BATCH_RUNS}, got {runs}"
+        ));
+    }
+
+    // resolved once, so the runs never query the database
+    let contender = ResolvedPokemon::resolve(contender).await?;
+    let challenger = ResolvedPokemon::resolve(challenger).await?;
+
+    let mut contender_wins = 0;
+    let mut total_turns = 0;
+    let mut total_winner_hp = 0;
+
+    for run in 0..runs {
+        let mut rng = StdRng::seed_from_u64(seed.wrapping_add(run as u64));
+
+        let log = pokemon_fight::process_fight_resolved(
+            &contender,
+            &challenger,
+            contender.pokemon.stats.hp,
+            challenger.pokemon.stats.hp,
+            config,
+            &mut rng,
+        );
+
+        let (winner, hp_left) = match log.log.last() {
+            Some(FightEvent::PokemonWinner { pokemon, hp_left }) => (pokemon, *hp_left),
+            _ => return Err(anyhow!("The fight log has no winner")),
+        };
+
+        if *winner == contender.pokemon.name {
+            contender_wins += 1;
+        }
+
+        total_turns += log.turns();
+        total_winner_hp += hp_left as u64;
+    }
+
+    Ok(BatchSummary {
+        runs,
+        seed,
+        contender_wins,
+        challenger_wins: runs - contender_wins,
+        contender_win_percentage: contender_wins as f32 / runs as f32 * 100.0,
+        average_turns: total_turns as f32 / runs as f32,
+        average_winner_hp: total_winner_hp as f32 / runs as f32,
+    })
+}
+
+/// How much picking second is worth in a trainer fight between two trainers
+#[derive(Serialize, Clone, Debug)]
+pub struct OrderFairness {
+    /// The number of fights simulated in each ordering
+    pub trials: u32,
+    /// The seed of the first trial of each ordering, trial `i` is seeded with `seed + i`
+    pub seed: u64,
+    /// The strategy both trainers fight with
+    pub strategy: FightStrategy,
+    /// The odds with the first trainer picking first (as the contender)
+    pub a_first: FightOdds,
+    /// The odds with the second trainer picking first (as the contender)
+    pub b_first: FightOdds,
+    /// The share of all fights, over both orderings, won by the trainer that picked second
+    pub second_pick_win_rate: f32,
+    /// The win rate of the trainer picking second minus the win rate of the trainer picking first,
+    /// in percentage points. Both orderings are counted, so the strength of the teams cancels out,
+    /// and a positive value is an advantage for picking second
+    pub order_advantage: f32,
+}
+
+/// Runs the trainer fight `trials` times in each ordering, with both trainers using `strategy`
+/// and the same seeds in both orderings, and measures the advantage that comes from pick order alone
+pub async fn order_fairness(
+    a: &Trainer,
+    b: &Trainer,
+    strategy: FightStrategy,
+    trials: u32,
+    seed: u64,
+    config: &FightConfig,
+) -> Result<OrderFairness> {
+    if a.name == b.name {
+        return Err(anyhow!(
+            "Pick order fairness needs two different trainers, got {} twice",
+            a.name
+        ));
+    }
+
+    // the challenger picks second
+    let a_first = trainer_fight_odds(
+        b,
+        a,
+        strategy.clone(),
+        strategy.clone(),
+        trials,
+        seed,
+        config,
+    )
+    .await?;
+    let b_first = trainer_fight_odds(
+        a,
+        b,
+        strategy.clone(),
+        strategy.clone(),
+        trials,
+        seed,
+        config,
+    )
+    .await?;
+
+    let second_pick_wins = a_first.challenger.wins + b_first.challenger.wins;
+    let second_pick_win_rate = second_pick_wins as f32 / (2 * trials) as f32;
+
+    Ok(OrderFairness {
+        trials,
+        seed,
+        strategy,
+        a_first,
+        b_first,
+        second_pick_win_rate,
+        order_advantage: (2.0 * second_pick_win_rate - 1.0) * 100.0,
+    })
+}
@@ -1,187 +1,768 @@
-use std::cmp::max;
 use anyhow::Result;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use rocket::tokio::{
+    self,
+    sync::mpsc::{self, UnboundedReceiver},
+};
+use serde::Serialize;
 
-use super::{Effectiveness, FightEvent, FightLog};
-use crate::pokemon::Pokemon;
+use super::{
+    Effectiveness, FightEvent, FightLog,
+    config::{FightConfig, TurnOrder},
+    predictor::MeanRng,
+    status::StatusCondition,
+};
+use crate::database::promise::MaybePromise;
+use crate::pokemon::{Pokemon, ptype::PokemonType, stats::MAX_ACCURACY};
+
+/// The stamina an attack costs
+pub const STAMINA_ATTACK_COST: u32 = 10;
+
+/// The stamina a pokemon regains at the end of each of its turns
+pub const STAMINA_REGEN: u32 = 5;
+
+/// The most turns a pokemon fight takes before ending in a draw,
+/// so pokemon that can't hurt each other (e.g. both always missing) don't fight forever
+pub const MAX_FIGHT_TURNS: u32 = 10_000;
+
+/// The highest type damage multiplier, step 4 of the fight algorithm
+pub const MAX_TYPE_MULTIPLIER: f32 = 2.5;
+
+/// The lowest type damage multiplier, step 4 of the fight algorithm
+pub const MIN_TYPE_MULTIPLIER: f32 = 0.1;
+
+/// The damage multiplier of a critical hit, step 7 of the fight algorithm
+pub const CRIT_MULTIPLIER: f32 = 1.5;
+
+/// One of the steps 3a-3h of the fight algorithm
+#[derive(Serialize, Clone, Debug)]
+pub struct MultiplierStep {
+    /// The letter of the step, `a` to `h`
+    pub step: char,
+    /// The type relationship the step checks
+    pub description: &'static str,
+    /// How much the step changes the multiplier if it applies
+    pub change: f32,
+    /// Whether the step applied
+    pub applied: bool,
+    /// The multiplier after this step
+    pub running_total: f32,
+}
+
+/// The type damage multiplier of an attack, with every step that led to it
+#[derive(Serialize, Clone, Debug)]
+pub struct TypeBreakdown {
+    /// Steps 3a-3h, in order
+    pub steps: Vec<MultiplierStep>,
+    /// The multiplier after step 3h
+    pub total: f32,
+    /// The multiplier clamped by step 4, which is what the attack uses
+    pub multiplier: f32,
+    /// The effectiveness label of the multiplier
+    pub effectiveness: Effectiveness,
+}
+
+/// Calculates the type damage multiplier of an attack, steps 3a-4 of the fight algorithm
+/// (see `process_fight_with_hp`)
+/// Within each pair of steps only one can apply, the first one listed in the pair
+/// for steps a-d, and the "Strong Against" one (f, h) for steps e-h
+pub fn type_damage_multiplier(
+    atk_ptype: &PokemonType,
+    atk_stype: Option<&PokemonType>,
+    def_ptype: &PokemonType,
+    def_stype: Option<&PokemonType>,
+) -> TypeBreakdown {
+    const STRONG: f32 = 0.375;
+    const WEAK: f32 = -0.225;
+
+    let mut steps = Vec::with_capacity(8);
+    let mut total: f32 = 1.0;
+
+    let mut step = |step, description, change, applied| {
+        if applied {
+            total += change;
+        }
+
+        steps.push(MultiplierStep {
+            step,
+            description,
+            change,
+            applied,
+            running_total: total,
+        });
+
+        applied
+    };
+
+    // Calculate primary vs primary type advantage
+    let a = step(
+        'a',
+        "Attacker's primary type is strong against the defender's primary type",
+        STRONG,
+        atk_ptype.is_strong_against(def_ptype),
+    );
+    step(
+        'b',
+        "Attacker's primary type is weak against the defender's primary type",
+        WEAK,
+        !a && atk_ptype.is_weak_against(def_ptype),
+    );
+
+    // Calculate secondary vs primary type advantage
+    let c = step(
+        'c',
+        "Attacker's primary type is strong against the defender's secondary type",
+        STRONG,
+        def_stype.is_some_and(|def_stype| atk_ptype.is_strong_against(def_stype)),
+    );
+    step(
+        'd',
+        "Attacker's primary type is weak against the defender's secondary type",
+        WEAK,
+        !c && def_stype.is_some_and(|def_stype| atk_ptype.is_weak_against(def_stype)),
+    );
+
+    // Calculate primary vs secondary type advantage
+    let f = atk_stype.is_some_and(|atk_stype| def_ptype.is_strong_against(atk_stype));
+    step(
+        'e',
+        "Defender's primary type is weak against the attacker's secondary type",
+        STRONG,
+        !f && atk_stype.is_some_and(|atk_stype| def_ptype.is_weak_against(atk_stype)),
+    );
+    step(
+        'f',
+        "Defender's primary type is strong against the attacker's secondary type",
+        WEAK,
+        f,
+    );
+
+    // Calculate secondary vs secondary type advantage
+    let secondaries = atk_stype.zip(def_stype);
+    let h =
+        secondaries.is_some_and(|(atk_stype, def_stype)| def_stype.is_strong_against(atk_stype));
+    step(
+        'g',
+        "Defender's secondary type is weak against the attacker's secondary type",
+        STRONG,
+        !h && secondaries
+            .is_some_and(|(atk_stype, def_stype)| def_stype.is_weak_against(atk_stype)),
+    );
+    step(
+        'h',
+        "Defender's secondary type is strong against the attacker's secondary type",
+        WEAK,
+        h,
+    );
+
+    let multiplier = total.clamp(MIN_TYPE_MULTIPLIER, MAX_TYPE_MULTIPLIER);
+
+    let effectiveness = match multiplier {
+        x if x > 1.8 => Effectiveness::SuperEffective,
+        x if x < 0.6 => Effectiveness::NotVeryEffective,
+        _ => Effectiveness::Normal,
+    };
+
+    TypeBreakdown {
+        steps,
+        total,
+        multiplier,
+        effectiveness,
+    }
+}
 
 /// Process a fight between two pokemon with a given amount of HP and return a log of the battle
 /// # The fight algorithm:
 /// 1. The pokemon with the highest `AGI`lity stat attacks first
-/// 2. The base damage is the pokemon's `ATK` (attack) stat
+///    With `FightConfig::turn_order` set to `AgilityWeighted`, who attacks first is instead rolled every round, weighted by agility
+///    If the attacker has a stamina stat, every attack costs `10` stamina and each turn regains `5`.
+///    An attacker with less than `10` stamina (and not at full stamina) rests instead, regaining `5`, and its turn ends
+///    A paralyzed attacker can't move `25%` of the time, and its turn ends
+///    An attack lands with a chance of the attacker's accuracy stat (in percent, `100` by default), otherwise it misses and the turn ends, still costing stamina
+/// 2. The base damage is the pokemon's `ATK` (attack) stat, halved if it's burned, times `1.5` if it holds a Choice Band (see `Item`), times its level multiplier: `(2 * level / 5 + 2) / 22`, which is `1.0` at the default level `50`, about `1.9` at level `100` and about `0.18` at level `5`
 /// 3. The type damage multiplier is calculated as follows, starting with a multiplier of `1`
-///   a) If the attacker's primary type is "Strong Against" the defender's primary type, add `0.375` to the type damage multiplier
-///   b) If the attacker's primary type is "Weak Against" the defender's primary type, subtract `0.225` from the type damage multiplier
-///   c) If the defender has a secondary type, and the attacker's primary type is "Strong Against" it, add `0.375` to the type damage multiplier
-///   d) If the defender has a secondary type, and the attacker's primary type is "Weak Against" it, subtract `0.225` from the type damage multiplier
-///   e) If the attacker has a secondary type, and the defender's primary type is "Weak Against" it, add `0.375` to the type damage multiplier
-///   f) If the attacker has a secondary type, and the defender's primary type is "Strong Against" it, subtract `0.225` from the type damage multiplier
-///   g) If both pokemon have a secondary type, and the defender's is "Weak Against" the attacker's, add `0.375` to the type damage multiplier
-///   h) If both pokemon have a secondary type, and the defender's is "Strong Against" the attacker's, subtract `0.225` from the type damage multiplier
+///    a) If the attacker's primary type is "Strong Against" the defender's primary type, add `0.375` to the type damage multiplier
+///    b) If the attacker's primary type is "Weak Against" the defender's primary type, subtract `0.225` from the type damage multiplier
+///    c) If the defender has a secondary type, and the attacker's primary type is "Strong Against" it, add `0.375` to the type damage multiplier
+///    d) If the defender has a secondary type, and the attacker's primary type is "Weak Against" it, subtract `0.225` from the type damage multiplier
+///    e) If the attacker has a secondary type, and the defender's primary type is "Weak Against" it, add `0.375` to the type damage multiplier
+///    f) If the attacker has a secondary type, and the defender's primary type is "Strong Against" it, subtract `0.225` from the type damage multiplier
+///    g) If both pokemon have a secondary type, and the defender's is "Weak Against" the attacker's, add `0.375` to the type damage multiplier
+///    h) If both pokemon have a secondary type, and the defender's is "Strong Against" the attacker's, subtract `0.225` from the type damage multiplier
 /// 4. The maximum type damage multiplier is `2.5`, the minimum is `0.1`. A type damage multiplier above `1.8` means an attack is "Super effective", while a type damage multiplier below `0.6` means an attack is "Not very effective"
 /// 5. A random multiplier between `0.8` and `1.2` is calculated
-/// 6. A defense multiplier is calculated by dividing the defender's `DEF`ense stat by the maximum value of `250.0`, multiplied by `0.75`, then subtracted from `1.0`, to give a total defense multiplier (which multiplies the damage incoming to the defender) between `0.0` for a `0 DEF` stat, and `0.75` for a `250 DEF` stat
+/// 6. A defense multiplier is calculated by dividing the defender's `DEF`ense stat by the maximum value of `250.0`, multiplied by `0.75`, then subtracted from `1.0`, to give a total defense multiplier (which multiplies the damage incoming to the defender). The subtracted share is capped at `0.75`, so the multiplier is between `1.0` (full damage) for a `0 DEF` stat, and `0.25` for a `250 DEF` stat or more
 /// 7. The base damage is multiplied by the type damage multiplier, the random multiplier, and the defense multiplier.
+///    If the hit is critical (with a `FightConfig::crit_chance` chance, `6.25%` by default), the damage is also multiplied by `1.5`
+///    If `FightConfig::damage_scale` is set, the damage is also multiplied by it, after the clamp of step 4, without changing the effectiveness.
+///    It is a global scale: it doesn't look at the type chart nor at the defender, so every hit of every attacker gets it,
+///    only an attacker with a secondary type gets half of it under `DualTypeScale::Split`
+///    In `Rain` a Water attacker, and in `Sun` a Fire attacker (by primary type), deals `1.5` times the damage (see `Weather`)
+///    If `FightConfig::max_hit_fraction` is set, the damage is capped to that fraction of the defender's max `HP`
 /// 8. The final damage is subtracted from the defender's `HP` (hit points) stat.
-/// 9. If the defender's `HP` falls below zero, a fight is concluded.
-/// 10. Otherwise, the roles of the attacker and the defender are reversed, the remaining `HP` is carried over to the next round, and the fight continues until one of the pokemons' `HP` falls to zero.
+/// 9. If the defender's `HP` falls to zero (less than half a point left rounds to zero), a fight is concluded. `HP` is never reported below zero.
+/// 10. Otherwise, if `FightConfig::status_chance` is set and the type damage multiplier is above `1`, the defender may get a status condition (see `StatusCondition`), if it has none yet
+/// 11. At the end of the attacker's turn, its burn or poison (if any) subtracts a fraction of its max `HP`, which can make it faint
+///     Then, in a `Sandstorm`, it loses 1/16 of its max `HP` unless it's a Rock or Ground type, which can make it faint
+///     Then, if it still stands and holds Leftovers, it heals 1/16 of its max `HP`, never past its max `HP`
+/// 12. The roles of the attacker and the defender are reversed, the remaining `HP` is carried over to the next round, and the fight continues until one of the pokemons' `HP` falls to zero. After `10000` turns with both pokemon standing (e.g. two pokemon that always miss), the fight ends in a draw instead, logging a `TurnLimit` event with the `turns` taken and no winner
 pub async fn process_fight_with_hp(
-    contender: &Pokemon,
-    challenger: &Pokemon,
-    mut contender_hp: u32,
-    mut challenger_hp: u32
+    contender: &CombatantState,
+    challenger: &CombatantState,
+    config: &FightConfig,
 ) -> Result<FightLog> {
-    // This is flipped because the starting pokemon is swapped by the last_to_attack logic
-    let starting_pokemon = if contender.stats.agility <= challenger.stats.agility {
-        contender
-    } else {
-        challenger
-    };
+    process_fight_with_hp_seeded(contender, challenger, rand::random(), config).await
+}
 
-    let contender_primary_type = contender.primary_type().clone().resolve().await?;
-    let contender_secondary_type = match contender.secondary_type().map(|t| t.clone().resolve()) {
-        Some(t) => Some(t.await?),
-        None => None,
-    };
+/// Same as `process_fight_with_hp`, but the random rolls are drawn from a generator seeded with `seed`,
+/// so the same seed always produces the same log. The seed is recorded in the log
+pub async fn process_fight_with_hp_seeded(
+    contender: &CombatantState,
+    challenger: &CombatantState,
+    seed: u64,
+    config: &FightConfig,
+) -> Result<FightLog> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut log = process_fight_with_rng(contender, challenger, config, &mut rng).await?;
+    log.seed = Some(seed);
+    Ok(log)
+}
 
-    let challenger_primary_type = challenger.primary_type().clone().resolve().await?;
-    let challenger_secondary_type = match challenger.secondary_type().map(|t| t.clone().resolve()) {
-        Some(t) => Some(t.await?),
-        None => None,
-    };
+/// Same as `process_fight_with_hp_seeded`, but the events are sent through the returned channel
+/// as the fight produces them, instead of being collected into a log
+/// The channel closes once a pokemon has won
+pub async fn stream_fight(
+    contender: &CombatantState,
+    challenger: &CombatantState,
+    seed: u64,
+    config: &FightConfig,
+) -> Result<UnboundedReceiver<FightEvent>> {
+    // resolved before the fight starts, so a missing type is an error rather than an empty stream
+    let contender_resolved = ResolvedPokemon::resolve(&contender.pokemon).await?;
+    let challenger_resolved = ResolvedPokemon::resolve(&challenger.pokemon).await?;
+    let (contender_hp, challenger_hp) = (contender.current_hp, challenger.current_hp);
+    let config = config.clone();
 
-    let mut last_to_attack = starting_pokemon.clone();
+    let (sender, receiver) = mpsc::unbounded_channel();
 
-    let mut log = FightLog {
-        contender_name: contender.name.clone(),
-        challenger_name: challenger.name.clone(),
-        log: vec![],
-    };
+    tokio::task::spawn_blocking(move || {
+        let mut rng = StdRng::seed_from_u64(seed);
 
-    while contender_hp > 0 && challenger_hp > 0 {
-        let (attacker, atk_ptype, atk_stype, atk_hp, defender, def_ptype, def_stype, mut def_hp) =
-            if &last_to_attack == challenger {
-                (
-                    contender,
-                    &contender_primary_type,
-                    &contender_secondary_type,
-                    contender_hp as f32,
-                    challenger,
-                    &challenger_primary_type,
-                    &challenger_secondary_type,
-                    challenger_hp as f32,
-                )
-            } else {
-                (
-                    challenger,
-                    &challenger_primary_type,
-                    &challenger_secondary_type,
-                    challenger_hp as f32,
-                    contender,
-                    &contender_primary_type,
-                    &contender_secondary_type,
-                    contender_hp as f32,
-                )
-            };
+        fight_events(
+            &contender_resolved,
+            &challenger_resolved,
+            contender_hp,
+            challenger_hp,
+            &config,
+            &mut rng,
+            // a client that stopped listening doesn't stop the fight, it's over quickly anyway
+            &mut |event| {
+                let _ = sender.send(event);
+            },
+        );
+    });
+
+    Ok(receiver)
+}
 
-        let mut damage_mult: f32 = 1.0;
+/// Same as `process_fight_with_hp`, but every random roll is drawn from `rng`,
+/// so a seeded generator always reproduces the same fight
+pub async fn process_fight_with_rng<R: Rng + Send>(
+    contender: &CombatantState,
+    challenger: &CombatantState,
+    config: &FightConfig,
+    rng: &mut R,
+) -> Result<FightLog> {
+    let contender_resolved = ResolvedPokemon::resolve(&contender.pokemon).await?;
+    let challenger_resolved = ResolvedPokemon::resolve(&challenger.pokemon).await?;
+
+    Ok(process_fight_resolved(
+        &contender_resolved,
+        &challenger_resolved,
+        contender.current_hp,
+        challenger.current_hp,
+        config,
+        rng,
+    ))
+}
+
+/// A pokemon as it stands in a fight: its stats hold its base (max) HP,
+/// while `current_hp` is what it has left, e.g. carried over from an earlier fight
+#[derive(Clone, Debug)]
+pub struct CombatantState {
+    /// The fighting pokemon
+    pub pokemon: Pokemon,
+    /// The HP the pokemon has left
+    pub current_hp: u32,
+}
 
-        // Calculate primary vs primary type advantage
-        if atk_ptype.is_strong_against(def_ptype) {
-            damage_mult += 0.375
-        } else if atk_ptype.is_weak_against(def_ptype) {
-            damage_mult -= 0.225
+impl CombatantState {
+    /// A pokemon entering the fight with the given HP left
+    pub fn new(pokemon: Pokemon, current_hp: u32) -> Self {
+        Self {
+            pokemon,
+            current_hp,
         }
+    }
 
-        // Calculate secondary vs primary type advantage
-        if let Some(def_stype) = def_stype {
-            if atk_ptype.is_strong_against(def_stype) {
-                damage_mult += 0.375
-            } else if atk_ptype.is_weak_against(def_stype) {
-                damage_mult -= 0.225
-            }
+    /// A pokemon entering the fight with full HP
+    pub fn full(pokemon: Pokemon) -> Self {
+        let current_hp = pokemon.stats.hp;
+        Self::new(pokemon, current_hp)
+    }
+
+    /// Whether the pokemon has no HP left
+    pub fn is_fainted(&self) -> bool {
+        self.current_hp == 0
+    }
+}
+
+/// A pokemon together with its already resolved types,
+/// so it can fight without any database access
+#[derive(Clone, Debug)]
+pub struct ResolvedPokemon {
+    /// The pokemon itself
+    pub pokemon: Pokemon,
+    /// The resolved primary type of the pokemon
+    pub primary_type: PokemonType,
+    /// The resolved secondary type of the pokemon, if it has one
+    pub secondary_type: Option<PokemonType>,
+}
+
+impl ResolvedPokemon {
+    /// Pairs a pokemon with types provided by the caller
+    pub fn new(
+        pokemon: Pokemon,
+        primary_type: PokemonType,
+        secondary_type: Option<PokemonType>,
+    ) -> Self {
+        Self {
+            pokemon,
+            primary_type,
+            secondary_type,
         }
+    }
 
-        // Calculate primary vs secondary type advantage
-        if let Some(atk_stype) = atk_stype {
-            if def_ptype.is_strong_against(atk_stype) {
-                damage_mult -= 0.225
-            } else if def_ptype.is_weak_against(atk_stype) {
-                damage_mult += 0.375
-            }
+    /// Resolves the types of a pokemon (from the database, unless they're concrete)
+    pub async fn resolve(pokemon: &Pokemon) -> Result<Self> {
+        let primary_type = pokemon.primary_type().clone().resolve().await?;
+        let secondary_type = match pokemon.secondary_type().map(|t| t.clone().resolve()) {
+            Some(t) => Some(t.await?),
+            None => None,
+        };
+
+        Ok(Self::new(pokemon.clone(), primary_type, secondary_type))
+    }
+
+    /// Turns this back into a pokemon that holds its types as concrete values,
+    /// so fighting with it again never queries the database for its types
+    /// The held item and the fight count are kept as they are
+    pub fn into_concrete(self) -> Pokemon {
+        let mut pokemon = Pokemon::new(
+            self.pokemon.name,
+            MaybePromise::from_concrete(self.primary_type),
+            self.secondary_type.map(MaybePromise::from_concrete),
+            self.pokemon.stats,
+        );
+        pokemon.held_item = self.pokemon.held_item;
+        pokemon.fight_count = self.pokemon.fight_count;
+        pokemon
+    }
+}
+
+/// The whole HP a pokemon has left, never below `0`
+/// Less than half a point rounds to `0`, which counts as fainted
+fn whole_hp(hp: f32) -> u32 {
+    hp.max(0.0).round() as u32
+}
+
+/// The share of its max HP a pokemon has left, `0.0` for a pokemon without max HP
+fn hp_fraction(hp_left: u32, max_hp: u32) -> f32 {
+    if max_hp == 0 {
+        return 0.0;
+    }
+
+    (hp_left as f32 / max_hp as f32).min(1.0)
+}
+
+/// The multiplier of the damage a defender with `defense` takes (step 6 of the fight algorithm):
+/// `1.0` at `0 DEF` down to `0.25` at `250 DEF` and above
+pub fn defense_multiplier(defense: u32) -> f32 {
+    1.0 - ((defense as f32 / 250.0) * 0.75).clamp(0.0, 0.75)
+}
+
+/// Rolls whether the contender attacks first in a round, with a chance proportional to its agility
+/// Two pokemon without any agility have even chances
+fn contender_strikes_first<R: Rng>(
+    contender: &ResolvedPokemon,
+    challenger: &ResolvedPokemon,
+    rng: &mut R,
+) -> bool {
+    let contender_agility = contender.pokemon.stats.agility as f64;
+    let total = contender_agility + challenger.pokemon.stats.agility as f64;
+
+    if total == 0.0 {
+        return rng.gen_bool(0.5);
+    }
+
+    rng.gen_bool(contender_agility / total)
+}
+
+/// The core of the fight engine, same as `process_fight_with_rng`
+/// but with the types of both pokemon already provided,
+/// so it never touches the database
+pub fn process_fight_resolved<R: Rng>(
+    contender: &ResolvedPokemon,
+    challenger: &ResolvedPokemon,
+    contender_hp: u32,
+    challenger_hp: u32,
+    config: &FightConfig,
+    rng: &mut R,
+) -> FightLog {
+    let mut log = FightLog::new(
+        contender.pokemon.name.clone(),
+        challenger.pokemon.name.clone(),
+    );
+
+    fight_events(
+        contender,
+        challenger,
+        contender_hp,
+        challenger_hp,
+        config,
+        rng,
+        &mut |event| log.log.push(event),
+    );
+
+    log.summarize();
+    log
+}
+
+/// Same as `process_fight_resolved`, but every event is handed to `emit` as soon as it happens
+/// instead of being collected into a log
+pub fn fight_events<R: Rng, E: FnMut(FightEvent)>(
+    contender: &ResolvedPokemon,
+    challenger: &ResolvedPokemon,
+    mut contender_hp: u32,
+    mut challenger_hp: u32,
+    config: &FightConfig,
+    rng: &mut R,
+    emit: &mut E,
+) {
+    // Turns are tracked by side rather than by pokemon, so two pokemon
+    // with the same name or stats still take turns correctly
+    // This is flipped because the sides are swapped before the first attack
+    let mut challenger_attacked_last =
+        contender.pokemon.stats.agility > challenger.pokemon.stats.agility;
+
+    let mut contender_status = StatusCondition::None;
+    let mut challenger_status = StatusCondition::None;
+
+    // every pokemon fight starts with full stamina
+    let mut contender_stamina = contender.pokemon.stats.stamina;
+    let mut challenger_stamina = challenger.pokemon.stats.stamina;
+
+    let mut turn = 0;
+
+    while contender_hp > 0 && challenger_hp > 0 {
+        if turn == MAX_FIGHT_TURNS {
+            emit(FightEvent::TurnLimit { turns: turn });
+            return;
         }
 
-        // Calculate secondary vs secondary type advantage
-        if let Some(atk_stype) = atk_stype {
-            if let Some(def_stype) = def_stype {
-                if def_stype.is_strong_against(atk_stype) {
-                    damage_mult -= 0.225
-                } else if def_stype.is_weak_against(atk_stype) {
-                    damage_mult += 0.375
-                }
-            }
+        // a round is one turn of each pokemon
+        if turn % 2 == 0 && config.turn_order == TurnOrder::AgilityWeighted {
+            challenger_attacked_last = contender_strikes_first(contender, challenger, rng);
         }
+        turn += 1;
 
-        // total max dmg mult = 2.5
-        // total min dmg mult = 0.1
+        let (attacker, mut atk_hp, defender, mut def_hp) = if challenger_attacked_last {
+            (
+                contender,
+                contender_hp as f32,
+                challenger,
+                challenger_hp as f32,
+            )
+        } else {
+            (
+                challenger,
+                challenger_hp as f32,
+                contender,
+                contender_hp as f32,
+            )
+        };
 
-        let effectiveness = match damage_mult {
-            x if x > 1.8 => Effectiveness::SuperEffective,
-            x if x < 0.6 => Effectiveness::NotVeryEffective,
-            _ => Effectiveness::Normal,
+        let (attacker_status, defender_status) = if challenger_attacked_last {
+            (&mut contender_status, &mut challenger_status)
+        } else {
+            (&mut challenger_status, &mut contender_status)
         };
 
+        let attacker_stamina = if challenger_attacked_last {
+            &mut contender_stamina
+        } else {
+            &mut challenger_stamina
+        };
+
+        // a pokemon too tired to attack rests instead, unless its stamina is already full
+        // (a pool smaller than the attack cost still allows an attack from full stamina)
+        if let (Some(stamina), Some(max_stamina)) =
+            (attacker_stamina.as_mut(), attacker.pokemon.stats.stamina)
+            && *stamina < STAMINA_ATTACK_COST
+            && *stamina < max_stamina
+        {
+            *stamina = (*stamina + STAMINA_REGEN).min(max_stamina);
+
+            emit(FightEvent::Rest {
+                pokemon: attacker.pokemon.name.clone(),
+                stamina: *stamina,
+            });
+
+            challenger_attacked_last = !challenger_attacked_last;
+            continue;
+        }
+
+        // only rolled for a paralyzed attacker, so other fights draw the same random numbers as before
+        if attacker_status.skip_chance() > 0.0 && rng.r#gen::<f32>() < attacker_status.skip_chance()
+        {
+            emit(FightEvent::Paralyzed {
+                pokemon: attacker.pokemon.name.clone(),
+            });
+
+            challenger_attacked_last = !challenger_attacked_last;
+            continue;
+        }
+
+        if let (Some(stamina), Some(max_stamina)) =
+            (attacker_stamina.as_mut(), attacker.pokemon.stats.stamina)
+        {
+            *stamina = (stamina.saturating_sub(STAMINA_ATTACK_COST) + STAMINA_REGEN).min(max_stamina);
+        }
+
+        // only rolled for an attacker that can miss, so other fights draw the same random numbers as before
+        if attacker.pokemon.stats.accuracy < MAX_ACCURACY
+            && rng.r#gen::<f32>() >= attacker.pokemon.stats.hit_chance()
+        {
+            emit(FightEvent::Missed {
+                attacker: attacker.pokemon.name.clone(),
+                defender: defender.pokemon.name.clone(),
+            });
+
+            challenger_attacked_last = !challenger_attacked_last;
+            continue;
+        }
+
+        let TypeBreakdown {
+            multiplier: damage_mult,
+            effectiveness,
+            ..
+        } = type_damage_multiplier(
+            &attacker.primary_type,
+            attacker.secondary_type.as_ref(),
+            &defender.primary_type,
+            defender.secondary_type.as_ref(),
+        );
+
         // 0.8 - 1.2
-        let rand_mult = 0.8 + (rand::random::<f32>() * 0.4);
+        let rand_mult = 0.8 + (rng.r#gen::<f32>() * 0.4);
+
+        // only rolled when crits are on, so fights without them draw the same random numbers as before
+        let crit = config.crit_chance > 0.0 && rng.r#gen::<f32>() < config.crit_chance;
+        let crit_mult = if crit { CRIT_MULTIPLIER } else { 1.0 };
+
+        let defense_mult = defense_multiplier(defender.pokemon.stats.defense);
+
+        let attack = attacker.pokemon.stats.attack as f32
+            * attacker_status.attack_multiplier()
+            * attacker.pokemon.held_item.map_or(1.0, |item| item.attack_multiplier())
+            * attacker.pokemon.stats.level_multiplier();
+
+        let scale_mult = config.damage_scale(attacker.secondary_type.is_some());
 
-        let defense_mult = 1.0 - ((defender.stats.defense as f32 / 100.0) * 0.5);
+        let weather_mult = config.weather.damage_multiplier(&attacker.primary_type);
 
-        let damage = ((attacker.stats.attack as f32 * damage_mult) * rand_mult) * defense_mult;
+        let damage = ((attack * damage_mult * scale_mult * weather_mult) * rand_mult)
+            * defense_mult
+            * crit_mult;
 
-        def_hp -= damage;
+        let damage = config.cap_damage(damage, defender.pokemon.stats.hp);
+
+        def_hp = (def_hp - damage).max(0.0);
+
+        let hp_left = whole_hp(def_hp);
 
         let event = FightEvent::Hit {
-            attacker: attacker.name.clone(),
-            defender: defender.name.clone(),
+            attacker: attacker.pokemon.name.clone(),
+            defender: defender.pokemon.name.clone(),
             damage: damage as u32,
-            hp_left: max(def_hp.round() as u32, 0),
+            hp_left,
+            attacker_hp: whole_hp(atk_hp),
             effectiveness,
+            crit,
+            hp_fraction: Some(hp_fraction(hp_left, defender.pokemon.stats.hp)),
         };
 
-        log.log.push(event);
+        emit(event);
 
-        if def_hp <= 0.0 {
+        // a defender reported with no HP left faints, even if a fraction of a point remains
+        if hp_left == 0 {
             let event = FightEvent::Fainted {
-                pokemon: defender.name.clone(),
+                pokemon: defender.pokemon.name.clone(),
             };
 
-            log.log.push(event);
+            emit(event);
 
             let event = FightEvent::PokemonWinner {
-                pokemon: attacker.name.clone(),
-                hp_left: atk_hp.round() as u32,
+                pokemon: attacker.pokemon.name.clone(),
+                hp_left: whole_hp(atk_hp),
             };
 
-            log.log.push(event);
+            emit(event);
+        } else {
+            // the chance is only rolled when it can succeed, so fights without
+            // status conditions draw the same random numbers as before
+            let condition = StatusCondition::inflicted_by(&attacker.primary_type, config);
+
+            if config.status_chance > 0.0
+                && damage_mult > 1.0
+                && *defender_status == StatusCondition::None
+                && condition != StatusCondition::None
+                && rng.r#gen::<f32>() < config.status_chance
+            {
+                *defender_status = condition;
+
+                emit(FightEvent::StatusApplied {
+                    pokemon: defender.pokemon.name.clone(),
+                    condition,
+                });
+            }
+
+            // the attacker's turn ends, its status chips its HP
+            if let Some(damage) = attacker_status.residual_damage(attacker.pokemon.stats.hp) {
+                atk_hp = (atk_hp - damage as f32).max(0.0);
+
+                emit(FightEvent::StatusDamage {
+                    pokemon: attacker.pokemon.name.clone(),
+                    condition: *attacker_status,
+                    damage,
+                    hp_left: whole_hp(atk_hp),
+                });
+
+                if whole_hp(atk_hp) == 0 {
+                    emit(FightEvent::Fainted {
+                        pokemon: attacker.pokemon.name.clone(),
+                    });
+
+                    emit(FightEvent::PokemonWinner {
+                        pokemon: defender.pokemon.name.clone(),
+                        hp_left: whole_hp(def_hp),
+                    });
+                }
+            }
+
+            // then the weather chips its HP, if it still stands
+            if whole_hp(atk_hp) > 0
+                && let Some(damage) = config.weather.residual_damage(
+                    &attacker.primary_type,
+                    attacker.secondary_type.as_ref(),
+                    attacker.pokemon.stats.hp,
+                )
+            {
+                atk_hp = (atk_hp - damage as f32).max(0.0);
+
+                emit(FightEvent::WeatherDamage {
+                    pokemon: attacker.pokemon.name.clone(),
+                    weather: config.weather,
+                    damage,
+                    hp_left: whole_hp(atk_hp),
+                });
+
+                if whole_hp(atk_hp) == 0 {
+                    emit(FightEvent::Fainted {
+                        pokemon: attacker.pokemon.name.clone(),
+                    });
+
+                    emit(FightEvent::PokemonWinner {
+                        pokemon: defender.pokemon.name.clone(),
+                        hp_left: whole_hp(def_hp),
+                    });
+                }
+            }
+
+            // then its held item heals it, never past its max HP
+            let max_hp = attacker.pokemon.stats.hp;
+
+            if whole_hp(atk_hp) > 0
+                && let Some(item) = attacker.pokemon.held_item
+                && let Some(heal) = item.end_of_turn_heal(max_hp)
+                && whole_hp(atk_hp) < max_hp
+            {
+                let amount = heal.min(max_hp - whole_hp(atk_hp));
+                atk_hp = (whole_hp(atk_hp) + amount) as f32;
+
+                emit(FightEvent::ItemHealed {
+                    pokemon: attacker.pokemon.name.clone(),
+                    amount,
+                    hp_left: whole_hp(atk_hp),
+                });
+            }
         }
 
-        last_to_attack = attacker.clone();
+        challenger_attacked_last = !challenger_attacked_last;
 
-        if &last_to_attack == challenger {
-            contender_hp = def_hp.round() as u32;
-            challenger_hp = atk_hp.round() as u32;
+        if challenger_attacked_last {
+            contender_hp = whole_hp(def_hp);
+            challenger_hp = whole_hp(atk_hp);
         } else {
-            contender_hp = atk_hp.round() as u32;
-            challenger_hp = def_hp.round() as u32;
+            contender_hp = whole_hp(atk_hp);
+            challenger_hp = whole_hp(def_hp);
         }
     }
-
-    Ok(log)
 }
 
 /// Processes a fight between two Pokemon and returns a log of the battle
-pub async fn process_fight(contender: &Pokemon, challenger: &Pokemon) -> Result<FightLog> {
-    let contender_hp = contender.stats.hp;
-    let challenger_hp = challenger.stats.hp;
+pub async fn process_fight(
+    contender: &Pokemon,
+    challenger: &Pokemon,
+    config: &FightConfig,
+) -> Result<FightLog> {
+    let contender = CombatantState::full(contender.clone());
+    let challenger = CombatantState::full(challenger.clone());
 
-    process_fight_with_hp(contender, challenger, contender_hp, challenger_hp).await
+    process_fight_with_hp(&contender, &challenger, config).await
+}
+
+/// Processes a fight between two Pokemon using a seeded random generator,
+/// the same seed always produces the same log
+pub async fn process_fight_seeded(
+    contender: &Pokemon,
+    challenger: &Pokemon,
+    seed: u64,
+    config: &FightConfig,
+) -> Result<FightLog> {
+    process_fight_with_hp_seeded(
+        &CombatantState::full(contender.clone()),
+        &CombatantState::full(challenger.clone()),
+        seed,
+        config,
+    )
+    .await
+}
+
+/// Processes a fight between two Pokemon without any randomness:
+/// the random multiplier (step 5) is always exactly `1.0`, and every other roll
+/// (status conditions, a weighted turn order) is fixed, so the log only depends on stats and types
+/// This is the same expected-damage assumption `predictor::MeanRng` makes for trainer fights
+pub async fn process_fight_deterministic(
+    contender: &Pokemon,
+    challenger: &Pokemon,
+    config: &FightConfig,
+) -> Result<FightLog> {
+    process_fight_with_rng(
+        &CombatantState::full(contender.clone()),
+        &CombatantState::full(challenger.clone()),
+        config,
+        &mut MeanRng,
+    )
+    .await
 }
@@ -0,0 +1,51 @@
+use anyhow::Result;
+use rand::RngCore;
+
+use super::{FightLog, FightStrategy, config::FightConfig, trainer_fight};
+use crate::trainer::Trainer;
+
+/// A "random" generator that always rolls the middle of the range,
+/// so every hit deals its expected damage (a random multiplier of exactly `1.0`)
+/// Fights simulated with it are deterministic predictions of the average outcome
+pub struct MeanRng;
+
+impl RngCore for MeanRng {
+    fn next_u32(&mut self) -> u32 {
+        // floats are made from the high bits, this is exactly 0.5
+        1 << 31
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        1 << 63
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        dest.fill(0x80);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> std::result::Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// Predicts the outcome of a trainer fight, with every hit dealing its expected damage
+/// The same fight always gives the same prediction, whatever strategy each trainer uses,
+/// as the `Random` strategy also draws from the fixed rolls
+pub async fn predict_trainer_fight(
+    challenger: &Trainer,
+    contender: &Trainer,
+    challenger_strat: FightStrategy,
+    contender_strat: FightStrategy,
+    config: &FightConfig,
+) -> Result<FightLog> {
+    trainer_fight::process_fight_with_rng(
+        challenger,
+        contender,
+        challenger_strat,
+        contender_strat,
+        config,
+        &mut MeanRng,
+    )
+    .await
+}
@@ -0,0 +1,213 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use super::{FightEvent, FightLog};
+use crate::database::{
+    AsDbString, DbHandle, DbQuery, DbRepr, DbValue,
+    link::DbLink,
+    promise::MaybePromise,
+    put::DbPut,
+    sanitize,
+};
+use crate::pokemon::Pokemon;
+
+/// A pokemon fight stored in the database, linked to the pokemon that took part in it
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct FightRecord {
+    /// The identifier of the stored fight
+    pub id: String,
+    /// The name of the contender pokemon
+    pub contender: String,
+    /// The name of the challenger pokemon
+    pub challenger: String,
+    /// The name of the pokemon that won, if the fight had a winner
+    pub winner: Option<String>,
+    /// The number of turns the fight took, see `FightLog::turns`
+    pub turn_count: u32,
+}
+
+/// Represents the relationship between a stored fight and a pokemon
+pub enum Relationship {
+    /// The pokemon fought in the fight
+    Participated,
+}
+
+impl AsDbString for Relationship {
+    fn as_db_string(&self) -> &'static str {
+        match self {
+            Relationship::Participated => "Participated",
+        }
+    }
+}
+
+impl DbRepr for FightRecord {
+    const DB_NODE_KIND: &'static str = "Fight";
+
+    fn get_db_identifier(&self) -> String {
+        format!("'{}'", sanitize(&self.id))
+    }
+
+    fn get_raw_identifier(&self) -> &str {
+        &self.id
+    }
+}
+
+impl DbPut for FightRecord {
+    fn put_args(&self) -> Vec<(&'static str, DbValue)> {
+        let mut args = vec![
+            ("id", self.id.as_str().into()),
+            ("contender", self.contender.as_str().into()),
+            ("challenger", self.challenger.as_str().into()),
+            ("turn_count", self.turn_count.into()),
+        ];
+
+        if let Some(winner) = &self.winner {
+            args.push(("winner", winner.as_str().into()));
+        }
+
+        args
+    }
+}
+
+/// A stored fight only points at its participants, it keeps no local copy of them
+impl DbLink<Pokemon> for FightRecord {
+    type RelationshipType = Relationship;
+
+    fn link_side_effect(
+        &mut self,
+        _other: &MaybePromise<Pokemon>,
+        _relationship_type: &Self::RelationshipType,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    fn unlink_side_effect(
+        &mut self,
+        _other: &MaybePromise<Pokemon>,
+        _relationship_type: &Self::RelationshipType,
+    ) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl FightRecord {
+    /// Makes a record of a pokemon fight under a new random identifier
+    pub fn from_log(log: &FightLog) -> Self {
+        let winner = log.log.iter().rev().find_map(|event| match event {
+            FightEvent::PokemonWinner { pokemon, .. } => Some(pokemon.clone()),
+            _ => None,
+        });
+
+        Self {
+            id: format!("{:016x}", rand::random::<u64>()),
+            contender: log.contender_name.clone(),
+            challenger: log.challenger_name.clone(),
+            winner,
+            turn_count: log.turns() as u32,
+        }
+    }
+
+    /// The queries `save` executes, in order, without executing them
+    pub fn save_queries(&self) -> Vec<DbQuery> {
+        let participant =
+            |name: &str| MaybePromise::<Pokemon>::from_ident_unchecked(name.to_string());
+
+        vec![
+            self.put_query(),
+            self.link_query(&participant(&self.contender), &Relationship::Participated),
+            self.link_query(&participant(&self.challenger), &Relationship::Participated),
+        ]
+    }
+
+    /// Stores the fight and links it to both participants in a single transaction,
+    /// so a fight is never stored without its participants
+    pub async fn save(&self) -> Result<()> {
+        let db = DbHandle::connect().await?;
+        let mut txn = db.inner.start_txn().await?;
+
+        if let Err(e) = txn.run_queries(self.save_queries()).await {
+            txn.rollback().await?;
+            return Err(e.into());
+        }
+
+        txn.commit().await?;
+
+        Ok(())
+    }
+
+    /// Gets every stored fight the named pokemon took part in
+    pub async fn involving(pokemon_name: &str) -> Result<Vec<FightRecord>> {
+        let db = DbHandle::connect().await?;
+
+        let query = DbQuery::new(format!(
+            "MATCH (f:{})-[:{}]->(p:{}) WHERE p.{} = $name RETURN DISTINCT f ORDER BY f.id;",
+            Self::DB_NODE_KIND,
+            Relationship::Participated.as_db_string(),
+            Pokemon::DB_NODE_KIND,
+            Pokemon::DB_IDENTIFIER_FIELD
+        ))
+        .param("name", pokemon_name);
+
+        debug!("Fights Involving Query: {:?}", query);
+
+        let mut q_out = db.inner.execute(query.into()).await?;
+
+        let mut fights = vec![];
+
+        while let Some(row) = q_out.next().await? {
+            fights.push(Self::from_node(&row.get::<neo4rs::Node>("f")?)?);
+        }
+
+        Ok(fights)
+    }
+
+    /// Gets every stored fight won by `winner` that took between `min_turns` and `max_turns` turns,
+    /// each filter only applying if given. The filters run on the stored properties, no log is read
+    pub async fn filter(
+        winner: Option<&str>,
+        min_turns: Option<u32>,
+        max_turns: Option<u32>,
+    ) -> Result<Vec<FightRecord>> {
+        let db = DbHandle::connect().await?;
+
+        let query = format!(
+            "MATCH (f:{}) WHERE ($winner IS NULL OR f.winner = $winner) \
+            AND ($min_turns IS NULL OR f.turn_count >= $min_turns) \
+            AND ($max_turns IS NULL OR f.turn_count <= $max_turns) \
+            RETURN f ORDER BY f.id;",
+            Self::DB_NODE_KIND
+        );
+
+        debug!(
+            "Filter Fights Query: {} with winner = {:?}, min_turns = {:?}, max_turns = {:?}",
+            query, winner, min_turns, max_turns
+        );
+
+        let query = neo4rs::query(&query)
+            .param("winner", winner.map(str::to_string))
+            .param("min_turns", min_turns.map(i64::from))
+            .param("max_turns", max_turns.map(i64::from));
+
+        let mut q_out = db.inner.execute(query).await?;
+
+        let mut fights = vec![];
+
+        while let Some(row) = q_out.next().await? {
+            fights.push(Self::from_node(&row.get::<neo4rs::Node>("f")?)?);
+        }
+
+        Ok(fights)
+    }
+
+    /// Reads a stored fight from its database node
+    fn from_node(node: &neo4rs::Node) -> Result<Self> {
+        Ok(Self {
+            id: node.get("id")?,
+            contender: node.get("contender")?,
+            challenger: node.get("challenger")?,
+            winner: node.get("winner").ok(),
+            // fights stored before turns were counted have no turn count
+            turn_count: node.get("turn_count").unwrap_or(0),
+        })
+    }
+}
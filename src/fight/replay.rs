@@ -0,0 +1,60 @@
+use anyhow::{Result, anyhow};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use serde::{Deserialize, Serialize};
+
+/// The current version of the replay token format
+const TOKEN_VERSION: u8 = 1;
+
+/// A compact description of a seeded pokemon fight,
+/// which is enough to re-run the fight and reproduce its exact log
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ReplayToken {
+    #[serde(rename = "v")]
+    version: u8,
+    /// The name of the contender pokemon
+    #[serde(rename = "c")]
+    pub contender: String,
+    /// The name of the challenger pokemon
+    #[serde(rename = "h")]
+    pub challenger: String,
+    /// The seed the fight was simulated with
+    #[serde(rename = "s")]
+    pub seed: u64,
+}
+
+impl ReplayToken {
+    /// Creates a new replay token for a fight
+    pub fn new(contender: String, challenger: String, seed: u64) -> Self {
+        Self {
+            version: TOKEN_VERSION,
+            contender,
+            challenger,
+            seed,
+        }
+    }
+
+    /// Encodes the token as a URL-safe string
+    pub fn encode(&self) -> String {
+        // serializing a struct of strings and numbers cannot fail
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap())
+    }
+
+    /// Decodes a token previously made with `encode`
+    pub fn decode(token: &str) -> Result<Self> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(token)
+            .map_err(|_| anyhow!("Invalid replay token"))?;
+
+        let token: Self =
+            serde_json::from_slice(&bytes).map_err(|_| anyhow!("Invalid replay token"))?;
+
+        if token.version != TOKEN_VERSION {
+            return Err(anyhow!(
+                "Unsupported replay token version {}",
+                token.version
+            ));
+        }
+
+        Ok(token)
+    }
+}
@@ -0,0 +1,156 @@
+use anyhow::{Result, anyhow};
+use serde::Serialize;
+
+use super::{FightEvent, FightStrategy, batch, config::FightConfig, trainer_fight};
+use crate::trainer::Trainer;
+
+/// The largest number of trainers a round robin can have, every pairing is a trainer fight
+pub const MAX_TRAINERS: usize = 16;
+
+/// A single trainer fight of a round robin
+#[derive(Serialize, Clone, Debug)]
+pub struct Fixture {
+    /// The trainer who picked their pokemon second
+    pub challenger: String,
+    /// The trainer who picked their pokemon first
+    pub contender: String,
+    /// The name of the winning trainer
+    pub winner: String,
+    /// The number of pokemon the winner had left standing
+    pub pokemon_left: usize,
+}
+
+/// A trainer's row in the round robin table
+#[derive(Serialize, Clone, Debug)]
+pub struct Standing {
+    /// The name of the trainer
+    pub trainer: String,
+    /// The number of fixtures the trainer played
+    pub played: usize,
+    /// The number of fixtures the trainer won
+    pub wins: usize,
+    /// The number of fixtures the trainer lost
+    pub losses: usize,
+    /// One point for every win, trainer fights can't end in a draw
+    pub points: usize,
+    /// The number of pokemon the trainer had left standing, summed over the fixtures they won
+    pub pokemon_left: usize,
+}
+
+/// The fixtures and final table of a round robin
+#[derive(Serialize, Clone, Debug)]
+pub struct RoundRobin {
+    /// Every fixture played, in schedule order
+    pub fixtures: Vec<Fixture>,
+    /// The standings, sorted by points (ties by pokemon left, then by name)
+    pub table: Vec<Standing>,
+}
+
+/// Plays every pairing of `entries` once, or twice with `home_and_away`,
+/// so each trainer is the contender (who picks first) once against every other trainer
+/// Fixtures are simulated concurrently, at most `max_concurrent_fights` (from `config`) at a time,
+/// and are returned in schedule order whichever finishes first
+/// At most `MAX_TRAINERS` trainers can be entered
+pub async fn round_robin(
+    entries: &[(Trainer, FightStrategy)],
+    home_and_away: bool,
+    config: &FightConfig,
+) -> Result<RoundRobin> {
+    if entries.len() > MAX_TRAINERS {
+        return Err(anyhow!(
+            "A round robin can have at most {MAX_TRAINERS} trainers, got {}",
+            entries.len()
+        ));
+    }
+
+    for (i, (trainer, _)) in entries.iter().enumerate() {
+        if entries[..i]
+            .iter()
+            .any(|(other, _)| other.name == trainer.name)
+        {
+            return Err(anyhow!(
+                "Trainer {} is entered more than once",
+                trainer.name
+            ));
+        }
+    }
+
+    let mut schedule = vec![];
+
+    for i in 0..entries.len() {
+        for j in (i + 1)..entries.len() {
+            schedule.push((i, j));
+
+            if home_and_away {
+                schedule.push((j, i));
+            }
+        }
+    }
+
+    let fights: Vec<_> = schedule
+        .into_iter()
+        .map(|(challenger, contender)| async move {
+            let (challenger, challenger_strat) = &entries[challenger];
+            let (contender, contender_strat) = &entries[contender];
+
+            let log = trainer_fight::process_fight(
+                challenger,
+                contender,
+                challenger_strat.clone(),
+                contender_strat.clone(),
+                config,
+            )
+            .await?;
+
+            let (winner, pokemon_left) = match log.log.last() {
+                Some(FightEvent::Winner {
+                    trainer,
+                    pokemon_left,
+                }) => (trainer.clone(), pokemon_left.len()),
+                _ => return Err(anyhow!("The fight log has no winner")),
+            };
+
+            Ok(Fixture {
+                challenger: challenger.name.clone(),
+                contender: contender.name.clone(),
+                winner,
+                pokemon_left,
+            })
+        })
+        .collect();
+
+    let fixtures: Vec<Fixture> = batch::run_bounded(fights, config.max_concurrent_fights).await?;
+
+    let mut table: Vec<Standing> = entries
+        .iter()
+        .map(|(trainer, _)| {
+            let played = fixtures
+                .iter()
+                .filter(|f| f.challenger == trainer.name || f.contender == trainer.name)
+                .count();
+            let won: Vec<&Fixture> = fixtures
+                .iter()
+                .filter(|f| f.winner == trainer.name)
+                .collect();
+            let wins = won.len();
+
+            Standing {
+                trainer: trainer.name.clone(),
+                played,
+                wins,
+                losses: played - wins,
+                points: wins,
+                pokemon_left: won.iter().map(|f| f.pokemon_left).sum(),
+            }
+        })
+        .collect();
+
+    table.sort_by(|a, b| {
+        b.points
+            .cmp(&a.points)
+            .then_with(|| b.pokemon_left.cmp(&a.pokemon_left))
+            .then_with(|| a.trainer.cmp(&b.trainer))
+    });
+
+    Ok(RoundRobin { fixtures, table })
+}
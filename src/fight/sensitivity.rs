@@ -0,0 +1,140 @@
+use anyhow::{Result, anyhow};
+use rand::{SeedableRng, rngs::StdRng};
+use serde::Serialize;
+
+use super::{
+    FightEvent,
+    config::FightConfig,
+    odds::MAX_TRIALS,
+    pokemon_fight::{self, ResolvedPokemon},
+};
+use crate::pokemon::{Pokemon, stats::PokemonStats};
+
+/// The number of trials used when the request doesn't specify one
+pub const DEFAULT_TRIALS: u32 = 100;
+
+/// Adds one point to a stat
+type RaiseStat = fn(&mut PokemonStats);
+
+/// The stats that get perturbed, by their name in the pokemon JSON
+const STATS: [(&str, RaiseStat); 4] = [
+    ("hp", |stats| stats.hp += 1),
+    ("atk", |stats| stats.attack += 1),
+    ("def", |stats| stats.defense += 1),
+    ("agi", |stats| stats.agility += 1),
+];
+
+/// How the win rate changes with one more point of a stat
+#[derive(Serialize, Clone, Debug)]
+pub struct StatSensitivity {
+    /// The stat, by its name in the pokemon JSON
+    pub stat: String,
+    /// The win rate with one more point of the stat
+    pub win_rate: f32,
+    /// `win_rate` minus the unchanged win rate
+    pub change: f32,
+}
+
+/// How much each of a pokemon's stats matters in a matchup
+#[derive(Serialize, Clone, Debug)]
+pub struct Sensitivity {
+    /// The name of the pokemon whose stats are perturbed
+    pub pokemon: String,
+    /// The name of the opponent
+    pub opponent: String,
+    /// The number of fights simulated for every stat
+    pub trials: u32,
+    /// The seed of the first trial, trial `i` is seeded with `seed + i`
+    pub seed: u64,
+    /// The win rate with the pokemon's stats unchanged
+    pub win_rate: f32,
+    /// The change in win rate for each stat
+    pub stats: Vec<StatSensitivity>,
+    /// The stat whose extra point raises the win rate the most,
+    /// none if no stat changes it
+    pub most_sensitive: Option<String>,
+}
+
+/// The share of `trials` seeded fights `pokemon` wins against `opponent`
+fn win_rate(
+    pokemon: &ResolvedPokemon,
+    opponent: &ResolvedPokemon,
+    trials: u32,
+    seed: u64,
+    config: &FightConfig,
+) -> f32 {
+    let wins = (0..trials)
+        .filter(|trial| {
+            let mut rng = StdRng::seed_from_u64(seed.wrapping_add(*trial as u64));
+            let log = pokemon_fight::process_fight_resolved(
+                pokemon,
+                opponent,
+                pokemon.pokemon.stats.hp,
+                opponent.pokemon.stats.hp,
+                config,
+                &mut rng,
+            );
+
+            matches!(
+                log.log.last(),
+                Some(FightEvent::PokemonWinner { pokemon: winner, .. }) if *winner == pokemon.pokemon.name
+            )
+        })
+        .count();
+
+    wins as f32 / trials as f32
+}
+
+/// Measures how the win rate of `pokemon` against `opponent` changes with one more point of each stat
+/// Every stat is measured over the same seeds, so the changes come from the stat alone
+/// The pokemon are perturbed as copies, nothing is written to the database
+pub async fn sensitivity(
+    pokemon: &Pokemon,
+    opponent: &Pokemon,
+    trials: u32,
+    seed: u64,
+    config: &FightConfig,
+) -> Result<Sensitivity> {
+    if trials == 0 || trials > MAX_TRIALS {
+        return Err(anyhow!(
+            "The number of trials must be between 1 and {MAX_TRIALS}, got {trials}"
+        ));
+    }
+
+    let resolved = ResolvedPokemon::resolve(pokemon).await?;
+    let opponent = ResolvedPokemon::resolve(opponent).await?;
+
+    let base = win_rate(&resolved, &opponent, trials, seed, config);
+
+    let stats: Vec<StatSensitivity> = STATS
+        .iter()
+        .map(|(stat, raise)| {
+            let mut perturbed = resolved.clone();
+            raise(&mut perturbed.pokemon.stats);
+
+            let rate = win_rate(&perturbed, &opponent, trials, seed, config);
+
+            StatSensitivity {
+                stat: stat.to_string(),
+                win_rate: rate,
+                change: rate - base,
+            }
+        })
+        .collect();
+
+    let most_sensitive = stats
+        .iter()
+        .filter(|s| s.change > 0.0)
+        .max_by(|a, b| a.change.total_cmp(&b.change))
+        .map(|s| s.stat.clone());
+
+    Ok(Sensitivity {
+        pokemon: pokemon.name.clone(),
+        opponent: opponent.pokemon.name.clone(),
+        trials,
+        seed,
+        win_rate: base,
+        stats,
+        most_sensitive,
+    })
+}
@@ -0,0 +1,160 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use super::{FightEvent, FightLog, FightStrategy, config::FightConfig, predictor};
+use crate::{database::promise::MaybePromise, pokemon::Pokemon, trainer::Trainer};
+
+/// The outcome of one pokemon fight within a predicted trainer fight
+#[derive(Serialize, Clone, Debug)]
+pub struct PredictedBout {
+    /// Our pokemon
+    pub pokemon: String,
+    /// The target's pokemon
+    pub opponent: String,
+    /// The name of the winning pokemon
+    pub winner: String,
+    /// The HP the winner has left
+    pub hp_left: u32,
+}
+
+/// A team assembled to beat a target trainer
+#[derive(Serialize, Clone, Debug)]
+pub struct SolvedTeam {
+    /// The names of the chosen pokemon, in the order they were picked
+    pub team: Vec<String>,
+    /// Whether the team is predicted to beat the target
+    /// If not, `team` is the closest attempt within the team size cap
+    pub predicted_win: bool,
+    /// The predicted pokemon fights of the team against the target
+    pub bouts: Vec<PredictedBout>,
+}
+
+/// How close a predicted fight came to beating the target:
+/// (won, target pokemon fainted, damage dealt to the target's pokemon)
+type Progress = (bool, usize, u32);
+
+fn progress(log: &FightLog, our_name: &str, target: &[Pokemon]) -> Progress {
+    let is_target = |name: &str| target.iter().any(|p| p.name == name);
+
+    let mut progress = (false, 0, 0);
+
+    for event in &log.log {
+        match event {
+            FightEvent::Winner { trainer, .. } => progress.0 = trainer == our_name,
+            FightEvent::Fainted { pokemon } if is_target(pokemon) => progress.1 += 1,
+            FightEvent::Hit {
+                defender, damage, ..
+            } if is_target(defender) => progress.2 += damage,
+            _ => {}
+        }
+    }
+
+    progress
+}
+
+fn bouts(log: &FightLog, our_name: &str) -> Vec<PredictedBout> {
+    let mut ours = String::new();
+    let mut theirs = String::new();
+    let mut bouts = vec![];
+
+    for event in &log.log {
+        match event {
+            FightEvent::ChoosePokemon { trainer, pokemon } if trainer == our_name => {
+                ours = pokemon.clone()
+            }
+            FightEvent::ChoosePokemon { pokemon, .. } => theirs = pokemon.clone(),
+            FightEvent::PokemonWinner { pokemon, hp_left } => bouts.push(PredictedBout {
+                pokemon: ours.clone(),
+                opponent: theirs.clone(),
+                winner: pokemon.clone(),
+                hp_left: *hp_left,
+            }),
+            _ => {}
+        }
+    }
+
+    bouts
+}
+
+/// Greedily assembles the smallest team from `available` that is predicted to beat `target`
+/// Each round adds the pokemon that gets the furthest against the target,
+/// until the team wins or reaches `max_team_size`
+/// Pokemon that share a name with one of the target's pokemon are never picked,
+/// as fights identify pokemon by name
+/// The pokemon should hold concrete types, as every candidate is checked with a full predicted fight
+pub async fn solve_team(
+    target: &Trainer,
+    available: &[Pokemon],
+    strategy: FightStrategy,
+    target_strategy: FightStrategy,
+    max_team_size: usize,
+    config: &FightConfig,
+) -> Result<SolvedTeam> {
+    let target_team =
+        futures::future::try_join_all(target.team.iter().map(|p| p.clone().resolve())).await?;
+
+    let mut ours = Trainer {
+        name: format!("{}'s challenger", target.name),
+        team: vec![],
+        strategy: None,
+    };
+
+    let mut team: Vec<Pokemon> = vec![];
+    let mut best_log = None;
+
+    while team.len() < max_team_size {
+        let mut best: Option<(Progress, &Pokemon, FightLog)> = None;
+
+        let candidates = available
+            .iter()
+            .filter(|p| !team.contains(p) && !target_team.contains(p));
+
+        for candidate in candidates {
+            ours.team = team
+                .iter()
+                .chain(std::iter::once(candidate))
+                .cloned()
+                .map(MaybePromise::from_concrete)
+                .collect();
+
+            let log = predictor::predict_trainer_fight(
+                &ours,
+                target,
+                strategy.clone(),
+                target_strategy.clone(),
+                config,
+            )
+            .await?;
+
+            let progress = progress(&log, &ours.name, &target_team);
+
+            if best.as_ref().is_none_or(|(best, _, _)| progress > *best) {
+                best = Some((progress, candidate, log));
+            }
+        }
+
+        let (progress, pokemon, log) = match best {
+            Some(best) => best,
+            None => break, // nothing left to pick
+        };
+
+        team.push(pokemon.clone());
+        best_log = Some(log);
+
+        if progress.0 {
+            break;
+        }
+    }
+
+    let predicted_win = best_log
+        .as_ref()
+        .is_some_and(|log| progress(log, &ours.name, &target_team).0);
+
+    Ok(SolvedTeam {
+        team: team.into_iter().map(|p| p.name).collect(),
+        predicted_win,
+        bouts: best_log
+            .map(|log| bouts(&log, &ours.name))
+            .unwrap_or_default(),
+    })
+}
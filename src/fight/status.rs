@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+
+use super::config::FightConfig;
+use crate::pokemon::ptype::PokemonType;
+
+/// The attack multiplier of a burned pokemon
+pub const BURN_ATTACK_MULTIPLIER: f32 = 0.5;
+
+/// The fraction of its max HP a burned pokemon loses at the end of each of its turns
+pub const BURN_DAMAGE_FRACTION: f32 = 1.0 / 16.0;
+
+/// The fraction of its max HP a poisoned pokemon loses at the end of each of its turns
+pub const POISON_DAMAGE_FRACTION: f32 = 1.0 / 8.0;
+
+/// The chance that a paralyzed pokemon can't move and skips its turn
+pub const PARALYSIS_SKIP_CHANCE: f32 = 0.25;
+
+/// A status condition a pokemon can suffer during a single pokemon fight
+/// It is part of the fight state only, it's never stored and doesn't carry over to the next fight
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StatusCondition {
+    /// No status condition, the state every pokemon starts a fight in
+    #[default]
+    None,
+    /// Lowers the attack and chips HP every turn
+    Burn,
+    /// Chips HP every turn
+    Poison,
+    /// Sometimes skips the pokemon's turn
+    Paralysis,
+}
+
+impl StatusCondition {
+    /// The status an attack of `attacker_type` inflicts, as set in the fight config,
+    /// `None` for a type that inflicts none
+    pub fn inflicted_by(attacker_type: &PokemonType, config: &FightConfig) -> Self {
+        if config.burn_types.contains(&attacker_type.name) {
+            Self::Burn
+        } else if config.poison_types.contains(&attacker_type.name) {
+            Self::Poison
+        } else if config.paralysis_types.contains(&attacker_type.name) {
+            Self::Paralysis
+        } else {
+            Self::None
+        }
+    }
+
+    /// The multiplier of the attack stat of a pokemon with this status
+    pub fn attack_multiplier(&self) -> f32 {
+        match self {
+            Self::Burn => BURN_ATTACK_MULTIPLIER,
+            Self::None | Self::Poison | Self::Paralysis => 1.0,
+        }
+    }
+
+    /// The HP a pokemon with `max_hp` loses at the end of each of its turns, at least 1,
+    /// `None` for a status that deals no damage
+    pub fn residual_damage(&self, max_hp: u32) -> Option<u32> {
+        let fraction = match self {
+            Self::Burn => BURN_DAMAGE_FRACTION,
+            Self::Poison => POISON_DAMAGE_FRACTION,
+            Self::None | Self::Paralysis => return None,
+        };
+
+        Some(((max_hp as f32 * fraction) as u32).max(1))
+    }
+
+    /// The chance that a pokemon with this status skips its turn
+    pub fn skip_chance(&self) -> f32 {
+        match self {
+            Self::Paralysis => PARALYSIS_SKIP_CHANCE,
+            Self::None | Self::Burn | Self::Poison => 0.0,
+        }
+    }
+}
@@ -1,7 +1,11 @@
+use std::collections::HashMap;
+
 use crate::trainer::Trainer;
 use anyhow::{anyhow, Result};
+use rand::{Rng, SeedableRng, rngs::StdRng};
 use crate::pokemon::Pokemon;
-use super::{FightEvent, FightLog, FightStrategy};
+use crate::database::promise::MaybePromise;
+use super::{FightEvent, FightLog, FightStrategy, config::FightConfig, effects::EffectRegistry, pokemon_fight::{CombatantState, ResolvedPokemon}};
 
 async fn process_victory(winner_name: String, winner_team: &[Pokemon]) -> FightEvent {
     FightEvent::Winner {
@@ -10,19 +14,98 @@ async fn process_victory(winner_name: String, winner_team: &[Pokemon]) -> FightE
     }
 }
 
+/// Checks that a trainer can take part in a fight before it starts:
+/// their team must not be empty, and every pokemon on it (along with its types) must resolve.
+/// `role` names the trainer's side in the error, such as `Challenger` or `Contender`
+pub async fn check_fight_ready(role: &str, trainer: &Trainer) -> Result<()> {
+    if trainer.team.is_empty() {
+        return Err(anyhow!("{} {} has no pokemon", role, trainer.name));
+    }
+
+    for member in &trainer.team {
+        let name = match member {
+            MaybePromise::Promise(p) => p.ident().to_string(),
+            MaybePromise::Concrete(c) => c.name.clone(),
+        };
+
+        let resolved = match member.clone().resolve().await {
+            Ok(pokemon) => ResolvedPokemon::resolve(&pokemon).await.map(|_| ()),
+            Err(e) => Err(e),
+        };
+
+        if let Err(e) = resolved {
+            return Err(anyhow!(
+                "{} {}'s pokemon {} could not be resolved: {}",
+                role, trainer.name, name, e
+            ));
+        }
+    }
+
+    Ok(())
+}
+
 /// Process a fight between two trainers and return a log of the battle
 /// ## Trainer Fight Algorithm
-/// 1. The trainer picked as the `contender` picks their pokemon first. If they've selected the `StrongestType` strategy, they use `StrongestSum` for their first pokemon instead (as the other party has yet to choose their pokemon)
+/// 1. The trainer picked as the `contender` picks their pokemon first. If they've selected the `StrongestType`, `CounterLead` or `SafestSwitch` strategy, they use `StrongestSum` for their first pokemon instead (as the other party has yet to choose their pokemon)
 /// 2. The trainer picked as the `challenger` picks their pokemon according to their strategy.
 /// 3. The two pokemon fight using the regular Pokemon Fight Algorithm
-/// 4. The winner's remaining `HP` is carried over to the next round, and the party whose pokemon fainted picks a new one using their strategy.
-/// 5. The first party to run out of pokemon loses the battle.
+/// 4. The winner's remaining `HP` is carried over to the next round, and the party whose pokemon fainted picks a new one (with full `HP`) using their strategy. `LowestHp` and `MostDurable` compare the pokemon's `HP` stats. Strategies see the enemy's current pokemon and remaining team.
+///    The faint effects enabled in the fight config (see `EffectRegistry::from_config`) are then applied to the new pokemon.
+/// 5. The first party to run out of pokemon loses the battle. The log then ends with a single `Winner` event listing the pokemon the winner has left.
 pub async fn process_fight(
     challenger: &Trainer,
     contender: &Trainer,
     challenger_strat: FightStrategy,
     contender_strat: FightStrategy,
+    config: &FightConfig,
+) -> Result<FightLog> {
+    let mut rng = StdRng::from_entropy();
+    process_fight_with_rng(challenger, contender, challenger_strat, contender_strat, config, &mut rng).await
+}
+
+/// Same as `process_fight`, but the pokemon fights draw their random rolls from `rng`
+pub async fn process_fight_with_rng<R: Rng + Send>(
+    challenger: &Trainer,
+    contender: &Trainer,
+    challenger_strat: FightStrategy,
+    contender_strat: FightStrategy,
+    config: &FightConfig,
+    rng: &mut R,
+) -> Result<FightLog> {
+    process_fight_carrying_hp(challenger, contender, challenger_strat, contender_strat, config, rng, &mut CarriedHp::new()).await
+}
+
+/// The HP a trainer's pokemon have left, by name, carried from one trainer fight to the next
+/// A pokemon missing from the map has full HP
+pub type CarriedHp = HashMap<String, u32>;
+
+/// A challenger's pokemon entering the fight with the HP it has left in `challenger_hp`
+fn enter(pokemon: Pokemon, challenger_hp: &CarriedHp) -> CombatantState {
+    match challenger_hp.get(&pokemon.name) {
+        Some(hp) => CombatantState::new(pokemon, *hp),
+        None => CombatantState::full(pokemon),
+    }
+}
+
+/// Same as `process_fight_with_rng`, but the challenger's pokemon enter with the HP they have left in `challenger_hp`
+/// instead of full HP, and `challenger_hp` is updated with what they have left after the fight (`0` for those that fainted)
+/// The challenger's team must not hold pokemon with no HP left
+pub async fn process_fight_carrying_hp<R: Rng + Send>(
+    challenger: &Trainer,
+    contender: &Trainer,
+    challenger_strat: FightStrategy,
+    contender_strat: FightStrategy,
+    config: &FightConfig,
+    rng: &mut R,
+    challenger_hp: &mut CarriedHp,
 ) -> Result<FightLog> {
+    // an empty team would otherwise only surface as a strategy picking nothing
+    for (role, trainer) in [("Challenger", challenger), ("Contender", contender)] {
+        if trainer.team.is_empty() {
+            return Err(anyhow!("{} {} has no pokemon", role, trainer.name));
+        }
+    }
+
     // Resolve all pokemon of each team
     let mut challenger_team =
         futures::future::try_join_all(challenger.team.iter().map(|p| p.clone().resolve())).await?;
@@ -31,44 +114,40 @@ pub async fn process_fight(
         futures::future::try_join_all(contender.team.iter().map(|p| p.clone().resolve())).await?;
 
     // Create a log of the fight
-    let mut log = FightLog {
-        contender_name: contender.name.clone(),
-        challenger_name: challenger.name.clone(),
-        log: vec![],
-    };
+    let mut log = FightLog::new(contender.name.clone(), challenger.name.clone());
 
     // Fight until one of the teams has no more pokemon
     // the contender is the first to choose their pokemon using their strategy.
 
     // contender chooses their pokemon
     let mut contender_pokemon = match
-    contender_strat.choose_pokemon(&contender_team, None).await {
-        Some(p) => Some(p),
+    contender_strat.choose_pokemon(&contender_team, None, &challenger_team, rng).await {
+        Some(p) => Some(CombatantState::full(p)),
         None => return Err(anyhow::anyhow!("Contender's strategy produced no valid pokemon")),
     };
 
     // add this to the log
     log.log.push(FightEvent::ChoosePokemon {
         trainer: contender.name.clone(),
-        pokemon: contender_pokemon.as_ref().unwrap().name.clone(),
+        pokemon: contender_pokemon.as_ref().unwrap().pokemon.name.clone(),
     });
 
     // challenger chooses their pokemon
     let mut challenger_pokemon = match
-    challenger_strat.choose_pokemon(&challenger_team, Some(&contender_pokemon.as_ref().unwrap())).await {
-        Some(p) => Some(p),
+    challenger_strat.choose_pokemon(&challenger_team, contender_pokemon.as_ref().map(|c| &c.pokemon), &contender_team, rng).await {
+        Some(p) => Some(enter(p, challenger_hp)),
         None => return Err(anyhow::anyhow!("Challenger's strategy produced no valid pokemon")),
     };
 
     // add this to the log
     log.log.push(FightEvent::ChoosePokemon {
         trainer: challenger.name.clone(),
-        pokemon: challenger_pokemon.as_ref().unwrap().name.clone(),
+        pokemon: challenger_pokemon.as_ref().unwrap().pokemon.name.clone(),
     });
 
-    // Set the HP of the pokemon
-    let mut contender_hp = contender_pokemon.as_ref().unwrap().stats.hp;
-    let mut challenger_hp = challenger_pokemon.as_ref().unwrap().stats.hp;
+    let effects = EffectRegistry::from_config(config);
+    let mut fainted_challenger: Option<Pokemon> = None;
+    let mut fainted_contender: Option<Pokemon> = None;
 
     loop {
         
@@ -77,40 +156,40 @@ pub async fn process_fight(
 
          match (&mut challenger_pokemon, &mut contender_pokemon) {
             (Some(chal_poke), None) => {
-                // choose a new pokemon for the contender or end the game
-                if contender_team.is_empty() {
-                    // challenger has no more pokemon, contender wins
-                    log.log.push(process_victory(challenger.name.clone(), &challenger_team).await);
-                    break;
-                }
-
+                // the contender's pokemon fainted and they have some left, choose the next one
                 contender_pokemon = match
-                contender_strat.choose_pokemon(&contender_team, Some(&chal_poke)).await {
-                    Some(p) => Some(p),
+                contender_strat.choose_pokemon(&contender_team, Some(&chal_poke.pokemon), &challenger_team, rng).await {
+                    // a freshly chosen pokemon enters with full HP
+                    Some(p) => Some(CombatantState::full(p)),
                     None => return Err(anyhow::anyhow!("Contender's strategy produced no valid pokemon")),
                 };
                 log.log.push(FightEvent::ChoosePokemon {
                     trainer: contender.name.clone(),
-                    pokemon: contender_pokemon.as_ref().unwrap().name.clone(),
+                    pokemon: contender_pokemon.as_ref().unwrap().pokemon.name.clone(),
                 });
+                if let Some(fainted) = fainted_contender.take()
+                    && let Some(next) = contender_pokemon.as_mut()
+                {
+                    log.log.append(&mut effects.on_faint(&fainted, &mut next.pokemon));
+                }
             },
             (None, Some(cont_poke)) => {
-                // choose a new pokemon for the challenger or end the game
-                if challenger_team.is_empty() {
-                    // contender has no more pokemon, challenger wins
-                    log.log.push(process_victory(contender.name.clone(), &contender_team).await);
-                    break;
-                }
-
+                // the challenger's pokemon fainted and they have some left, choose the next one
                 challenger_pokemon = match
-                challenger_strat.choose_pokemon(&challenger_team, Some(&cont_poke)).await {
-                    Some(p) => Some(p),
+                challenger_strat.choose_pokemon(&challenger_team, Some(&cont_poke.pokemon), &contender_team, rng).await {
+                    // a freshly chosen pokemon enters with the HP it has left, full unless carried over
+                    Some(p) => Some(enter(p, challenger_hp)),
                     None => return Err(anyhow::anyhow!("Challenger's strategy produced no valid pokemon")),
                 };
                 log.log.push(FightEvent::ChoosePokemon {
                     trainer: challenger.name.clone(),
-                    pokemon: challenger_pokemon.as_ref().unwrap().name.clone(),
+                    pokemon: challenger_pokemon.as_ref().unwrap().pokemon.name.clone(),
                 });
+                if let Some(fainted) = fainted_challenger.take()
+                    && let Some(next) = challenger_pokemon.as_mut()
+                {
+                    log.log.append(&mut effects.on_faint(&fainted, &mut next.pokemon));
+                }
             },
             (None, None) => {
                 // Both cannot be fainted, this situation should not occur
@@ -118,11 +197,11 @@ pub async fn process_fight(
             }
             (Some(chal_poke), Some(cont_poke)) => {
                 // fight
-                let mut fight_log = super::pokemon_fight::process_fight_with_hp(
+                let mut fight_log = super::pokemon_fight::process_fight_with_rng(
                     chal_poke,
                     cont_poke,
-                    challenger_hp,
-                    contender_hp
+                    config,
+                    rng,
                 ).await?;
 
                 // get one-before-last item to find out who fainted
@@ -131,11 +210,14 @@ pub async fn process_fight(
                 match pre_last_fight_event {
                     FightEvent::Fainted { pokemon} => {
                         // remove the fainted pokemon from the team
-                        if chal_poke.name == *pokemon {
+                        if chal_poke.pokemon.name == *pokemon {
                             challenger_team.retain(|p| p.name != *pokemon);
+                            challenger_hp.insert(pokemon.clone(), 0);
+                            fainted_challenger = Some(chal_poke.pokemon.clone());
                             should_remove_challenger = true;
-                        } else if cont_poke.name == *pokemon {
+                        } else if cont_poke.pokemon.name == *pokemon {
                             contender_team.retain(|p| p.name != *pokemon);
+                            fainted_contender = Some(cont_poke.pokemon.clone());
                             should_remove_contender = true;
                         }
                     }
@@ -153,10 +235,11 @@ pub async fn process_fight(
                         hp_left,
                     } => {
                         // and set the hp of the remaining pokemon accordingly, to carry it over to the next fight
-                        if chal_poke.name == *pokemon {
-                            challenger_hp = *hp_left;
-                        } else if cont_poke.name == *pokemon {
-                            contender_hp = *hp_left;
+                        if chal_poke.pokemon.name == *pokemon {
+                            chal_poke.current_hp = *hp_left;
+                            challenger_hp.insert(pokemon.clone(), *hp_left);
+                        } else if cont_poke.pokemon.name == *pokemon {
+                            cont_poke.current_hp = *hp_left;
                         }
                     }
                     _ => {
@@ -166,6 +249,17 @@ pub async fn process_fight(
 
                 // append the fight log to the main log
                 log.log.append(&mut fight_log.log);
+
+                // the first party to run out of pokemon loses, the battle ends with a single winner
+                if challenger_team.is_empty() {
+                    log.log.push(process_victory(contender.name.clone(), &contender_team).await);
+                    break;
+                }
+
+                if contender_team.is_empty() {
+                    log.log.push(process_victory(challenger.name.clone(), &challenger_team).await);
+                    break;
+                }
             }
 
         }
@@ -178,5 +272,6 @@ pub async fn process_fight(
         
     }
 
+    log.summarize();
     Ok(log)
 }
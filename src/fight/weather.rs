@@ -0,0 +1,81 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::pokemon::ptype::PokemonType;
+
+/// The damage multiplier of an attacker whose primary type the weather favors
+pub const WEATHER_BOOST_MULTIPLIER: f32 = 1.5;
+
+/// The fraction of its max HP a pokemon hurt by a sandstorm loses at the end of each of its turns
+pub const SANDSTORM_DAMAGE_FRACTION: f32 = 1.0 / 16.0;
+
+/// The types a sandstorm doesn't hurt
+pub const SANDSTORM_IMMUNE_TYPES: [&str; 2] = ["Rock", "Ground"];
+
+/// The weather a pokemon fight takes place in, the same for both pokemon for the whole fight
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Weather {
+    /// No effect on the fight
+    #[default]
+    Clear,
+    /// Boosts the attacks of Water types
+    Rain,
+    /// Boosts the attacks of Fire types
+    Sun,
+    /// Chips HP every turn, except from Rock and Ground types
+    Sandstorm,
+}
+
+impl Weather {
+    /// The multiplier of the damage of an attacker of `attacker_type` in this weather,
+    /// by the attacker's primary type
+    pub fn damage_multiplier(&self, attacker_type: &PokemonType) -> f32 {
+        let boosted = match self {
+            Self::Rain => "Water",
+            Self::Sun => "Fire",
+            Self::Clear | Self::Sandstorm => return 1.0,
+        };
+
+        if attacker_type.name == boosted {
+            WEATHER_BOOST_MULTIPLIER
+        } else {
+            1.0
+        }
+    }
+
+    /// The HP a pokemon with `max_hp` and these types loses at the end of each of its turns, at least 1,
+    /// `None` for a weather that deals no damage or a pokemon it doesn't hurt
+    pub fn residual_damage(
+        &self,
+        primary_type: &PokemonType,
+        secondary_type: Option<&PokemonType>,
+        max_hp: u32,
+    ) -> Option<u32> {
+        if *self != Self::Sandstorm {
+            return None;
+        }
+
+        let immune = |ptype: &PokemonType| SANDSTORM_IMMUNE_TYPES.contains(&ptype.name.as_str());
+
+        if immune(primary_type) || secondary_type.is_some_and(immune) {
+            return None;
+        }
+
+        Some(((max_hp as f32 * SANDSTORM_DAMAGE_FRACTION) as u32).max(1))
+    }
+}
+
+impl FromStr for Weather {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Clear" => Ok(Self::Clear),
+            "Rain" => Ok(Self::Rain),
+            "Sun" => Ok(Self::Sun),
+            "Sandstorm" => Ok(Self::Sandstorm),
+            _ => Err(()),
+        }
+    }
+}
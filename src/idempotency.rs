@@ -0,0 +1,198 @@
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use rocket::request::{FromRequest, Outcome, Request};
+use serde::{Deserialize, Serialize};
+
+use crate::json::{ApiError, JsonResult, JsonStatus};
+
+/// The header clients send to make a mutating request safe to retry
+pub const IDEMPOTENCY_HEADER: &str = "Idempotency-Key";
+
+/// The `Idempotency-Key` of a request, if it has one
+/// The key is scoped to the request's method and path,
+/// so the same key can be reused on different endpoints
+pub struct IdempotencyKey {
+    key: Option<String>,
+    /// A hash of the request's body, `0` for a request without one
+    body: u64,
+}
+
+impl IdempotencyKey {
+    /// A key for a request without a body
+    pub fn new(key: Option<String>) -> Self {
+        Self { key, body: 0 }
+    }
+
+    /// The same key, for a request with `body`,
+    /// so the key can't be reused with another body
+    pub fn with_body(self, body: &impl Serialize) -> Self {
+        let mut hasher = DefaultHasher::new();
+        serde_json::to_string(body)
+            .unwrap_or_default()
+            .hash(&mut hasher);
+
+        Self {
+            body: hasher.finish(),
+            ..self
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for IdempotencyKey {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let key = request
+            .headers()
+            .get_one(IDEMPOTENCY_HEADER)
+            .map(|key| format!("{} {} {}", request.method(), request.uri().path(), key));
+
+        Outcome::Success(IdempotencyKey::new(key))
+    }
+}
+
+/// The `[idempotency]` section of the config file
+#[derive(Deserialize)]
+#[serde(default)]
+struct IdempotencyConfig {
+    /// How long a processed key is remembered, in seconds
+    ttl_secs: u64,
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        Self { ttl_secs: 3600 }
+    }
+}
+
+/// What the cache knows about a key
+enum Entry {
+    /// A request with the key is being processed
+    InFlight {
+        /// The hash of the request's body
+        body: u64,
+    },
+    /// A request with the key succeeded with `data`
+    Done {
+        /// The hash of the request's body
+        body: u64,
+        /// When the result was stored
+        stored_at: Instant,
+        /// The data of the result
+        data: serde_json::Value,
+    },
+}
+
+/// Remembers the successful results of requests made with an `Idempotency-Key`,
+/// so a retried request gets the original result instead of being processed again
+pub struct IdempotencyCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+/// Removes the in-flight entry of a key when its request ends without storing a result,
+/// e.g. because it failed or its client went away
+struct Reservation<'c> {
+    entries: &'c Mutex<HashMap<String, Entry>>,
+    key: &'c str,
+}
+
+impl Drop for Reservation<'_> {
+    fn drop(&mut self) {
+        let mut entries = self.entries.lock().unwrap();
+
+        if let Some(Entry::InFlight { .. }) = entries.get(self.key) {
+            entries.remove(self.key);
+        }
+    }
+}
+
+impl IdempotencyCache {
+    /// Creates an empty cache that remembers keys for `ttl`
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Creates an empty cache with the TTL from the config file
+    pub fn load() -> Self {
+        let config: IdempotencyConfig = crate::config::read_section_or_default("idempotency");
+        Self::new(Duration::from_secs(config.ttl_secs))
+    }
+
+    /// Runs `handler`, unless a request with the same key has already succeeded,
+    /// in which case its stored result is replayed
+    /// While a request with the key is still running, other requests with it are refused as a conflict,
+    /// and a key can't be reused with a different body
+    /// Requests without a key always run, and errors are never stored so they can be retried
+    pub async fn run<F>(&self, key: &IdempotencyKey, handler: F) -> JsonResult<'static>
+    where
+        F: Future<Output = JsonResult<'static>>,
+    {
+        let (body, key) = match &key.key {
+            Some(name) => (key.body, name),
+            None => return handler.await,
+        };
+
+        let reservation = {
+            let mut entries = self.entries.lock().unwrap();
+            entries.retain(|_, entry| match entry {
+                Entry::Done { stored_at, .. } => stored_at.elapsed() < self.ttl,
+                Entry::InFlight { .. } => true,
+            });
+
+            match entries.get(key) {
+                Some(Entry::InFlight { body: stored } | Entry::Done { body: stored, .. })
+                    if *stored != body =>
+                {
+                    return Err(ApiError::bad_request(format!(
+                        "The {IDEMPOTENCY_HEADER} was already used with a different body"
+                    )));
+                }
+                Some(Entry::InFlight { .. }) => {
+                    return Err(ApiError::conflict(format!(
+                        "A request with this {IDEMPOTENCY_HEADER} is still in progress, retry it later"
+                    )));
+                }
+                Some(Entry::Done { data, .. }) => {
+                    info!("Replaying the stored result of {}", key);
+                    return Ok(JsonStatus::data_owned(data.clone()));
+                }
+                None => {
+                    entries.insert(key.clone(), Entry::InFlight { body });
+                }
+            }
+
+            Reservation {
+                entries: &self.entries,
+                key,
+            }
+        };
+
+        let result = handler.await?;
+
+        // keep the data only, successful results always have an `Ok` status
+        let mut value = serde_json::to_value(&result).map_err(ApiError::internal)?;
+        let data = value["data"].take();
+
+        self.entries.lock().unwrap().insert(
+            key.clone(),
+            Entry::Done {
+                body,
+                stored_at: Instant::now(),
+                data: data.clone(),
+            },
+        );
+        drop(reservation);
+
+        Ok(JsonStatus::data_owned(data))
+    }
+}
@@ -1,8 +1,11 @@
 use erased_serde::Serialize as EraSerialize;
+use rocket::http::Status as HttpStatus;
 use rocket::response::Responder;
 use rocket::response::content::RawJson;
 use serde::Serialize;
 
+use crate::database::error::{ErrorClass, classify};
+
 #[derive(Serialize)]
 /// Represents the status of a JSON response.
 ///
@@ -15,6 +18,17 @@ pub enum Status {
     Error(String),
 }
 
+impl Status {
+    /// The HTTP status a response with this status is sent with, unless overridden
+    /// `200 OK` for `Ok` and `400 Bad Request` for `Error`
+    pub fn default_code(&self) -> HttpStatus {
+        match self {
+            Status::Ok => HttpStatus::Ok,
+            Status::Error(_) => HttpStatus::BadRequest,
+        }
+    }
+}
+
 #[derive(Serialize)]
 #[serde(untagged)]
 enum Data<'a> {
@@ -31,9 +45,12 @@ enum Data<'a> {
 ///
 /// * `status` - The status of the JSON response.
 /// * `data` - The data associated with the JSON response, with a lifetime `'a`.
+/// * `code` - The HTTP status the response is sent with, not part of the body.
 pub struct JsonStatus<'a> {
     status: Status,
     data: Data<'a>,
+    #[serde(skip)]
+    code: HttpStatus,
 }
 
 /// Converts a `JsonStatus` into a `RawJson<String>`.
@@ -75,9 +92,134 @@ impl<T: std::error::Error> From<T> for JsonStatus<'static> {
     }
 }
 
-/// A type alias for a Result that contains either a successful JsonStatus or an error JsonStatus.
-/// The success case has a lifetime parameter 'a, while the error case has a static lifetime.
-pub type JsonResult<'a> = Result<JsonStatus<'a>, JsonStatus<'static>>;
+/// A type alias for a Result that contains either a successful JsonStatus or an `ApiError`.
+/// The success case has a lifetime parameter 'a.
+pub type JsonResult<'a> = Result<JsonStatus<'a>, ApiError>;
+
+/// An error an endpoint fails with, each kind is sent with its own HTTP status.
+///
+/// The body keeps the shape of every other response, `{"status": {"Error": "..."}, "data": []}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApiError {
+    /// A named pokemon, type or trainer doesn't exist, sent as `404 Not Found`.
+    NotFound(String),
+    /// The request is invalid, sent as `400 Bad Request`.
+    BadRequest(String),
+    /// The request conflicts with existing data, e.g. a duplicate name, sent as `409 Conflict`.
+    Conflict(String),
+    /// The response would hold more items than allowed, sent as `413 Payload Too Large`.
+    PayloadTooLarge(String),
+    /// The server failed to handle a valid request, e.g. the database is down,
+    /// sent as `500 Internal Server Error`.
+    Internal(String),
+}
+
+impl ApiError {
+    /// Creates an `ApiError::NotFound` with the given message.
+    pub fn not_found<T: ToString>(message: T) -> Self {
+        ApiError::NotFound(message.to_string())
+    }
+
+    /// Creates an `ApiError::BadRequest` with the given message.
+    pub fn bad_request<T: ToString>(message: T) -> Self {
+        ApiError::BadRequest(message.to_string())
+    }
+
+    /// Creates an `ApiError::Conflict` with the given message.
+    pub fn conflict<T: ToString>(message: T) -> Self {
+        ApiError::Conflict(message.to_string())
+    }
+
+    /// Creates an `ApiError::PayloadTooLarge` with the given message.
+    pub fn payload_too_large<T: ToString>(message: T) -> Self {
+        ApiError::PayloadTooLarge(message.to_string())
+    }
+
+    /// Creates an `ApiError::Internal` with the given message.
+    pub fn internal<T: ToString>(message: T) -> Self {
+        ApiError::Internal(message.to_string())
+    }
+
+    /// Creates an `ApiError` from an `anyhow::Error`, picking the variant by its causes.
+    ///
+    /// Missing nodes are `NotFound` and constraint violations `Conflict`. Any other failure of the
+    /// database (e.g. `DbHandle::connect` failing) is `Internal`, while errors that don't come
+    /// from the database are checks the request failed, so they're `BadRequest`.
+    ///
+    /// # Arguments
+    ///
+    /// * `error` - The error to be included in the response.
+    pub fn from_anyhow(error: anyhow::Error) -> Self {
+        let message = format!("{:?}", error);
+
+        match classify(&error) {
+            ErrorClass::NotFound => ApiError::NotFound(message),
+            ErrorClass::Conflict => ApiError::Conflict(message),
+            ErrorClass::Transient => ApiError::Internal(message),
+            ErrorClass::Permanent if is_database_error(&error) => ApiError::Internal(message),
+            ErrorClass::Permanent => ApiError::BadRequest(message),
+        }
+    }
+
+    /// The HTTP status the error is sent with.
+    pub fn code(&self) -> HttpStatus {
+        match self {
+            ApiError::NotFound(_) => HttpStatus::NotFound,
+            ApiError::BadRequest(_) => HttpStatus::BadRequest,
+            ApiError::Conflict(_) => HttpStatus::Conflict,
+            ApiError::PayloadTooLarge(_) => HttpStatus::PayloadTooLarge,
+            ApiError::Internal(_) => HttpStatus::InternalServerError,
+        }
+    }
+
+    /// The message of the error, as sent in the response.
+    pub fn message(&self) -> &str {
+        match self {
+            ApiError::NotFound(message)
+            | ApiError::BadRequest(message)
+            | ApiError::Conflict(message)
+            | ApiError::PayloadTooLarge(message)
+            | ApiError::Internal(message) => message,
+        }
+    }
+}
+
+/// Whether an error was returned by the database driver, or by the network on the way to it.
+fn is_database_error(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .any(|cause| cause.is::<neo4rs::Error>() || cause.is::<std::io::Error>())
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message())
+    }
+}
+
+/// Converts any `std::error::Error` into an `ApiError::BadRequest`, logging it,
+/// so `?` can be used on such errors in endpoints.
+impl<T: std::error::Error> From<T> for ApiError {
+    fn from(error: T) -> Self {
+        info!("Error while running request: {}", error);
+        ApiError::BadRequest(format!("{}", error))
+    }
+}
+
+/// Converts an `ApiError` into the error `JsonStatus` it's sent as, with the matching HTTP status.
+impl From<ApiError> for JsonStatus<'static> {
+    fn from(error: ApiError) -> Self {
+        let code = error.code();
+        JsonStatus::error(error.message()).with_code(code)
+    }
+}
+
+impl<'r> Responder<'r, 'static> for ApiError {
+    /// Sends the error as an error `JsonStatus`, with the HTTP status of its variant.
+    fn respond_to(self, request: &'r rocket::Request<'_>) -> rocket::response::Result<'static> {
+        JsonStatus::from(self).respond_to(request)
+    }
+}
 
 /// Implementation of `JsonStatus` with a static lifetime.
 ///
@@ -116,6 +258,7 @@ impl JsonStatus<'static> {
     /// A new `JsonStatus` instance with the specified status and data.
     pub fn new_owned(status: Status, data: impl EraSerialize + 'static) -> Self {
         JsonStatus {
+            code: status.default_code(),
             status,
             data: Data::Owned(Box::new(data)),
         }
@@ -132,6 +275,7 @@ impl JsonStatus<'static> {
     /// A new `JsonStatus` instance with the specified status and an empty data vector.
     pub fn new_empty(status: Status) -> Self {
         JsonStatus {
+            code: status.default_code(),
             status,
             data: Data::Owned(Box::new(Vec::<u8>::new())),
         }
@@ -150,6 +294,7 @@ impl JsonStatus<'static> {
         JsonStatus {
             status: Status::Error(message.to_string()),
             data: Data::Owned(Box::new(Vec::<u8>::new())),
+            code: HttpStatus::BadRequest,
         }
     }
 
@@ -168,6 +313,7 @@ impl JsonStatus<'static> {
             data: Data::Owned(Box::new(
                 message.map_or(Vec::<u8>::new(), |msg| msg.to_string().into_bytes()),
             )),
+            code: HttpStatus::Ok,
         }
     }
 
@@ -184,6 +330,7 @@ impl JsonStatus<'static> {
         JsonStatus {
             status: Status::Ok,
             data: Data::Owned(Box::new(data)),
+            code: HttpStatus::Ok,
         }
     }
 
@@ -202,7 +349,8 @@ impl JsonStatus<'static> {
 }
 
 impl<'r, 'o: 'r> Responder<'r, 'o> for JsonStatus<'o> {
-    /// Converts the `JsonStatus` into a `RawJson` response and delegates the response to it.
+    /// Converts the `JsonStatus` into a `RawJson` response and delegates the response to it,
+    /// then sets the HTTP status of the response to the code of the `JsonStatus`.
     ///
     /// # Arguments
     ///
@@ -213,7 +361,10 @@ impl<'r, 'o: 'r> Responder<'r, 'o> for JsonStatus<'o> {
     ///
     /// A `Result` containing the response to be sent back to the client.
     fn respond_to(self, request: &'r rocket::Request<'_>) -> rocket::response::Result<'o> {
-        RawJson::<String>::from(self).respond_to(request)
+        let code = self.code;
+        let mut response = RawJson::<String>::from(self).respond_to(request)?;
+        response.set_status(code);
+        Ok(response)
     }
 }
 
@@ -234,6 +385,7 @@ impl<'a> JsonStatus<'a> {
     /// ```
     pub fn new_ref(status: Status, data: &'a dyn EraSerialize) -> Self {
         JsonStatus {
+            code: status.default_code(),
             status,
             data: Data::Ref(data),
         }
@@ -260,4 +412,21 @@ impl<'a> JsonStatus<'a> {
     pub fn data_ref<T: EraSerialize>(data: &'a T) -> Self {
         JsonStatus::new_ref(Status::Ok, data)
     }
+
+    /// Replaces the HTTP status the response is sent with, e.g. `409 Conflict` for an error.
+    ///
+    /// # Arguments
+    /// * `code` - The HTTP status to send the response with
+    ///
+    /// # Returns
+    /// The same `JsonStatus` with the new HTTP status
+    pub fn with_code(mut self, code: HttpStatus) -> Self {
+        self.code = code;
+        self
+    }
+
+    /// The HTTP status the response is sent with.
+    pub fn code(&self) -> HttpStatus {
+        self.code
+    }
 }
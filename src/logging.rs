@@ -0,0 +1,106 @@
+use std::sync::{OnceLock, RwLock};
+
+use anyhow::{Result, anyhow};
+use env_filter::Filter;
+use log::{Log, Metadata, Record};
+
+/// The environment variable the initial log filter is read from
+pub const LOG_FILTER_VAR: &str = "RUST_LOG";
+
+/// The filter used when `RUST_LOG` is not set, same as `env_logger`'s default
+pub const DEFAULT_LOG_FILTER: &str = "error";
+
+static LOGGER: OnceLock<ReloadableLogger> = OnceLock::new();
+
+/// Parses a filter in the `RUST_LOG` format, e.g. `info,pokemon_simulator::database=debug`
+fn parse_filter(spec: &str) -> Result<Filter> {
+    let mut builder = env_filter::Builder::new();
+    builder
+        .try_parse(spec)
+        .map_err(|e| anyhow!("Invalid log filter {spec}: {e}"))?;
+    Ok(builder.build())
+}
+
+/// An `env_logger` whose filter can be replaced while the server runs
+pub struct ReloadableLogger {
+    /// Formats and writes the records that pass `filter`, lets every level through itself
+    inner: env_logger::Logger,
+    filter: RwLock<(String, Filter)>,
+}
+
+impl ReloadableLogger {
+    /// Creates a logger with the given initial filter
+    pub fn new(spec: &str) -> Result<Self> {
+        let inner = env_logger::Builder::new()
+            .filter_level(log::LevelFilter::Trace)
+            .build();
+
+        Ok(Self {
+            inner,
+            filter: RwLock::new((spec.to_string(), parse_filter(spec)?)),
+        })
+    }
+
+    /// The current filter, as it was set
+    pub fn filter(&self) -> String {
+        self.filter.read().unwrap().0.clone()
+    }
+
+    /// Replaces the filter, the old filter is kept if `spec` is invalid
+    pub fn set_filter(&self, spec: &str) -> Result<()> {
+        let filter = parse_filter(spec)?;
+        let max_level = filter.filter();
+
+        *self.filter.write().unwrap() = (spec.to_string(), filter);
+
+        // only raise the global level for the installed logger,
+        // the `log` macros skip anything above it before reaching `enabled`
+        if LOGGER
+            .get()
+            .is_some_and(|logger| std::ptr::eq(logger, self))
+        {
+            log::set_max_level(max_level);
+        }
+
+        Ok(())
+    }
+}
+
+impl Log for ReloadableLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.filter.read().unwrap().1.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if self.filter.read().unwrap().1.matches(record) {
+            self.inner.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Installs the reloadable logger with the filter from `RUST_LOG`
+/// Does nothing if it was already installed
+/// An invalid `RUST_LOG` falls back to the default filter
+pub fn init() {
+    let logger = LOGGER.get_or_init(|| {
+        let spec = std::env::var(LOG_FILTER_VAR).unwrap_or(DEFAULT_LOG_FILTER.to_string());
+
+        ReloadableLogger::new(&spec).unwrap_or_else(|e| {
+            eprintln!("{e}, using {DEFAULT_LOG_FILTER}");
+            ReloadableLogger::new(DEFAULT_LOG_FILTER).unwrap()
+        })
+    });
+
+    if log::set_logger(logger).is_ok() {
+        log::set_max_level(logger.filter.read().unwrap().1.filter());
+    }
+}
+
+/// The installed logger, if `init` was called
+pub fn logger() -> Option<&'static ReloadableLogger> {
+    LOGGER.get()
+}
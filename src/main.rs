@@ -13,27 +13,117 @@
 //! ## API Endpoints
 //! 
 //! - `GET /api/` - Health check endpoint that returns OK status
-//! - `GET /api/pokemons` - A list of all pokemons
-//! - `POST /api/pokemons` - With a pokemon JSON in the body (same format as what comes from the `GET /api/pokemons` endpoint) adds a new pokemon
-//! - `GET /api/trainers` - A list of all trainers and their pokemon
+//! - `GET /api/health` - Readiness check: connects to the database and runs a trivial query, OK only if both succeed, otherwise a `503 Service Unavailable` error with the reason. `GET /api/` stays a cheap liveness check that doesn't touch the database
+//! - `GET /api/version` - Build information: the crate version, the git commit (if known at build time) and the configured database host
+//! - `GET /api/pokemons?skip=0&limit=50` - A page of the list of all pokemons, ordered by name: `{"total": 127, "skip": 0, "limit": 50, "items": [...], "warnings": []}`. `total` is the number of pokemons across all pages, `skip` defaults to `0` and `limit` to `50` (at most `500`). A pokemon that can't be read (e.g. its primary type link was deleted) is left out of `items` and listed in `warnings` as `{"identifier": "...", "error": "..."}` instead of failing the whole page
+//! - `GET /api/pokemons/count` - The number of pokemons, without listing them
+//! - `GET /api/pokemons/<name>` - A single pokemon by its name, names are case sensitive. If it doesn't exist, the error suggests up to 3 of the closest existing names
+//! - `POST /api/pokemons` - With a pokemon JSON in the body (same format as what comes from the `GET /api/pokemons` and `GET /api/pokemons/<name>/export` endpoints) adds a new pokemon. Types can be given as bare names or as full type objects, only their names are used. The types must already exist, a missing type is refused with an error naming it. The `sta` (stamina) stat is optional, without it the pokemon never tires. The `level` is optional too, `50` by default (see step 2 of the Pokemon Fight Algorithm). The `hp` stat must be between `1` and `255`, `atk`, `def` and `agi` between `0` and `255`, the `level` between `1` and `100`, and the `accuracy` (optional, `100` by default) between `1` and `100`, a stat out of range is refused with an error naming it
+//! - `POST /api/pokemons/bulk` - With a JSON array of pokemon (each in the `POST /api/pokemons` format) in the body adds all of them, each checked the same way. A refused pokemon doesn't stop the others, the valid ones are stored together in one transaction. Returns the result of every pokemon in order: `[{"name": "...", "status": "ok"}, {"name": "...", "status": "error", "message": "..."}]`
+//! - `POST /api/pokemons/import_csv` - With a `text/csv` pokedex in the body adds a pokemon for every row, each checked like by `POST /api/pokemons`. The first row must be the header `name,hp,atk,def,agi,primary_type,secondary_type`, an empty `secondary_type` means none, and the stats missing from the columns take their defaults. A row that can't be parsed or is refused doesn't stop the others. Returns the result of every row, with its `line` in the CSV: `[{"line": 2, "name": "...", "status": "ok"}, {"line": 3, "name": "", "status": "error", "message": "..."}]`
+//! - `GET /api/pokemons/export_csv` - Every pokemon as a pokedex CSV in the format `POST /api/pokemons/import_csv` takes, ordered by name
+//! - `PUT /api/pokemons/<name>/primary_type/<type_name>` - Changes the primary type of a pokemon, e.g. one entered wrong. The type must already exist and can't be the pokemon's secondary type, a missing type is refused with an error naming it
+//! - `PUT /api/pokemons/<name>/secondary_type/<type_name>` - Sets the secondary type of a pokemon, replacing the one it had. The type must already exist and can't be the pokemon's primary type, a missing type is refused with an error naming it
+//! - `DELETE /api/pokemons/<name>/secondary_type` - Removes the secondary type of a pokemon
+//! - `PUT /api/pokemons/<name>/item/<item>` - Gives a pokemon an item to hold into its fights, replacing the one it held. `item` is `ChoiceBand` (`1.5` times the attack, see step 2 of the Pokemon Fight Algorithm) or `Leftovers` (heals every turn, see step 11). The held item is listed as `held_item` on the pokemon
+//! - `GET /api/pokemons/<name>/power` - A single comparable power level for a pokemon: its weighted stats plus a bonus for every existing type it's strong against. The weights are set in the `[power]` config section (`hp_weight`, `attack_weight`, `defense_weight`, `agility_weight`, `coverage_bonus`)
+//! - `GET /api/pokemons/<name>/suggest_secondary?goal=offense` - Tries every existing type except the pokemon's primary one as its secondary type, and ranks them against every other pokemon using the type damage multipliers only. Each suggestion lists its `coverage` (pokemon it would hit for more than neutral damage), `resistances` (pokemon that would hit it for less than neutral damage) and the mean multipliers both ways. `goal` is `offense` (default, ranked by coverage) or `defense` (ranked by resistances). Nothing is changed
+//! - `GET /api/pokemons/<name>/sensitivity/<opponent_name>?trials=100&seed=1` - How one more point of each of the pokemon's stats (`hp`, `atk`, `def`, `agi`) changes its win rate against the opponent, to see which stat matters most in the matchup. Every stat is measured over the same `trials` seeded fights (default `100`, at most `1000`), on copies of the pokemon, nothing is stored. Returns the unchanged `win_rate`, each stat's `win_rate` and `change`, and the `most_sensitive` stat (none if no stat raises the win rate)
+//! - `GET /api/pokemons/orphans` - A list of all pokemons that no trainer owns, e.g. to find unused entries before deleting them
+//! - `GET /api/pokemons/champions` - For each type, the pokemon of that type (primary or secondary) with the highest stat total (`hp` + `atk` + `def` + `agi`), as a map of type name to pokemon. Ties go to the alphabetically first name
+//! - `GET /api/pokemons/usage` - All pokemons sorted by their `fight_count`, the number of fights simulated with `?track=true` they took part in, most used first. Ties are in alphabetical order
+//! - `GET /api/pokemons/<name>/export` - A single pokemon with its types as bare names, ready to be sent back to `POST /api/pokemons` (e.g. after changing its name)
+//! - `GET /api/pokemons/<name>/fights` - Every stored fight the pokemon took part in (fights simulated with `?save=true`), as their `id`, `contender`, `challenger`, `winner` and `turn_count`
+//! - `GET /api/fights?winner=<name>&min_turns=<n>&max_turns=<n>` - The stored fights won by `winner` that took between `min_turns` and `max_turns` turns (both inclusive), in the same format as `GET /api/pokemons/<name>/fights`. Every filter is optional, the filters run on the `winner` and `turn_count` stored with each fight
+//! - `GET /api/types` - The names of all pokemon types, sorted
+//! - `POST /api/types/<name>` - Creates a new type with no strengths or weaknesses, link it to other types with `POST /api/types/<a>/beats/<b>`. The name must be 1 to 30 characters long and not taken
+//! - `GET /api/types/<name>` - A single type with the types it is `strong_against` and `weak_against` as bare names: `{"name": "Fire", "strong_against": ["Grass"], "weak_against": ["Water"]}`. If it doesn't exist, the error suggests up to 3 of the closest existing names
+//! - `POST /api/types/<a>/beats/<b>` - Makes type `a` strong against type `b` and type `b` weak against type `a`, both or neither (in a single transaction), so the chart stays symmetric. Returns both types with their updated relationships
+//! - `POST /api/types/<a>/strong_against/<b>` and `POST /api/types/<a>/weak_against/<b>` - Makes type `a` strong (or weak) against type `b`, on `a`'s side only, unlike `beats`. Linking the same pair twice changes nothing. Both types must exist. Returns type `a` in the format of `GET /api/types/<name>`
+//! - `DELETE /api/types/<a>/strong_against/<b>` and `DELETE /api/types/<a>/weak_against/<b>` - Removes what the matching `POST` added, returning type `a` the same way
+//! - `GET /api/types/<name>/ranking` - How the type stands in the whole type chart, offensively (by how many other types it's strong against) and defensively (by how few it's weak against). Each side has the `count`, a `score` (the count as a share of the other types, `0.0` to `1.0`, higher is better), a `percentile` (the share of other types scoring strictly lower) and a `rank` (`1` is the best, ties share a place)
+//! - `GET /api/trainers?skip=0&limit=50` - A page of the list of all trainers and their pokemon, ordered by name, paginated like `GET /api/pokemons`
+//! - `GET /api/trainers/count` - The number of trainers, without listing them
+//! - `GET /api/trainers/<name>/team_size` - The number of pokemon a trainer owns, without fetching its team. A missing trainer is a `404 Not Found` error
 //! - `POST /api/trainer_pokemons/<trainer_name>` - Creates a trainer with name `<trainer_name>`
 //! - `DELETE /api/trainer_pokemons/<trainer_name>` - Deletes a trainer with name `<trainer_name>`
 //! - `GET /api/trainer_pokemons/<trainer_name>` - Returns a full list of all pokemons of a particular trainer
-//! - `POST /api/trainer_pokemons/<trainer_name>/<pokemon_name>` - Adds a pokemon to a trainer's team, name is case sensitive.
+//! - `POST /api/trainer_pokemons/<trainer_name>/<pokemon_name>` - Adds a pokemon to a trainer's team, name is case sensitive. A team holds at most 6 pokemon, adding to a full team is refused with a "Team is full" error
 //! - `DELETE /api/trainer_pokemons/<trainer_name>/<pokemon_name>` - Removes a pokemon from a trainer's team, name is case sensitive.
-//! - `GET /api/simulate_fight/<contender_name>/<challenger_name>` - Simulate a fight between two pokemon, names are case sensitive.
-//! - `GET /api/simulate_trainer_fight/<challenger_name>/<challenger_strategy>/<contender_name>/<contender_strategy>` - Simulate a fight between two trainers, possible strategies listed below, names are case sensitive.
+//! - `PUT /api/trainer_pokemons/<trainer_name>/strategy/<strategy>` - Sets the strategy the trainer fights with when a fight doesn't name one, a trainer that never set one fights with `StrongestSum`
+//! - `GET /api/trainers/<name>/strategy_picks?vs=<pokemon_name>` - The pokemon every strategy would pick from the trainer's team against the `vs` pokemon, or as the opening pick without `vs`. A debugging aid for comparing strategies, the `Random` pick changes between calls
+//! - `GET /api/simulate_fight/<contender_name>/<challenger_name>` - Simulate a fight between two pokemon, names are case sensitive. If a name doesn't exist, the error suggests up to 3 of the closest existing names
+//!     - Besides the `log` of events, the response has the outcome: the `winner`'s name, `total_rounds` (one per pokemon turn) and `winner_hp_left`, the HP the winner had left
+//!     - `?seed=<n>` makes the fight reproducible. The log has the `seed` the fight was simulated with, a random one unless `?seed=` was passed, so any fight can be replayed by passing its seed back (`?deterministic=true` logs have no seed). `?token=true` returns a shareable replay token for the fight instead of its log
+//!     - `?normalized=true` adds `hp_fraction` to every `Hit` event: the defender's HP left as a share (`0.0` to `1.0`) of its max HP
+//!     - `?save=true` stores the fight in the database, linked to both pokemon, and returns `{"fight_id": ..., "log": ...}` instead of the bare log
+//!     - `?deterministic=true` fixes the random multiplier (step 5 of the Pokemon Fight Algorithm) to `1.0` and every other roll too (no hit is critical), so the same matchup always gives the same log, which only depends on stats and types. It takes precedence over `seed` and can't be combined with `token=true`
+//!     - `?track=true` adds one to the `fight_count` of both pokemon (once if a pokemon fights itself, see `allow_self_fights` below), see `GET /api/pokemons/usage`
+//!     - `?level=<n>` makes both pokemon fight at level `n` (`1` to `100`) instead of their own, to compare how a matchup changes with the level. The stored pokemon keep their level. It can't be combined with `token=true`
+//!     - `?weather=<weather>` makes the fight take place in `Clear`, `Rain`, `Sun` or `Sandstorm` weather instead of the `weather` of the `[fight]` config section (`Clear` by default), see steps 7 and 11 of the Pokemon Fight Algorithm. It can't be combined with `token=true`
+//! - `GET /api/simulate_fight/<contender_name>/<challenger_name>/length_distribution?trials=200&seed=1` - Simulates the fight `trials` times (default `200`, at most `1000`), each with its own seed, and returns how many turns (hits and rests) the fights took: the `min`, `max` and `mean`, and `turns`, how many fights took each number of turns. A wide distribution means a swingy matchup. `seed` is optional, the same seed gives the same distribution
+//! - `GET /api/simulate_fight/<contender_name>/<challenger_name>/batch?runs=1000&seed=1` - Simulates the fight `runs` times (default `1000`, at most `10000`), each with its own seed, and returns who won how often instead of the logs: `contender_wins`, `challenger_wins`, `contender_win_percentage` (`0.0` to `100.0`), `average_turns` and `average_winner_hp`. The pokemon are fetched once for all runs. `seed` is optional, a random one is used (and returned) without it
+//! - `GET /api/simulate_fight_stream/<contender_name>/<challenger_name>?seed=1` - Simulates the fight like `GET /api/simulate_fight/<contender_name>/<challenger_name>`, but as server-sent events (`text/event-stream`): every event of the fight's log is sent as a JSON message as soon as it happens, then an `end` event with `{"contender_name": ..., "challenger_name": ..., "seed": ...}` once a pokemon has won. `seed` is optional, a random one is used (and sent with the `end` event) without it. Errors, such as a missing pokemon, are returned as JSON before any event is sent
+//! - `GET /api/simulate_fight/<contender_name>/<challenger_name>/timeline?seed=1` - The running total of the damage each pokemon has dealt after every turn (hit or rest) of the seeded fight, for a line chart: `{"seed": 1, "turns": 6, "damage": {"Charmander": [30, 30, 62, ...], "Bulbasaur": [0, 24, 24, ...]}}`. Entry `i` of a series is the total after turn `i + 1`, the last entries are the total damage each pokemon dealt. `seed` is optional, a random one is used (and returned) without it
+//! - `GET /api/replay/<token>` - Re-runs the fight encoded in a replay token, reproducing its exact log
+//! - `POST /api/simulate_fight_with_chart` - Simulate a fight between two pokemon using an inline type chart override, without changing the stored chart. Body: `{"contender": "...", "challenger": "...", "chart": {"Fire": {"strong_against": ["Grass"], "weak_against": ["Water"]}}}`, types missing from the chart keep their stored relationships
+//! - `GET /api/matchup/<attacker_name>/<defender_name>/type_breakdown` - How the type damage multiplier of the attacker hitting the defender is calculated: every step (3a-3h) of the Pokemon Fight Algorithm with whether it applied and the running total, then the multiplier clamped by step 4 and its effectiveness label
+//! - `GET /api/simulate_trainer_fight/<challenger_name>/<challenger_strategy>/<contender_name>/<contender_strategy>` - Simulate a fight between two trainers, possible strategies listed below, names are case sensitive. Both teams are checked first, a trainer with no pokemon or a pokemon that fails to resolve is reported by name before any fighting happens.
+//!     - The response has the same outcome fields as a pokemon fight, with the winning trainer as the `winner` and `winner_hp_left` taken from the last pokemon fight
+//!     - `?normalized=true` adds `hp_fraction` to every `Hit` event, as for a pokemon fight
+//! - `GET /api/simulate_trainer_fight_auto/<challenger_name>/<contender_name>` - Same as the trainer fight above, but each trainer fights with their own stored strategy
+//! - `POST /api/simulate_trainer_fight` - Same as the trainer fight above, with the trainers and their strategies in the body instead of the path, so names with slashes or spaces need no percent-encoding. Body: `{"challenger": "...", "challenger_strategy": "StrongestType", "contender": "...", "contender_strategy": "Random"}`, `?normalized=true` works the same
+//! - `GET /api/simulate_trainer_fight/<challenger_name>/<challenger_strategy>/<contender_name>/<contender_strategy>/odds?trials=50&seed=1` - Simulate the trainer fight `trials` times (default `50`, at most `1000`), each with its own seed, and return each trainer's win rate, average number of pokemon left standing, and how many trials ended with each number of pokemon left standing. `seed` is optional, the same seed gives the same odds
+//! - `GET /api/trainers/<a>/<b>/fairness?strategy=StrongestType&trials=50&seed=1` - How much picking second is worth: the trainer fight is simulated `trials` times (default `50`, at most `1000`) with `a` picking first and as many times with `b` picking first, over the same seeds, both trainers using `strategy` (default `StrongestType`). Returns the odds of both orderings, the `second_pick_win_rate` over all fights, and the `order_advantage`: the second picker's win rate minus the first picker's, in percentage points. Playing both orderings cancels out the strength of the teams, so what remains comes from pick order alone. The same `seed` always gives the same result, `Random` picks included
+//! - `GET /api/gauntlet/<pokemon_name>/<trainer_name>/odds?trials=100&seed=1` - Runs the pokemon through the trainer's whole team `trials` times (default `100`, at most `1000`): it fights the team's pokemon one after another in team order, carrying its `HP` over while every opponent enters with full `HP`, until it faints or clears the team. Returns the `mean`, `median` and `max` number of opponents defeated, and `clear_depth`, how many trials ended with each number of opponents defeated (the team size meaning the whole team was cleared). `seed` is optional, the same seed gives the same odds
+//! - `POST /api/solve_team/<target_trainer>` - Greedily assembles the smallest team of existing pokemon predicted to beat a trainer, with every hit dealing its expected damage. Body: `{"strategy": "StrongestType", "target_strategy": "StrongestSum", "max_team_size": 6}` (`max_team_size` is optional). Returns the chosen pokemon, whether they are predicted to win, and the predicted pokemon fights
+//! - `POST /api/round_robin` - Plays every pairing of the given trainers (at most `16`) once and returns every fixture, with the number of pokemon the winner had left, and the standings table, sorted by points (one per win), then by the number of pokemon left summed over the won fixtures. Body: `{"trainers": [{"trainer": "...", "strategy": "StrongestSum"}], "home_and_away": false}`. With `home_and_away` every pairing is played twice, so each trainer gets to be the contender (who picks first) once. At most `max_concurrent_fights` (default `4`, from the `[fight]` config section) fixtures are simulated at the same time, the fixtures are always listed in schedule order
+//! - `POST /api/tournament` - Same as `POST /api/round_robin` with every trainer fighting with the same strategy and every pairing played once, but returns only the standings table. Body: `{"trainers": ["Ash", "Misty"], "strategy": "StrongestType"}`
+//! - `POST /api/campaign` - Takes a trainer through an ordered list of steps and returns every step's log, the HP each pokemon had left after every fight, and whether the trainer survived (won every fight). Body: `{"trainer": "...", "strategy": "StrongestSum", "steps": [{"fight": {"trainer": "...", "strategy": "StrongestSum"}}, "heal"], "fatigue": 0.1, "seed": 42}`. The trainer is the challenger of every fight, their pokemon carry their HP from one fight to the next and fainted pokemon sit out, until a `heal` step restores the whole team. After every won fight, each pokemon still standing loses a `fatigue` share (default `0`) of its attack, defense and agility, which also lasts until a `heal`. The campaign ends at the first lost fight, passing a `seed` makes it reproducible
+//! - `GET /api/search?q=<term>&limit=10` - The pokemon, type and trainer names containing the term, ignoring case, as `{"pokemons": [...], "types": [...], "trainers": [...]}`. Each category holds at most `limit` (default `10`, at most `100`) names, in alphabetical order
+//! - `GET /api/export.ndjson` - Streams every type, pokemon, trainer and relationship as newline-delimited JSON, one record per line, reading them from the database as they're sent. The first line is a `header` record, a failed export ends with an `error` record
+//! - `POST /api/admin/snapshot` - Writes every type, pokemon and trainer to the server-side `snapshot_path` file (default `config/snapshot.json`) from the `[admin]` config section
+//! - `POST /api/admin/restore` - Replaces the whole database with the snapshot file, in a single transaction. Snapshots of another format version, or with relationships to missing nodes, are refused
+//! - `POST /api/admin/import.ndjson` - With the output of `GET /api/export.ndjson` in the body replaces the whole database, in a single transaction and with the same checks as a restore. Bodies over 8 MiB are refused, unless the `ndjson` limit is raised in the Rocket config
+//! - `POST /api/admin/repair` - Removes relationships (`Owns`, `PrimaryType`, `SecondaryType`, `StrongAgainst`, `WeakAgainst`) whose target is no longer a node of the right kind with a name, e.g. after a manual database edit, and returns a report of every relationship removed
+//! - `POST /api/admin/log_level` - With `{ "filter": "info,pokemon_simulator::database=debug" }` in the body replaces the log filter (same format as `RUST_LOG`) until the next restart, e.g. to see the database queries while debugging. An invalid filter is refused and the current one is kept
+//! - `POST /api/admin/explain` - Returns the Cypher queries a write operation would execute, without executing them, each as its `text` and the `params` bound to it (names are always bound as parameters, never written into the query text). Body: `{"operation": "put pokemon Pikachu", "body": {...}}`, where `body` is what the operation's endpoint takes. Supported operations: `put pokemon <name>` (the body is the pokemon) and `put trainer <name>`
+//!
+//! The mutating (`POST` and `DELETE`) pokemon and trainer endpoints accept an `Idempotency-Key` header: a retried request with the same key gets the original successful result instead of being processed again. A retry that arrives while the first request is still running gets a `409 Conflict` and can be retried later, and reusing a key with a different body is refused with a `400 Bad Request`. Keys are remembered for `ttl_secs` (default `3600`) from the `[idempotency]` config section
+//!
+//! List endpoints return at most `max_list_items` (default `1000`, from the `[api]` config section) items in one response, larger lists return a `413 Payload Too Large` error. The paginated lists (`GET /api/pokemons` and `GET /api/trainers`) suggest the `?skip=` and `?limit=` to ask for smaller pages
+//!
+//! JSON request bodies over `max_json_body_kib` KiB (default `1024`, from the `[api]` config section) are refused with a `413 Payload Too Large` status and a JSON error, before being parsed
+//!
+//! The database connection is read from the `[database]` section of `config/config.toml` (`host`, `port`, `username`, `password`). Each option can be overridden with an environment variable (`POKESIM_DB_HOST`, `POKESIM_DB_PORT`, `POKESIM_DB_USER`, `POKESIM_DB_PASS`), so the precedence is environment variable > config file > default (`neo4j`, `7687`, `neo4j`, `pass`). Without a config file every section keeps its defaults, while a config file that can't be read or parsed (e.g. a directory left by a container mount) fails with an error naming its absolute path
+//!
+//! The admin endpoints require the `X-Admin-Token` header to match the `token` of the `[admin]` config section, and are disabled while no token is configured
+//!
+//! The expensive endpoints can be switched off with boolean flags in the `[features]` config section, read once at startup: `odds` (trainer fight odds and fairness), `length_distribution`, `batch`, `gauntlet`, `sensitivity`, `solve_team` and `round_robin` (which also switches off `POST /api/tournament`). Every feature is enabled by default, a disabled one answers with a `403 Forbidden` status and a JSON "Feature disabled" error naming its flag
+//!
+//! A pokemon or a trainer fighting itself (e.g. `/api/simulate_fight/Pikachu/Pikachu`) is usually a mistake, so the pokemon and trainer fight endpoints refuse fights whose two names are the same with an error saying so. Set `allow_self_fights = true` in the `[fight]` config section to allow them
 //! 
 //! ### Fight Strageies
 //! The fight strategy changhes how a trainer picks their next pokemon upon a pokemon's faint, or the first pokemon to go and battle
 //! - `StrongestAtk` - Always choose the pokemon with the highest attack stat in your team
 //! - `StrongestDef` - Always choose the pokemon with the highest defense stat in your team
 //! - `StrongestSum` - Always choose the pokemon that has the highest atk+def sum
+//!     - Ties of `StrongestAtk`, `StrongestDef` and `StrongestSum` go to the pokemon with the highest total of all stats, then to the alphabetically first name
 //! - `StrongestType` - If you can, choose a pokemon that has the best type advantage over the current enemy pokemon, or, if not possible, use `StrongestSum` instead
-//! - `Random` - Always choose a random pokemon
+//!     - Preference order: strong against both enemy types, then strong against one enemy type with no weaknesses, then strong against one enemy type, then no weaknesses, then only one weakness. Ties within a tier go to the highest atk+def sum
+//! - `CounterLead` - Looks at the enemy's remaining team: choose a pokemon with no weaknesses against the current enemy pokemon, keeping back the best counters for the enemy's upcoming pokemon, or, if none is safe, use `StrongestType` instead
+//! - `SafestSwitch` - Choose the pokemon that is weak against the fewest of the current enemy pokemon's types, ties go to the highest atk+def sum, so a safe pokemon is chosen over a stronger one with weaknesses. Uses `StrongestSum` for the first pick
+//! - `LowestHp` - Always choose the pokemon with the lowest HP stat in your team, sacrificing the weakest pokemon first to preserve the strong ones
+//! - `MostDurable` - Always choose the pokemon that has the highest hp+def sum
+//!     - Ties of `LowestHp` go to the pokemon with the lowest total of all stats, then to the alphabetically first name, ties of `MostDurable` are broken like those of `StrongestSum`
+//! - `Random` - Always choose a random pokemon, drawn from the fight's random numbers, so a fight with a `seed` makes the same picks
 //! ## Pokemon Fight Algorithm
 //! 1. The pokemon with the highest `AGI`lity stat attacks first
-//! 2. The base damage is the pokemon's `ATK` (attack) stat
+//!     - With `turn_order = "AgilityWeighted"` in the `[fight]` config section, who attacks first is rolled at the start of every round (one turn of each pokemon) instead, with a chance proportional to agility: a pokemon with `70 AGI` against `30 AGI` attacks first 70% of the time. The default `"Alternate"` lets the faster pokemon attack first, then the pokemon take turns
+//!     - If the attacker has a stamina (`sta`) stat, every attack costs `10` stamina and each turn regains `5`. An attacker with less than `10` stamina (and not at full stamina) rests instead, regaining `5`, and its turn ends. Pokemon without a stamina stat never tire
+//!     - A paralyzed attacker can't move `25%` of the time: a `Paralyzed` event is logged instead of a hit, and its turn ends
+//!     - An attack lands with a chance of the attacker's `accuracy` stat, in percent. A missed attack logs a `Missed` event instead of a hit, still costs stamina, and its turn ends. Pokemon without an accuracy stat never miss
+//! 2. The base damage is the pokemon's `ATK` (attack) stat, halved if it's burned, times its level multiplier: `(2 * level / 5 + 2) / 22`, which is `1.0` at the default level `50`, about `1.9` at level `100` and about `0.18` at level `5`
 //! 3. The type damage multiplier is calculated as follows, starting with a multiplier of `1`
 //!     1. If the attacker's primary type is "Strong Against" the defender's primary type, add `0.375` to the type damage multiplier
 //!     2. If the attacker's primary type is "Weak Against" the defender's primary type, subtract `0.225` from the type damage multiplier
@@ -46,24 +136,30 @@
 //!        
 //! 4. The maximum type damage multiplier is `2.5`, the minimum is `0.1`. A type damage multiplier above `1.8` means an attack is "Super effective", while a type damage multiplier below `0.6` means an attack is "Not very effective"
 //! 5. A random multiplier between `0.8` and `1.2` is calculated
-//! 6. A defense multiplier is calculated by dividing the defender's `DEF`ense stat by the maximum value of `250.0`, multiplied by `0.75`, then subtracted from `1.0`, to give a total defense multiplier (which multiplies the damage incoming to the defender) between `0.0` for a `0 DEF` stat, and `0.75` for a `250 DEF` stat
+//! 6. A defense multiplier is calculated by dividing the defender's `DEF`ense stat by the maximum value of `250.0`, multiplied by `0.75`, then subtracted from `1.0`, to give a total defense multiplier (which multiplies the damage incoming to the defender). The subtracted share is capped at `0.75`, so the multiplier is between `1.0` (full damage) for a `0 DEF` stat, and `0.25` for a `250 DEF` stat or more
 //! 7. The base damage is multiplied by the type damage multiplier, the random multiplier, and the defense multiplier.
+//!     - A hit is critical with a `crit_chance` chance (set in the `[fight]` config section, `0.0625` by default, `0.0` turns critical hits off), multiplying the damage by `1.5`. The `Hit` event has `"crit": true` for a critical hit
+//!     - If `damage_scale` is set in the `[fight]` config section, the damage is also multiplied by it (off by default). It is a global scale, not a same-type bonus: it applies to every hit of every attacker alike, whatever the types of either pokemon, and none of steps 3a-3h affect it. It's applied after the clamp of step 4, so it can take the damage past the `2.5` type damage multiplier cap, and it changes neither the effectiveness of the attack nor the status chance of step 10. With `dual_type_scale = "Split"` an attacker with a secondary type only gets half of the scale's change
+//!     - If `max_hit_fraction` is set in the `[fight]` config section, the damage is capped to that fraction of the defender's max `HP` (uncapped by default)
 //! 8. The final damage is subtracted from the defender's `HP` (hit points) stat.
-//! 9. If the defender's `HP` falls below zero, a fight is concluded.
-//! 10. Otherwise, the roles of the attacker and the defender are reversed, the remaining `HP` is carried over to the next round, and the fight continues until one of the pokemons' `HP` falls to zero.
+//! 9. If the defender's `HP` falls to zero (less than half a point left rounds to zero), a fight is concluded. `HP` is never reported below zero.
+//! 10. Otherwise, if `status_chance` is set in the `[fight]` config section and the type damage multiplier is above `1`, the defender gets a status condition with that chance, if it has none yet: attackers whose primary type is in `burn_types` (default `Fire`) burn, those in `poison_types` (default `Poison`) poison, those in `paralysis_types` (default `Electric`) paralyze. Status conditions are off by default and last until the end of the pokemon fight
+//! 11. At the end of the attacker's turn, a burn subtracts 1/16 and poison 1/8 of its max `HP`, which can make it faint. Paralysis deals no damage
+//! 12. The roles of the attacker and the defender are reversed, the remaining `HP` is carried over to the next round, and the fight continues until one of the pokemons' `HP` falls to zero. After `10000` turns with both pokemon standing (e.g. two pokemon that always miss), the fight ends in a draw instead, logging a `TurnLimit` event with the `turns` taken and no winner
 //! 
 //! ## Trainer Fight Algorithm
-//! 1. The trainer picked as the `contender` picks their pokemon first. If they've selected the `StrongestType` strategy, they use `StrongestSum` for their first pokemon instead (as the other party has yet to choose their pokemon)
+//! 1. The trainer picked as the `contender` picks their pokemon first. If they've selected the `StrongestType`, `CounterLead` or `SafestSwitch` strategy, they use `StrongestSum` for their first pokemon instead (as the other party has yet to choose their pokemon)
 //! 2. The trainer picked as the `challenger` picks their pokemon according to their strategy.
 //! 3. The two pokemon fight using the regular Pokemon Fight Algorithm
-//! 4. The winner's remaining `HP` is carried over to the next round, and the party whose pokemon fainted picks a new one using their strategy.
-//! 5. The first party to run out of pokemon loses the battle.
+//! 4. The winner's remaining `HP` is carried over to the next round, and the party whose pokemon fainted picks a new one (with full `HP`) using their strategy. `LowestHp` and `MostDurable` compare the pokemon's `HP` stats.
+//!     - If `revenge_boost` is set in the `[fight]` config section, the new pokemon's `ATK` is raised by 10% for as long as it stays in the fight (off by default)
+//! 5. The first party to run out of pokemon loses the battle. The log then ends with a single `Winner` event listing the pokemon the winner has left.
 
 #![feature(associated_type_defaults)]
 #![deny(missing_docs)]
 #![deny(rustdoc::missing_crate_level_docs)]
 
-use std::{env, net::Ipv4Addr, str::FromStr};
+use std::{env, net::Ipv4Addr, str::FromStr, time::Duration};
 
 /// Module containing JSON-related types and functionality for API responses.
 pub mod json;
@@ -80,25 +176,32 @@ pub mod database;
 /// Module containing fight simulation logic
 pub mod fight;
 
+/// Module for reading sections of the config file
+pub mod config;
+
+/// Module for making mutating requests safe to retry with an `Idempotency-Key`
+pub mod idempotency;
+
+/// Module containing token-guarded operator endpoints
+pub mod admin;
+
+/// Module for the logger whose filter can be changed at runtime
+pub mod logging;
+
+/// Module for switching expensive endpoints off in the config file
+pub mod features;
+
 #[doc(hidden)]
 mod tests;
 use crate::json::JsonResult;
 use json::JsonStatus;
 use log::{warn, info};
+use rocket::http::Status;
 use rocket_cors::{AllowedMethods, AllowedOrigins, CorsOptions};
 
 #[macro_use]
 extern crate rocket;
 
-const DEFAULT_CONFIG_FILE: &str = "\
-[database]
-";
-
-const DEFAULT_DB_HOST: &str = "neo4j";
-const DEFAULT_DB_PORT: &str = "7687";
-const DEFAULT_DB_USER: &str = "neo4j";
-const DEFAULT_DB_PASS: &str = "pass";
-
 /// Creates a CORS fairing with the specified configuration.
 /// Allows all origins, GET, POST, and DELETE methods, and credentials.
 /// # Returns
@@ -125,7 +228,7 @@ fn make_cors() -> CorsOptions {
 #[launch]
 #[tokio::main]
 async fn rocket() -> _ {
-    env_logger::init();
+    logging::init();
     let cors = make_cors().to_cors().expect("Error creating CORS fairing");
 
     let args = env::args().collect::<Vec<_>>();
@@ -135,77 +238,120 @@ async fn rocket() -> _ {
         info!("Working directory is {}", std::env::current_dir().as_ref().unwrap().to_str().unwrap());
     };
 
-    let db_host: &str = if args.len() > 1 && args[1].len() > 8 && args[1][..8] == *"DB_HOST=" {
-        info!("Setting configured database host to: {}", args[1][8..].to_string());
-        &args[1][8..]
-    } else {
-        DEFAULT_DB_HOST
-    };
+    // database options used to be passed as `DB_HOST=...` arguments
+    for arg in args.iter().skip(1) {
+        if let Some((name, _)) = arg.split_once('=')
+            && name.starts_with("DB_")
+        {
+            warn!("Ignoring the {name} argument, set the POKESIM_{name} environment variable instead");
+        }
+    }
 
-    let db_port: &str = if args.len() > 2 && args[2].len() > 8 && args[2][..8] == *"DB_PORT=" {
-        info!("Setting configured database port to: {}", args[2][8..].to_string());
-        &args[2][8..]
-    } else {
-        DEFAULT_DB_PORT
-    };
-
-    let db_user: &str = if args.len() > 3 && args[3].len() > 8 && args[3][..8] == *"DB_USER=" {
-        info!("Setting configured database user to: {}", args[3][8..].to_string());
-        &args[3][8..]
-    } else {
-        DEFAULT_DB_USER
-    };
-
-    let db_pass: &str = if args.len() > 4 && args[4].len() > 8 && args[4][..8] == *"DB_PASS=" {
-        info!("Setting configured database password to: {}", args[4][8..].to_string());
-        &args[4][8..]
-    } else {
-        DEFAULT_DB_PASS
-    };
-
-    // Create default config file for the database, if it doesn't exist
-    if std::fs::exists("config/config.toml").is_ok_and(|e| !e) {
-        let mut def_cfg = DEFAULT_CONFIG_FILE.to_string();
-
-        def_cfg = def_cfg + "host = \"" + db_host + "\"\n";
-        def_cfg = def_cfg + "port = \"" + db_port + "\"\n";
-        def_cfg = def_cfg + "username = \"" + db_user + "\"\n";
-        def_cfg = def_cfg + "password = \"" + db_pass + "\"\n";
-        
-        info!("Generated config file:\n{}", def_cfg);
-
-        if let Err(e) = std::fs::write(
-            "config/config.toml",
-            def_cfg.as_bytes()
-        ) {
-            warn!("The config file could not be created, but the program will continue anyway.");
-            warn!("This could cause further issues.");
-            warn!("Error writing default config file \"config/config.toml\": {e}");
-        };
-    }warn!("Config file already exists. Not overwriting.");
+    let api_config = config::ApiConfig::load();
 
     let config = rocket::Config {
         port: 8000,
         address: Ipv4Addr::new(0, 0, 0, 0).into(),
+        limits: api_config.limits(),
         ..rocket::Config::default()
     };
 
     rocket::build()
         .configure(config)
         .attach(cors)
+        .manage(api_config)
+        .manage(fight::config::FightConfig::load())
+        .manage(pokemon::power::PowerConfig::load())
+        .manage(idempotency::IdempotencyCache::load())
+        .manage(admin::AdminConfig::load())
+        .manage(features::FeatureConfig::load())
         .mount("/api", routes![
             index,
+            health,
+            version,
             pokemon::endpoints::get_pokemons,
+            pokemon::endpoints::count_pokemons,
+            pokemon::endpoints::get_pokemon,
             pokemon::endpoints::add_pokemon,
+            pokemon::endpoints::add_pokemons_bulk,
+            pokemon::endpoints::import_pokemons_csv,
+            pokemon::endpoints::export_pokemons_csv,
+            pokemon::endpoints::set_primary_type,
+            pokemon::endpoints::set_secondary_type,
+            pokemon::endpoints::clear_secondary_type,
+            pokemon::endpoints::set_held_item,
+            pokemon::endpoints::get_pokemon_power,
+            pokemon::endpoints::get_secondary_suggestions,
+            pokemon::endpoints::get_pokemon_sensitivity,
+            pokemon::endpoints::get_orphan_pokemons,
+            pokemon::endpoints::get_champions,
+            pokemon::endpoints::get_pokemon_usage,
+            pokemon::endpoints::export_pokemon,
+            pokemon::endpoints::get_pokemon_fights,
+            pokemon::ptype_endpoints::get_types,
+            pokemon::ptype_endpoints::get_type,
+            pokemon::ptype_endpoints::create_type,
+            pokemon::ptype_endpoints::add_strong_against,
+            pokemon::ptype_endpoints::add_weak_against,
+            pokemon::ptype_endpoints::remove_strong_against,
+            pokemon::ptype_endpoints::remove_weak_against,
+            pokemon::endpoints::type_beats,
+            pokemon::endpoints::get_type_ranking,
             trainer::endpoints::get_trainers,
+            trainer::endpoints::count_trainers,
+            trainer::endpoints::get_team_size,
             trainer::endpoints::create_trainer,
             trainer::endpoints::delete_trainer,
             trainer::endpoints::get_trainer_pokemons,
             trainer::endpoints::add_pokemon_to_trainer,
             trainer::endpoints::remove_pokemon_from_trainer,
+            trainer::endpoints::set_trainer_strategy,
+            trainer::endpoints::get_strategy_picks,
             fight::endpoints::simulate_fight,
-            fight::endpoints::simulate_trainer_fight
+            fight::endpoints::get_fights,
+            fight::endpoints::fight_length_distribution,
+            fight::endpoints::fight_batch,
+            fight::endpoints::simulate_fight_stream,
+            fight::endpoints::fight_timeline,
+            fight::endpoints::replay_fight,
+            fight::endpoints::simulate_fight_with_chart,
+            fight::endpoints::type_breakdown,
+            fight::endpoints::simulate_trainer_fight,
+            fight::endpoints::simulate_trainer_fight_auto,
+            fight::endpoints::simulate_trainer_fight_body,
+            fight::endpoints::simulate_trainer_fight_odds,
+            fight::endpoints::trainer_fight_fairness,
+            fight::endpoints::gauntlet_odds,
+            fight::endpoints::solve_team,
+            fight::endpoints::play_round_robin,
+            fight::endpoints::play_tournament,
+            fight::endpoints::play_campaign,
+            admin::endpoints::snapshot,
+            admin::endpoints::restore,
+            admin::endpoints::repair,
+            admin::endpoints::import_ndjson,
+            database::endpoints::export_ndjson,
+            database::endpoints::search,
+            admin::endpoints::set_log_level,
+            admin::endpoints::explain
         ])
+        .register("/api", catchers![payload_too_large, features::feature_disabled])
+}
+
+/// Catches request bodies over their data limit, e.g. a JSON body over `max_json_body_kib`.
+/// # Returns
+/// A `413 Payload Too Large` JSON error response with the JSON body limit.
+#[catch(413)]
+pub fn payload_too_large(request: &rocket::Request<'_>) -> (Status, JsonStatus<'static>) {
+    let limit = request.limits().get("json").unwrap_or_default();
+
+    (
+        Status::PayloadTooLarge,
+        JsonStatus::error(format!(
+            "Payload too large: the request body exceeds the {} limit",
+            limit
+        )),
+    )
 }
 
 /// Health check endpoint that returns an OK status.
@@ -225,3 +371,58 @@ pub async fn index<'a>() -> JsonResult<'a> {
     info!("Request to /api");
     Ok(JsonStatus::ok::<String>(None))
 }
+
+/// How long the health check waits for the database before reporting it unreachable
+pub const HEALTH_TIMEOUT_SECS: u64 = 5;
+
+/// Readiness check endpoint that connects to the database and runs `RETURN 1`.
+/// Unlike `index`, which answers OK as long as the server runs, this fails while the database
+/// can't be reached, so a load balancer stops sending requests that would fail anyway.
+/// # Returns
+/// A JSON response with an OK status if the query succeeded within `HEALTH_TIMEOUT_SECS`,
+/// otherwise a `503 Service Unavailable` JSON error with the reason.
+#[get("/health")]
+pub async fn health() -> JsonStatus<'static> {
+    info!("Request to /api/health");
+
+    let timeout = Duration::from_secs(HEALTH_TIMEOUT_SECS);
+
+    let error = match rocket::tokio::time::timeout(timeout, database::DbHandle::ping()).await {
+        Ok(Ok(())) => return JsonStatus::ok::<String>(None),
+        Ok(Err(e)) => format!("The database is unreachable: {}", e),
+        Err(_) => format!(
+            "The database is unreachable: no answer within {} seconds",
+            HEALTH_TIMEOUT_SECS
+        ),
+    };
+
+    warn!("Health check failed: {}", error);
+    JsonStatus::error(error).with_code(Status::ServiceUnavailable)
+}
+
+/// Build information of the running server
+#[derive(serde::Serialize)]
+pub struct VersionInfo {
+    /// The crate version
+    pub version: &'static str,
+    /// The git commit the server was built from, if known at build time
+    pub commit: Option<&'static str>,
+    /// The effective database host (credentials are never exposed)
+    pub db_host: Option<String>,
+}
+
+/// Returns the build information of the running server.
+/// # Returns
+/// A JSON response with the crate version, git commit and configured database host.
+#[get("/version")]
+pub async fn version<'a>() -> JsonResult<'a> {
+    info!("Request to /api/version");
+
+    let db_host = config::DatabaseConfig::load().ok().map(|db| db.host);
+
+    Ok(JsonStatus::data_owned(VersionInfo {
+        version: env!("CARGO_PKG_VERSION"),
+        commit: option_env!("GIT_COMMIT"),
+        db_host,
+    }))
+}
@@ -0,0 +1,120 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    NewPokemon, Pokemon,
+    stats::{DEFAULT_LEVEL, MAX_ACCURACY, PokemonStats},
+};
+
+/// The columns of a pokedex CSV, in order, which is also its header row
+pub const COLUMNS: [&str; 7] = [
+    "name",
+    "hp",
+    "atk",
+    "def",
+    "agi",
+    "primary_type",
+    "secondary_type",
+];
+
+/// A pokemon as a row of a pokedex CSV
+/// Only the stats a spreadsheet usually holds are kept, the others take their defaults on import
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CsvRow {
+    /// The name of the Pokemon
+    pub name: String,
+    /// The hit points of the Pokemon
+    pub hp: u32,
+    /// The attack power of the Pokemon
+    pub atk: u32,
+    /// The defense power of the Pokemon
+    pub def: u32,
+    /// The agility of the Pokemon
+    pub agi: u32,
+    /// The name of the primary type
+    pub primary_type: String,
+    /// The name of the secondary type, an empty column for none
+    pub secondary_type: Option<String>,
+}
+
+impl CsvRow {
+    /// The row of a pokemon, its types are written by name
+    pub fn from_pokemon(pokemon: &Pokemon) -> Self {
+        Self {
+            name: pokemon.name.clone(),
+            hp: pokemon.stats.hp,
+            atk: pokemon.stats.attack,
+            def: pokemon.stats.defense,
+            agi: pokemon.stats.agility,
+            primary_type: pokemon.primary_type().ident().to_string(),
+            secondary_type: pokemon.secondary_type().map(|t| t.ident().to_string()),
+        }
+    }
+
+    /// The row as the body `POST /api/pokemons` takes, so it's checked the same way
+    pub fn into_new_pokemon(self) -> NewPokemon {
+        NewPokemon {
+            name: self.name,
+            primary_type: self.primary_type,
+            secondary_type: self.secondary_type,
+            stats: PokemonStats {
+                hp: self.hp,
+                attack: self.atk,
+                defense: self.def,
+                agility: self.agi,
+                stamina: None,
+                level: DEFAULT_LEVEL,
+                accuracy: MAX_ACCURACY,
+            },
+        }
+    }
+
+    /// The row as a line of CSV, ending with a newline
+    pub fn to_line(&self) -> Result<String> {
+        let mut writer = csv::WriterBuilder::new()
+            .has_headers(false)
+            .from_writer(vec![]);
+        writer.serialize(self)?;
+        Ok(String::from_utf8(writer.into_inner()?)?)
+    }
+}
+
+/// The header row of a pokedex CSV, ending with a newline
+pub fn header_line() -> String {
+    format!("{}\n", COLUMNS.join(","))
+}
+
+/// Parses a pokedex CSV, which must start with the header row
+/// Every data row is returned with its line number, rows that can't be parsed
+/// come with the reason instead, so one bad row doesn't hide the others
+pub fn read_rows(text: &str) -> Vec<(u64, Result<CsvRow, String>)> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(true)
+        .trim(csv::Trim::All)
+        .from_reader(text.as_bytes());
+
+    let headers = match reader.headers() {
+        Ok(headers) if headers.iter().eq(COLUMNS) => headers.clone(),
+        Ok(_) => {
+            return vec![(
+                1,
+                Err(format!("The header row must be {}", COLUMNS.join(","))),
+            )];
+        }
+        Err(e) => return vec![(1, Err(e.to_string()))],
+    };
+
+    reader
+        .records()
+        .map(|record| match record {
+            Ok(record) => {
+                let line = record.position().map_or(0, |p| p.line());
+                let row = record
+                    .deserialize::<CsvRow>(Some(&headers))
+                    .map_err(|e| e.to_string());
+                (line, row)
+            }
+            Err(e) => (e.position().map_or(0, |p| p.line()), Err(e.to_string())),
+        })
+        .collect()
+}
@@ -1,16 +1,74 @@
-use rocket::serde::json::Json;
+use std::collections::{BTreeMap, BTreeSet};
+
+use futures::{Stream, stream};
+use rocket::{State, http::ContentType, response::stream::TextStream, serde::json::Json};
+use serde::Serialize;
 
 use crate::{
-    database::get::DbGet,
-    json::{self, JsonResult, JsonStatus},
-    pokemon::Pokemon,
+    config::ApiConfig,
+    database::{
+        DbRepr,
+        get::{DEFAULT_PAGE_LIMIT, DbGet},
+        promise::MaybePromise,
+        suggest,
+        update::DbUpdate,
+    },
+    features::{Enabled, Sensitivity},
+    fight::{config::FightConfig, pokemon_fight::ResolvedPokemon, record::FightRecord, sensitivity},
+    idempotency::{IdempotencyCache, IdempotencyKey},
+    json::{self, ApiError, JsonResult, JsonStatus},
+    pokemon::{
+        NewPokemon, Pokemon, csv_io,
+        item::Item,
+        power::{PowerConfig, power_rating},
+        ranking::type_ranking,
+        ptype::PokemonType,
+        secondary::{SuggestionGoal, suggest_secondary},
+    },
 };
 
-/// Endpoint for getting a list of all Pokemon.
-#[get("/pokemons")]
-pub async fn get_pokemons<'a>() -> JsonResult<'a> {
+/// Endpoint for getting a page of the list of all Pokemon, ordered by name.
+/// `limit` defaults to 50 and is capped, the page comes with the total number of Pokemon
+#[get("/pokemons?<skip>&<limit>")]
+pub async fn get_pokemons<'a>(
+    skip: Option<u64>,
+    limit: Option<u64>,
+    api_config: &State<ApiConfig>,
+) -> JsonResult<'a> {
     info!("Request to /api/pokemons");
-    let pokemons = Pokemon::get_all().await.map_err(JsonStatus::from_anyhow)?;
+    let page = Pokemon::get_page(skip.unwrap_or(0), limit.unwrap_or(DEFAULT_PAGE_LIMIT))
+        .await
+        .map_err(ApiError::from_anyhow)?;
+    api_config.check_page_len(page.items.len())?;
+    Ok(JsonStatus::data_owned(page))
+}
+
+/// Endpoint for counting the Pokemon, without listing them.
+#[get("/pokemons/count")]
+pub async fn count_pokemons<'a>() -> JsonResult<'a> {
+    info!("Request to /api/pokemons/count");
+    let count = Pokemon::count().await.map_err(ApiError::from_anyhow)?;
+    Ok(JsonStatus::data_owned(count))
+}
+
+/// Endpoint for listing the Pokemon no trainer owns.
+#[get("/pokemons/orphans")]
+pub async fn get_orphan_pokemons<'a>(api_config: &State<ApiConfig>) -> JsonResult<'a> {
+    info!("Request to /api/pokemons/orphans");
+    let pokemons = Pokemon::get_orphans().await.map_err(ApiError::from_anyhow)?;
+    api_config.check_list_len(pokemons.len())?;
+    Ok(JsonStatus::data_owned(pokemons))
+}
+
+/// Endpoint for listing all Pokemon by how many tracked fights they took part in, most used first.
+#[get("/pokemons/usage")]
+pub async fn get_pokemon_usage<'a>(api_config: &State<ApiConfig>) -> JsonResult<'a> {
+    info!("Request to /api/pokemons/usage");
+    let mut pokemons = Pokemon::get_all_with_types()
+        .await
+        .map_err(ApiError::from_anyhow)?;
+    api_config.check_list_len(pokemons.len())?;
+    Pokemon::sort_by_usage(&mut pokemons);
     Ok(JsonStatus::data_owned(pokemons))
 }
 
@@ -18,37 +76,579 @@ pub async fn get_pokemons<'a>() -> JsonResult<'a> {
 #[get("/pokemons/<name>")]
 pub async fn get_pokemon<'a>(name: String) -> JsonResult<'a> {
     info!("Request to /api/pokemons/{}", name);
-    let pokemon = Pokemon::get_first(&name).await.map_err(JsonStatus::from_anyhow)?;
+    let pokemon = suggest::find::<Pokemon>("Pokemon", &name).await?;
     Ok(JsonStatus::data_owned(pokemon))
 }
 
-/// Endpoint to add a pokemon
-#[post("/pokemons", data = "<pokemon>")]
-pub async fn add_pokemon<'a>(mut pokemon: Json<Pokemon>) -> JsonResult<'a> {
+/// Endpoint for getting the highest stat-total Pokemon of each type, by type name.
+#[get("/pokemons/champions")]
+pub async fn get_champions<'a>(api_config: &State<ApiConfig>) -> JsonResult<'a> {
+    info!("Request to /api/pokemons/champions");
+    let pokemons = Pokemon::get_all_with_types()
+        .await
+        .map_err(ApiError::from_anyhow)?;
+    let champions = Pokemon::champions(&pokemons);
+    api_config.check_list_len(champions.len())?;
+    Ok(JsonStatus::data_owned(champions))
+}
+
+/// Endpoint for fetching a single Pokemon in the format `POST /api/pokemons` takes,
+/// with its types as bare names.
+#[get("/pokemons/<name>/export")]
+pub async fn export_pokemon<'a>(name: String) -> JsonResult<'a> {
+    info!("Request to /api/pokemons/{}/export", name);
+    let pokemon = suggest::find::<Pokemon>("Pokemon", &name).await?;
+    Ok(JsonStatus::data_owned(pokemon.with_type_names()))
+}
+
+/// Endpoint for getting every stored fight the Pokemon took part in, with their outcomes.
+#[get("/pokemons/<name>/fights")]
+pub async fn get_pokemon_fights<'a>(name: String, api_config: &State<ApiConfig>) -> JsonResult<'a> {
+    info!("Request to /api/pokemons/{}/fights", name);
+    let pokemon = suggest::find::<Pokemon>("Pokemon", &name).await?;
+    let fights = FightRecord::involving(&pokemon.name)
+        .await
+        .map_err(ApiError::from_anyhow)?;
+    api_config.check_list_len(fights.len())?;
+    Ok(JsonStatus::data_owned(fights))
+}
+
+/// Endpoint for rating a Pokemon with a single comparable power level.
+#[get("/pokemons/<name>/power")]
+pub async fn get_pokemon_power<'a>(name: String, config: &State<PowerConfig>) -> JsonResult<'a> {
+    info!("Request to /api/pokemons/{}/power", name);
+
+    let pokemon = suggest::find::<Pokemon>("Pokemon", &name).await?;
+
+    let primary_type = pokemon
+        .primary_type()
+        .clone()
+        .resolve()
+        .await
+        .map_err(ApiError::from_anyhow)?;
+
+    let secondary_type = match pokemon.secondary_type() {
+        Some(t) => Some(t.clone().resolve().await.map_err(ApiError::from_anyhow)?),
+        None => None,
+    };
+
+    let all_types = PokemonType::get_all()
+        .await
+        .map_err(ApiError::from_anyhow)?;
+
+    Ok(JsonStatus::data_owned(power_rating(
+        &pokemon,
+        &primary_type,
+        secondary_type.as_ref(),
+        &all_types,
+        config,
+    )))
+}
+
+/// Endpoint for ranking every type as a hypothetical secondary type of a Pokemon, against all other Pokemon.
+/// `goal` is `offense` (default) to rank by coverage, or `defense` to rank by resistances
+#[get("/pokemons/<name>/suggest_secondary?<goal>")]
+pub async fn get_secondary_suggestions<'a>(name: String, goal: Option<String>) -> JsonResult<'a> {
+    info!("Request to /api/pokemons/{}/suggest_secondary", name);
+
+    let goal = match goal {
+        Some(goal) => goal
+            .parse()
+            .map_err(|_| ApiError::bad_request("Invalid goal, expected offense or defense"))?,
+        None => SuggestionGoal::Offense,
+    };
+
+    let pokemon = suggest::find::<Pokemon>("Pokemon", &name).await?;
+
+    let pokemon = ResolvedPokemon::resolve(&pokemon)
+        .await
+        .map_err(ApiError::from_anyhow)?;
+
+    let all_types = PokemonType::get_all()
+        .await
+        .map_err(ApiError::from_anyhow)?;
+
+    let roster = Pokemon::get_all_with_types()
+        .await
+        .map_err(ApiError::from_anyhow)?;
+
+    // the roster's types are already concrete, so this doesn't query the database
+    let roster = futures::future::try_join_all(roster.iter().map(ResolvedPokemon::resolve))
+        .await
+        .map_err(ApiError::from_anyhow)?;
+
+    let suggestions =
+        suggest_secondary(&pokemon, &all_types, &roster, goal).map_err(ApiError::from_anyhow)?;
+
+    Ok(JsonStatus::data_owned(suggestions))
+}
+
+/// Endpoint for measuring how one more point of each of a Pokemon's stats changes its win rate against another.
+/// `trials` defaults to 100 and is capped, passing a `seed` makes the result reproducible
+#[get("/pokemons/<name>/sensitivity/<opponent_name>?<trials>&<seed>")]
+pub async fn get_pokemon_sensitivity<'a>(
+    _enabled: Enabled<Sensitivity>,
+    name: String,
+    opponent_name: String,
+    trials: Option<u32>,
+    seed: Option<u64>,
+    config: &State<FightConfig>,
+) -> JsonResult<'a> {
+    info!("Request to /api/pokemons/{}/sensitivity/{}", name, opponent_name);
+
+    let pokemon = suggest::find::<Pokemon>("Pokemon", &name).await?;
+
+    let opponent = suggest::find::<Pokemon>("Opponent", &opponent_name).await?;
+
+    let report = sensitivity::sensitivity(
+        &pokemon,
+        &opponent,
+        trials.unwrap_or(sensitivity::DEFAULT_TRIALS),
+        seed.unwrap_or_else(rand::random),
+        config,
+    )
+    .await
+    .map_err(ApiError::from_anyhow)?;
+
+    Ok(JsonStatus::data_owned(report))
+}
+
+/// Endpoint to add a pokemon, whose types must already exist
+#[post("/pokemons", data = "<new_pokemon>")]
+pub async fn add_pokemon<'a>(
+    new_pokemon: Json<NewPokemon>,
+    key: IdempotencyKey,
+    idempotency: &State<IdempotencyCache>,
+) -> JsonResult<'a> {
     info!("Request to /api/pokemons");
 
-    if pokemon.name.len() > 30 {
-        return Err(JsonStatus::error("Name is too long"));
+    idempotency
+        .run(&key.with_body(&*new_pokemon), async move {
+            let mut new_pokemon = new_pokemon.into_inner();
+
+            // also removes slashes because of GET incompatiblity
+            new_pokemon.validate().map_err(ApiError::bad_request)?;
+
+            // do not allow duplicates
+            if Pokemon::exists(&new_pokemon.name)
+                .await
+                .map_err(ApiError::from_anyhow)?
+            {
+                return Err(ApiError::conflict("Pokemon already exists"));
+            }
+
+            let primary_type = suggest::find::<PokemonType>("Type", &new_pokemon.primary_type).await?;
+
+            let secondary_type = match &new_pokemon.secondary_type {
+                Some(name) => Some(MaybePromise::from_concrete(
+                    suggest::find::<PokemonType>("Type", name).await?,
+                )),
+                None => None,
+            };
+
+            Pokemon::new_to_db(
+                new_pokemon.name,
+                MaybePromise::from_concrete(primary_type),
+                secondary_type,
+                new_pokemon.stats,
+            )
+            .await
+            .map_err(ApiError::from_anyhow)?;
+
+            Ok(JsonStatus::new_empty(json::Status::Ok))
+        })
+        .await
+}
+
+/// Whether a pokemon of a bulk import was stored
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportStatus {
+    /// The pokemon was stored
+    Ok,
+    /// The pokemon was refused, see the message
+    Error,
+}
+
+/// The result of importing one pokemon of a bulk import
+#[derive(Serialize, Clone, Debug)]
+pub struct ImportResult {
+    /// The name of the pokemon, as given
+    pub name: String,
+    /// Whether the pokemon was stored
+    pub status: ImportStatus,
+    /// Why the pokemon was refused
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Looks up a type of a bulk import, each type is only read from the database once per import
+async fn import_type(
+    name: &str,
+    types: &mut BTreeMap<String, PokemonType>,
+) -> Result<PokemonType, String> {
+    if let Some(ptype) = types.get(name) {
+        return Ok(ptype.clone());
     }
 
-    if pokemon.name.is_empty() {
-        return Err(JsonStatus::error("Name cannot be empty"));
+    let ptype = suggest::find::<PokemonType>("Type", name)
+        .await
+        .map_err(|e| e.message().to_string())?;
+
+    types.insert(name.to_string(), ptype.clone());
+    Ok(ptype)
+}
+
+/// Checks a pokemon of a bulk import like `add_pokemon` does, looking its types up in `types` first
+/// `taken` holds the names already imported earlier in the same request
+async fn check_import(
+    mut new_pokemon: NewPokemon,
+    types: &mut BTreeMap<String, PokemonType>,
+    taken: &BTreeSet<String>,
+) -> Result<Pokemon, String> {
+    new_pokemon.validate()?;
+
+    if taken.contains(&new_pokemon.name)
+        || Pokemon::exists(&new_pokemon.name)
+            .await
+            .map_err(|e| e.to_string())?
+    {
+        return Err("Pokemon already exists".to_string());
     }
 
-    // remove slashes because of GET incompatiblity
-    pokemon.name = pokemon.name.replace("\\", "");
-    pokemon.name = pokemon.name.replace("/", "");
+    let primary_type = import_type(&new_pokemon.primary_type, types).await?;
+    let secondary_type = match &new_pokemon.secondary_type {
+        Some(name) => Some(MaybePromise::from_concrete(import_type(name, types).await?)),
+        None => None,
+    };
+
+    Ok(Pokemon::new(
+        new_pokemon.name,
+        MaybePromise::from_concrete(primary_type),
+        secondary_type,
+        new_pokemon.stats,
+    ))
+}
 
-    // do not allow duplicates
-    if Pokemon::get_first(&pokemon.name).await.is_ok() {
-        return Err(JsonStatus::error("Pokemon already exists"));
+/// Endpoint to add many pokemon at once, each checked like by `POST /api/pokemons`.
+/// A refused pokemon doesn't stop the others, every valid one is stored in a single transaction
+/// Returns the result of every pokemon, in the order they were given
+#[post("/pokemons/bulk", data = "<new_pokemons>")]
+pub async fn add_pokemons_bulk<'a>(
+    new_pokemons: Json<Vec<NewPokemon>>,
+    key: IdempotencyKey,
+    idempotency: &State<IdempotencyCache>,
+) -> JsonResult<'a> {
+    info!("Request to /api/pokemons/bulk");
+
+    idempotency
+        .run(&key.with_body(&*new_pokemons), async move {
+            let mut types = BTreeMap::new();
+            let mut taken = BTreeSet::new();
+            let mut accepted = vec![];
+            let mut results = vec![];
+
+            for new_pokemon in new_pokemons.into_inner() {
+                let name = new_pokemon.name.clone();
+
+                match check_import(new_pokemon, &mut types, &taken).await {
+                    Ok(pokemon) => {
+                        taken.insert(pokemon.name.clone());
+                        accepted.push(pokemon);
+                        results.push(ImportResult {
+                            name,
+                            status: ImportStatus::Ok,
+                            message: None,
+                        });
+                    }
+                    Err(message) => results.push(ImportResult {
+                        name,
+                        status: ImportStatus::Error,
+                        message: Some(message),
+                    }),
+                }
+            }
+
+            Pokemon::put_many(&accepted)
+                .await
+                .map_err(ApiError::from_anyhow)?;
+
+            Ok(JsonStatus::data_owned(results))
+        })
+        .await
+}
+
+/// The result of importing one row of a pokedex CSV
+#[derive(Serialize, Clone, Debug)]
+pub struct CsvImportResult {
+    /// The line of the row in the CSV, the header row is line 1
+    pub line: u64,
+    /// The result of importing the row's pokemon, without a name if the row couldn't be parsed
+    #[serde(flatten)]
+    pub result: ImportResult,
+}
+
+/// Endpoint to add the pokemon of a pokedex CSV, one per row, each checked like by `POST /api/pokemons`.
+/// A row that can't be parsed or is refused doesn't stop the others
+/// Returns the result of every row, with its line number
+#[post("/pokemons/import_csv", format = "text/csv", data = "<body>")]
+pub async fn import_pokemons_csv<'a>(
+    body: String,
+    key: IdempotencyKey,
+    idempotency: &State<IdempotencyCache>,
+) -> JsonResult<'a> {
+    info!("Request to /api/pokemons/import_csv");
+
+    idempotency
+        .run(&key.with_body(&body), async move {
+            let mut types = BTreeMap::new();
+            let mut taken = BTreeSet::new();
+            let mut results = vec![];
+
+            for (line, row) in csv_io::read_rows(&body) {
+                let row = match row {
+                    Ok(row) => row,
+                    Err(message) => {
+                        results.push(CsvImportResult {
+                            line,
+                            result: ImportResult {
+                                name: String::new(),
+                                status: ImportStatus::Error,
+                                message: Some(message),
+                            },
+                        });
+                        continue;
+                    }
+                };
+
+                let name = row.name.clone();
+
+                let stored = match check_import(row.into_new_pokemon(), &mut types, &taken).await {
+                    Ok(pokemon) => Pokemon::new_to_db(
+                        pokemon.name.clone(),
+                        pokemon.primary_type().clone(),
+                        pokemon.secondary_type().cloned(),
+                        pokemon.stats.clone(),
+                    )
+                    .await
+                    .map_err(|e| e.to_string()),
+                    Err(message) => Err(message),
+                };
+
+                let result = match stored {
+                    Ok(pokemon) => {
+                        taken.insert(pokemon.name);
+                        ImportResult {
+                            name,
+                            status: ImportStatus::Ok,
+                            message: None,
+                        }
+                    }
+                    Err(message) => ImportResult {
+                        name,
+                        status: ImportStatus::Error,
+                        message: Some(message),
+                    },
+                };
+
+                results.push(CsvImportResult { line, result });
+            }
+
+            Ok(JsonStatus::data_owned(results))
+        })
+        .await
+}
+
+/// Endpoint streaming every pokemon as a pokedex CSV, ordered by name, starting with the header row.
+#[get("/pokemons/export_csv")]
+pub async fn export_pokemons_csv()
+-> Result<(ContentType, TextStream<impl Stream<Item = String>>), ApiError> {
+    info!("Request to /api/pokemons/export_csv");
+
+    let pokemons = Pokemon::get_all_with_types()
+        .await
+        .map_err(ApiError::from_anyhow)?;
+
+    let lines = pokemons
+        .iter()
+        .map(|pokemon| csv_io::CsvRow::from_pokemon(pokemon).to_line())
+        .collect::<anyhow::Result<Vec<String>>>()
+        .map_err(ApiError::from_anyhow)?;
+
+    Ok((
+        ContentType::CSV,
+        TextStream(stream::iter(
+            std::iter::once(csv_io::header_line()).chain(lines),
+        )),
+    ))
+}
+
+/// Endpoint to change the primary type of a Pokemon.
+/// The type must already exist, and can't be the Pokemon's secondary type
+#[put("/pokemons/<name>/primary_type/<type_name>")]
+pub async fn set_primary_type<'a>(
+    name: String,
+    type_name: String,
+    key: IdempotencyKey,
+    idempotency: &State<IdempotencyCache>,
+) -> JsonResult<'a> {
+    info!("Request to /api/pokemons/{}/primary_type/{}", name, type_name);
+
+    idempotency
+        .run(&key, async move {
+            let mut pokemon = suggest::find::<Pokemon>("Pokemon", &name).await?;
+
+            let primary_type = suggest::find::<PokemonType>("Type", &type_name).await?;
+
+            if pokemon
+                .secondary_type()
+                .is_some_and(|t| t.ident() == primary_type.name)
+            {
+                return Err(ApiError::bad_request(
+                    "The primary type can't be the same as the secondary type",
+                ));
+            }
+
+            pokemon
+                .set_primary_type(MaybePromise::from_concrete(primary_type))
+                .await
+                .map_err(ApiError::from_anyhow)?;
+
+            Ok(JsonStatus::new_empty(json::Status::Ok))
+        })
+        .await
+}
+
+/// Endpoint to set the secondary type of a Pokemon, replacing the one it had.
+/// The type must already exist, and can't be the Pokemon's primary type
+#[put("/pokemons/<name>/secondary_type/<type_name>")]
+pub async fn set_secondary_type<'a>(
+    name: String,
+    type_name: String,
+    key: IdempotencyKey,
+    idempotency: &State<IdempotencyCache>,
+) -> JsonResult<'a> {
+    info!("Request to /api/pokemons/{}/secondary_type/{}", name, type_name);
+
+    idempotency
+        .run(&key, async move {
+            let mut pokemon = suggest::find::<Pokemon>("Pokemon", &name).await?;
+
+            let secondary_type = suggest::find::<PokemonType>("Type", &type_name).await?;
+
+            if pokemon.primary_type().ident() == secondary_type.name {
+                return Err(ApiError::bad_request(
+                    "The secondary type can't be the same as the primary type",
+                ));
+            }
+
+            pokemon
+                .set_secondary_type(Some(MaybePromise::from_concrete(secondary_type)))
+                .await
+                .map_err(ApiError::from_anyhow)?;
+
+            Ok(JsonStatus::new_empty(json::Status::Ok))
+        })
+        .await
+}
+
+/// Endpoint to remove the secondary type of a Pokemon.
+#[delete("/pokemons/<name>/secondary_type")]
+pub async fn clear_secondary_type<'a>(
+    name: String,
+    key: IdempotencyKey,
+    idempotency: &State<IdempotencyCache>,
+) -> JsonResult<'a> {
+    info!("Request to /api/pokemons/{}/secondary_type", name);
+
+    idempotency
+        .run(&key, async move {
+            let mut pokemon = suggest::find::<Pokemon>("Pokemon", &name).await?;
+
+            if pokemon.secondary_type().is_none() {
+                return Err(ApiError::bad_request("Pokemon has no secondary type"));
+            }
+
+            pokemon
+                .set_secondary_type(None)
+                .await
+                .map_err(ApiError::from_anyhow)?;
+
+            Ok(JsonStatus::new_empty(json::Status::Ok))
+        })
+        .await
+}
+
+/// Endpoint to give a Pokemon an item to hold into its fights, replacing the one it held.
+#[put("/pokemons/<name>/item/<item>")]
+pub async fn set_held_item<'a>(
+    name: String,
+    item: String,
+    key: IdempotencyKey,
+    idempotency: &State<IdempotencyCache>,
+) -> JsonResult<'a> {
+    info!("Request to /api/pokemons/{}/item/{}", name, item);
+
+    idempotency
+        .run(&key, async move {
+            let item: Item = item.parse().map_err(|_| {
+                let names: Vec<&str> = Item::ALL.iter().map(Item::name).collect();
+                ApiError::bad_request(format!(
+                    "Invalid item, expected one of {}",
+                    names.join(", ")
+                ))
+            })?;
+
+            let mut pokemon = suggest::find::<Pokemon>("Pokemon", &name).await?;
+
+            pokemon.held_item = Some(item);
+
+            pokemon
+                .update(pokemon.get_raw_identifier())
+                .await
+                .map_err(ApiError::from_anyhow)?;
+
+            Ok(JsonStatus::new_empty(json::Status::Ok))
+        })
+        .await
+}
+
+/// Endpoint to make type `a` strong against type `b` and `b` weak against `a`, in one transaction.
+#[post("/types/<a>/beats/<b>")]
+pub async fn type_beats<'a>(a: String, b: String) -> JsonResult<'a> {
+    info!("Request to /api/types/{}/beats/{}", a, b);
+
+    let mut types = vec![];
+
+    for name in [&a, &b] {
+        types.push(suggest::find::<PokemonType>("Type", name).await?);
     }
 
-    let mut pokemon = pokemon.into_inner();
-    pokemon
-        .put_with_relationships()
+    let mut defender = types.pop().unwrap();
+    let mut attacker = types.pop().unwrap();
+
+    attacker
+        .beats(&mut defender)
+        .await
+        .map_err(ApiError::from_anyhow)?;
+
+    Ok(JsonStatus::data_owned(vec![attacker, defender]))
+}
+
+/// Endpoint to rank a type offensively and defensively against the whole type chart.
+#[get("/types/<name>/ranking")]
+pub async fn get_type_ranking<'a>(name: String) -> JsonResult<'a> {
+    info!("Request to /api/types/{}/ranking", name);
+
+    let all_types = PokemonType::get_all()
         .await
-        .map_err(JsonStatus::from_anyhow)?;
+        .map_err(ApiError::from_anyhow)?;
 
-    Ok(JsonStatus::new_empty(json::Status::Ok))
+    match type_ranking(&name, &all_types) {
+        Some(ranking) => Ok(JsonStatus::data_owned(ranking)),
+        None => {
+            let message = suggest::not_found::<PokemonType>("Type", &name).await;
+            Err(ApiError::not_found(message))
+        }
+    }
 }
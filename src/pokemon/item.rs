@@ -0,0 +1,61 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// The attack multiplier of a pokemon holding a Choice Band
+pub const CHOICE_BAND_ATTACK_MULTIPLIER: f32 = 1.5;
+
+/// The fraction of its max HP a pokemon holding Leftovers heals at the end of each of its turns
+pub const LEFTOVERS_HEAL_FRACTION: f32 = 1.0 / 16.0;
+
+/// An item a pokemon holds into its fights, stored on the pokemon
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Item {
+    /// Raises the attack by half
+    ChoiceBand,
+    /// Heals a little HP every turn
+    Leftovers,
+}
+
+impl Item {
+    /// Every item, in declaration order
+    pub const ALL: [Item; 2] = [Item::ChoiceBand, Item::Leftovers];
+
+    /// The name of the item, the same one `from_str` parses
+    pub fn name(&self) -> &'static str {
+        match self {
+            Item::ChoiceBand => "ChoiceBand",
+            Item::Leftovers => "Leftovers",
+        }
+    }
+
+    /// The multiplier of the attack stat of a pokemon holding this item
+    pub fn attack_multiplier(&self) -> f32 {
+        match self {
+            Item::ChoiceBand => CHOICE_BAND_ATTACK_MULTIPLIER,
+            Item::Leftovers => 1.0,
+        }
+    }
+
+    /// The HP a pokemon with `max_hp` holding this item heals at the end of each of its turns,
+    /// at least 1, `None` for an item that doesn't heal
+    /// The healing is capped at the pokemon's max HP by the fight
+    pub fn end_of_turn_heal(&self, max_hp: u32) -> Option<u32> {
+        match self {
+            Item::Leftovers => Some(((max_hp as f32 * LEFTOVERS_HEAL_FRACTION) as u32).max(1)),
+            Item::ChoiceBand => None,
+        }
+    }
+}
+
+impl FromStr for Item {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ChoiceBand" => Ok(Item::ChoiceBand),
+            "Leftovers" => Ok(Item::Leftovers),
+            _ => Err(()),
+        }
+    }
+}
@@ -4,22 +4,42 @@ pub mod ptype;
 /// Pokemon Stats (hp, etc) module
 pub mod stats;
 
+/// Pokemon CSV import and export module
+pub mod csv_io;
+
 /// Pokemon http endpoints module
 pub mod endpoints;
 
-use std::pin::Pin;
+/// Pokemon type http endpoints module
+pub mod ptype_endpoints;
+
+/// Pokemon power rating module
+pub mod power;
+
+/// Pokemon type chart ranking module
+pub mod ranking;
+
+/// Pokemon secondary type suggestion module
+pub mod secondary;
 
-use serde::{Deserialize, Serialize};
+/// Pokemon held item module
+pub mod item;
+
+use std::{collections::BTreeMap, pin::Pin};
+
+use item::Item;
+use serde::{Deserialize, Deserializer, Serialize};
 use stats::PokemonStats;
 
 use crate::database::{
-    AsDbString, DbRepr,
+    AsDbString, DbHandle, DbQuery, DbRepr, DbValue,
     delete::DbDelete,
     get::DbGet,
     link::DbLink,
     promise::{MaybePromise, Promised},
     put::DbPut,
     sanitize,
+    update::DbUpdate,
 };
 
 use anyhow::Result;
@@ -45,6 +65,78 @@ pub struct Pokemon {
     secondary_type: Option<MaybePromise<PokemonType>>,
     /// The base stats of the Pokemon
     pub stats: PokemonStats,
+    /// How many tracked fights (`?track=true`) the Pokemon took part in
+    #[serde(default)]
+    pub fight_count: u32,
+    /// The item the Pokemon holds into its fights, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub held_item: Option<Item>,
+}
+
+/// The body of `POST /api/pokemons`: a new pokemon with its types given by name
+/// The types must already exist, they're looked up before the pokemon is stored
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NewPokemon {
+    /// The name of the Pokemon
+    pub name: String,
+    /// The name of the primary type
+    #[serde(deserialize_with = "type_name")]
+    pub primary_type: String,
+    /// The name of the secondary type, if the Pokemon has one
+    #[serde(default, deserialize_with = "optional_type_name")]
+    pub secondary_type: Option<String>,
+    /// The base stats of the Pokemon
+    pub stats: PokemonStats,
+}
+
+/// A type given either by its bare name or as a whole type object,
+/// of which only the name is used
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TypeRef {
+    Name(String),
+    Type { name: String },
+}
+
+impl TypeRef {
+    fn into_name(self) -> String {
+        match self {
+            TypeRef::Name(name) | TypeRef::Type { name } => name,
+        }
+    }
+}
+
+/// The longest name a pokemon can have
+pub const MAX_NAME_LEN: usize = 30;
+
+impl NewPokemon {
+    /// Checks the name and the stats, then removes the slashes from the name, which would break `GET` paths
+    pub fn validate(&mut self) -> Result<(), String> {
+        if self.name.len() > MAX_NAME_LEN {
+            return Err("Name is too long".to_string());
+        }
+
+        if self.name.is_empty() {
+            return Err("Name cannot be empty".to_string());
+        }
+
+        self.stats.validate()?;
+
+        self.name = self.name.replace("\\", "");
+        self.name = self.name.replace("/", "");
+
+        Ok(())
+    }
+}
+
+fn type_name<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    TypeRef::deserialize(deserializer).map(TypeRef::into_name)
+}
+
+fn optional_type_name<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error> {
+    Option::<TypeRef>::deserialize(deserializer).map(|t| t.map(TypeRef::into_name))
 }
 
 impl PartialEq for Pokemon {
@@ -68,21 +160,104 @@ impl DbRepr for Pokemon {
 
 /// Does not include types, which must be linked as relationships
 impl DbPut for Pokemon {
-    fn put_args(&self) -> String {
-        format!(
-            "{{ name: '{}', hp: {}, attack: {}, defense: {}, agility: {} }}",
-            sanitize(&self.name),
-            self.stats.hp,
-            self.stats.attack,
-            self.stats.defense,
-            self.stats.agility
-        )
+    fn put_args(&self) -> Vec<(&'static str, DbValue)> {
+        let mut args = vec![
+            ("name", self.name.as_str().into()),
+            ("hp", self.stats.hp.into()),
+            ("attack", self.stats.attack.into()),
+            ("defense", self.stats.defense.into()),
+            ("agility", self.stats.agility.into()),
+            ("level", self.stats.level.into()),
+            ("accuracy", self.stats.accuracy.into()),
+        ];
+
+        if let Some(stamina) = self.stats.stamina {
+            args.push(("stamina", stamina.into()));
+        }
+
+        // pokemon that never took part in a tracked fight have no fight count field
+        if self.fight_count > 0 {
+            args.push(("fight_count", self.fight_count.into()));
+        }
+
+        if let Some(item) = &self.held_item {
+            args.push(("held_item", item.name().into()));
+        }
+
+        args
+    }
+}
+
+/// Updates the stats and the held item, types are changed through their relationships
+/// and the fight count only through `count_fight`
+impl DbUpdate for Pokemon {
+    fn update_args(&self) -> Vec<(&'static str, DbValue)> {
+        vec![
+            ("hp", self.stats.hp.into()),
+            ("attack", self.stats.attack.into()),
+            ("defense", self.stats.defense.into()),
+            ("agility", self.stats.agility.into()),
+            ("stamina", self.stats.stamina.into()),
+            ("level", self.stats.level.into()),
+            ("accuracy", self.stats.accuracy.into()),
+            ("held_item", self.held_item.map(|item| item.name()).into()),
+        ]
     }
 }
 
 impl DbDelete for Pokemon {}
 
 impl Pokemon {
+    /// Creates a new pokemon without placing it in the database
+    /// Use `new_to_db` to also store it and link its types
+    pub fn new(
+        name: String,
+        primary_type: MaybePromise<PokemonType>,
+        secondary_type: Option<MaybePromise<PokemonType>>,
+        stats: PokemonStats,
+    ) -> Self {
+        Self {
+            name,
+            primary_type,
+            secondary_type,
+            stats,
+            fight_count: 0,
+            held_item: None,
+        }
+    }
+
+    /// The queries `put_with_relationships` executes, in order, without executing them
+    pub fn put_queries(&self) -> Vec<DbQuery> {
+        let mut queries = vec![
+            self.put_query(),
+            self.link_query(&self.primary_type, &Relationship::PrimaryType),
+        ];
+
+        if let Some(secondary_type) = &self.secondary_type {
+            queries.push(self.link_query(secondary_type, &Relationship::SecondaryType));
+        }
+
+        queries
+    }
+
+    /// Puts every pokemon in the database with its types, over a single connection and in one transaction,
+    /// so either all of them are stored or none are
+    pub async fn put_many(pokemons: &[Pokemon]) -> Result<()> {
+        let db = DbHandle::connect().await?;
+        let mut txn = db.inner.start_txn().await?;
+
+        let queries: Vec<DbQuery> = pokemons.iter().flat_map(Pokemon::put_queries).collect();
+
+        if let Err(e) = txn.run_queries(queries).await {
+            txn.rollback().await?;
+            return Err(e.into());
+        }
+
+        txn.commit().await?;
+
+        Ok(())
+    }
+
     /// Puts the Pokemon in the database with its types
     pub async fn put_with_relationships(&mut self) -> Result<()> {
         self.put_self_only().await?;
@@ -98,6 +273,29 @@ impl Pokemon {
         Ok(())
     }
 
+    /// A copy of the pokemon holding its types as bare names,
+    /// which is the format `POST /api/pokemons` takes
+    pub fn with_type_names(&self) -> Self {
+        let name_only =
+            |t: &MaybePromise<PokemonType>| MaybePromise::from_ident_unchecked(t.ident().to_string());
+
+        Self {
+            name: self.name.clone(),
+            primary_type: name_only(&self.primary_type),
+            secondary_type: self.secondary_type.as_ref().map(name_only),
+            stats: self.stats.clone(),
+            fight_count: self.fight_count,
+            held_item: self.held_item,
+        }
+    }
+
+    /// Reads the held item of a pokemon node, pokemon holding nothing have no held item field
+    fn held_item_from_node(node: &neo4rs::Node) -> Option<Item> {
+        node.get::<String>("held_item")
+            .ok()
+            .and_then(|item| item.parse().ok())
+    }
+
     /// Returns the primary type of the Pokemon
     pub fn primary_type(&self) -> &MaybePromise<PokemonType> {
         &self.primary_type
@@ -122,6 +320,8 @@ impl Pokemon {
             primary_type,
             secondary_type,
             stats,
+            fight_count: 0,
+            held_item: None,
         };
 
         // put the pokemon in the db
@@ -138,6 +338,174 @@ impl Pokemon {
         Ok(new)
     }
 
+    /// Makes a pokemon from its node and the nodes of its types,
+    /// for queries that fetch a pokemon together with its types
+    pub fn from_db_nodes(
+        node: &neo4rs::Node,
+        primary_type: neo4rs::Node,
+        secondary_type: Option<neo4rs::Node>,
+    ) -> Result<Self> {
+        Ok(Self {
+            name: node.get("name")?,
+            primary_type: MaybePromise::from_promise(PokemonType::promise_from_node(primary_type)),
+            secondary_type: secondary_type
+                .map(|t| MaybePromise::from_promise(PokemonType::promise_from_node(t))),
+            stats: PokemonStats::from_db_node(node)?,
+            fight_count: node.get("fight_count").unwrap_or(0),
+            held_item: Self::held_item_from_node(node),
+        })
+    }
+
+    /// Gets all pokemon that no trainer owns, with their types, using a single query
+    pub async fn get_orphans() -> Result<Vec<Pokemon>> {
+        let db = DbHandle::connect().await?;
+
+        let query = "MATCH (p:Pokemon)-[:PrimaryType]->(pt:PokemonType) \
+            WHERE NOT (:Trainer)-[:Owns]->(p) \
+            OPTIONAL MATCH (p)-[:SecondaryType]->(st:PokemonType) \
+            RETURN p, pt, st ORDER BY p.name;";
+
+        debug!("Get Orphans Query: {}", query);
+
+        let mut q_out = db.inner.execute(query.into()).await?;
+
+        let mut pokemons = vec![];
+
+        while let Some(row) = q_out.next().await? {
+            let node = row.get::<neo4rs::Node>("p")?;
+            let primary_type = row.get::<neo4rs::Node>("pt")?;
+            let secondary_type = row.get::<Option<neo4rs::Node>>("st")?;
+            pokemons.push(Self::from_db_nodes(&node, primary_type, secondary_type)?);
+        }
+
+        debug!("Get Orphans Result Count: {}", pokemons.len());
+
+        Ok(pokemons)
+    }
+
+    /// Gets all pokemon with their types, using a single query
+    pub async fn get_all_with_types() -> Result<Vec<Pokemon>> {
+        let db = DbHandle::connect().await?;
+
+        let query = "MATCH (p:Pokemon)-[:PrimaryType]->(pt:PokemonType) \
+            OPTIONAL MATCH (p)-[:SecondaryType]->(st:PokemonType) \
+            RETURN p, pt, st ORDER BY p.name;";
+
+        debug!("Get All With Types Query: {}", query);
+
+        let mut q_out = db.inner.execute(query.into()).await?;
+
+        let mut pokemons = vec![];
+
+        while let Some(row) = q_out.next().await? {
+            let node = row.get::<neo4rs::Node>("p")?;
+            let primary_type = row.get::<neo4rs::Node>("pt")?;
+            let secondary_type = row.get::<Option<neo4rs::Node>>("st")?;
+            pokemons.push(Self::from_db_nodes(&node, primary_type, secondary_type)?);
+        }
+
+        Ok(pokemons)
+    }
+
+    /// The query `count_fight` executes, without executing it
+    pub fn count_fight_query(&self) -> DbQuery {
+        DbQuery::new(format!(
+            "MATCH (n:{}) WHERE n.{} = $name SET n.fight_count = coalesce(n.fight_count, 0) + 1",
+            Self::DB_NODE_KIND,
+            Self::DB_IDENTIFIER_FIELD
+        ))
+        .param("name", self.get_raw_identifier())
+    }
+
+    /// Adds one to the stored fight count of the pokemon, in the database itself,
+    /// so concurrent fights never lose a count and no other property is written
+    pub async fn count_fight(&self) -> Result<()> {
+        let db = DbHandle::connect().await?;
+        let mut q_res = db.inner.execute(self.count_fight_query().into()).await?;
+        let _none = q_res.next().await?;
+        Ok(())
+    }
+
+    /// Sorts pokemon by their fight count, most used first, ties in alphabetical order
+    pub fn sort_by_usage(pokemons: &mut [Pokemon]) {
+        pokemons.sort_by(|a, b| {
+            b.fight_count
+                .cmp(&a.fight_count)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+    }
+
+    /// The pokemon with the highest stat total of each type, by type name
+    /// A pokemon counts for both its primary and its secondary type,
+    /// ties go to the alphabetically first name
+    pub fn champions(pokemons: &[Pokemon]) -> BTreeMap<String, Pokemon> {
+        let mut champions: BTreeMap<String, Pokemon> = BTreeMap::new();
+
+        for pokemon in pokemons {
+            let types = std::iter::once(pokemon.primary_type()).chain(pokemon.secondary_type());
+
+            for ptype in types {
+                let better = match champions.get(ptype.ident()) {
+                    // names are swapped, so on equal totals the smaller name wins
+                    Some(champion) => {
+                        (pokemon.stats.total(), &champion.name)
+                            > (champion.stats.total(), &pokemon.name)
+                    }
+                    None => true,
+                };
+
+                if better {
+                    champions.insert(ptype.ident().to_string(), pokemon.clone());
+                }
+            }
+        }
+
+        champions
+    }
+
+    /// Change the primary type of a pokemon
+    /// Every `PrimaryType` relationship of the pokemon is replaced by one to the new type in a single query,
+    /// so exactly one remains, and nothing changes if the pokemon or the new type don't exist
+    /// The relationship isn't unlinked with `unlink_from`, which refuses to leave a pokemon without a primary type
+    pub async fn set_primary_type(
+        &mut self,
+        new_primary_type: MaybePromise<PokemonType>,
+    ) -> Result<()> {
+        let db = DbHandle::connect().await?;
+
+        let query = DbQuery::new(format!(
+            "MATCH (a:{}), (b:{}) WHERE a.{} = $a AND b.{} = $b \
+            OPTIONAL MATCH (a)-[r:{}]->(:{}) DELETE r \
+            WITH DISTINCT a, b MERGE (a)-[:{}]->(b) RETURN a.{} AS name;",
+            Self::DB_NODE_KIND,
+            PokemonType::DB_NODE_KIND,
+            Self::DB_IDENTIFIER_FIELD,
+            PokemonType::DB_IDENTIFIER_FIELD,
+            Relationship::PrimaryType.as_db_string(),
+            PokemonType::DB_NODE_KIND,
+            Relationship::PrimaryType.as_db_string(),
+            Self::DB_IDENTIFIER_FIELD,
+        ))
+        .param("a", self.get_raw_identifier())
+        .param("b", new_primary_type.ident());
+
+        debug!("Set Primary Type Query: {:?}", query);
+
+        let mut q_res = db.inner.execute(query.into()).await?;
+
+        if q_res.next().await?.is_none() {
+            return Err(anyhow::anyhow!(
+                "Pokemon {} or type {} not found",
+                self.name,
+                new_primary_type.ident()
+            ));
+        }
+
+        self.primary_type = new_primary_type;
+
+        Ok(())
+    }
+
     /// Change the secondary type of a pokemon
     /// This is possible because the secondary type is an Option
     pub async fn set_secondary_type(
@@ -174,32 +542,24 @@ impl DbGet for Pokemon {
         Box::pin(async move {
             let identifier = node.get::<String>("name")?;
 
-            let primary_type = Self::get_linked_by_id(
-                &Relationship::PrimaryType,
-                format!("'{}'", sanitize(&identifier)),
-            )
-            .await?
-            .into_iter()
-            .next()
-            .ok_or(anyhow::anyhow!("No primary type found for Pokemon"))?;
-            let secondary_type = Self::get_linked_by_id(
-                &Relationship::SecondaryType,
-                format!("'{}'", sanitize(&identifier)),
-            )
-            .await?
-            .into_iter()
-            .next();
+            let primary_type = Self::get_linked_by_id(&Relationship::PrimaryType, &identifier)
+                .await?
+                .into_iter()
+                .next()
+                .ok_or(anyhow::anyhow!("No primary type found for Pokemon"))?;
+            let secondary_type = Self::get_linked_by_id(&Relationship::SecondaryType, &identifier)
+                .await?
+                .into_iter()
+                .next();
 
             Ok(Self {
                 name: identifier,
                 primary_type,
                 secondary_type,
-                stats: PokemonStats {
-                    hp: node.get("hp")?,
-                    attack: node.get("attack")?,
-                    defense: node.get("defense")?,
-                    agility: node.get("agility")?,
-                },
+                stats: PokemonStats::from_db_node(&node)?,
+                // pokemon stored before fights were tracked have no fight count field
+                fight_count: node.get("fight_count").unwrap_or(0),
+                held_item: Self::held_item_from_node(&node),
             })
         })
     }
@@ -253,7 +613,7 @@ impl DbLink<PokemonType> for Pokemon {
     ) -> Result<()> {
         match *relationship_type {
             Relationship::PrimaryType => {
-                // TODO: Implement a way to change a pokemon's primary type
+                // a pokemon always has a primary type, `set_primary_type` replaces it
                 Err(anyhow::anyhow!("Primary type cannot be unlinked"))
             }
             Relationship::SecondaryType => {
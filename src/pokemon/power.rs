@@ -0,0 +1,84 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Pokemon, ptype::PokemonType};
+
+/// Weights of the power rating, read from the `[power]` section of the config file
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct PowerConfig {
+    /// Weight of the `HP` stat
+    pub hp_weight: f32,
+    /// Weight of the `ATK` stat
+    pub attack_weight: f32,
+    /// Weight of the `DEF` stat
+    pub defense_weight: f32,
+    /// Weight of the `AGI` stat
+    pub agility_weight: f32,
+    /// Bonus for every existing type the pokemon is strong against
+    pub coverage_bonus: f32,
+}
+
+impl Default for PowerConfig {
+    fn default() -> Self {
+        Self {
+            hp_weight: 1.0,
+            attack_weight: 1.0,
+            defense_weight: 1.0,
+            agility_weight: 1.0,
+            coverage_bonus: 10.0,
+        }
+    }
+}
+
+impl PowerConfig {
+    /// Loads the power rating weights from the config file, or defaults if not configured
+    pub fn load() -> Self {
+        crate::config::read_section_or_default("power")
+    }
+}
+
+/// The power rating of a pokemon, and what it is made of
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PowerRating {
+    /// The name of the pokemon
+    pub pokemon: String,
+    /// The weighted sum of the pokemon's stats
+    pub stats_score: f32,
+    /// How many existing types the pokemon is strong against
+    pub coverage: usize,
+    /// The final power rating, the stats score plus the coverage bonus
+    pub power: f32,
+}
+
+/// Rates a pokemon with a single comparable number:
+/// the weighted sum of its stats, plus a bonus for every type in `all_types`
+/// that its primary or secondary type is strong against
+pub fn power_rating(
+    pokemon: &Pokemon,
+    primary_type: &PokemonType,
+    secondary_type: Option<&PokemonType>,
+    all_types: &[PokemonType],
+    config: &PowerConfig,
+) -> PowerRating {
+    let stats = &pokemon.stats;
+
+    let stats_score = stats.hp as f32 * config.hp_weight
+        + stats.attack as f32 * config.attack_weight
+        + stats.defense as f32 * config.defense_weight
+        + stats.agility as f32 * config.agility_weight;
+
+    let coverage = all_types
+        .iter()
+        .filter(|t| {
+            primary_type.is_strong_against(t)
+                || secondary_type.is_some_and(|s| s.is_strong_against(t))
+        })
+        .count();
+
+    PowerRating {
+        pokemon: pokemon.name.clone(),
+        stats_score,
+        coverage,
+        power: stats_score + coverage as f32 * config.coverage_bonus,
+    }
+}
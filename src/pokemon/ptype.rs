@@ -3,7 +3,7 @@ use std::pin::Pin;
 use serde::{Deserialize, Serialize};
 
 use crate::database::{
-    AsDbString, DbRepr,
+    AsDbString, DbHandle, DbRepr, DbValue,
     delete::DbDelete,
     get::DbGet,
     link::DbLink,
@@ -13,7 +13,7 @@ use crate::database::{
     update::DbUpdate,
 };
 
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 
 /// Represents a type of Pokemon, including its strengths and weaknesses
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -31,6 +31,20 @@ pub struct PokemonType {
 }
 
 impl PokemonType {
+    /// Creates a new PokemonType with the given relationships,
+    /// without placing it in the database
+    pub fn new(
+        name: String,
+        strong_against: Vec<MaybePromise<PokemonType>>,
+        weak_against: Vec<MaybePromise<PokemonType>>,
+    ) -> Self {
+        Self {
+            name,
+            strong_against,
+            weak_against,
+        }
+    }
+
     /// Creates a new PokemonType and places it in the database
     /// Does nothing on duplicate
     pub async fn new_to_db(name: String) -> Result<Self> {
@@ -45,6 +59,32 @@ impl PokemonType {
         Ok(new)
     }
 
+    /// Returns the types this Pokemon type is strong against
+    pub fn strong_against(&self) -> &[MaybePromise<PokemonType>] {
+        &self.strong_against
+    }
+
+    /// Returns the types this Pokemon type is weak against
+    pub fn weak_against(&self) -> &[MaybePromise<PokemonType>] {
+        &self.weak_against
+    }
+
+    /// A copy of the type holding its strengths and weaknesses as bare names
+    pub fn with_relation_names(&self) -> Self {
+        let names_only = |types: &[MaybePromise<PokemonType>]| {
+            types
+                .iter()
+                .map(|t| MaybePromise::from_ident_unchecked(t.ident().to_string()))
+                .collect()
+        };
+
+        Self {
+            name: self.name.clone(),
+            strong_against: names_only(&self.strong_against),
+            weak_against: names_only(&self.weak_against),
+        }
+    }
+
     /// Checks if this Pokemon type is strong against another type
     pub fn is_strong_against(&self, other: &PokemonType) -> bool {
         self.strong_against.iter().any(|t| t.ident() == other.name)
@@ -54,6 +94,43 @@ impl PokemonType {
     pub fn is_weak_against(&self, other: &PokemonType) -> bool {
         self.weak_against.iter().any(|t| t.ident() == other.name)
     }
+
+    /// Makes this type strong against `other`, and `other` weak against this type,
+    /// in a single transaction so the chart never holds only one of the two
+    /// Links that already exist are kept as they are
+    pub async fn beats(&mut self, other: &mut PokemonType) -> Result<()> {
+        if self.name == other.name {
+            return Err(anyhow!("Type {} can't beat itself", self.name));
+        }
+
+        let this = MaybePromise::from_ident_unchecked(self.name.clone());
+        let that = MaybePromise::from_ident_unchecked(other.name.clone());
+
+        let queries = [
+            self.link_query(&that, &Relationship::StrongAgainst),
+            other.link_query(&this, &Relationship::WeakAgainst),
+        ];
+
+        let db = DbHandle::connect().await?;
+        let mut txn = db.inner.start_txn().await?;
+
+        if let Err(e) = txn.run_queries(queries).await {
+            txn.rollback().await?;
+            return Err(e.into());
+        }
+
+        txn.commit().await?;
+
+        if !self.is_strong_against(other) {
+            self.link_side_effect(&that, &Relationship::StrongAgainst)?;
+        }
+
+        if !other.is_weak_against(self) {
+            other.link_side_effect(&this, &Relationship::WeakAgainst)?;
+        }
+
+        Ok(())
+    }
 }
 
 impl DbRepr for PokemonType {
@@ -94,16 +171,17 @@ impl DbLink<PokemonType> for PokemonType {
         other: &MaybePromise<PokemonType>,
         relationship_type: &Self::RelationshipType,
     ) -> Result<()> {
-        match relationship_type {
-            Self::RelationshipType::StrongAgainst => {
-                self.strong_against.push(other.clone());
-                Ok(())
-            }
-            Self::RelationshipType::WeakAgainst => {
-                self.weak_against.push(other.clone());
-                Ok(())
-            }
+        let types = match relationship_type {
+            Self::RelationshipType::StrongAgainst => &mut self.strong_against,
+            Self::RelationshipType::WeakAgainst => &mut self.weak_against,
+        };
+
+        // the link is merged in the database, so linking twice must not list the type twice
+        if !types.iter().any(|t| t.ident() == other.ident()) {
+            types.push(other.clone());
         }
+
+        Ok(())
     }
 
     fn unlink_side_effect(
@@ -155,14 +233,14 @@ impl DbGet for PokemonType {
 }
 
 impl DbPut for PokemonType {
-    fn put_args(&self) -> String {
-        format!("{{name: '{}'}}", sanitize(&self.name))
+    fn put_args(&self) -> Vec<(&'static str, DbValue)> {
+        vec![("name", self.name.as_str().into())]
     }
 }
 
 impl DbUpdate for PokemonType {
-    fn update_args(&self) -> String {
-        format!("SET name = '{}'", sanitize(&self.name))
+    fn update_args(&self) -> Vec<(&'static str, DbValue)> {
+        vec![("name", self.name.as_str().into())]
     }
 }
 
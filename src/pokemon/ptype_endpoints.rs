@@ -0,0 +1,116 @@
+use rocket::State;
+
+use crate::{
+    config::ApiConfig,
+    database::{get::DbGet, link::DbLink, promise::MaybePromise, suggest},
+    idempotency::{IdempotencyCache, IdempotencyKey},
+    json::{self, ApiError, JsonResult, JsonStatus},
+    pokemon::ptype::{PokemonType, Relationship},
+};
+
+/// Endpoint for listing the names of all Pokemon types.
+#[get("/types")]
+pub async fn get_types<'a>(api_config: &State<ApiConfig>) -> JsonResult<'a> {
+    info!("Request to /api/types");
+    let mut names = PokemonType::get_all_identifiers()
+        .await
+        .map_err(ApiError::from_anyhow)?;
+    api_config.check_list_len(names.len())?;
+    names.sort();
+    Ok(JsonStatus::data_owned(names))
+}
+
+/// Endpoint for fetching a single Pokemon type by its name,
+/// with its strengths and weaknesses as bare names.
+#[get("/types/<name>")]
+pub async fn get_type<'a>(name: String) -> JsonResult<'a> {
+    info!("Request to /api/types/{}", name);
+    let ptype = suggest::find::<PokemonType>("Type", &name).await?;
+    Ok(JsonStatus::data_owned(ptype.with_relation_names()))
+}
+
+/// Endpoint for creating a new Pokemon type, with no strengths or weaknesses.
+#[post("/types/<name>")]
+pub async fn create_type<'a>(
+    name: String,
+    key: IdempotencyKey,
+    idempotency: &State<IdempotencyCache>,
+) -> JsonResult<'a> {
+    info!("Request to /api/types/{}", name);
+
+    idempotency
+        .run(&key, async move {
+            if name.len() > 30 {
+                return Err(ApiError::bad_request("Name is too long"));
+            }
+
+            if name.is_empty() {
+                return Err(ApiError::bad_request("Name cannot be empty"));
+            }
+
+            // do not allow duplicate types
+            if PokemonType::exists(&name)
+                .await
+                .map_err(ApiError::from_anyhow)?
+            {
+                return Err(ApiError::conflict("Type already exists"));
+            }
+
+            PokemonType::new_to_db(name)
+                .await
+                .map_err(ApiError::from_anyhow)?;
+
+            Ok(JsonStatus::new_empty(json::Status::Ok))
+        })
+        .await
+}
+
+/// Links or unlinks type `a` to type `b` with `relationship`, once both are found,
+/// and returns `a` with its strengths and weaknesses as bare names
+async fn relate<'a>(a: &str, b: &str, relationship: Relationship, link: bool) -> JsonResult<'a> {
+    let mut types = vec![];
+
+    for name in [a, b] {
+        types.push(suggest::find::<PokemonType>("Type", name).await?);
+    }
+
+    let other = MaybePromise::from_concrete(types.pop().unwrap());
+    let mut ptype = types.pop().unwrap();
+
+    let result = if link {
+        ptype.link_to(&other, &relationship).await
+    } else {
+        ptype.unlink_from(&other, &relationship).await
+    };
+    result.map_err(ApiError::from_anyhow)?;
+
+    Ok(JsonStatus::data_owned(ptype.with_relation_names()))
+}
+
+/// Endpoint to make type `a` strong against type `b`, linking it twice changes nothing.
+#[post("/types/<a>/strong_against/<b>")]
+pub async fn add_strong_against<'a>(a: String, b: String) -> JsonResult<'a> {
+    info!("Request to /api/types/{}/strong_against/{}", a, b);
+    relate(&a, &b, Relationship::StrongAgainst, true).await
+}
+
+/// Endpoint to make type `a` weak against type `b`, linking it twice changes nothing.
+#[post("/types/<a>/weak_against/<b>")]
+pub async fn add_weak_against<'a>(a: String, b: String) -> JsonResult<'a> {
+    info!("Request to /api/types/{}/weak_against/{}", a, b);
+    relate(&a, &b, Relationship::WeakAgainst, true).await
+}
+
+/// Endpoint to make type `a` no longer strong against type `b`.
+#[delete("/types/<a>/strong_against/<b>")]
+pub async fn remove_strong_against<'a>(a: String, b: String) -> JsonResult<'a> {
+    info!("Request to /api/types/{}/strong_against/{}", a, b);
+    relate(&a, &b, Relationship::StrongAgainst, false).await
+}
+
+/// Endpoint to make type `a` no longer weak against type `b`.
+#[delete("/types/<a>/weak_against/<b>")]
+pub async fn remove_weak_against<'a>(a: String, b: String) -> JsonResult<'a> {
+    info!("Request to /api/types/{}/weak_against/{}", a, b);
+    relate(&a, &b, Relationship::WeakAgainst, false).await
+}
@@ -0,0 +1,80 @@
+use serde::Serialize;
+
+use super::ptype::PokemonType;
+
+/// How a type scores on one side of the chart, compared with every other type
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct TypeScore {
+    /// How many other types this type is strong against (offensive) or weak against (defensive)
+    pub count: usize,
+    /// The count as a share of the other types in the chart, from `0.0` to `1.0`, higher is better.
+    /// Defensively this is the share of other types the type is not weak against
+    pub score: f32,
+    /// The share of the other types whose score is strictly lower, from `0.0` to `1.0`
+    pub percentile: f32,
+    /// The type's place among all types, `1` being the best, tied types share a place
+    pub rank: usize,
+}
+
+/// A type's offensive and defensive standing in the whole type chart
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct TypeRanking {
+    /// The name of the ranked type
+    pub name: String,
+    /// The number of types in the chart, including the ranked one
+    pub types: usize,
+    /// Ranked by how many other types the type is strong against
+    pub offensive: TypeScore,
+    /// Ranked by how few other types the type is weak against
+    pub defensive: TypeScore,
+}
+
+/// Scores `counts[index]` against the other counts, a higher count being better
+fn score(counts: &[usize], index: usize) -> TypeScore {
+    let count = counts[index];
+    let others = counts.len().saturating_sub(1).max(1) as f32;
+    let lower = counts.iter().filter(|c| **c < count).count();
+    let higher = counts.iter().filter(|c| **c > count).count();
+
+    TypeScore {
+        count,
+        score: count as f32 / others,
+        percentile: lower as f32 / others,
+        rank: higher + 1,
+    }
+}
+
+/// Ranks the type named `name` against every type in `all_types`, offensively and defensively
+/// Only relationships with other types in `all_types` are counted
+/// Returns `None` if no type in `all_types` has that name
+pub fn type_ranking(name: &str, all_types: &[PokemonType]) -> Option<TypeRanking> {
+    let index = all_types.iter().position(|t| t.name == name)?;
+
+    let others = |ptype: &PokemonType, relation: fn(&PokemonType, &PokemonType) -> bool| {
+        all_types
+            .iter()
+            .filter(|other| other.name != ptype.name && relation(ptype, other))
+            .count()
+    };
+
+    let strong: Vec<usize> = all_types
+        .iter()
+        .map(|t| others(t, PokemonType::is_strong_against))
+        .collect();
+
+    // defensively, every type the ranked type is not weak against counts in its favour
+    let not_weak: Vec<usize> = all_types
+        .iter()
+        .map(|t| all_types.len() - 1 - others(t, PokemonType::is_weak_against))
+        .collect();
+
+    let mut defensive = score(&not_weak, index);
+    defensive.count = all_types.len() - 1 - defensive.count;
+
+    Some(TypeRanking {
+        name: all_types[index].name.clone(),
+        types: all_types.len(),
+        offensive: score(&strong, index),
+        defensive,
+    })
+}
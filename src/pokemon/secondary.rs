@@ -0,0 +1,150 @@
+use std::{cmp::Ordering, str::FromStr};
+
+use anyhow::{Result, anyhow};
+use serde::Serialize;
+
+use super::ptype::PokemonType;
+use crate::fight::pokemon_fight::{ResolvedPokemon, type_damage_multiplier};
+
+/// What a secondary type suggestion optimizes for
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum SuggestionGoal {
+    /// Hitting as many roster pokemon as possible for more than neutral damage
+    Offense,
+    /// Taking less than neutral damage from as many roster pokemon as possible
+    Defense,
+}
+
+impl FromStr for SuggestionGoal {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "offense" => Ok(SuggestionGoal::Offense),
+            "defense" => Ok(SuggestionGoal::Defense),
+            _ => Err(()),
+        }
+    }
+}
+
+/// How a pokemon would fare against the roster with a hypothetical secondary type
+#[derive(Serialize, Clone, Debug)]
+pub struct SecondarySuggestion {
+    /// The name of the hypothetical secondary type
+    pub secondary_type: String,
+    /// How many roster pokemon the pokemon would hit for more than neutral damage
+    pub coverage: usize,
+    /// How many roster pokemon would hit the pokemon for less than neutral damage
+    pub resistances: usize,
+    /// The average type damage multiplier of the pokemon's attacks on the roster, higher is better
+    pub mean_offense: f32,
+    /// The average type damage multiplier of the roster's attacks on the pokemon, lower is better
+    pub mean_defense: f32,
+}
+
+/// The secondary types a pokemon could have, best first
+#[derive(Serialize, Clone, Debug)]
+pub struct SecondarySuggestions {
+    /// The name of the pokemon
+    pub pokemon: String,
+    /// What the suggestions are ranked by
+    pub goal: SuggestionGoal,
+    /// The pokemon's actual secondary type, if it has one
+    pub current_secondary_type: Option<String>,
+    /// The number of roster pokemon the types were tried against
+    pub roster_size: usize,
+    /// Every type except the pokemon's primary type, ranked by `goal`
+    pub suggestions: Vec<SecondarySuggestion>,
+}
+
+/// Ranks `a` before `b` if it scores better for `goal`, other scores and then names break ties
+fn compare(a: &SecondarySuggestion, b: &SecondarySuggestion, goal: SuggestionGoal) -> Ordering {
+    let offense = b
+        .coverage
+        .cmp(&a.coverage)
+        .then_with(|| b.mean_offense.total_cmp(&a.mean_offense));
+    let defense = b
+        .resistances
+        .cmp(&a.resistances)
+        .then_with(|| a.mean_defense.total_cmp(&b.mean_defense));
+
+    match goal {
+        SuggestionGoal::Offense => offense.then(defense),
+        SuggestionGoal::Defense => defense.then(offense),
+    }
+    .then_with(|| a.secondary_type.cmp(&b.secondary_type))
+}
+
+/// Tries every type in `all_types` as the secondary type of `pokemon` and ranks them by `goal`,
+/// using only the type damage multipliers (steps 3-4 of the fight algorithm) against every `roster` pokemon
+/// other than `pokemon` itself. Nothing is changed, the pokemon keeps its actual types
+pub fn suggest_secondary(
+    pokemon: &ResolvedPokemon,
+    all_types: &[PokemonType],
+    roster: &[ResolvedPokemon],
+    goal: SuggestionGoal,
+) -> Result<SecondarySuggestions> {
+    let roster: Vec<&ResolvedPokemon> = roster
+        .iter()
+        .filter(|other| other.pokemon.name != pokemon.pokemon.name)
+        .collect();
+
+    if roster.is_empty() {
+        return Err(anyhow!(
+            "There are no other pokemon to suggest a secondary type for {} against",
+            pokemon.pokemon.name
+        ));
+    }
+
+    let primary = &pokemon.primary_type;
+
+    let mut suggestions: Vec<SecondarySuggestion> = all_types
+        .iter()
+        .filter(|candidate| candidate.name != primary.name)
+        .map(|candidate| {
+            let offense: Vec<f32> = roster
+                .iter()
+                .map(|other| {
+                    type_damage_multiplier(
+                        primary,
+                        Some(candidate),
+                        &other.primary_type,
+                        other.secondary_type.as_ref(),
+                    )
+                    .multiplier
+                })
+                .collect();
+
+            let defense: Vec<f32> = roster
+                .iter()
+                .map(|other| {
+                    type_damage_multiplier(
+                        &other.primary_type,
+                        other.secondary_type.as_ref(),
+                        primary,
+                        Some(candidate),
+                    )
+                    .multiplier
+                })
+                .collect();
+
+            SecondarySuggestion {
+                secondary_type: candidate.name.clone(),
+                coverage: offense.iter().filter(|m| **m > 1.0).count(),
+                resistances: defense.iter().filter(|m| **m < 1.0).count(),
+                mean_offense: offense.iter().sum::<f32>() / roster.len() as f32,
+                mean_defense: defense.iter().sum::<f32>() / roster.len() as f32,
+            }
+        })
+        .collect();
+
+    suggestions.sort_by(|a, b| compare(a, b, goal));
+
+    Ok(SecondarySuggestions {
+        pokemon: pokemon.pokemon.name.clone(),
+        goal,
+        current_secondary_type: pokemon.secondary_type.as_ref().map(|t| t.name.clone()),
+        roster_size: roster.len(),
+        suggestions,
+    })
+}
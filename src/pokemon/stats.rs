@@ -1,5 +1,25 @@
 use serde::{Deserialize, Serialize};
 
+/// The level of a pokemon that doesn't have one, at which its damage isn't scaled
+pub const DEFAULT_LEVEL: u32 = 50;
+
+/// The highest level a pokemon can have
+pub const MAX_LEVEL: u32 = 100;
+
+/// The highest value the HP, attack, defense and agility stats can have
+pub const MAX_STAT: u32 = 255;
+
+/// The accuracy of a pokemon that doesn't have one, whose attacks never miss
+pub const MAX_ACCURACY: u32 = 100;
+
+fn default_level() -> u32 {
+    DEFAULT_LEVEL
+}
+
+fn default_accuracy() -> u32 {
+    MAX_ACCURACY
+}
+
 /// Represents the base stats of a Pokemon, including HP, attack, defense, and agility
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct PokemonStats {
@@ -18,4 +38,75 @@ pub struct PokemonStats {
     /// This stat determines attack priority in battle
     #[serde(rename = "agi")]
     pub agility: u32,
+
+    /// The stamina pool attacks draw from in battle, `None` never runs out
+    #[serde(rename = "sta", default, skip_serializing_if = "Option::is_none")]
+    pub stamina: Option<u32>,
+
+    /// The level of the Pokemon, which scales the damage it deals, see `level_multiplier`
+    #[serde(default = "default_level")]
+    pub level: u32,
+
+    /// The chance (in percent, `0` to `100`) that the Pokemon's attacks land, see `hit_chance`
+    #[serde(default = "default_accuracy")]
+    pub accuracy: u32,
+}
+
+impl PokemonStats {
+    /// Reads the stats stored on a pokemon's database node
+    pub fn from_db_node(node: &neo4rs::Node) -> anyhow::Result<Self> {
+        Ok(Self {
+            hp: node.get("hp")?,
+            attack: node.get("attack")?,
+            defense: node.get("defense")?,
+            agility: node.get("agility")?,
+            // pokemon stored before stamina existed have no stamina field
+            stamina: node.get("stamina").ok(),
+            // pokemon stored before levels existed are at the default level
+            level: node.get("level").unwrap_or(DEFAULT_LEVEL),
+            // pokemon stored before accuracy existed never miss
+            accuracy: node.get("accuracy").unwrap_or(MAX_ACCURACY),
+        })
+    }
+
+    /// How many times the damage the pokemon deals at its level is of the damage it deals at the default level,
+    /// following the level term of the main series damage formula, `2 * level / 5 + 2`
+    /// It's `1.0` at level 50, about `1.9` at level 100 and about `0.18` at level 5
+    pub fn level_multiplier(&self) -> f32 {
+        let term = |level: u32| 2.0 * level as f32 / 5.0 + 2.0;
+        term(self.level) / term(DEFAULT_LEVEL)
+    }
+
+    /// The chance (`0.0` to `1.0`) that an attack of the pokemon lands
+    pub fn hit_chance(&self) -> f32 {
+        self.accuracy.min(MAX_ACCURACY) as f32 / MAX_ACCURACY as f32
+    }
+
+    /// Checks that every stat is in range: HP from 1, attack, defense and agility from 0, all up to `MAX_STAT`,
+    /// the level from 1 to `MAX_LEVEL` and the accuracy up to `MAX_ACCURACY`. The error names the first stat out of range
+    pub fn validate(&self) -> Result<(), String> {
+        let ranges = [
+            ("hp", self.hp, 1, MAX_STAT),
+            ("atk", self.attack, 0, MAX_STAT),
+            ("def", self.defense, 0, MAX_STAT),
+            ("agi", self.agility, 0, MAX_STAT),
+            ("level", self.level, 1, MAX_LEVEL),
+            ("accuracy", self.accuracy, 1, MAX_ACCURACY),
+        ];
+
+        for (field, value, min, max) in ranges {
+            if !(min..=max).contains(&value) {
+                return Err(format!(
+                    "The {field} stat must be between {min} and {max}, got {value}"
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The sum of every stat, except stamina, level and accuracy
+    pub fn total(&self) -> u32 {
+        self.hp + self.attack + self.defense + self.agility
+    }
 }
@@ -9,7 +9,7 @@ use rocket::local::blocking::Client;
 #[allow(unused)]
 fn create_test_rocket() -> Rocket<Build> {
     let cors = make_cors().to_cors().expect("Error creating CORS fairing");
-    rocket::build().attach(cors).mount("/api", routes![index])
+    rocket::build().attach(cors).mount("/api", routes![index, version])
 }
 
 #[test]
@@ -29,6 +29,25 @@ fn test_index_endpoint() {
     assert_eq!(json["data"], serde_json::Value::Array(vec![]));
 }
 
+#[test]
+fn test_version_endpoint() {
+    let client = Client::tracked(create_test_rocket()).expect("Failed to create client");
+    let response = client.get("/api/version").dispatch();
+
+    assert_eq!(response.status(), Status::Ok);
+
+    let body = response
+        .into_string()
+        .expect("Response body should be readable");
+    let json: serde_json::Value =
+        serde_json::from_str(&body).expect("Response should be valid JSON");
+
+    assert_eq!(json["status"], "Ok");
+    assert_eq!(json["data"]["version"], env!("CARGO_PKG_VERSION"));
+    assert!(json["data"].get("commit").is_some());
+    assert!(json["data"].get("password").is_none());
+}
+
 #[test]
 fn test_cors_headers() {
     let client = Client::tracked(create_test_rocket()).expect("Failed to create client");
@@ -125,6 +144,28 @@ fn test_invalid_method() {
     assert_eq!(response.status(), Status::NotFound);
 }
 
+#[test]
+fn test_health_endpoint_reports_json_status() {
+    let rocket = rocket::build().mount("/api", routes![health]);
+    let client = Client::tracked(rocket).expect("Failed to create client");
+    let response = client.get("/api/health").dispatch();
+
+    // OK with a database, 503 without one, but never a missing route
+    let status = response.status();
+    assert!(
+        status == Status::Ok || status == Status::ServiceUnavailable,
+        "Expected 200 OK or 503 Service Unavailable, got {}",
+        status
+    );
+
+    let json: serde_json::Value = serde_json::from_str(&response.into_string().unwrap()).unwrap();
+    assert!(json.get("status").is_some(), "{json}");
+    if status == Status::ServiceUnavailable {
+        let error = json["status"]["Error"].as_str().unwrap();
+        assert!(error.starts_with("The database is unreachable"), "{error}");
+    }
+}
+
 #[test]
 fn test_nonexistent_endpoint() {
     let client = Client::tracked(create_test_rocket()).expect("Failed to create client");
@@ -173,3 +214,1304 @@ fn test_cors_configuration() {
     assert!(methods.iter().any(|m| m.as_str() == "POST"));
     assert!(methods.iter().any(|m| m.as_str() == "DELETE"));
 }
+
+#[test]
+fn test_page_limit_is_capped() {
+    use crate::database::get::{DEFAULT_PAGE_LIMIT, MAX_PAGE_LIMIT, check_page_limit};
+
+    assert!(check_page_limit(1).is_ok());
+    assert!(check_page_limit(DEFAULT_PAGE_LIMIT).is_ok());
+    assert!(check_page_limit(MAX_PAGE_LIMIT).is_ok());
+
+    for limit in [0, MAX_PAGE_LIMIT + 1] {
+        let error = check_page_limit(limit).expect_err("the limit should be refused");
+        assert!(error.to_string().contains("between 1 and 500"));
+    }
+}
+
+#[test]
+fn test_list_size_guard() {
+    use crate::config::ApiConfig;
+
+    let config = ApiConfig {
+        max_list_items: 2,
+        ..ApiConfig::default()
+    };
+
+    assert!(config.check_list_len(0).is_ok());
+    assert!(config.check_list_len(2).is_ok());
+
+    let error = config.check_list_len(3).expect_err("3 items should exceed the cap");
+    assert_eq!(error.code(), rocket::http::Status::PayloadTooLarge);
+    assert!(error.message().contains("Payload too large"));
+    // a list that can't be paginated doesn't suggest it
+    assert!(!error.message().contains("skip="));
+
+    assert!(config.check_page_len(2).is_ok());
+
+    let error = config.check_page_len(3).expect_err("3 items should exceed the cap");
+    assert_eq!(error.code(), rocket::http::Status::PayloadTooLarge);
+    assert!(error.message().contains("skip=0&limit=2"));
+}
+
+#[test]
+fn test_pokemon_accepts_both_type_forms() {
+    use crate::database::promise::MaybePromise;
+    use crate::pokemon::{Pokemon, ptype::PokemonType, stats::PokemonStats};
+
+    let fire = PokemonType::new(
+        "Fire".to_string(),
+        vec![MaybePromise::from_ident_unchecked("Grass".to_string())],
+        vec![],
+    );
+    let pokemon = Pokemon::new(
+        "Ember".to_string(),
+        MaybePromise::from_concrete(fire),
+        None,
+        PokemonStats {
+            hp: 100,
+            attack: 50,
+            defense: 40,
+            agility: 30,
+            stamina: None,
+            level: 50,
+            accuracy: 100,
+        },
+    );
+
+    // with resolved types, as in a trainer's team
+    let resolved = serde_json::to_value(&pokemon).unwrap();
+    assert!(resolved["primary_type"].is_object());
+
+    // as exported, with the types as bare names
+    let exported = serde_json::to_value(pokemon.with_type_names()).unwrap();
+    assert_eq!(exported["primary_type"], "Fire");
+    assert!(exported["secondary_type"].is_null());
+
+    for body in [resolved, exported] {
+        let posted: Pokemon = serde_json::from_value(body).unwrap();
+        assert_eq!(posted.primary_type().ident(), "Fire");
+        assert!(posted.secondary_type().is_none());
+        assert_eq!(posted.stats.attack, 50);
+    }
+}
+
+#[test]
+fn test_pokemon_export_round_trip() {
+    use crate::database::{DbHandle, get::DbGet, promise::MaybePromise, put::DbPut};
+    use crate::idempotency::IdempotencyCache;
+    use crate::pokemon::{Pokemon, endpoints, ptype::PokemonType, stats::PokemonStats};
+
+    let runtime = rocket::tokio::runtime::Runtime::new().unwrap();
+
+    let primary = PokemonType::new("ExportTestType1".to_string(), vec![], vec![]);
+    let secondary = PokemonType::new("ExportTestType2".to_string(), vec![], vec![]);
+    runtime.block_on(async {
+        primary.put_self_only().await.unwrap();
+        secondary.put_self_only().await.unwrap();
+        Pokemon::new_to_db(
+            "ExportTestMon".to_string(),
+            MaybePromise::from_concrete(primary),
+            Some(MaybePromise::from_concrete(secondary)),
+            PokemonStats {
+                hp: 100,
+                attack: 50,
+                defense: 40,
+                agility: 30,
+                stamina: None,
+                level: 50,
+                accuracy: 100,
+            },
+        )
+        .await
+        .unwrap();
+    });
+
+    let rocket = rocket::build().manage(IdempotencyCache::load()).mount(
+        "/api",
+        routes![endpoints::export_pokemon, endpoints::add_pokemon],
+    );
+    let client = Client::tracked(rocket).expect("Failed to create client");
+
+    let body = client
+        .get("/api/pokemons/ExportTestMon/export")
+        .dispatch()
+        .into_string()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(json["status"], "Ok");
+
+    let mut exported = json["data"].clone();
+    exported["name"] = "ExportTestCopy".into();
+
+    let body = client
+        .post("/api/pokemons")
+        .body(exported.to_string())
+        .dispatch()
+        .into_string()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(json["status"], "Ok", "{body}");
+
+    let copy = runtime.block_on(Pokemon::get_first("ExportTestCopy"));
+
+    runtime.block_on(async {
+        let db = DbHandle::connect().await.unwrap();
+        let mut q_res = db
+            .inner
+            .execute("MATCH (n) WHERE n.name STARTS WITH 'ExportTest' DETACH DELETE n;".into())
+            .await
+            .unwrap();
+        let _none = q_res.next().await.unwrap();
+    });
+
+    let copy = copy.unwrap();
+    assert_eq!(copy.primary_type().ident(), "ExportTestType1");
+    assert_eq!(
+        copy.secondary_type().map(|t| t.ident()),
+        Some("ExportTestType2")
+    );
+}
+#[test]
+fn test_type_beats_links_both_ways() {
+    use crate::database::{DbHandle, get::DbGet};
+    use crate::pokemon::{endpoints, ptype::PokemonType};
+
+    let runtime = rocket::tokio::runtime::Runtime::new().unwrap();
+
+    runtime.block_on(async {
+        PokemonType::new_to_db("BeatsTestFire".to_string())
+            .await
+            .unwrap();
+        PokemonType::new_to_db("BeatsTestGrass".to_string())
+            .await
+            .unwrap();
+    });
+
+    let rocket = rocket::build().mount("/api", routes![endpoints::type_beats]);
+    let client = Client::tracked(rocket).expect("Failed to create client");
+
+    // the second call must not duplicate the edges
+    for _ in 0..2 {
+        let body = client
+            .post("/api/types/BeatsTestFire/beats/BeatsTestGrass")
+            .dispatch()
+            .into_string()
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(json["status"], "Ok", "{body}");
+    }
+
+    // reloaded from the database
+    let fire = runtime.block_on(PokemonType::get_first("BeatsTestFire"));
+    let grass = runtime.block_on(PokemonType::get_first("BeatsTestGrass"));
+
+    runtime.block_on(async {
+        let db = DbHandle::connect().await.unwrap();
+        let mut q_res = db
+            .inner
+            .execute("MATCH (n) WHERE n.name STARTS WITH 'BeatsTest' DETACH DELETE n;".into())
+            .await
+            .unwrap();
+        let _none = q_res.next().await.unwrap();
+    });
+
+    let (fire, grass) = (fire.unwrap(), grass.unwrap());
+    assert!(fire.is_strong_against(&grass));
+    assert!(grass.is_weak_against(&fire));
+    assert!(!fire.is_weak_against(&grass));
+    assert!(!grass.is_strong_against(&fire));
+    assert_eq!(fire.strong_against().len(), 1);
+    assert_eq!(grass.weak_against().len(), 1);
+}
+#[test]
+fn test_oversized_pokemon_body_is_refused() {
+    use crate::config::ApiConfig;
+    use crate::idempotency::IdempotencyCache;
+
+    let api_config = ApiConfig {
+        max_json_body_kib: 1,
+        ..ApiConfig::default()
+    };
+    let rocket = rocket::custom(rocket::Config {
+        limits: api_config.limits(),
+        ..rocket::Config::debug_default()
+    })
+    .manage(IdempotencyCache::load())
+    .mount("/api", routes![pokemon::endpoints::add_pokemon])
+    .register("/api", catchers![payload_too_large]);
+    let client = Client::tracked(rocket).expect("Failed to create client");
+
+    // a 2 KiB name makes the body larger than the 1 KiB limit
+    let body = serde_json::json!({
+        "name": "x".repeat(2048),
+        "primary_type": "Normal",
+        "secondary_type": null,
+        "stats": { "hp": 1, "atk": 1, "def": 1, "agi": 1 }
+    });
+    let response = client
+        .post("/api/pokemons")
+        .body(body.to_string())
+        .dispatch();
+
+    assert_eq!(response.status(), Status::PayloadTooLarge);
+
+    let json: serde_json::Value = serde_json::from_str(&response.into_string().unwrap()).unwrap();
+    assert!(json["status"]["Error"].as_str().unwrap().contains("1KiB"));
+}
+#[test]
+fn test_add_pokemon_with_missing_type_names_it() {
+    use crate::idempotency::IdempotencyCache;
+    use crate::pokemon::endpoints;
+
+    let rocket = rocket::build()
+        .manage(IdempotencyCache::load())
+        .mount("/api", routes![endpoints::add_pokemon]);
+    let client = Client::tracked(rocket).expect("Failed to create client");
+
+    let body = serde_json::json!({
+        "name": "MissingTypeTestMon",
+        "primary_type": "MissingTypeTestType",
+        "stats": { "hp": 100, "atk": 50, "def": 50, "agi": 50 }
+    });
+    let body = client
+        .post("/api/pokemons")
+        .body(body.to_string())
+        .dispatch()
+        .into_string()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+
+    let error = json["status"]["Error"].as_str().expect(&body);
+    assert!(
+        error.starts_with("Type 'MissingTypeTestType' not found"),
+        "{error}"
+    );
+}
+
+#[test]
+fn test_new_pokemon_takes_type_names_or_objects() {
+    use crate::pokemon::NewPokemon;
+
+    let by_name: NewPokemon = serde_json::from_value(serde_json::json!({
+        "name": "Mon",
+        "primary_type": "Fire",
+        "secondary_type": null,
+        "stats": { "hp": 1, "atk": 1, "def": 1, "agi": 1 }
+    }))
+    .unwrap();
+    assert_eq!(by_name.primary_type, "Fire");
+    assert_eq!(by_name.secondary_type, None);
+
+    let by_object: NewPokemon = serde_json::from_value(serde_json::json!({
+        "name": "Mon",
+        "primary_type": { "name": "Fire", "strong_against": ["Grass"], "weak_against": [] },
+        "secondary_type": "Rock",
+        "stats": { "hp": 1, "atk": 1, "def": 1, "agi": 1 }
+    }))
+    .unwrap();
+    assert_eq!(by_object.primary_type, "Fire");
+    assert_eq!(by_object.secondary_type.as_deref(), Some("Rock"));
+}
+#[test]
+fn test_search_categorizes_matches() {
+    use crate::database::{DbHandle, endpoints, promise::MaybePromise, put::DbPut};
+    use crate::pokemon::{Pokemon, ptype::PokemonType, stats::PokemonStats};
+    use crate::trainer::Trainer;
+
+    let runtime = rocket::tokio::runtime::Runtime::new().unwrap();
+
+    runtime.block_on(async {
+        let ptype = PokemonType::new("SearchTestType".to_string(), vec![], vec![]);
+        ptype.put_self_only().await.unwrap();
+        Pokemon::new_to_db(
+            "SearchTestMarlin".to_string(),
+            MaybePromise::from_concrete(ptype),
+            None,
+            PokemonStats {
+                hp: 100,
+                attack: 50,
+                defense: 50,
+                agility: 50,
+                stamina: None,
+                level: 50,
+                accuracy: 100,
+            },
+        )
+        .await
+        .unwrap();
+        Trainer {
+            name: "SearchTestCarla".to_string(),
+            team: vec![],
+            strategy: None,
+        }
+        .put_self_only()
+        .await
+        .unwrap();
+    });
+
+    let rocket = rocket::build().mount("/api", routes![endpoints::search]);
+    let client = Client::tracked(rocket).expect("Failed to create client");
+
+    // both names contain "arl", the search ignores case
+    let body = client
+        .get("/api/search?q=ARL&limit=100")
+        .dispatch()
+        .into_string()
+        .unwrap();
+
+    runtime.block_on(async {
+        let db = DbHandle::connect().await.unwrap();
+        let mut q_res = db
+            .inner
+            .execute("MATCH (n) WHERE n.name STARTS WITH 'SearchTest' DETACH DELETE n;".into())
+            .await
+            .unwrap();
+        let _none = q_res.next().await.unwrap();
+    });
+
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(json["status"], "Ok", "{body}");
+
+    let names = |category: &str| -> Vec<String> {
+        json["data"][category]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|n| n.as_str())
+            .filter(|n| n.starts_with("SearchTest"))
+            .map(str::to_string)
+            .collect()
+    };
+
+    assert_eq!(names("pokemons"), vec!["SearchTestMarlin"]);
+    assert_eq!(names("trainers"), vec!["SearchTestCarla"]);
+    assert!(names("types").is_empty());
+}
+#[test]
+fn test_disabled_odds_feature_is_refused() {
+    use crate::features::{FeatureConfig, feature_disabled};
+    use crate::fight::{config::FightConfig, endpoints};
+
+    let rocket = rocket::build()
+        .manage(FightConfig::default())
+        .manage(FeatureConfig {
+            odds: false,
+            ..FeatureConfig::default()
+        })
+        .mount(
+            "/api",
+            routes![
+                endpoints::simulate_fight,
+                endpoints::simulate_trainer_fight_odds
+            ],
+        )
+        .register("/api", catchers![feature_disabled]);
+    let client = Client::tracked(rocket).expect("Failed to create client");
+
+    let response = client
+        .get("/api/simulate_trainer_fight/Ash/Random/Gary/Random/odds?trials=10&seed=1")
+        .dispatch();
+    assert_eq!(response.status(), Status::Forbidden);
+
+    let json: serde_json::Value = serde_json::from_str(&response.into_string().unwrap()).unwrap();
+    let error = json["status"]["Error"].as_str().unwrap();
+    assert!(error.starts_with("Feature disabled: 'odds'"), "{error}");
+
+    // the fight itself is not behind a flag, so it gets past the guard to the pokemon lookup
+    let response = client
+        .get("/api/simulate_fight/FeatureTestMissing/FeatureTestMissing?seed=1")
+        .dispatch();
+    assert_ne!(response.status(), Status::Forbidden);
+
+    let body = response.into_string().unwrap();
+    assert!(!body.contains("Feature disabled"), "{body}");
+}
+#[test]
+fn test_tracked_fight_counts_both_pokemon() {
+    use crate::database::{DbHandle, get::DbGet, promise::MaybePromise, put::DbPut};
+    use crate::fight::{config::FightConfig, endpoints};
+    use crate::pokemon::{Pokemon, ptype::PokemonType, stats::PokemonStats};
+
+    let runtime = rocket::tokio::runtime::Runtime::new().unwrap();
+
+    runtime.block_on(async {
+        let ptype = PokemonType::new("UsageTestType".to_string(), vec![], vec![]);
+        ptype.put_self_only().await.unwrap();
+
+        for name in ["UsageTestAlpha", "UsageTestBeta"] {
+            Pokemon::new_to_db(
+                name.to_string(),
+                MaybePromise::from_concrete(ptype.clone()),
+                None,
+                PokemonStats {
+                    hp: 100,
+                    attack: 50,
+                    defense: 50,
+                    agility: 50,
+                    stamina: None,
+                    level: 50,
+                    accuracy: 100,
+                },
+            )
+            .await
+            .unwrap();
+        }
+    });
+
+    let rocket = rocket::build()
+        .manage(FightConfig::default())
+        .mount("/api", routes![endpoints::simulate_fight]);
+    let client = Client::tracked(rocket).expect("Failed to create client");
+
+    let body = client
+        .get("/api/simulate_fight/UsageTestAlpha/UsageTestBeta?seed=1&track=true")
+        .dispatch()
+        .into_string()
+        .unwrap();
+
+    let counts = runtime.block_on(async {
+        let mut counts = vec![];
+        for name in ["UsageTestAlpha", "UsageTestBeta"] {
+            counts.push(Pokemon::get_first(name).await.unwrap().fight_count);
+        }
+
+        let db = DbHandle::connect().await.unwrap();
+        let mut q_res = db
+            .inner
+            .execute("MATCH (n) WHERE n.name STARTS WITH 'UsageTest' DETACH DELETE n;".into())
+            .await
+            .unwrap();
+        let _none = q_res.next().await.unwrap();
+
+        counts
+    });
+
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(json["status"], "Ok", "{body}");
+    assert_eq!(counts, vec![1, 1]);
+}
+#[test]
+fn test_self_fight_is_refused_by_default() {
+    use crate::fight::{config::FightConfig, endpoints};
+
+    let rocket = rocket::build()
+        .manage(FightConfig::default())
+        .mount("/api", routes![endpoints::simulate_fight]);
+    let client = Client::tracked(rocket).expect("Failed to create client");
+
+    // refused before either pokemon is looked up, so no database is needed
+    let body = client
+        .get("/api/simulate_fight/Pikachu/Pikachu")
+        .dispatch()
+        .into_string()
+        .unwrap();
+
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+    let error = json["status"]["Error"].as_str().expect(&body);
+    assert!(
+        error.starts_with("Pokemon 'Pikachu' can't fight itself"),
+        "{error}"
+    );
+    assert!(error.contains("allow_self_fights"), "{error}");
+
+    let allowed = FightConfig {
+        allow_self_fights: true,
+        ..FightConfig::default()
+    };
+    assert!(allowed.check_self_fight("Trainer", "Ash", "Ash").is_ok());
+    assert!(
+        FightConfig::default()
+            .check_self_fight("Trainer", "Ash", "Gary")
+            .is_ok()
+    );
+}
+
+/// Checked on the mounted routes, since dispatching the request would need the database
+#[test]
+fn test_get_pokemon_route_is_mounted() {
+    use rocket::http::Method;
+
+    let client = Client::tracked(rocket()).expect("Failed to create client");
+
+    assert!(
+        client
+            .rocket()
+            .routes()
+            .any(|route| route.method == Method::Get && route.uri == "/api/pokemons/<name>"),
+        "GET /api/pokemons/<name> should be mounted"
+    );
+}
+
+#[test]
+fn test_type_relations_serialize_as_names() {
+    use crate::database::promise::MaybePromise;
+    use crate::pokemon::ptype::PokemonType;
+
+    let grass = PokemonType::new("Grass".to_string(), vec![], vec![]);
+    let fire = PokemonType::new(
+        "Fire".to_string(),
+        vec![MaybePromise::from_concrete(grass)],
+        vec![MaybePromise::from_ident_unchecked("Water".to_string())],
+    );
+
+    let json = serde_json::to_value(fire.with_relation_names()).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "name": "Fire",
+            "strong_against": ["Grass"],
+            "weak_against": ["Water"],
+        })
+    );
+}
+
+#[test]
+fn test_create_type_refuses_long_names() {
+    use crate::idempotency::IdempotencyCache;
+
+    let rocket = rocket::build()
+        .manage(IdempotencyCache::load())
+        .mount("/api", routes![pokemon::ptype_endpoints::create_type]);
+    let client = Client::tracked(rocket).expect("Failed to create client");
+
+    let name = "T".repeat(31);
+    let body = client
+        .post(format!("/api/types/{name}"))
+        .dispatch()
+        .into_string()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(json["status"]["Error"], "Name is too long");
+}
+
+#[test]
+fn test_type_relationship_endpoints_link_once() {
+    use crate::database::{DbHandle, get::DbGet};
+    use crate::pokemon::{ptype::PokemonType, ptype_endpoints};
+
+    let runtime = rocket::tokio::runtime::Runtime::new().unwrap();
+
+    runtime.block_on(async {
+        PokemonType::new_to_db("RelateTestWater".to_string())
+            .await
+            .unwrap();
+        PokemonType::new_to_db("RelateTestFire".to_string())
+            .await
+            .unwrap();
+    });
+
+    let rocket = rocket::build().mount(
+        "/api",
+        routes![
+            ptype_endpoints::add_strong_against,
+            ptype_endpoints::remove_strong_against
+        ],
+    );
+    let client = Client::tracked(rocket).expect("Failed to create client");
+
+    // the second call must not list the type twice
+    for _ in 0..2 {
+        let body = client
+            .post("/api/types/RelateTestWater/strong_against/RelateTestFire")
+            .dispatch()
+            .into_string()
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(json["status"], "Ok", "{body}");
+        assert_eq!(
+            json["data"]["strong_against"],
+            serde_json::json!(["RelateTestFire"])
+        );
+    }
+
+    let water = runtime.block_on(PokemonType::get_first("RelateTestWater"));
+    let fire = runtime.block_on(PokemonType::get_first("RelateTestFire"));
+
+    let body = client
+        .delete("/api/types/RelateTestWater/strong_against/RelateTestFire")
+        .dispatch()
+        .into_string()
+        .unwrap();
+    let unlinked: serde_json::Value = serde_json::from_str(&body).unwrap();
+
+    runtime.block_on(async {
+        let db = DbHandle::connect().await.unwrap();
+        let mut q_res = db
+            .inner
+            .execute("MATCH (n) WHERE n.name STARTS WITH 'RelateTest' DETACH DELETE n;".into())
+            .await
+            .unwrap();
+        let _none = q_res.next().await.unwrap();
+    });
+
+    let (water, fire) = (water.unwrap(), fire.unwrap());
+    assert!(water.is_strong_against(&fire));
+    assert_eq!(water.strong_against().len(), 1);
+    // only one side is linked
+    assert!(!fire.is_weak_against(&water));
+
+    assert_eq!(unlinked["status"], "Ok", "{body}");
+    assert_eq!(unlinked["data"]["strong_against"], serde_json::json!([]));
+}
+
+#[test]
+fn test_linking_a_type_twice_lists_it_once() {
+    use crate::database::{link::DbLink, promise::MaybePromise};
+    use crate::pokemon::ptype::{PokemonType, Relationship};
+
+    let mut water = PokemonType::new("Water".to_string(), vec![], vec![]);
+    let fire = MaybePromise::from_ident_unchecked("Fire".to_string());
+
+    for _ in 0..2 {
+        water
+            .link_side_effect(&fire, &Relationship::StrongAgainst)
+            .unwrap();
+    }
+
+    assert_eq!(water.strong_against().len(), 1);
+    assert!(water.weak_against().is_empty());
+}
+
+#[test]
+fn test_stats_out_of_range_are_refused() {
+    use crate::pokemon::stats::PokemonStats;
+
+    let valid = PokemonStats {
+        hp: 100,
+        attack: 50,
+        defense: 50,
+        agility: 50,
+        stamina: None,
+        level: 50,
+        accuracy: 100,
+    };
+    assert!(valid.validate().is_ok());
+
+    let edges = PokemonStats {
+        hp: 1,
+        attack: 0,
+        defense: 255,
+        agility: 0,
+        stamina: None,
+        level: 100,
+        accuracy: 100,
+    };
+    assert!(edges.validate().is_ok());
+
+    let cases: Vec<(&str, PokemonStats)> = vec![
+        (
+            "hp",
+            PokemonStats {
+                hp: 0,
+                ..valid.clone()
+            },
+        ),
+        (
+            "hp",
+            PokemonStats {
+                hp: 256,
+                ..valid.clone()
+            },
+        ),
+        (
+            "atk",
+            PokemonStats {
+                attack: 4_000_000_000,
+                ..valid.clone()
+            },
+        ),
+        (
+            "def",
+            PokemonStats {
+                defense: 256,
+                ..valid.clone()
+            },
+        ),
+        (
+            "agi",
+            PokemonStats {
+                agility: 300,
+                ..valid.clone()
+            },
+        ),
+        (
+            "level",
+            PokemonStats {
+                level: 0,
+                ..valid.clone()
+            },
+        ),
+        (
+            "level",
+            PokemonStats {
+                level: 101,
+                ..valid.clone()
+            },
+        ),
+    ];
+
+    for (field, stats) in cases {
+        let error = stats.validate().expect_err(field);
+        assert!(error.starts_with(&format!("The {field} stat")), "{error}");
+    }
+}
+
+#[test]
+fn test_add_pokemon_refuses_a_stat_out_of_range() {
+    use crate::idempotency::IdempotencyCache;
+    use crate::pokemon::endpoints;
+
+    let rocket = rocket::build()
+        .manage(IdempotencyCache::load())
+        .mount("/api", routes![endpoints::add_pokemon]);
+    let client = Client::tracked(rocket).expect("Failed to create client");
+
+    // the stats are checked before the database is touched
+    let body = serde_json::json!({
+        "name": "ZeroHpTestMon",
+        "primary_type": "Normal",
+        "stats": { "hp": 0, "atk": 50, "def": 50, "agi": 50 }
+    });
+    let body = client
+        .post("/api/pokemons")
+        .body(body.to_string())
+        .dispatch()
+        .into_string()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+
+    assert_eq!(
+        json["status"]["Error"],
+        "The hp stat must be between 1 and 255, got 0"
+    );
+}
+
+#[test]
+fn test_seventh_team_member_is_refused() {
+    use crate::database::{DbHandle, promise::MaybePromise, put::DbPut};
+    use crate::idempotency::IdempotencyCache;
+    use crate::pokemon::{Pokemon, ptype::PokemonType, stats::PokemonStats};
+    use crate::trainer::{Trainer, endpoints};
+
+    let runtime = rocket::tokio::runtime::Runtime::new().unwrap();
+
+    let names: Vec<String> = (1..=7).map(|i| format!("TeamSizeTestMon{i}")).collect();
+    let ptype = PokemonType::new("TeamSizeTestType".to_string(), vec![], vec![]);
+    runtime.block_on(async {
+        ptype.put_self_only().await.unwrap();
+        for name in &names {
+            Pokemon::new_to_db(
+                name.clone(),
+                MaybePromise::from_concrete(ptype.clone()),
+                None,
+                PokemonStats {
+                    hp: 100,
+                    attack: 50,
+                    defense: 50,
+                    agility: 50,
+                    stamina: None,
+                    level: 50,
+                    accuracy: 100,
+                },
+            )
+            .await
+            .unwrap();
+        }
+        Trainer {
+            name: "TeamSizeTestTrainer".to_string(),
+            team: vec![],
+            strategy: None,
+        }
+        .put_self_only()
+        .await
+        .unwrap();
+    });
+
+    let rocket = rocket::build().manage(IdempotencyCache::load()).mount(
+        "/api",
+        routes![
+            endpoints::add_pokemon_to_trainer,
+            endpoints::remove_pokemon_from_trainer
+        ],
+    );
+    let client = Client::tracked(rocket).expect("Failed to create client");
+
+    let status = |body: String| -> serde_json::Value {
+        serde_json::from_str::<serde_json::Value>(&body).unwrap()["status"].clone()
+    };
+    let add = |name: &str| {
+        status(
+            client
+                .post(format!("/api/trainer_pokemons/TeamSizeTestTrainer/{name}"))
+                .dispatch()
+                .into_string()
+                .unwrap(),
+        )
+    };
+
+    let added: Vec<serde_json::Value> = names.iter().map(|name| add(name)).collect();
+
+    // a removal makes room again
+    let removed = status(
+        client
+            .delete("/api/trainer_pokemons/TeamSizeTestTrainer/TeamSizeTestMon1")
+            .dispatch()
+            .into_string()
+            .unwrap(),
+    );
+    let readded = add("TeamSizeTestMon7");
+
+    runtime.block_on(async {
+        let db = DbHandle::connect().await.unwrap();
+        let mut q_res = db
+            .inner
+            .execute("MATCH (n) WHERE n.name STARTS WITH 'TeamSizeTest' DETACH DELETE n;".into())
+            .await
+            .unwrap();
+        let _none = q_res.next().await.unwrap();
+    });
+
+    for status in &added[..6] {
+        assert_eq!(status, "Ok");
+    }
+    assert_eq!(added[6]["Error"], "Team is full");
+    assert_eq!(removed, "Ok");
+    assert_eq!(readded, "Ok");
+}
+
+#[test]
+fn test_trainer_fight_body_accepts_names_with_spaces() {
+    use crate::database::{DbHandle, link::DbLink, promise::MaybePromise, put::DbPut};
+    use crate::fight::{config::FightConfig, endpoints};
+    use crate::pokemon::{Pokemon, ptype::PokemonType, stats::PokemonStats};
+    use crate::trainer::{Relationship, Trainer};
+
+    let runtime = rocket::tokio::runtime::Runtime::new().unwrap();
+
+    runtime.block_on(async {
+        let ptype = PokemonType::new("BodyFightTestType".to_string(), vec![], vec![]);
+        ptype.put_self_only().await.unwrap();
+
+        for (trainer, pokemon) in [
+            ("BodyFightTest Ash", "BodyFightTestAlpha"),
+            ("BodyFightTest Gary", "BodyFightTestBeta"),
+        ] {
+            Pokemon::new_to_db(
+                pokemon.to_string(),
+                MaybePromise::from_concrete(ptype.clone()),
+                None,
+                PokemonStats {
+                    hp: 100,
+                    attack: 50,
+                    defense: 50,
+                    agility: 50,
+                    stamina: None,
+                    level: 50,
+                    accuracy: 100,
+                },
+            )
+            .await
+            .unwrap();
+
+            let mut trainer = Trainer {
+                name: trainer.to_string(),
+                team: vec![],
+                strategy: None,
+            };
+            trainer.put_self_only().await.unwrap();
+            trainer
+                .link_to(
+                    &MaybePromise::from_ident_unchecked(pokemon.to_string()),
+                    &Relationship::Owns,
+                )
+                .await
+                .unwrap();
+        }
+    });
+
+    let rocket = rocket::build()
+        .manage(FightConfig::default())
+        .mount("/api", routes![endpoints::simulate_trainer_fight_body]);
+    let client = Client::tracked(rocket).expect("Failed to create client");
+
+    let body = client
+        .post("/api/simulate_trainer_fight")
+        .body(
+            serde_json::json!({
+                "challenger": "BodyFightTest Ash",
+                "challenger_strategy": "StrongestType",
+                "contender": "BodyFightTest Gary",
+                "contender_strategy": "Random",
+            })
+            .to_string(),
+        )
+        .dispatch()
+        .into_string()
+        .unwrap();
+
+    runtime.block_on(async {
+        let db = DbHandle::connect().await.unwrap();
+        let mut q_res = db
+            .inner
+            .execute("MATCH (n) WHERE n.name STARTS WITH 'BodyFightTest' DETACH DELETE n;".into())
+            .await
+            .unwrap();
+        let _none = q_res.next().await.unwrap();
+    });
+
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(json["status"], "Ok", "{body}");
+    assert_eq!(json["data"]["challenger_name"], "BodyFightTest Ash");
+    assert!(json["data"]["winner"].is_string(), "{body}");
+}
+
+#[test]
+fn test_bulk_import_keeps_going_past_a_bad_pokemon() {
+    use crate::database::{DbHandle, get::DbGet, put::DbPut};
+    use crate::idempotency::IdempotencyCache;
+    use crate::pokemon::{Pokemon, endpoints, ptype::PokemonType};
+
+    let runtime = rocket::tokio::runtime::Runtime::new().unwrap();
+
+    runtime.block_on(async {
+        PokemonType::new("BulkTestType".to_string(), vec![], vec![])
+            .put_self_only()
+            .await
+            .unwrap();
+    });
+
+    let rocket = rocket::build()
+        .manage(IdempotencyCache::load())
+        .mount("/api", routes![endpoints::add_pokemons_bulk]);
+    let client = Client::tracked(rocket).expect("Failed to create client");
+
+    let pokemon = |name: &str| {
+        serde_json::json!({
+            "name": name,
+            "primary_type": "BulkTestType",
+            "stats": { "hp": 100, "atk": 50, "def": 50, "agi": 50 }
+        })
+    };
+    let body = serde_json::json!([
+        pokemon("BulkTestAlpha"),
+        pokemon(""),
+        pokemon("BulkTestCharlie"),
+    ]);
+    let body = client
+        .post("/api/pokemons/bulk")
+        .body(body.to_string())
+        .dispatch()
+        .into_string()
+        .unwrap();
+
+    let stored = runtime.block_on(async {
+        let stored = [
+            Pokemon::get_first("BulkTestAlpha").await.is_ok(),
+            Pokemon::get_first("BulkTestCharlie").await.is_ok(),
+        ];
+
+        let db = DbHandle::connect().await.unwrap();
+        let mut q_res = db
+            .inner
+            .execute("MATCH (n) WHERE n.name STARTS WITH 'BulkTest' DETACH DELETE n;".into())
+            .await
+            .unwrap();
+        let _none = q_res.next().await.unwrap();
+
+        stored
+    });
+
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(json["status"], "Ok", "{body}");
+
+    let results = json["data"].as_array().unwrap();
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0]["status"], "ok");
+    assert!(results[0].get("message").is_none());
+    assert_eq!(results[1]["status"], "error");
+    assert_eq!(results[1]["message"], "Name cannot be empty");
+    assert_eq!(results[2]["status"], "ok");
+
+    assert_eq!(stored, [true, true]);
+}
+
+#[test]
+fn test_pokedex_csv_round_trip() {
+    use crate::database::promise::MaybePromise;
+    use crate::pokemon::{Pokemon, csv_io, ptype::PokemonType, stats::PokemonStats};
+
+    let fire = PokemonType::new("Fire".to_string(), vec![], vec![]);
+    let flying = PokemonType::new("Flying".to_string(), vec![], vec![]);
+    let stats = |hp, attack| PokemonStats {
+        hp,
+        attack,
+        defense: 40,
+        agility: 60,
+        stamina: None,
+        level: 50,
+        accuracy: 100,
+    };
+
+    let pokemons = [
+        Pokemon::new(
+            "Charizard".to_string(),
+            MaybePromise::from_concrete(fire.clone()),
+            Some(MaybePromise::from_concrete(flying)),
+            stats(78, 84),
+        ),
+        Pokemon::new(
+            "Mr. Flame, Jr".to_string(),
+            MaybePromise::from_concrete(fire),
+            None,
+            stats(39, 52),
+        ),
+    ];
+
+    let rows: Vec<csv_io::CsvRow> = pokemons.iter().map(csv_io::CsvRow::from_pokemon).collect();
+    let mut exported = csv_io::header_line();
+    for row in &rows {
+        exported.push_str(&row.to_line().unwrap());
+    }
+
+    assert!(exported.starts_with("name,hp,atk,def,agi,primary_type,secondary_type\n"));
+    assert!(exported.contains("Charizard,78,84,40,60,Fire,Flying\n"));
+
+    let imported: Vec<(u64, Result<csv_io::CsvRow, String>)> = csv_io::read_rows(&exported);
+    assert_eq!(imported.len(), 2);
+    assert_eq!(imported[0].0, 2);
+    assert_eq!(imported[1].0, 3);
+    let imported: Vec<csv_io::CsvRow> = imported.into_iter().map(|(_, r)| r.unwrap()).collect();
+    assert_eq!(imported, rows);
+    assert_eq!(imported[1].secondary_type, None);
+
+    let new_pokemon = imported[0].clone().into_new_pokemon();
+    assert_eq!(new_pokemon.secondary_type.as_deref(), Some("Flying"));
+    assert_eq!(new_pokemon.stats.attack, 84);
+
+    // a bad row is reported by line, the rows around it still parse
+    let rows = csv_io::read_rows(
+        "name,hp,atk,def,agi,primary_type,secondary_type\n\
+        Bulbasaur,45,49,49,45,Grass,Poison\n\
+        Squirtle,lots,48,65,43,Water,\n\
+        Pidgey,40,45,40,56,Normal,Flying\n",
+    );
+    assert_eq!(rows.len(), 3);
+    assert!(rows[0].1.is_ok());
+    assert_eq!(rows[1].0, 3);
+    assert!(rows[1].1.is_err());
+    assert!(rows[2].1.is_ok());
+
+    let wrong_header = csv_io::read_rows("pokemon,hp\nBulbasaur,45\n");
+    assert_eq!(wrong_header.len(), 1);
+    assert!(wrong_header[0].1.as_ref().unwrap_err().contains("header row"));
+}
+
+#[test]
+fn test_secondary_type_can_be_set_and_cleared() {
+    use crate::database::{DbHandle, link::DbLink, promise::MaybePromise, put::DbPut};
+    use crate::idempotency::IdempotencyCache;
+    use crate::pokemon::{Pokemon, Relationship, endpoints, ptype::PokemonType, stats::PokemonStats};
+
+    let runtime = rocket::tokio::runtime::Runtime::new().unwrap();
+
+    let pokemon = runtime.block_on(async {
+        for name in ["SecondaryTestFire", "SecondaryTestFlying"] {
+            PokemonType::new(name.to_string(), vec![], vec![])
+                .put_self_only()
+                .await
+                .unwrap();
+        }
+
+        Pokemon::new_to_db(
+            "SecondaryTestMon".to_string(),
+            MaybePromise::from_ident_unchecked("SecondaryTestFire".to_string()),
+            None,
+            PokemonStats {
+                hp: 100,
+                attack: 50,
+                defense: 50,
+                agility: 50,
+                stamina: None,
+                level: 50,
+                accuracy: 100,
+            },
+        )
+        .await
+        .unwrap()
+    });
+
+    let rocket = rocket::build().manage(IdempotencyCache::load()).mount(
+        "/api",
+        routes![endpoints::set_secondary_type, endpoints::clear_secondary_type],
+    );
+    let client = Client::tracked(rocket).expect("Failed to create client");
+
+    let status = |body: String| -> serde_json::Value {
+        serde_json::from_str::<serde_json::Value>(&body).unwrap()["status"].clone()
+    };
+    let secondary_types =
+        || runtime.block_on(pokemon.link_count(&Relationship::SecondaryType));
+
+    let missing = status(
+        client
+            .put("/api/pokemons/SecondaryTestMon/secondary_type/SecondaryTestMissing")
+            .dispatch()
+            .into_string()
+            .unwrap(),
+    );
+    let same_as_primary = status(
+        client
+            .put("/api/pokemons/SecondaryTestMon/secondary_type/SecondaryTestFire")
+            .dispatch()
+            .into_string()
+            .unwrap(),
+    );
+    let set = status(
+        client
+            .put("/api/pokemons/SecondaryTestMon/secondary_type/SecondaryTestFlying")
+            .dispatch()
+            .into_string()
+            .unwrap(),
+    );
+    let after_set = secondary_types();
+    let cleared = status(
+        client
+            .delete("/api/pokemons/SecondaryTestMon/secondary_type")
+            .dispatch()
+            .into_string()
+            .unwrap(),
+    );
+    let after_clear = secondary_types();
+    let cleared_again = status(
+        client
+            .delete("/api/pokemons/SecondaryTestMon/secondary_type")
+            .dispatch()
+            .into_string()
+            .unwrap(),
+    );
+
+    runtime.block_on(async {
+        let db = DbHandle::connect().await.unwrap();
+        let mut q_res = db
+            .inner
+            .execute("MATCH (n) WHERE n.name STARTS WITH 'SecondaryTest' DETACH DELETE n;".into())
+            .await
+            .unwrap();
+        let _none = q_res.next().await.unwrap();
+    });
+
+    assert!(
+        missing["Error"]
+            .as_str()
+            .unwrap()
+            .starts_with("Type 'SecondaryTestMissing' not found")
+    );
+    assert!(same_as_primary["Error"].is_string());
+    assert_eq!(set, "Ok");
+    assert_eq!(after_set.unwrap(), 1);
+    assert_eq!(cleared, "Ok");
+    assert_eq!(after_clear.unwrap(), 0);
+    assert_eq!(cleared_again["Error"], "Pokemon has no secondary type");
+}
+
+#[test]
+fn test_missing_trainer_is_not_found() {
+    use crate::trainer::endpoints;
+
+    let rocket = rocket::build().mount("/api", routes![endpoints::get_trainer_pokemons]);
+    let client = Client::tracked(rocket).expect("Failed to create client");
+
+    let response = client
+        .get("/api/trainer_pokemons/StatusTestMissingTrainer")
+        .dispatch();
+    assert_eq!(response.status(), Status::NotFound);
+
+    let json: serde_json::Value = serde_json::from_str(&response.into_string().unwrap()).unwrap();
+    assert!(
+        json["status"]["Error"]
+            .as_str()
+            .unwrap()
+            .starts_with("Trainer 'StatusTestMissingTrainer' not found")
+    );
+}
+
+#[test]
+fn test_team_size_counts_owned_pokemon() {
+    use crate::database::{DbHandle, link::DbLink, promise::MaybePromise, put::DbPut};
+    use crate::pokemon::{Pokemon, ptype::PokemonType, stats::PokemonStats};
+    use crate::trainer::{Relationship, Trainer, endpoints};
+
+    let runtime = rocket::tokio::runtime::Runtime::new().unwrap();
+
+    let ptype = PokemonType::new("TeamCountTestType".to_string(), vec![], vec![]);
+    runtime.block_on(async {
+        ptype.put_self_only().await.unwrap();
+
+        let mut trainer = Trainer {
+            name: "TeamCountTestTrainer".to_string(),
+            team: vec![],
+            strategy: None,
+        };
+        trainer.put_self_only().await.unwrap();
+
+        for name in ["TeamCountTestAlpha", "TeamCountTestBravo"] {
+            Pokemon::new_to_db(
+                name.to_string(),
+                MaybePromise::from_concrete(ptype.clone()),
+                None,
+                PokemonStats {
+                    hp: 100,
+                    attack: 50,
+                    defense: 50,
+                    agility: 50,
+                    stamina: None,
+                    level: 50,
+                    accuracy: 100,
+                },
+            )
+            .await
+            .unwrap();
+
+            trainer
+                .link_to(
+                    &MaybePromise::from_ident_unchecked(name.to_string()),
+                    &Relationship::Owns,
+                )
+                .await
+                .unwrap();
+        }
+    });
+
+    let rocket = rocket::build().mount("/api", routes![endpoints::get_team_size]);
+    let client = Client::tracked(rocket).expect("Failed to create client");
+
+    let body = |response: rocket::local::blocking::LocalResponse| -> serde_json::Value {
+        serde_json::from_str(&response.into_string().unwrap()).unwrap()
+    };
+
+    let counted = client
+        .get("/api/trainers/TeamCountTestTrainer/team_size")
+        .dispatch();
+    let counted_status = counted.status();
+    let counted = body(counted);
+
+    let missing = client
+        .get("/api/trainers/TeamCountTestMissing/team_size")
+        .dispatch();
+    let missing_status = missing.status();
+
+    runtime.block_on(async {
+        let db = DbHandle::connect().await.unwrap();
+        let mut q_res = db
+            .inner
+            .execute("MATCH (n) WHERE n.name STARTS WITH 'TeamCountTest' DETACH DELETE n;".into())
+            .await
+            .unwrap();
+        let _none = q_res.next().await.unwrap();
+    });
+
+    assert_eq!(counted_status, Status::Ok);
+    assert_eq!(counted["data"], 2);
+    assert_eq!(missing_status, Status::NotFound);
+}
@@ -0,0 +1,61 @@
+#[allow(unused_imports)]
+use crate::config::DatabaseConfig;
+
+#[test]
+fn test_database_config_env_overrides_file() {
+    let file: DatabaseConfig = toml::from_str(
+        r#"
+        host = "file-host"
+        port = "7688"
+        "#,
+    )
+    .expect("A [database] section should parse");
+
+    // file values win over defaults, missing options keep their defaults
+    assert_eq!(file.host, "file-host");
+    assert_eq!(file.port, 7688);
+    assert_eq!(file.username, DatabaseConfig::default().username);
+
+    let previous = std::env::var(DatabaseConfig::HOST_VAR).ok();
+
+    // SAFETY: no other test sets this variable, and it is restored right after reading it
+    unsafe { std::env::set_var(DatabaseConfig::HOST_VAR, "env-host") };
+    let effective = file.clone().with_env_overrides();
+    match previous {
+        Some(previous) => unsafe { std::env::set_var(DatabaseConfig::HOST_VAR, previous) },
+        None => unsafe { std::env::remove_var(DatabaseConfig::HOST_VAR) },
+    }
+
+    let effective = effective.expect("A set host variable should always apply");
+    assert_eq!(effective.host, "env-host");
+    assert_eq!(effective.port, file.port);
+}
+
+#[test]
+fn test_unreadable_config_file_names_its_absolute_path() {
+    use crate::config::read_section_at;
+
+    // a directory where the file should be, as a container mount of a missing file leaves behind
+    let dir = std::env::temp_dir().join(format!("pokesim-config-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let result = read_section_at::<DatabaseConfig>(&dir, "database");
+    std::fs::remove_dir(&dir).unwrap();
+
+    let error = format!(
+        "{:#}",
+        result.expect_err("A directory is not a readable config file")
+    );
+    assert!(
+        error.starts_with(&format!(
+            "Could not read the config file at {}",
+            dir.display()
+        )),
+        "{error}"
+    );
+
+    // a missing file is not an error, every option keeps its default
+    let missing = read_section_at::<DatabaseConfig>(&dir.join("config.toml"), "database")
+        .expect("A missing config file should give the defaults");
+    assert_eq!(missing, DatabaseConfig::default());
+}
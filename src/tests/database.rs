@@ -122,8 +122,11 @@ async fn test_db_put() {
     }
 
     impl DbPut for Status {
-        fn put_args(&self) -> String {
-            format!("{{id: {}, status: '{}'}}", self.id, self.status)
+        fn put_args(&self) -> Vec<(&'static str, crate::database::DbValue)> {
+            vec![
+                ("id", self.id.as_str().into()),
+                ("status", self.status.as_str().into()),
+            ]
         }
     }
 
@@ -168,7 +171,7 @@ async fn test_db_put() {
         .inner
         .execute(
             format!(
-                "MATCH (n:{}) WHERE n.id = {} DELETE n;",
+                "MATCH (n:{}) WHERE n.id = '{}' DELETE n;",
                 Status::DB_NODE_KIND,
                 u32::MAX
             )
@@ -196,8 +199,11 @@ async fn test_db_delete() {
     }
 
     impl DbPut for Status {
-        fn put_args(&self) -> String {
-            format!("{{id: {}, status: '{}'}}", self.id, self.status)
+        fn put_args(&self) -> Vec<(&'static str, crate::database::DbValue)> {
+            vec![
+                ("id", self.id.as_str().into()),
+                ("status", self.status.as_str().into()),
+            ]
         }
     }
 
@@ -258,8 +264,11 @@ async fn test_db_update() {
     }
 
     impl DbPut for Status {
-        fn put_args(&self) -> String {
-            format!("{{id: {}, status: '{}'}}", self.id, self.status)
+        fn put_args(&self) -> Vec<(&'static str, crate::database::DbValue)> {
+            vec![
+                ("id", self.id.as_str().into()),
+                ("status", self.status.as_str().into()),
+            ]
         }
     }
 
@@ -292,8 +301,8 @@ async fn test_db_update() {
     }
 
     impl DbUpdate for Status {
-        fn update_args(&self) -> String {
-            format!("n.status = '{}'", self.status)
+        fn update_args(&self) -> Vec<(&'static str, crate::database::DbValue)> {
+            vec![("status", self.status.as_str().into())]
         }
     }
 
@@ -326,3 +335,1407 @@ async fn test_db_update() {
 
     assert!(status.await.is_err());
 }
+
+/// Wraps a query executor, counting the queries run through it
+#[allow(unused)]
+struct CountingExecutor<E> {
+    inner: E,
+    queries: std::sync::atomic::AtomicUsize,
+}
+
+#[allow(unused)]
+impl<E> CountingExecutor<E> {
+    fn new(inner: E) -> Self {
+        Self {
+            inner,
+            queries: std::sync::atomic::AtomicUsize::new(0),
+        }
+    }
+
+    fn queries(&self) -> usize {
+        self.queries.load(std::sync::atomic::Ordering::SeqCst)
+    }
+}
+
+impl<E: crate::database::QueryExecutor> crate::database::QueryExecutor for CountingExecutor<E> {
+    fn fetch_all(
+        &self,
+        query: crate::database::DbQuery,
+    ) -> impl Future<Output = anyhow::Result<Vec<neo4rs::Row>>> + Send {
+        self.queries
+            .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        self.inner.fetch_all(query)
+    }
+}
+
+/// A stand-in database that answers every query with the same rows
+#[allow(unused)]
+struct CannedRows {
+    columns: Vec<&'static str>,
+    rows: Vec<Vec<neo4rs::BoltType>>,
+}
+
+impl crate::database::QueryExecutor for CannedRows {
+    fn fetch_all(
+        &self,
+        _query: crate::database::DbQuery,
+    ) -> impl Future<Output = anyhow::Result<Vec<neo4rs::Row>>> + Send {
+        let fields: neo4rs::BoltList = self
+            .columns
+            .iter()
+            .map(|column| neo4rs::BoltType::from(*column))
+            .collect::<Vec<_>>()
+            .into();
+
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| neo4rs::Row::new(fields.clone(), row.clone().into()))
+            .collect();
+
+        async move { Ok(rows) }
+    }
+}
+
+/// A node as the database returns it, with the given label and properties
+#[allow(unused)]
+fn bolt_node(label: &str, properties: &[(&str, neo4rs::BoltType)]) -> neo4rs::BoltType {
+    neo4rs::BoltNode::new(
+        neo4rs::BoltInteger::new(0),
+        vec![neo4rs::BoltType::from(label)].into(),
+        properties
+            .iter()
+            .map(|(key, value)| (neo4rs::BoltString::from(*key), value.clone()))
+            .collect(),
+    )
+    .into()
+}
+
+#[tokio::test]
+async fn test_trainers_with_shared_pokemon_take_one_query() {
+    use crate::database::promise::MaybePromise;
+    use crate::trainer::Trainer;
+    use neo4rs::{BoltNull, BoltType};
+
+    let ptype = bolt_node("PokemonType", &[("name", "MockType".into())]);
+    let pokemon = |name: &str| {
+        bolt_node(
+            "Pokemon",
+            &[
+                ("name", name.into()),
+                ("hp", 100.into()),
+                ("attack", 50.into()),
+                ("defense", 50.into()),
+                ("agility", 50.into()),
+            ],
+        )
+    };
+    let row = |trainer: &str, pokemon_name: &str| {
+        vec![
+            bolt_node("Trainer", &[("name", trainer.into())]),
+            pokemon(pokemon_name),
+            ptype.clone(),
+            BoltType::Null(BoltNull),
+        ]
+    };
+
+    // every trainer owns MockMon1, the last one also owns MockMon2
+    let db = CountingExecutor::new(CannedRows {
+        columns: vec!["t", "p", "pt", "st"],
+        rows: vec![
+            row("MockTrainer1", "MockMon1"),
+            row("MockTrainer2", "MockMon1"),
+            row("MockTrainer3", "MockMon1"),
+            row("MockTrainer3", "MockMon2"),
+        ],
+    });
+
+    let trainers = Trainer::get_all_with_teams_on(&db).await.unwrap();
+
+    assert_eq!(db.queries(), 1);
+    assert_eq!(
+        trainers
+            .iter()
+            .map(|t| (t.name.as_str(), t.team.len()))
+            .collect::<Vec<_>>(),
+        vec![
+            ("MockTrainer1", 1),
+            ("MockTrainer2", 1),
+            ("MockTrainer3", 2)
+        ]
+    );
+    assert!(
+        trainers.iter().flat_map(|t| &t.team).all(
+            |p| matches!(p, MaybePromise::Concrete(p) if p.primary_type().ident() == "MockType")
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_get_all_trainers_with_teams() {
+    use crate::database::{DbHandle, link::DbLink, promise::MaybePromise, put::DbPut};
+    use crate::pokemon::{Pokemon, ptype::PokemonType, stats::PokemonStats};
+    use crate::trainer::{Relationship, Trainer};
+
+    let ptype = PokemonType::new("BatchTestType".to_string(), vec![], vec![]);
+    ptype.put_self_only().await.unwrap();
+
+    let mut pokemons = vec![];
+    for name in ["BatchTestMon1", "BatchTestMon2"] {
+        let pokemon = Pokemon::new_to_db(
+            name.to_string(),
+            MaybePromise::from_concrete(ptype.clone()),
+            None,
+            PokemonStats {
+                hp: 100,
+                attack: 50,
+                defense: 50,
+                agility: 50,
+                stamina: None,
+                level: 50,
+                accuracy: 100,
+            },
+        )
+        .await
+        .unwrap();
+        pokemons.push(MaybePromise::from_concrete(pokemon));
+    }
+
+    // every trainer owns the first pokemon, the last one owns both
+    let names = ["BatchTestTrainer1", "BatchTestTrainer2", "BatchTestTrainer3"];
+    for (i, name) in names.iter().enumerate() {
+        let mut trainer = Trainer {
+            name: name.to_string(),
+            team: vec![],
+            strategy: None,
+        };
+        trainer.put_self_only().await.unwrap();
+        trainer.link_to(&pokemons[0], &Relationship::Owns).await.unwrap();
+        if i == 2 {
+            trainer.link_to(&pokemons[1], &Relationship::Owns).await.unwrap();
+        }
+    }
+
+    let db = CountingExecutor::new(DbHandle::connect().await.unwrap());
+    let trainers = Trainer::get_all_with_teams_on(&db).await.unwrap();
+    assert_eq!(db.queries(), 1);
+
+    for (i, name) in names.iter().enumerate() {
+        let trainer = trainers.iter().find(|t| t.name == *name).unwrap();
+        assert_eq!(trainer.team.len(), if i == 2 { 2 } else { 1 });
+        assert!(
+            trainer
+                .team
+                .iter()
+                .all(|p| matches!(p, MaybePromise::Concrete(p) if p.primary_type().ident() == "BatchTestType"))
+        );
+    }
+
+    // clean up
+    let db = DbHandle::connect().await.unwrap();
+    let mut q_res = db
+        .inner
+        .execute(
+            "MATCH (n) WHERE n.name STARTS WITH 'BatchTest' DETACH DELETE n;"
+                .into(),
+        )
+        .await
+        .unwrap();
+    let _none = q_res.next().await.unwrap();
+}
+
+#[test]
+fn test_error_classification() {
+    use crate::database::error::{ErrorClass, NotFound, classify, classify_neo4j_code};
+    use std::io;
+
+    let connection_refused = neo4rs::Error::IOError {
+        detail: io::Error::new(io::ErrorKind::ConnectionRefused, "refused"),
+    };
+    assert_eq!(classify(&connection_refused.into()), ErrorClass::Transient);
+    assert_eq!(
+        classify(&neo4rs::Error::ConnectionError.into()),
+        ErrorClass::Transient
+    );
+    assert_eq!(
+        classify(&neo4rs::Error::AuthenticationError("bad password".to_string()).into()),
+        ErrorClass::Permanent
+    );
+    assert_eq!(
+        classify(&neo4rs::Error::UnsupportedScheme("http".to_string()).into()),
+        ErrorClass::Permanent
+    );
+
+    // a missing config file is not worth retrying
+    let missing_file = io::Error::new(io::ErrorKind::NotFound, "config.toml");
+    assert_eq!(classify(&missing_file.into()), ErrorClass::Permanent);
+
+    let not_found = anyhow::Error::new(NotFound {
+        kind: "Pokemon".to_string(),
+        identifier: "'Missingno'".to_string(),
+    });
+    assert_eq!(classify(&not_found), ErrorClass::NotFound);
+    assert_eq!(
+        classify(&not_found.context("while fetching a trainer's team")),
+        ErrorClass::NotFound
+    );
+
+    assert_eq!(
+        classify(&anyhow::anyhow!("Something unexpected")),
+        ErrorClass::Permanent
+    );
+
+    assert_eq!(
+        classify_neo4j_code("Neo.ClientError.Schema.ConstraintValidationFailed"),
+        ErrorClass::Conflict
+    );
+    assert_eq!(
+        classify_neo4j_code("Neo.ClientError.Statement.SyntaxError"),
+        ErrorClass::Permanent
+    );
+    assert_eq!(
+        classify_neo4j_code("Neo.TransientError.Transaction.DeadlockDetected"),
+        ErrorClass::Transient
+    );
+    assert_eq!(
+        classify_neo4j_code("Neo.ClientError.Database.DatabaseNotFound"),
+        ErrorClass::Permanent
+    );
+}
+
+/// Restoring replaces the whole database, so this may disturb other database tests running at the same time
+#[tokio::test]
+async fn test_snapshot_restore_round_trip() {
+    use crate::database::{
+        DbHandle, get::DbGet, link::DbLink, promise::MaybePromise, put::DbPut,
+        snapshot::Snapshot,
+    };
+    use crate::pokemon::{Pokemon, ptype::PokemonType, stats::PokemonStats};
+    use crate::trainer::{Relationship, Trainer};
+
+    let stats = PokemonStats {
+        hp: 100,
+        attack: 50,
+        defense: 50,
+        agility: 50,
+        stamina: None,
+        level: 50,
+        accuracy: 100,
+    };
+
+    let ptype = PokemonType::new("SnapshotTestType".to_string(), vec![], vec![]);
+    ptype.put_self_only().await.unwrap();
+
+    let pokemon = Pokemon::new_to_db(
+        "SnapshotTestMon".to_string(),
+        MaybePromise::from_concrete(ptype.clone()),
+        None,
+        stats.clone(),
+    )
+    .await
+    .unwrap();
+
+    let mut trainer = Trainer {
+        name: "SnapshotTestTrainer".to_string(),
+        team: vec![],
+        strategy: None,
+    };
+    trainer.put_self_only().await.unwrap();
+    trainer
+        .link_to(&MaybePromise::from_concrete(pokemon.clone()), &Relationship::Owns)
+        .await
+        .unwrap();
+
+    let path = std::env::temp_dir().join("pokemon-simulator-snapshot-test.json");
+    Snapshot::capture().await.unwrap().write_to(&path).unwrap();
+
+    // mutate: remove the trainer, add a pokemon that was not snapshotted
+    let db = DbHandle::connect().await.unwrap();
+    let mut q_res = db
+        .inner
+        .execute("MATCH (t:Trainer {name: 'SnapshotTestTrainer'}) DETACH DELETE t;".into())
+        .await
+        .unwrap();
+    let _none = q_res.next().await.unwrap();
+    Pokemon::new_to_db(
+        "SnapshotTestIntruder".to_string(),
+        MaybePromise::from_concrete(ptype.clone()),
+        None,
+        stats,
+    )
+    .await
+    .unwrap();
+
+    Snapshot::read_from(&path).unwrap().restore().await.unwrap();
+
+    let restored = Trainer::get_first("SnapshotTestTrainer").await.unwrap();
+    assert_eq!(restored.team.len(), 1);
+    assert_eq!(restored.team[0].ident(), "SnapshotTestMon");
+    assert!(Pokemon::get_first("SnapshotTestIntruder").await.is_err());
+
+    // clean up
+    std::fs::remove_file(&path).ok();
+    let mut q_res = db
+        .inner
+        .execute("MATCH (n) WHERE n.name STARTS WITH 'SnapshotTest' DETACH DELETE n;".into())
+        .await
+        .unwrap();
+    let _none = q_res.next().await.unwrap();
+}
+
+#[test]
+fn test_snapshot_validation() {
+    use crate::database::snapshot::{SNAPSHOT_VERSION, Snapshot};
+
+    let snapshot: Snapshot = serde_json::from_value(serde_json::json!({
+        "version": SNAPSHOT_VERSION,
+        "types": [{"name": "Fire", "strong_against": [], "weak_against": ["Water"]}],
+        "pokemons": [],
+        "trainers": [],
+    }))
+    .unwrap();
+
+    let error = snapshot.validate().expect_err("Water is not in the snapshot");
+    assert!(error.to_string().contains("Water"));
+
+    let mut newer = snapshot.clone();
+    newer.version = SNAPSHOT_VERSION + 1;
+    newer.types.clear();
+    let error = newer.validate().expect_err("Other versions should be refused");
+    assert!(error.to_string().contains("version"));
+
+    newer.version = SNAPSHOT_VERSION;
+    assert!(newer.validate().is_ok());
+    // wipe first, then recreate
+    assert_eq!(newer.restore_queries().len(), 1);
+}
+
+#[tokio::test]
+async fn test_get_orphan_pokemons() {
+    use crate::database::{DbHandle, link::DbLink, promise::MaybePromise, put::DbPut};
+    use crate::pokemon::{Pokemon, ptype::PokemonType, stats::PokemonStats};
+    use crate::trainer::{Relationship, Trainer};
+
+    let ptype = PokemonType::new("OrphanTestType".to_string(), vec![], vec![]);
+    ptype.put_self_only().await.unwrap();
+
+    let mut pokemons = vec![];
+    for name in ["OrphanTestOwned", "OrphanTestUnowned"] {
+        let pokemon = Pokemon::new_to_db(
+            name.to_string(),
+            MaybePromise::from_concrete(ptype.clone()),
+            None,
+            PokemonStats {
+                hp: 100,
+                attack: 50,
+                defense: 50,
+                agility: 50,
+                stamina: None,
+                level: 50,
+                accuracy: 100,
+            },
+        )
+        .await
+        .unwrap();
+        pokemons.push(MaybePromise::from_concrete(pokemon));
+    }
+
+    let mut trainer = Trainer {
+        name: "OrphanTestTrainer".to_string(),
+        team: vec![],
+        strategy: None,
+    };
+    trainer.put_self_only().await.unwrap();
+    trainer.link_to(&pokemons[0], &Relationship::Owns).await.unwrap();
+
+    let orphans: Vec<String> = Pokemon::get_orphans()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|p| p.name)
+        .filter(|name| name.starts_with("OrphanTest"))
+        .collect();
+
+    assert_eq!(orphans, vec!["OrphanTestUnowned"]);
+
+    // clean up
+    let db = DbHandle::connect().await.unwrap();
+    let mut q_res = db
+        .inner
+        .execute(
+            "MATCH (n) WHERE n.name STARTS WITH 'OrphanTest' DETACH DELETE n;"
+                .into(),
+        )
+        .await
+        .unwrap();
+    let _none = q_res.next().await.unwrap();
+}
+
+#[test]
+fn test_not_found_suggestions() {
+    use crate::database::suggest::{MAX_SUGGESTIONS, levenshtein, not_found_message};
+
+    assert_eq!(levenshtein("Pikchu", "Pikachu"), 1);
+    assert_eq!(levenshtein("kitten", "sitting"), 3);
+    assert_eq!(levenshtein("", "abc"), 3);
+
+    let names: Vec<String> = ["Pikachu", "Raichu", "Charmander", "Pichu", "Bulbasaur"]
+        .iter()
+        .map(|n| n.to_string())
+        .collect();
+
+    assert_eq!(
+        not_found_message("Pokemon", "Pikchu", &names),
+        "Pokemon 'Pikchu' not found; did you mean: Pichu, Pikachu?"
+    );
+    assert_eq!(
+        not_found_message("Contender", "charmandr", &names),
+        "Contender 'charmandr' not found; did you mean: Charmander?"
+    );
+    assert_eq!(
+        not_found_message("Pokemon", "Mewtwo", &names),
+        "Pokemon 'Mewtwo' not found"
+    );
+
+    let many: Vec<String> = (0..10).map(|i| format!("Mon{i}")).collect();
+    let message = not_found_message("Pokemon", "Mon", &many);
+    assert_eq!(message.matches(", ").count() + 1, MAX_SUGGESTIONS);
+}
+
+#[tokio::test]
+async fn test_repair_dangling_relationships() {
+    use crate::database::{DbHandle, repair::repair};
+
+    let db = DbHandle::connect().await.unwrap();
+
+    // the trainer's pokemon lost its label in a manual edit, the other pokemon is fine
+    let mut q_res = db
+        .inner
+        .execute(
+            "CREATE (t:Trainer {name: 'RepairTestTrainer'}) \
+            CREATE (t)-[:Owns]->(:RepairTestGhost {name: 'RepairTestGhost'}) \
+            CREATE (t)-[:Owns]->(:Pokemon {name: 'RepairTestMon', hp: 1, attack: 1, defense: 1, agility: 1});"
+                .into(),
+        )
+        .await
+        .unwrap();
+    let _none = q_res.next().await.unwrap();
+
+    let report = repair().await.unwrap();
+
+    let removed: Vec<_> = report
+        .removed
+        .iter()
+        .filter(|r| r.source == "RepairTestTrainer")
+        .collect();
+    assert_eq!(removed.len(), 1);
+    assert_eq!(removed[0].relationship, "Owns");
+    assert_eq!(removed[0].target.as_deref(), Some("RepairTestGhost"));
+
+    let mut q_out = db
+        .inner
+        .execute(
+            "MATCH (:Trainer {name: 'RepairTestTrainer'})-[:Owns]->(p) RETURN p.name AS name;"
+                .into(),
+        )
+        .await
+        .unwrap();
+    let mut owned = vec![];
+    while let Some(row) = q_out.next().await.unwrap() {
+        owned.push(row.get::<String>("name").unwrap());
+    }
+    assert_eq!(owned, vec!["RepairTestMon"]);
+
+    // clean up
+    let mut q_res = db
+        .inner
+        .execute(
+            "MATCH (n) WHERE n.name STARTS WITH 'RepairTest' DETACH DELETE n;"
+                .into(),
+        )
+        .await
+        .unwrap();
+    let _none = q_res.next().await.unwrap();
+}
+
+#[test]
+fn test_ndjson_round_trip() {
+    use crate::database::{
+        ndjson::Record,
+        promise::MaybePromise,
+        snapshot::{SNAPSHOT_VERSION, Snapshot},
+    };
+    use crate::pokemon::{Pokemon, ptype::PokemonType, stats::PokemonStats};
+    use crate::database::{DbRepr, promise::Promised};
+    use crate::trainer::Trainer;
+
+    fn promise<T: DbRepr + Promised>(name: &str) -> MaybePromise<T> {
+        MaybePromise::from_ident_unchecked(name.to_string())
+    }
+
+    let stats = PokemonStats {
+        hp: 100,
+        attack: 50,
+        defense: 40,
+        agility: 30,
+        stamina: None,
+        level: 50,
+        accuracy: 100,
+    };
+
+    let snapshot = Snapshot {
+        version: SNAPSHOT_VERSION,
+        types: vec![
+            PokemonType::new(
+                "Fire".to_string(),
+                vec![promise("Grass")],
+                vec![promise("Water")],
+            ),
+            PokemonType::new("Water".to_string(), vec![promise("Fire")], vec![]),
+            PokemonType::new("Grass".to_string(), vec![], vec![promise("Fire")]),
+        ],
+        pokemons: vec![
+            Pokemon::new("Ember".to_string(), promise("Fire"), None, stats.clone()),
+            Pokemon::new(
+                "Steam".to_string(),
+                promise("Water"),
+                Some(promise("Fire")),
+                stats.clone(),
+            ),
+        ],
+        trainers: vec![
+            Trainer {
+                name: "Red".to_string(),
+                team: vec![promise("Ember"), promise("Steam")],
+                strategy: None,
+            },
+            Trainer {
+                name: "Blue".to_string(),
+                team: vec![],
+                strategy: None,
+            },
+        ],
+    };
+
+    let ndjson = snapshot.to_ndjson();
+    assert_eq!(ndjson.lines().count(), snapshot.to_records().len());
+    assert!(
+        ndjson
+            .lines()
+            .all(|line| serde_json::from_str::<Record>(line).is_ok())
+    );
+
+    let imported = Snapshot::from_ndjson(&ndjson).unwrap();
+    imported.validate().unwrap();
+    assert_eq!(imported.to_ndjson(), ndjson);
+
+    // records other than the header may come in any order, e.g. relationships first
+    let mut lines: Vec<&str> = ndjson.lines().collect();
+    lines[1..].reverse();
+    let reordered = Snapshot::from_ndjson(&lines.join("\n")).unwrap();
+    assert_eq!(reordered.pokemons.len(), 2);
+    assert_eq!(
+        reordered
+            .trainers
+            .iter()
+            .find(|t| t.name == "Red")
+            .unwrap()
+            .team
+            .len(),
+        2
+    );
+
+    assert!(Snapshot::from_ndjson(&lines[1..].join("\n")).is_err());
+    assert!(
+        Snapshot::from_ndjson(&format!(
+            "{ndjson}{{\"kind\":\"error\",\"message\":\"lost\"}}"
+        ))
+        .is_err()
+    );
+}
+#[test]
+fn test_update_query_binds_values() {
+    use crate::database::{DbValue, update::DbUpdate};
+    use crate::fight::FightStrategy;
+    use crate::trainer::Trainer;
+
+    let mut trainer = Trainer {
+        name: "a' DETACH DELETE n //".to_string(),
+        team: vec![],
+        strategy: Some(FightStrategy::Random),
+    };
+
+    let query = trainer.update_query(&trainer.name);
+    assert_eq!(
+        query.text,
+        "MATCH (n:Trainer) WHERE n.name = $id SET n.strategy = $strategy"
+    );
+    assert_eq!(
+        query.params["id"],
+        DbValue::String("a' DETACH DELETE n //".to_string())
+    );
+    assert_eq!(
+        query.params["strategy"],
+        DbValue::String("Random".to_string())
+    );
+
+    // clearing the strategy removes the property
+    trainer.strategy = None;
+    assert_eq!(
+        trainer.update_query(&trainer.name).params["strategy"],
+        DbValue::Null
+    );
+}
+
+#[test]
+fn test_count_fight_increments_in_the_database() {
+    use crate::database::{DbQuery, promise::MaybePromise};
+    use crate::pokemon::{Pokemon, stats::PokemonStats};
+
+    let mut pokemon = Pokemon::new(
+        "a' DETACH DELETE n //".to_string(),
+        MaybePromise::from_ident_unchecked("Normal".to_string()),
+        None,
+        PokemonStats {
+            hp: 100,
+            attack: 50,
+            defense: 50,
+            agility: 50,
+            stamina: None,
+            level: 50,
+            accuracy: 100,
+        },
+    );
+    pokemon.fight_count = 7;
+
+    // the count is never read from the loaded pokemon, so concurrent fights can't overwrite each other
+    assert_eq!(
+        pokemon.count_fight_query(),
+        DbQuery::new(
+            "MATCH (n:Pokemon) WHERE n.name = $name SET n.fight_count = coalesce(n.fight_count, 0) + 1"
+        )
+        .param("name", "a' DETACH DELETE n //")
+    );
+}
+
+/// Both fights count from the same stale copy of the pokemon, neither count may be lost
+#[tokio::test]
+async fn test_concurrent_fight_counts_add_up() {
+    use crate::database::{DbHandle, get::DbGet, promise::MaybePromise};
+    use crate::pokemon::{Pokemon, ptype::PokemonType, stats::PokemonStats};
+
+    PokemonType::new_to_db("CountFightTestType".to_string())
+        .await
+        .unwrap();
+    let mut pokemon = Pokemon::new(
+        "CountFightTestMon".to_string(),
+        MaybePromise::from_ident_unchecked("CountFightTestType".to_string()),
+        None,
+        PokemonStats {
+            hp: 100,
+            attack: 50,
+            defense: 50,
+            agility: 50,
+            stamina: None,
+            level: 50,
+            accuracy: 100,
+        },
+    );
+    pokemon.put_with_relationships().await.unwrap();
+
+    let (first, second) = rocket::tokio::join!(pokemon.count_fight(), pokemon.count_fight());
+    let counted = Pokemon::get_first("CountFightTestMon").await;
+
+    // clean up
+    let db = DbHandle::connect().await.unwrap();
+    let mut q_res = db
+        .inner
+        .execute("MATCH (n) WHERE n.name STARTS WITH 'CountFightTest' DETACH DELETE n;".into())
+        .await
+        .unwrap();
+    let _none = q_res.next().await.unwrap();
+
+    first.unwrap();
+    second.unwrap();
+    assert_eq!(counted.unwrap().fight_count, 2);
+}
+
+#[test]
+fn test_explain_put_matches_executed_queries() {
+    use crate::database::{DbQuery, DbValue, explain::explain, link::DbLink, put::DbPut};
+    use crate::pokemon::{Pokemon, Relationship};
+
+    let body = serde_json::json!({
+        "name": "Farfetch'd",
+        "primary_type": "Normal",
+        "secondary_type": "Flying",
+        "stats": { "hp": 52, "atk": 90, "def": 55, "agi": 60 }
+    });
+    let pokemon: Pokemon = serde_json::from_value(body.clone()).unwrap();
+
+    let explanation = explain("put pokemon Farfetch'd", Some(body)).unwrap();
+
+    // the same queries `put_self_only` and `link_to` execute
+    assert_eq!(
+        explanation.queries,
+        vec![
+            pokemon.put_query(),
+            pokemon.link_query(pokemon.primary_type(), &Relationship::PrimaryType),
+            pokemon.link_query(
+                pokemon.secondary_type().unwrap(),
+                &Relationship::SecondaryType
+            ),
+        ]
+    );
+    // names are bound as parameters, never written into the query text
+    assert_eq!(
+        explanation.queries[0].text,
+        "MERGE (n:Pokemon { name: $name, hp: $hp, attack: $attack, defense: $defense, agility: $agility, \
+        level: $level, accuracy: $accuracy })"
+    );
+    assert_eq!(
+        explanation.queries[0].params["name"],
+        DbValue::String("Farfetch'd".to_string())
+    );
+    assert_eq!(explanation.queries[0].params["hp"], DbValue::Integer(52));
+    assert_eq!(
+        explanation.queries[1].text,
+        "MATCH (a:Pokemon), (b:PokemonType) WHERE a.name = $a AND b.name = $b \
+        MERGE (a)-[:PrimaryType]->(b);"
+    );
+    assert_eq!(
+        explanation.queries[1].params["b"],
+        DbValue::String("Normal".to_string())
+    );
+
+    let trainer = explain("put trainer Ash", None).unwrap();
+    assert_eq!(
+        trainer.queries,
+        vec![DbQuery::new("MERGE (n:Trainer { name: $name })").param("name", "Ash")]
+    );
+
+    assert!(explain("put pokemon Pidgey", None).is_err());
+    assert!(explain("delete trainer Ash", None).is_err());
+    assert!(explain("put pokemon", None).is_err());
+}
+#[tokio::test]
+async fn test_get_first_meta_missing_node() {
+    use crate::database::get::DbGet;
+    use crate::pokemon::Pokemon;
+
+    let (pokemon, meta) = Pokemon::get_first_meta("NoSuchPokemonForMeta")
+        .await
+        .expect("A missing node is not an error");
+
+    assert!(pokemon.is_none());
+    assert!(!meta.found);
+    assert_eq!(meta.rows, 0);
+    assert!(meta.elapsed > std::time::Duration::ZERO);
+
+    // the wrapper still reports the missing node as an error
+    assert!(Pokemon::get_first("NoSuchPokemonForMeta").await.is_err());
+}
+/// Two stored fights share FightsTestShared, so both come back for it and one for each other pokemon
+#[tokio::test]
+async fn test_pokemon_fights_returns_shared_fights() {
+    use crate::database::{DbHandle, promise::MaybePromise, put::DbPut};
+    use crate::fight::{FightEvent, FightLog, record::FightRecord};
+    use crate::pokemon::{Pokemon, ptype::PokemonType, stats::PokemonStats};
+
+    let ptype = PokemonType::new("FightsTestType".to_string(), vec![], vec![]);
+    ptype.put_self_only().await.unwrap();
+
+    for name in ["FightsTestShared", "FightsTestFirst", "FightsTestSecond"] {
+        Pokemon::new_to_db(
+            name.to_string(),
+            MaybePromise::from_concrete(ptype.clone()),
+            None,
+            PokemonStats {
+                hp: 100,
+                attack: 50,
+                defense: 50,
+                agility: 50,
+                stamina: None,
+                level: 50,
+                accuracy: 100,
+            },
+        )
+        .await
+        .unwrap();
+    }
+
+    let mut saved = vec![];
+    for opponent in ["FightsTestFirst", "FightsTestSecond"] {
+        let log = FightLog {
+            contender_name: "FightsTestShared".to_string(),
+            challenger_name: opponent.to_string(),
+            log: vec![FightEvent::PokemonWinner {
+                pokemon: opponent.to_string(),
+                hp_left: 10,
+            }],
+            seed: None,
+            winner: None,
+            total_rounds: 0,
+            winner_hp_left: None,
+        };
+        let record = FightRecord::from_log(&log);
+        record.save().await.unwrap();
+        saved.push(record);
+    }
+
+    let shared = FightRecord::involving("FightsTestShared").await.unwrap();
+    let first = FightRecord::involving("FightsTestFirst").await.unwrap();
+
+    // clean up
+    let db = DbHandle::connect().await.unwrap();
+    for id in saved.iter().map(|r| &r.id) {
+        let mut q_res = db
+            .inner
+            .execute(format!("MATCH (f:Fight {{ id: '{id}' }}) DETACH DELETE f;").into())
+            .await
+            .unwrap();
+        let _none = q_res.next().await.unwrap();
+    }
+    let mut q_res = db
+        .inner
+        .execute("MATCH (n) WHERE n.name STARTS WITH 'FightsTest' DETACH DELETE n;".into())
+        .await
+        .unwrap();
+    let _none = q_res.next().await.unwrap();
+
+    saved.sort_by(|a, b| a.id.cmp(&b.id));
+    assert_eq!(shared, saved);
+    assert_eq!(first.len(), 1);
+    assert_eq!(first[0].winner.as_deref(), Some("FightsTestFirst"));
+}
+/// Two `Owns` edges to each pokemon still give each pokemon once, ordered by name
+#[tokio::test]
+async fn test_get_linked_by_id_dedups_and_orders() {
+    use crate::database::{DbHandle, link::DbLink, promise::MaybePromise, put::DbPut};
+    use crate::pokemon::{Pokemon, ptype::PokemonType, stats::PokemonStats};
+    use crate::trainer::{Relationship, Trainer};
+
+    let ptype = PokemonType::new("LinkedTestType".to_string(), vec![], vec![]);
+    ptype.put_self_only().await.unwrap();
+
+    // created out of order, so a stable order can't come from insertion
+    for name in ["LinkedTestCharlie", "LinkedTestAlpha", "LinkedTestBravo"] {
+        Pokemon::new_to_db(
+            name.to_string(),
+            MaybePromise::from_concrete(ptype.clone()),
+            None,
+            PokemonStats {
+                hp: 100,
+                attack: 50,
+                defense: 50,
+                agility: 50,
+                stamina: None,
+                level: 50,
+                accuracy: 100,
+            },
+        )
+        .await
+        .unwrap();
+    }
+
+    let trainer = Trainer {
+        name: "LinkedTestTrainer".to_string(),
+        team: vec![],
+        strategy: None,
+    };
+    trainer.put_self_only().await.unwrap();
+
+    // CREATE (unlike MERGE) duplicates the edges
+    let db = DbHandle::connect().await.unwrap();
+    let mut q_res = db
+        .inner
+        .execute(
+            "MATCH (t:Trainer { name: 'LinkedTestTrainer' }), (p:Pokemon) \
+            WHERE p.name STARTS WITH 'LinkedTest' \
+            CREATE (t)-[:Owns]->(p), (t)-[:Owns]->(p);"
+                .into(),
+        )
+        .await
+        .unwrap();
+    let _none = q_res.next().await.unwrap();
+
+    let team = Trainer::get_linked_by_id(&Relationship::Owns, "LinkedTestTrainer")
+        .await
+        .unwrap();
+    let names: Vec<String> = team.iter().map(|p| p.ident().to_string()).collect();
+
+    // clean up
+    let mut q_res = db
+        .inner
+        .execute("MATCH (n) WHERE n.name STARTS WITH 'LinkedTest' DETACH DELETE n;".into())
+        .await
+        .unwrap();
+    let _none = q_res.next().await.unwrap();
+
+    assert_eq!(
+        names,
+        vec!["LinkedTestAlpha", "LinkedTestBravo", "LinkedTestCharlie"]
+    );
+}
+/// Three stored fights with different winners and lengths, each filter picks its subset of them
+#[tokio::test]
+async fn test_filter_fights_by_winner_and_turns() {
+    use crate::database::DbHandle;
+    use crate::fight::{FightEvent, FightLog, record::FightRecord};
+
+    let fight = |winner: &str, turns: usize| {
+        let mut log: Vec<FightEvent> = (0..turns)
+            .map(|_| FightEvent::Rest {
+                pokemon: winner.to_string(),
+                stamina: 10,
+            })
+            .collect();
+        log.push(FightEvent::PokemonWinner {
+            pokemon: winner.to_string(),
+            hp_left: 10,
+        });
+
+        FightRecord::from_log(&FightLog {
+            contender_name: "FilterTestAlpha".to_string(),
+            challenger_name: "FilterTestBeta".to_string(),
+            log,
+            seed: None,
+            winner: None,
+            total_rounds: 0,
+            winner_hp_left: None,
+        })
+    };
+
+    let saved = vec![
+        fight("FilterTestAlpha", 2),
+        fight("FilterTestBeta", 5),
+        fight("FilterTestAlpha", 8),
+    ];
+    for record in &saved {
+        record.save().await.unwrap();
+    }
+
+    // other stored fights may match the turn filters, only the saved ones are compared
+    let filtered = |fights: Vec<FightRecord>| -> Vec<u32> {
+        fights
+            .iter()
+            .filter(|f| saved.contains(f))
+            .map(|f| f.turn_count)
+            .collect()
+    };
+
+    let alpha = FightRecord::filter(Some("FilterTestAlpha"), None, None).await;
+    let middle = FightRecord::filter(None, Some(3), Some(6)).await;
+    let long_alpha = FightRecord::filter(Some("FilterTestAlpha"), Some(5), None).await;
+
+    // clean up
+    let db = DbHandle::connect().await.unwrap();
+    for id in saved.iter().map(|r| &r.id) {
+        let mut q_res = db
+            .inner
+            .execute(format!("MATCH (f:Fight {{ id: '{id}' }}) DETACH DELETE f;").into())
+            .await
+            .unwrap();
+        let _none = q_res.next().await.unwrap();
+    }
+
+    let mut alpha = filtered(alpha.unwrap());
+    alpha.sort_unstable();
+    assert_eq!(alpha, vec![2, 8]);
+    assert_eq!(filtered(middle.unwrap()), vec![5]);
+    assert_eq!(filtered(long_alpha.unwrap()), vec![8]);
+}
+/// The pokemon have no types, so they can't be resolved, and the counted trainer has an empty local team,
+/// so the count can only come from the links in the database
+#[tokio::test]
+async fn test_link_count_counts_owned_pokemon() {
+    use crate::database::{DbHandle, link::DbLink, promise::MaybePromise, put::DbPut};
+    use crate::pokemon::{Pokemon, stats::PokemonStats};
+    use crate::trainer::{Relationship, Trainer};
+
+    let mut trainer = Trainer {
+        name: "CountTestTrainer".to_string(),
+        team: vec![],
+        strategy: None,
+    };
+    trainer.put_self_only().await.unwrap();
+
+    for name in ["CountTestAlpha", "CountTestBravo", "CountTestCharlie"] {
+        Pokemon::new(
+            name.to_string(),
+            MaybePromise::from_ident_unchecked("CountTestMissingType".to_string()),
+            None,
+            PokemonStats {
+                hp: 100,
+                attack: 50,
+                defense: 50,
+                agility: 50,
+                stamina: None,
+                level: 50,
+                accuracy: 100,
+            },
+        )
+        .put_self_only()
+        .await
+        .unwrap();
+
+        trainer
+            .link_to(
+                &MaybePromise::from_ident_unchecked(name.to_string()),
+                &Relationship::Owns,
+            )
+            .await
+            .unwrap();
+    }
+
+    let counted = Trainer {
+        name: "CountTestTrainer".to_string(),
+        team: vec![],
+        strategy: None,
+    };
+    let count = counted.link_count(&Relationship::Owns).await;
+
+    // clean up
+    let db = DbHandle::connect().await.unwrap();
+    let mut q_res = db
+        .inner
+        .execute("MATCH (n) WHERE n.name STARTS WITH 'CountTest' DETACH DELETE n;".into())
+        .await
+        .unwrap();
+    let _none = q_res.next().await.unwrap();
+
+    assert_eq!(count.unwrap(), 3);
+}
+
+#[tokio::test]
+async fn test_injected_pokemon_name_round_trips() {
+    use crate::database::{
+        DbHandle, delete::DbDelete, get::DbGet, promise::MaybePromise, put::DbPut,
+        update::DbUpdate,
+    };
+    use crate::pokemon::{Pokemon, ptype::PokemonType, stats::PokemonStats};
+    use crate::trainer::Trainer;
+
+    const NAME: &str = "a' DETACH DELETE n //";
+
+    let stats = PokemonStats {
+        hp: 100,
+        attack: 50,
+        defense: 50,
+        agility: 50,
+        stamina: None,
+        level: 50,
+        accuracy: 100,
+    };
+
+    PokemonType::new_to_db("InjectTestType".to_string())
+        .await
+        .unwrap();
+    let typed = || MaybePromise::from_ident_unchecked("InjectTestType".to_string());
+
+    Pokemon::new(
+        "InjectTestWitness".to_string(),
+        typed(),
+        None,
+        stats.clone(),
+    )
+    .put_self_only()
+    .await
+    .unwrap();
+    Pokemon::new(NAME.to_string(), typed(), None, stats)
+        .put_with_relationships()
+        .await
+        .unwrap();
+
+    let mut leveled = Pokemon::get_first(NAME).await.unwrap();
+    leveled.stats.level = 51;
+    leveled.update(NAME).await.unwrap();
+
+    let injected = Pokemon::get_first(NAME).await;
+    let witness = Pokemon::get_first("InjectTestWitness").await;
+
+    let trainer = Trainer {
+        name: NAME.to_string(),
+        team: vec![],
+        strategy: None,
+    };
+    trainer.put_self_only().await.unwrap();
+    Trainer::delete(NAME).await.unwrap();
+    let deleted_trainer = Trainer::get_first(NAME).await;
+    let fights = crate::fight::record::FightRecord::involving(NAME).await;
+
+    // clean up
+    let db = DbHandle::connect().await.unwrap();
+    let mut q_res = db
+        .inner
+        .execute(
+            neo4rs::query("MATCH (n) WHERE n.name IN $names DETACH DELETE n;")
+                .param("names", vec![NAME, "InjectTestWitness", "InjectTestType"]),
+        )
+        .await
+        .unwrap();
+    let _none = q_res.next().await.unwrap();
+
+    let injected = injected.unwrap();
+    assert_eq!(injected.name, NAME);
+    assert_eq!(injected.stats.level, 51);
+    assert_eq!(injected.primary_type().ident(), "InjectTestType");
+    assert!(witness.is_ok());
+    assert!(deleted_trainer.is_err());
+    assert_eq!(fights.unwrap(), vec![]);
+}
+
+#[tokio::test]
+async fn test_second_page_skips_the_first() {
+    use crate::database::{
+        DbHandle,
+        get::{DbGet, MAX_PAGE_LIMIT},
+        put::DbPut,
+    };
+    use crate::trainer::Trainer;
+
+    for name in ["PageTestA", "PageTestB", "PageTestC", "PageTestD"] {
+        Trainer {
+            name: name.to_string(),
+            team: vec![],
+            strategy: None,
+        }
+        .put_self_only()
+        .await
+        .unwrap();
+    }
+
+    let names =
+        |trainers: &[Trainer]| -> Vec<String> { trainers.iter().map(|t| t.name.clone()).collect() };
+
+    let everyone = Trainer::get_page(0, MAX_PAGE_LIMIT).await;
+    let first = Trainer::get_page(0, 2).await;
+    let second = Trainer::get_page(2, 2).await;
+    let second_with_teams = Trainer::get_page_with_teams(2, 2).await;
+
+    // clean up
+    let db = DbHandle::connect().await.unwrap();
+    let mut q_res = db
+        .inner
+        .execute("MATCH (n:Trainer) WHERE n.name STARTS WITH 'PageTest' DETACH DELETE n;".into())
+        .await
+        .unwrap();
+    let _none = q_res.next().await.unwrap();
+
+    let everyone = names(&everyone.unwrap().items);
+    let first = first.unwrap();
+    let second = second.unwrap();
+
+    assert!(everyone.len() >= 4);
+    assert_eq!(first.total, second.total);
+    assert_eq!(names(&first.items), everyone[..2]);
+    assert_eq!(names(&second.items), everyone[2..4]);
+    assert_eq!(names(&second_with_teams.unwrap().items), everyone[2..4]);
+}
+
+#[tokio::test]
+async fn test_pokemon_without_primary_type_is_skipped() {
+    use crate::database::{DbHandle, get::DbGet, promise::MaybePromise, put::DbPut};
+    use crate::pokemon::{Pokemon, ptype::PokemonType, stats::PokemonStats};
+
+    let ptype = PokemonType::new("TypelessTestType".to_string(), vec![], vec![]);
+    ptype.put_self_only().await.unwrap();
+
+    Pokemon::new_to_db(
+        "TypelessTestHealthy".to_string(),
+        MaybePromise::from_concrete(ptype),
+        None,
+        PokemonStats {
+            hp: 100,
+            attack: 10,
+            defense: 10,
+            agility: 10,
+            stamina: None,
+            level: 50,
+            accuracy: 100,
+        },
+    )
+    .await
+    .unwrap();
+
+    // a pokemon whose primary type link was deleted
+    let db = DbHandle::connect().await.unwrap();
+    let mut q_res = db
+        .inner
+        .execute(
+            "CREATE (:Pokemon {name: 'TypelessTestBroken', hp: 100, attack: 10, \
+            defense: 10, agility: 10, level: 50});"
+                .into(),
+        )
+        .await
+        .unwrap();
+    let _none = q_res.next().await.unwrap();
+
+    let all = Pokemon::get_all_checked().await;
+    let page = Pokemon::get_page(0, 500).await;
+
+    // clean up
+    let mut q_res = db
+        .inner
+        .execute("MATCH (n) WHERE n.name STARTS WITH 'TypelessTest' DETACH DELETE n;".into())
+        .await
+        .unwrap();
+    let _none = q_res.next().await.unwrap();
+
+    let (pokemons, warnings) = all.unwrap();
+    assert!(pokemons.iter().any(|p| p.name == "TypelessTestHealthy"));
+    assert!(pokemons.iter().all(|p| p.name != "TypelessTestBroken"));
+    assert!(
+        warnings
+            .iter()
+            .any(|w| w.identifier.as_deref() == Some("TypelessTestBroken"))
+    );
+
+    let page = page.unwrap();
+    assert!(page.items.iter().any(|p| p.name == "TypelessTestHealthy"));
+    assert!(
+        page.warnings
+            .iter()
+            .any(|w| w.identifier.as_deref() == Some("TypelessTestBroken"))
+    );
+}
+
+#[tokio::test]
+async fn test_count_goes_up_after_a_put() {
+    use crate::database::{DbHandle, get::DbGet, put::DbPut};
+    use crate::trainer::Trainer;
+
+    let before = Trainer::count().await;
+
+    Trainer {
+        name: "CountUpTestTrainer".to_string(),
+        team: vec![],
+        strategy: None,
+    }
+    .put_self_only()
+    .await
+    .unwrap();
+
+    let after = Trainer::count().await;
+
+    // clean up
+    let db = DbHandle::connect().await.unwrap();
+    let mut q_res = db
+        .inner
+        .execute("MATCH (n:Trainer) WHERE n.name = 'CountUpTestTrainer' DETACH DELETE n;".into())
+        .await
+        .unwrap();
+    let _none = q_res.next().await.unwrap();
+
+    assert_eq!(after.unwrap(), before.unwrap() + 1);
+}
+
+#[tokio::test]
+async fn test_trainer_strategy_round_trips() {
+    use crate::database::{DbHandle, DbRepr, get::DbGet, put::DbPut, update::DbUpdate};
+    use crate::fight::FightStrategy;
+    use crate::trainer::Trainer;
+
+    let mut trainer = Trainer {
+        name: "StrategyTestTrainer".to_string(),
+        team: vec![],
+        strategy: None,
+    };
+    trainer.put_self_only().await.unwrap();
+    let unset = Trainer::get_first("StrategyTestTrainer").await;
+
+    trainer.strategy = Some(FightStrategy::CounterLead);
+    trainer.update(&trainer.get_db_identifier()).await.unwrap();
+    let stored = Trainer::get_first("StrategyTestTrainer").await;
+    let listed = Trainer::get_all_with_teams().await;
+
+    // clean up
+    let db = DbHandle::connect().await.unwrap();
+    let mut q_res = db
+        .inner
+        .execute("MATCH (n:Trainer) WHERE n.name = 'StrategyTestTrainer' DETACH DELETE n;".into())
+        .await
+        .unwrap();
+    let _none = q_res.next().await.unwrap();
+
+    let unset = unset.unwrap();
+    assert!(unset.strategy.is_none());
+    assert!(matches!(unset.strategy(), FightStrategy::StrongestSum));
+
+    assert!(matches!(
+        stored.unwrap().strategy,
+        Some(FightStrategy::CounterLead)
+    ));
+    assert!(matches!(
+        listed
+            .unwrap()
+            .into_iter()
+            .find(|t| t.name == "StrategyTestTrainer")
+            .unwrap()
+            .strategy,
+        Some(FightStrategy::CounterLead)
+    ));
+}
+
+#[tokio::test]
+async fn test_set_primary_type_replaces_the_link() {
+    use crate::database::{
+        DbHandle, get::DbGet, link::DbLink, promise::MaybePromise, put::DbPut,
+    };
+    use crate::pokemon::{Pokemon, Relationship, ptype::PokemonType, stats::PokemonStats};
+
+    for name in ["PrimaryTestWrong", "PrimaryTestRight"] {
+        PokemonType::new(name.to_string(), vec![], vec![])
+            .put_self_only()
+            .await
+            .unwrap();
+    }
+
+    let mut pokemon = Pokemon::new_to_db(
+        "PrimaryTestMon".to_string(),
+        MaybePromise::from_ident_unchecked("PrimaryTestWrong".to_string()),
+        None,
+        PokemonStats {
+            hp: 100,
+            attack: 50,
+            defense: 50,
+            agility: 50,
+            stamina: None,
+            level: 50,
+            accuracy: 100,
+        },
+    )
+    .await
+    .unwrap();
+
+    let changed = pokemon
+        .set_primary_type(MaybePromise::from_ident_unchecked(
+            "PrimaryTestRight".to_string(),
+        ))
+        .await;
+    let missing = pokemon
+        .set_primary_type(MaybePromise::from_ident_unchecked(
+            "PrimaryTestMissing".to_string(),
+        ))
+        .await;
+    let refetched = Pokemon::get_first("PrimaryTestMon").await.unwrap();
+    let resolved = refetched.primary_type().clone().resolve().await;
+    let links = pokemon.link_count(&Relationship::PrimaryType).await;
+
+    // clean up
+    let db = DbHandle::connect().await.unwrap();
+    let mut q_res = db
+        .inner
+        .execute("MATCH (n) WHERE n.name STARTS WITH 'PrimaryTest' DETACH DELETE n;".into())
+        .await
+        .unwrap();
+    let _none = q_res.next().await.unwrap();
+
+    assert!(changed.is_ok());
+    assert!(missing.is_err());
+    assert_eq!(pokemon.primary_type().ident(), "PrimaryTestRight");
+
+    assert_eq!(refetched.primary_type().ident(), "PrimaryTestRight");
+    assert_eq!(resolved.unwrap().name, "PrimaryTestRight");
+    assert_eq!(links.unwrap(), 1);
+}
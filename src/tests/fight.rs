@@ -0,0 +1,2934 @@
+#[allow(unused_imports)]
+use crate::database::promise::MaybePromise;
+#[allow(unused_imports)]
+use crate::fight::FightStrategy;
+#[allow(unused_imports)]
+use crate::fight::config::FightConfig;
+#[allow(unused_imports)]
+use crate::pokemon::Pokemon;
+#[allow(unused_imports)]
+use crate::pokemon::ptype::PokemonType;
+#[allow(unused_imports)]
+use crate::pokemon::stats::PokemonStats;
+
+/// A fixed RNG for strategy picks, only `Random` draws from it
+#[allow(unused)]
+fn picker_rng() -> rand::rngs::StdRng {
+    rand::SeedableRng::seed_from_u64(0)
+}
+
+/// Builds a type that is not stored in the database
+#[allow(unused)]
+fn ptype(name: &str, strong_against: &[&str], weak_against: &[&str]) -> PokemonType {
+    let promises = |names: &[&str]| {
+        names
+            .iter()
+            .map(|n| MaybePromise::from_ident_unchecked(n.to_string()))
+            .collect()
+    };
+
+    PokemonType::new(
+        name.to_string(),
+        promises(strong_against),
+        promises(weak_against),
+    )
+}
+
+/// Builds a pokemon with concrete (already resolved) types
+#[allow(unused)]
+fn pokemon(
+    name: &str,
+    primary_type: &PokemonType,
+    secondary_type: Option<&PokemonType>,
+    attack: u32,
+    defense: u32,
+) -> Pokemon {
+    Pokemon::new(
+        name.to_string(),
+        MaybePromise::from_concrete(primary_type.clone()),
+        secondary_type.map(|t| MaybePromise::from_concrete(t.clone())),
+        PokemonStats {
+            hp: 100,
+            attack,
+            defense,
+            agility: 50,
+            stamina: None,
+            level: 50,
+            accuracy: 100,
+        },
+    )
+}
+
+/// A small type chart used by the strategy tests
+/// The enemy is always a Fire/Rock dual type
+#[allow(unused)]
+struct Chart {
+    fire: PokemonType,
+    rock: PokemonType,
+    water: PokemonType,
+    fighting: PokemonType,
+    grass: PokemonType,
+    normal: PokemonType,
+    ice: PokemonType,
+    bug: PokemonType,
+    ground: PokemonType,
+    electric: PokemonType,
+}
+
+#[allow(unused)]
+fn chart() -> Chart {
+    Chart {
+        fire: ptype("Fire", &["Grass", "Ice", "Bug"], &["Water", "Rock"]),
+        rock: ptype(
+            "Rock",
+            &["Fire", "Ice", "Bug"],
+            &["Water", "Grass", "Fighting"],
+        ),
+        water: ptype("Water", &["Fire", "Rock"], &["Grass"]),
+        fighting: ptype("Fighting", &["Rock", "Normal", "Ice"], &[]),
+        grass: ptype("Grass", &["Rock", "Water"], &["Fire"]),
+        normal: ptype("Normal", &[], &[]),
+        ice: ptype("Ice", &["Grass"], &["Fire"]),
+        bug: ptype("Bug", &["Grass"], &["Fire", "Rock"]),
+        ground: ptype("Ground", &["Fire"], &[]),
+        electric: ptype("Electric", &["Water"], &[]),
+    }
+}
+
+#[allow(unused)]
+fn enemy(c: &Chart) -> Pokemon {
+    pokemon("Enemy", &c.fire, Some(&c.rock), 50, 50)
+}
+
+#[allow(unused)]
+async fn strongest_type_pick(team: &[Pokemon], enemy: &Pokemon) -> String {
+    FightStrategy::StrongestType
+        .choose_pokemon(
+            team,
+            Some(enemy),
+            std::slice::from_ref(enemy),
+            &mut picker_rng(),
+        )
+        .await
+        .expect("StrongestType should pick a pokemon from a non-empty team")
+        .name
+}
+
+#[tokio::test]
+async fn test_strongest_type_prefers_strong_against_both() {
+    let c = chart();
+    let team = vec![
+        pokemon("GrassMon", &c.grass, None, 200, 200),
+        pokemon("FightingMon", &c.fighting, None, 150, 150),
+        pokemon("WaterMon", &c.water, None, 10, 10),
+    ];
+
+    assert_eq!(strongest_type_pick(&team, &enemy(&c)).await, "WaterMon");
+}
+
+#[tokio::test]
+async fn test_strongest_type_dual_type_covers_both() {
+    let c = chart();
+    // Neither type alone covers both enemy types, together they do
+    let team = vec![
+        pokemon("FightingMon", &c.fighting, None, 150, 150),
+        pokemon("GroundFighting", &c.ground, Some(&c.fighting), 10, 10),
+    ];
+
+    assert_eq!(
+        strongest_type_pick(&team, &enemy(&c)).await,
+        "GroundFighting"
+    );
+}
+
+#[tokio::test]
+async fn test_strongest_type_prefers_no_weakness_over_weakness() {
+    let c = chart();
+    // Both are strong against Rock only, but Grass is weak against Fire
+    let team = vec![
+        pokemon("GrassMon", &c.grass, None, 200, 200),
+        pokemon("FightingMon", &c.fighting, None, 10, 10),
+    ];
+
+    assert_eq!(strongest_type_pick(&team, &enemy(&c)).await, "FightingMon");
+}
+
+#[tokio::test]
+async fn test_strongest_type_prefers_strong_against_one() {
+    let c = chart();
+    // Grass is strong against Rock despite its weakness, Normal has no advantage
+    let team = vec![
+        pokemon("NormalMon", &c.normal, None, 200, 200),
+        pokemon("GrassMon", &c.grass, None, 10, 10),
+    ];
+
+    assert_eq!(strongest_type_pick(&team, &enemy(&c)).await, "GrassMon");
+}
+
+#[tokio::test]
+async fn test_strongest_type_prefers_no_weakness() {
+    let c = chart();
+    let team = vec![
+        pokemon("IceMon", &c.ice, None, 200, 200),
+        pokemon("NormalMon", &c.normal, None, 10, 10),
+    ];
+
+    assert_eq!(strongest_type_pick(&team, &enemy(&c)).await, "NormalMon");
+}
+
+#[tokio::test]
+async fn test_strongest_type_secondary_weakness_counts() {
+    let c = chart();
+    // The dual type's secondary type is weak against Fire, the single type has no weakness
+    let team = vec![
+        pokemon("NormalIce", &c.normal, Some(&c.ice), 200, 200),
+        pokemon("ElectricMon", &c.electric, None, 10, 10),
+    ];
+
+    assert_eq!(strongest_type_pick(&team, &enemy(&c)).await, "ElectricMon");
+}
+
+#[tokio::test]
+async fn test_strongest_type_prefers_one_weakness() {
+    let c = chart();
+    // Bug is weak against both enemy types, Ice only against Fire
+    let team = vec![
+        pokemon("BugMon", &c.bug, None, 200, 200),
+        pokemon("IceMon", &c.ice, None, 10, 10),
+    ];
+
+    assert_eq!(strongest_type_pick(&team, &enemy(&c)).await, "IceMon");
+}
+
+#[tokio::test]
+async fn test_strongest_type_falls_back_to_strongest_sum() {
+    let c = chart();
+    let team = vec![
+        pokemon("WeakBug", &c.bug, None, 10, 10),
+        pokemon("StrongBug", &c.bug, None, 100, 100),
+        pokemon("BugIce", &c.bug, Some(&c.ice), 50, 50),
+    ];
+
+    assert_eq!(strongest_type_pick(&team, &enemy(&c)).await, "StrongBug");
+}
+
+#[tokio::test]
+async fn test_strongest_type_ties_use_strongest_sum() {
+    let c = chart();
+    let team = vec![
+        pokemon("WeakWater", &c.water, None, 10, 10),
+        pokemon("StrongWater", &c.water, None, 100, 100),
+    ];
+
+    assert_eq!(strongest_type_pick(&team, &enemy(&c)).await, "StrongWater");
+}
+
+#[tokio::test]
+async fn test_strongest_type_single_type_enemy() {
+    let c = chart();
+    let enemy = pokemon("Enemy", &c.fire, None, 50, 50);
+    // Ground covers the enemy's only type, so it ranks with dual coverage
+    let team = vec![
+        pokemon("NormalMon", &c.normal, None, 200, 200),
+        pokemon("GroundMon", &c.ground, None, 10, 10),
+    ];
+
+    assert_eq!(strongest_type_pick(&team, &enemy).await, "GroundMon");
+}
+
+#[tokio::test]
+async fn test_strongest_type_without_enemy_uses_strongest_sum() {
+    let c = chart();
+    let team = vec![
+        pokemon("WaterMon", &c.water, None, 10, 10),
+        pokemon("NormalMon", &c.normal, None, 100, 100),
+    ];
+
+    let pick = FightStrategy::StrongestType
+        .choose_pokemon(&team, None, &[], &mut picker_rng())
+        .await
+        .unwrap();
+
+    assert_eq!(pick.name, "NormalMon");
+}
+
+#[tokio::test]
+async fn test_replay_token_reproduces_fight() {
+    use crate::fight::pokemon_fight::process_fight_seeded;
+    use crate::fight::replay::ReplayToken;
+
+    let c = chart();
+    let contender = pokemon("WaterMon", &c.water, None, 60, 40);
+    let challenger = pokemon("FireMon", &c.fire, Some(&c.rock), 70, 30);
+
+    let original = process_fight_seeded(&contender, &challenger, 1234, &FightConfig::default())
+        .await
+        .unwrap();
+
+    let token = ReplayToken::new(contender.name.clone(), challenger.name.clone(), 1234).encode();
+
+    assert!(
+        token
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+        "Token should be URL-safe: {}",
+        token
+    );
+
+    let decoded = ReplayToken::decode(&token).unwrap();
+    assert_eq!(decoded.contender, "WaterMon");
+    assert_eq!(decoded.challenger, "FireMon");
+
+    let replayed = process_fight_seeded(
+        &contender,
+        &challenger,
+        decoded.seed,
+        &FightConfig::default(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        serde_json::to_string(&original).unwrap(),
+        serde_json::to_string(&replayed).unwrap()
+    );
+}
+
+#[test]
+fn test_replay_token_rejects_garbage() {
+    use crate::fight::replay::ReplayToken;
+
+    assert!(ReplayToken::decode("not a token!").is_err());
+    assert!(ReplayToken::decode("e30").is_err()); // "{}"
+}
+
+#[tokio::test]
+async fn test_damage_cap_limits_each_hit() {
+    use crate::fight::FightEvent;
+    use crate::fight::pokemon_fight::process_fight_seeded;
+
+    let c = chart();
+    let stats = |hp, attack| PokemonStats {
+        hp,
+        attack,
+        defense: 0,
+        agility: 50,
+        stamina: None,
+        level: 50,
+        accuracy: 100,
+    };
+
+    // A super effective hit from this attacker would one-shot the defender many times over
+    let attacker = Pokemon::new(
+        "Extreme".to_string(),
+        MaybePromise::from_concrete(c.water.clone()),
+        Some(MaybePromise::from_concrete(c.ground.clone())),
+        stats(200, 5000),
+    );
+    let defender = Pokemon::new(
+        "Target".to_string(),
+        MaybePromise::from_concrete(c.fire.clone()),
+        Some(MaybePromise::from_concrete(c.rock.clone())),
+        stats(200, 10),
+    );
+
+    let config = FightConfig {
+        max_hit_fraction: Some(0.25),
+        ..FightConfig::default()
+    };
+
+    let log = process_fight_seeded(&attacker, &defender, 7, &config)
+        .await
+        .unwrap();
+
+    let hits: Vec<_> = log
+        .log
+        .iter()
+        .filter_map(|e| match e {
+            FightEvent::Hit {
+                attacker, damage, ..
+            } => Some((attacker.clone(), *damage)),
+            _ => None,
+        })
+        .collect();
+
+    assert!(hits.iter().all(|(_, damage)| *damage <= 50));
+    assert!(
+        hits.iter()
+            .any(|(a, damage)| a == "Extreme" && *damage == 50)
+    );
+
+    // Uncapped, the first hit ends the fight
+    let uncapped = process_fight_seeded(&attacker, &defender, 7, &FightConfig::default())
+        .await
+        .unwrap();
+
+    assert!(uncapped.log.iter().any(|e| matches!(
+        e,
+        FightEvent::Hit { attacker, damage, .. } if attacker == "Extreme" && *damage > 50
+    )));
+}
+
+/// Builds a trainer whose team is not stored in the database
+#[allow(unused)]
+fn trainer(name: &str, team: Vec<Pokemon>) -> crate::trainer::Trainer {
+    crate::trainer::Trainer {
+        name: name.to_string(),
+        team: team.into_iter().map(MaybePromise::from_concrete).collect(),
+        strategy: None,
+    }
+}
+
+/// The enemy leads with a fragile but hard-hitting Fire pokemon, and keeps a Fire/Rock tank in reserve
+/// The stats leave enough margin that every random roll produces the same winner
+#[tokio::test]
+async fn test_counter_lead_saves_counter_for_enemy_reserve() {
+    use crate::fight::FightEvent;
+    use crate::fight::trainer_fight::process_fight;
+
+    let c = chart();
+    let with_stats = |name: &str,
+                      primary: &PokemonType,
+                      secondary: Option<&PokemonType>,
+                      hp,
+                      attack,
+                      agility| {
+        Pokemon::new(
+            name.to_string(),
+            MaybePromise::from_concrete(primary.clone()),
+            secondary.map(|t| MaybePromise::from_concrete(t.clone())),
+            PokemonStats {
+                hp,
+                attack,
+                defense: 0,
+                agility,
+                stamina: None,
+                level: 50,
+                accuracy: 100,
+            },
+        )
+    };
+
+    let enemy = trainer(
+        "Enemy",
+        vec![
+            with_stats("Ember", &c.fire, None, 40, 350, 200),
+            with_stats("Magma", &c.fire, Some(&c.rock), 500, 200, 150),
+        ],
+    );
+    let ours = trainer(
+        "Ours",
+        vec![
+            with_stats("Tide", &c.water, None, 200, 300, 100),
+            with_stats("Brawler", &c.normal, None, 400, 60, 250),
+        ],
+    );
+
+    let first_pick = |log: &crate::fight::FightLog| {
+        log.log
+            .iter()
+            .find_map(|e| match e {
+                FightEvent::ChoosePokemon { trainer, pokemon } if trainer == "Ours" => {
+                    Some(pokemon.clone())
+                }
+                _ => None,
+            })
+            .unwrap()
+    };
+    let winner = |log: &crate::fight::FightLog| match log.log.last() {
+        Some(FightEvent::Winner { trainer, .. }) => trainer.clone(),
+        other => panic!("Expected a winner event, got {:?}", other),
+    };
+
+    // Greedy: Tide counters Ember, but gets one-shot, leaving nothing for Magma
+    let greedy = process_fight(
+        &ours,
+        &enemy,
+        FightStrategy::StrongestType,
+        FightStrategy::StrongestAtk,
+        &FightConfig::default(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(first_pick(&greedy), "Tide");
+    assert_eq!(winner(&greedy), "Enemy");
+
+    // Lookahead: Brawler safely handles Ember, Tide is kept back for Magma
+    let lookahead = process_fight(
+        &ours,
+        &enemy,
+        FightStrategy::CounterLead,
+        FightStrategy::StrongestAtk,
+        &FightConfig::default(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(first_pick(&lookahead), "Brawler");
+    assert_eq!(winner(&lookahead), "Ours");
+}
+
+#[tokio::test]
+async fn test_identical_stats_alternate_turns() {
+    use crate::fight::FightEvent;
+    use crate::fight::pokemon_fight::process_fight_seeded;
+
+    let c = chart();
+    // Same types and stats, only the names differ
+    let first = pokemon("TwinA", &c.normal, None, 10, 0);
+    let second = pokemon("TwinB", &c.normal, None, 10, 0);
+
+    let log = process_fight_seeded(&first, &second, 42, &FightConfig::default())
+        .await
+        .unwrap();
+
+    let attackers: Vec<_> = log
+        .log
+        .iter()
+        .filter_map(|e| match e {
+            FightEvent::Hit { attacker, .. } => Some(attacker.as_str()),
+            _ => None,
+        })
+        .collect();
+
+    assert!(attackers.len() > 2);
+    assert!(
+        attackers.windows(2).all(|w| w[0] != w[1]),
+        "Attackers should alternate: {:?}",
+        attackers
+    );
+}
+
+#[tokio::test]
+async fn test_chart_override_changes_outcome() {
+    use crate::fight::FightEvent;
+    use crate::fight::chart::{TypeChartOverride, TypeRelations};
+    use crate::fight::pokemon_fight::process_fight;
+
+    let c = chart();
+    let with_hp = |name: &str, ptype: &PokemonType, hp| {
+        Pokemon::new(
+            name.to_string(),
+            MaybePromise::from_concrete(ptype.clone()),
+            None,
+            PokemonStats {
+                hp,
+                attack: 60,
+                defense: 0,
+                agility: 50,
+                stamina: None,
+                level: 50,
+                accuracy: 100,
+            },
+        )
+    };
+    let water = with_hp("WaterMon", &c.water, 120);
+    let fire = with_hp("FireMon", &c.fire, 100);
+
+    let winner = |log: crate::fight::FightLog| match log.log.last() {
+        Some(FightEvent::PokemonWinner { pokemon, .. }) => pokemon.clone(),
+        other => panic!("Expected a pokemon winner event, got {:?}", other),
+    };
+
+    let stored = process_fight(&water, &fire, &FightConfig::default())
+        .await
+        .unwrap();
+    assert_eq!(winner(stored), "WaterMon");
+
+    // Flip the Fire/Water relationship: Fire is now strong against Water
+    let relations = |strong: &[&str], weak: &[&str]| TypeRelations {
+        strong_against: strong.iter().map(|s| s.to_string()).collect(),
+        weak_against: weak.iter().map(|s| s.to_string()).collect(),
+    };
+    let chart = TypeChartOverride(
+        [
+            ("Fire".to_string(), relations(&["Water"], &[])),
+            ("Water".to_string(), relations(&[], &["Fire"])),
+        ]
+        .into_iter()
+        .collect(),
+    );
+
+    let water = chart.apply(&water).await.unwrap();
+    let fire = chart.apply(&fire).await.unwrap();
+
+    let overridden = process_fight(&water, &fire, &FightConfig::default())
+        .await
+        .unwrap();
+    assert_eq!(winner(overridden), "FireMon");
+}
+
+#[test]
+fn test_process_fight_resolved_without_db() {
+    use crate::fight::Effectiveness;
+    use crate::fight::FightEvent;
+    use crate::fight::pokemon_fight::{ResolvedPokemon, process_fight_resolved};
+    use rand::{SeedableRng, rngs::StdRng};
+
+    let c = chart();
+    // The pokemon only hold promises, which would need the database to resolve
+    let unresolved = |name: &str, type_name: &str| {
+        Pokemon::new(
+            name.to_string(),
+            MaybePromise::from_ident_unchecked(type_name.to_string()),
+            None,
+            PokemonStats {
+                hp: 100,
+                attack: 40,
+                defense: 20,
+                agility: 50,
+                stamina: None,
+                level: 50,
+                accuracy: 100,
+            },
+        )
+    };
+
+    let water = ResolvedPokemon::new(unresolved("WaterMon", "Water"), c.water.clone(), None);
+    let fire = ResolvedPokemon::new(
+        unresolved("FireMon", "Fire"),
+        c.fire.clone(),
+        Some(c.rock.clone()),
+    );
+
+    let mut rng = StdRng::seed_from_u64(3);
+    let log = process_fight_resolved(&water, &fire, 100, 100, &FightConfig::default(), &mut rng);
+
+    assert!(log.log.iter().any(|e| matches!(
+        e,
+        FightEvent::Hit {
+            attacker,
+            effectiveness: Effectiveness::NotVeryEffective,
+            ..
+        } if attacker == "FireMon"
+    )));
+    assert!(matches!(
+        log.log.last(),
+        Some(FightEvent::PokemonWinner { pokemon, .. }) if pokemon == "WaterMon"
+    ));
+}
+
+#[test]
+fn test_super_effective_hits_are_labelled_in_the_log() {
+    use crate::fight::Effectiveness;
+    use crate::fight::FightEvent;
+    use crate::fight::pokemon_fight::{ResolvedPokemon, process_fight_resolved};
+    use rand::{SeedableRng, rngs::StdRng};
+
+    let c = chart();
+    let fighter = |name: &str| {
+        Pokemon::new(
+            name.to_string(),
+            MaybePromise::from_ident_unchecked("Water".to_string()),
+            None,
+            PokemonStats {
+                hp: 100,
+                attack: 40,
+                defense: 20,
+                agility: 50,
+                stamina: None,
+                level: 50,
+                accuracy: 100,
+            },
+        )
+    };
+
+    // water/fighting hitting fire/rock has a type damage multiplier of 2.125
+    let attacker = ResolvedPokemon::new(
+        fighter("SplashMon"),
+        c.water.clone(),
+        Some(c.fighting.clone()),
+    );
+    let defender = ResolvedPokemon::new(fighter("MagmaMon"), c.fire.clone(), Some(c.rock.clone()));
+
+    let mut rng = StdRng::seed_from_u64(5);
+    let log = process_fight_resolved(
+        &attacker,
+        &defender,
+        100,
+        100,
+        &FightConfig::default(),
+        &mut rng,
+    );
+
+    let hit = log
+        .log
+        .iter()
+        .find(|e| matches!(e, FightEvent::Hit { attacker, .. } if attacker == "SplashMon"))
+        .expect("SplashMon should land a hit");
+    assert!(matches!(
+        hit,
+        FightEvent::Hit {
+            effectiveness: Effectiveness::SuperEffective,
+            ..
+        }
+    ));
+
+    let json = serde_json::to_string(hit).unwrap();
+    assert!(
+        json.contains("\"effectiveness\":\"SuperEffective\""),
+        "{json}"
+    );
+}
+
+#[test]
+fn test_leftovers_heal_up_to_max_hp() {
+    use crate::fight::FightEvent;
+    use crate::fight::pokemon_fight::{ResolvedPokemon, process_fight_resolved};
+    use crate::pokemon::item::Item;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    let c = chart();
+
+    let mut holder = fighter("LeftoversMon", &c.normal, 160, 30, 50);
+    holder.held_item = Some(Item::Leftovers);
+    let holder = ResolvedPokemon::new(holder, c.normal.clone(), None);
+    let opponent = fighter("PlainMon", &c.normal, 160, 20, 50);
+    let opponent = ResolvedPokemon::new(opponent, c.normal.clone(), None);
+
+    // the holder starts hurt, so it has room to heal
+    let mut rng = StdRng::seed_from_u64(9);
+    let log = process_fight_resolved(
+        &holder,
+        &opponent,
+        100,
+        160,
+        &FightConfig::default(),
+        &mut rng,
+    );
+
+    let mut holder_hp = 100;
+    let mut heals = 0;
+
+    for event in &log.log {
+        match event {
+            FightEvent::Hit {
+                defender, hp_left, ..
+            } if defender == "LeftoversMon" => holder_hp = *hp_left,
+            FightEvent::ItemHealed {
+                pokemon,
+                amount,
+                hp_left,
+            } => {
+                assert_eq!(pokemon, "LeftoversMon");
+                // 1/16 of 160, or less when that would pass the max HP
+                assert!(*amount > 0 && *amount <= 10);
+                assert_eq!(*hp_left, (holder_hp + amount).min(160));
+                assert!(*hp_left <= 160);
+                holder_hp = *hp_left;
+                heals += 1;
+            }
+            _ => {}
+        }
+    }
+
+    assert!(heals > 0, "{:?}", log.log);
+}
+
+#[test]
+fn test_choice_band_raises_the_damage() {
+    use crate::fight::FightEvent;
+    use crate::fight::pokemon_fight::{ResolvedPokemon, process_fight_resolved};
+    use crate::pokemon::item::{CHOICE_BAND_ATTACK_MULTIPLIER, Item};
+    use rand::{SeedableRng, rngs::StdRng};
+
+    let c = chart();
+    let mut attacker = fighter("BandMon", &c.normal, 100, 60, 90);
+    let defender = fighter("TargetMon", &c.normal, 255, 10, 10);
+
+    let first_hit = |attacker: &Pokemon| {
+        let attacker = ResolvedPokemon::new(attacker.clone(), c.normal.clone(), None);
+        let defender = ResolvedPokemon::new(defender.clone(), c.normal.clone(), None);
+        let config = FightConfig {
+            crit_chance: 0.0,
+            ..FightConfig::default()
+        };
+
+        let mut rng = StdRng::seed_from_u64(4);
+        let log = process_fight_resolved(&attacker, &defender, 100, 255, &config, &mut rng);
+
+        log.log
+            .into_iter()
+            .find_map(|event| match event {
+                FightEvent::Hit { damage, .. } => Some(damage),
+                _ => None,
+            })
+            .unwrap()
+    };
+
+    let plain = first_hit(&attacker);
+    attacker.held_item = Some(Item::ChoiceBand);
+    let banded = first_hit(&attacker);
+
+    // the same seed rolls the same random multiplier, only the attack changes
+    let expected = plain as f32 * CHOICE_BAND_ATTACK_MULTIPLIER;
+    assert!(
+        (banded as f32 - expected).abs() <= 1.5,
+        "{plain} -> {banded}"
+    );
+}
+
+#[test]
+fn test_rain_raises_the_damage_of_water_types() {
+    use crate::fight::FightEvent;
+    use crate::fight::pokemon_fight::{ResolvedPokemon, process_fight_resolved};
+    use crate::fight::weather::Weather;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    let c = chart();
+    let attacker = fighter("SplashMon", &c.water, 100, 60, 90);
+    let defender = fighter("TargetMon", &c.normal, 255, 10, 10);
+
+    let first_hit = |weather: Weather| {
+        let attacker = ResolvedPokemon::new(attacker.clone(), c.water.clone(), None);
+        let defender = ResolvedPokemon::new(defender.clone(), c.normal.clone(), None);
+        let config = FightConfig {
+            crit_chance: 0.0,
+            weather,
+            ..FightConfig::default()
+        };
+
+        let mut rng = StdRng::seed_from_u64(4);
+        let log = process_fight_resolved(&attacker, &defender, 100, 255, &config, &mut rng);
+
+        log.log
+            .into_iter()
+            .find_map(|event| match event {
+                FightEvent::Hit { damage, .. } => Some(damage),
+                _ => None,
+            })
+            .unwrap()
+    };
+
+    let clear = first_hit(Weather::Clear);
+    let rain = first_hit(Weather::Rain);
+
+    // the same seed rolls the same random multiplier, only the weather changes
+    assert!(rain > clear, "{clear} -> {rain}");
+
+    // the sun only boosts Fire types
+    assert_eq!(first_hit(Weather::Sun), clear);
+}
+
+#[test]
+fn test_sandstorm_spares_rock_types() {
+    use crate::fight::FightEvent;
+    use crate::fight::pokemon_fight::{ResolvedPokemon, process_fight_resolved};
+    use crate::fight::weather::Weather;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    let c = chart();
+    let normal = ResolvedPokemon::new(
+        fighter("DustMon", &c.normal, 160, 20, 90),
+        c.normal.clone(),
+        None,
+    );
+    let rock = ResolvedPokemon::new(
+        fighter("PebbleMon", &c.rock, 160, 20, 10),
+        c.rock.clone(),
+        None,
+    );
+
+    let config = FightConfig {
+        weather: Weather::Sandstorm,
+        ..FightConfig::default()
+    };
+
+    let mut rng = StdRng::seed_from_u64(2);
+    let log = process_fight_resolved(&normal, &rock, 160, 160, &config, &mut rng);
+
+    let hurt: Vec<_> = log
+        .log
+        .iter()
+        .filter_map(|event| match event {
+            FightEvent::WeatherDamage {
+                pokemon,
+                weather,
+                damage,
+                ..
+            } => Some((pokemon.as_str(), *weather, *damage)),
+            _ => None,
+        })
+        .collect();
+
+    assert!(!hurt.is_empty());
+    assert!(
+        hurt.iter()
+            .all(|hurt| *hurt == ("DustMon", Weather::Sandstorm, 160 / 16))
+    );
+}
+
+#[test]
+fn test_power_rating_rewards_coverage() {
+    use crate::pokemon::power::{PowerConfig, power_rating};
+
+    let c = chart();
+    let all_types = vec![
+        c.fire.clone(),
+        c.rock.clone(),
+        c.water.clone(),
+        c.grass.clone(),
+        c.normal.clone(),
+    ];
+
+    // Same stats, Fighting is strong against Rock and Normal, Ground only against Fire
+    let fighting = pokemon("FightingMon", &c.fighting, None, 50, 50);
+    let ground = pokemon("GroundMon", &c.ground, None, 50, 50);
+
+    let config = PowerConfig::default();
+    let fighting_power = power_rating(&fighting, &c.fighting, None, &all_types, &config);
+    let ground_power = power_rating(&ground, &c.ground, None, &all_types, &config);
+
+    assert_eq!(fighting_power.stats_score, ground_power.stats_score);
+    assert_eq!(fighting_power.coverage, 2);
+    assert_eq!(ground_power.coverage, 1);
+    assert!(fighting_power.power > ground_power.power);
+}
+
+#[test]
+fn test_hit_events_report_both_hps() {
+    use crate::fight::FightEvent;
+    use crate::fight::pokemon_fight::{ResolvedPokemon, process_fight_resolved};
+    use rand::{SeedableRng, rngs::StdRng};
+
+    let c = chart();
+    let first = ResolvedPokemon::new(
+        pokemon("NormalA", &c.normal, None, 30, 10),
+        c.normal.clone(),
+        None,
+    );
+    let second = ResolvedPokemon::new(
+        pokemon("NormalB", &c.normal, None, 30, 10),
+        c.normal.clone(),
+        None,
+    );
+
+    // Both start below full HP, as if carried over from an earlier fight
+    let mut rng = StdRng::seed_from_u64(11);
+    let log = process_fight_resolved(&first, &second, 80, 90, &FightConfig::default(), &mut rng);
+
+    let hits: Vec<_> = log
+        .log
+        .iter()
+        .filter_map(|e| match e {
+            FightEvent::Hit {
+                attacker,
+                hp_left,
+                attacker_hp,
+                ..
+            } => Some((attacker.as_str(), *attacker_hp, *hp_left)),
+            _ => None,
+        })
+        .collect();
+
+    // The challenger attacks first on an agility tie, with its carried over HP
+    assert_eq!(hits[0].0, "NormalB");
+    assert_eq!(hits[0].1, 90);
+
+    for pair in hits.windows(2) {
+        let (_, prev_attacker_hp, prev_defender_hp) = pair[0];
+        let (_, attacker_hp, defender_hp) = pair[1];
+
+        // last turn's defender attacks now, and last turn's attacker defends
+        assert_eq!(attacker_hp, prev_defender_hp);
+        assert!(defender_hp < prev_attacker_hp);
+        assert!(attacker_hp + defender_hp < prev_attacker_hp + prev_defender_hp);
+    }
+
+    let last_hit = hits.last().unwrap();
+    assert!(matches!(
+        log.log.last(),
+        Some(FightEvent::PokemonWinner { pokemon, hp_left })
+            if pokemon == last_hit.0 && *hp_left == last_hit.1
+    ));
+}
+
+/// Builds a defenseless pokemon with the given stats, so predicted damage is easy to follow
+#[allow(unused)]
+fn fighter(name: &str, primary_type: &PokemonType, hp: u32, attack: u32, agility: u32) -> Pokemon {
+    let mut pokemon = pokemon(name, primary_type, None, attack, 0);
+    pokemon.stats.hp = hp;
+    pokemon.stats.agility = agility;
+    pokemon
+}
+
+/// Tide beats the Fire lead but falls to the Grass reserve, which Blaze then beats
+/// Neither can win alone, so the solver needs exactly the two of them
+#[tokio::test]
+async fn test_solve_team_finds_two_pokemon_team() {
+    let c = chart();
+    let target = trainer(
+        "Target",
+        vec![
+            fighter("Ember", &c.fire, 100, 61, 60),
+            fighter("Sprout", &c.grass, 100, 60, 60),
+        ],
+    );
+    let available = vec![
+        fighter("Tide", &c.water, 100, 60, 50),
+        fighter("Blaze", &c.fire, 100, 60, 50),
+        fighter("Pebble", &c.normal, 50, 10, 50),
+    ];
+
+    let solved = crate::fight::solver::solve_team(
+        &target,
+        &available,
+        FightStrategy::StrongestType,
+        FightStrategy::StrongestAtk,
+        crate::trainer::MAX_TEAM_SIZE,
+        &FightConfig::default(),
+    )
+    .await
+    .expect("Solving against concrete pokemon should not touch the database");
+
+    assert!(solved.predicted_win);
+    assert_eq!(solved.team, vec!["Tide", "Blaze"]);
+    assert!(
+        solved
+            .bouts
+            .iter()
+            .any(|b| b.pokemon == "Tide" && b.opponent == "Ember" && b.winner == "Tide")
+    );
+    assert!(
+        solved
+            .bouts
+            .iter()
+            .any(|b| b.pokemon == "Blaze" && b.opponent == "Sprout" && b.winner == "Blaze")
+    );
+}
+
+/// Banded hits hard enough to beat the faster Wall, but only if its Choice Band
+/// survives being turned back into a concrete pokemon, as `solve_team` and campaigns do
+#[tokio::test]
+async fn test_held_item_survives_into_concrete() {
+    use crate::fight::pokemon_fight::ResolvedPokemon;
+    use crate::pokemon::item::Item;
+
+    let c = chart();
+    let target = trainer("Target", vec![fighter("Wall", &c.normal, 150, 60, 60)]);
+
+    let mut banded = fighter("Banded", &c.normal, 150, 60, 50);
+    banded.held_item = Some(Item::ChoiceBand);
+    banded.fight_count = 3;
+
+    let concrete = ResolvedPokemon::new(banded.clone(), c.normal.clone(), None).into_concrete();
+    assert_eq!(concrete.held_item, Some(Item::ChoiceBand));
+    assert_eq!(concrete.fight_count, 3);
+
+    let solve = |available: Vec<Pokemon>| {
+        let target = &target;
+        async move {
+            crate::fight::solver::solve_team(
+                target,
+                &available,
+                FightStrategy::StrongestAtk,
+                FightStrategy::StrongestAtk,
+                crate::trainer::MAX_TEAM_SIZE,
+                &FightConfig::default(),
+            )
+            .await
+            .expect("Solving against concrete pokemon should not touch the database")
+        }
+    };
+
+    banded.held_item = None;
+    assert!(!solve(vec![banded]).await.predicted_win);
+    assert!(solve(vec![concrete]).await.predicted_win);
+}
+
+#[tokio::test]
+async fn test_round_robin_plays_every_pairing() {
+    let c = chart();
+    let entries: Vec<_> = ["Red", "Blue", "Green"]
+        .iter()
+        .map(|name| {
+            let team = vec![
+                fighter(&format!("{name}Water"), &c.water, 100, 50, 50),
+                fighter(&format!("{name}Fire"), &c.fire, 100, 50, 50),
+            ];
+            (trainer(name, team), FightStrategy::StrongestType)
+        })
+        .collect();
+
+    for home_and_away in [false, true] {
+        let league = crate::fight::round_robin::round_robin(
+            &entries,
+            home_and_away,
+            &FightConfig::default(),
+        )
+        .await
+        .expect("A round robin of concrete teams should not touch the database");
+
+        let games_per_pairing = if home_and_away { 2 } else { 1 };
+        assert_eq!(league.fixtures.len(), 3 * games_per_pairing);
+
+        for (a, _) in &entries {
+            for (b, _) in &entries {
+                if a.name == b.name {
+                    continue;
+                }
+
+                let played = league
+                    .fixtures
+                    .iter()
+                    .filter(|f| {
+                        (f.challenger == a.name && f.contender == b.name)
+                            || (f.challenger == b.name && f.contender == a.name)
+                    })
+                    .count();
+                assert_eq!(played, games_per_pairing);
+            }
+        }
+
+        // every trainer plays both others, and every game has exactly one winner and one loser
+        assert_eq!(league.table.len(), 3);
+        assert!(
+            league
+                .table
+                .iter()
+                .all(|s| s.played == 2 * games_per_pairing)
+        );
+        assert_eq!(
+            league.table.iter().map(|s| s.wins).sum::<usize>(),
+            league.fixtures.len()
+        );
+        assert_eq!(
+            league.table.iter().map(|s| s.losses).sum::<usize>(),
+            league.fixtures.len()
+        );
+        assert!(league.table.windows(2).all(|w| w[0].points >= w[1].points));
+    }
+}
+
+/// Weakling leads and faints to Tank's first hit, so Avenger is the next entrant
+#[tokio::test]
+async fn test_revenge_boost_raises_next_entrant_attack() {
+    use crate::fight::FightEvent;
+    use crate::fight::trainer_fight::process_fight;
+
+    let c = chart();
+    let ours = trainer(
+        "Ours",
+        vec![
+            fighter("Weakling", &c.normal, 10, 120, 10),
+            fighter("Avenger", &c.normal, 1000, 100, 10),
+        ],
+    );
+    let enemy = trainer("Enemy", vec![fighter("Tank", &c.normal, 10000, 500, 100)]);
+    let (ours, enemy) = (&ours, &enemy);
+
+    let boosts = |revenge_boost: bool| async move {
+        let config = FightConfig {
+            revenge_boost,
+            ..FightConfig::default()
+        };
+        let log = process_fight(
+            ours,
+            enemy,
+            FightStrategy::StrongestAtk,
+            FightStrategy::StrongestAtk,
+            &config,
+        )
+        .await
+        .expect("A fight of concrete teams should not touch the database");
+
+        log.log
+            .into_iter()
+            .filter_map(|e| match e {
+                FightEvent::StatBoost {
+                    pokemon,
+                    cause,
+                    attack,
+                } => Some((pokemon, cause, attack)),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        boosts(true).await,
+        vec![("Avenger".to_string(), "Weakling".to_string(), 110)]
+    );
+    assert!(boosts(false).await.is_empty());
+}
+
+#[test]
+fn test_type_breakdown_dual_vs_dual() {
+    use crate::fight::Effectiveness;
+    use crate::fight::pokemon_fight::type_damage_multiplier;
+
+    let c = chart();
+
+    // (attacker types, defender types, steps a-h that apply)
+    let cases = [
+        (
+            (&c.water, &c.fighting),
+            (&c.fire, &c.rock),
+            [true, false, true, false, false, false, true, false],
+        ),
+        (
+            (&c.fire, &c.bug),
+            (&c.water, &c.rock),
+            [false, true, false, true, false, false, false, true],
+        ),
+        (
+            (&c.fighting, &c.grass),
+            (&c.water, &c.fire),
+            [false, false, false, false, true, false, false, true],
+        ),
+        (
+            (&c.normal, &c.fire),
+            (&c.water, &c.normal),
+            [false, false, false, false, false, true, false, false],
+        ),
+    ];
+
+    for ((atk_p, atk_s), (def_p, def_s), expected) in cases {
+        let breakdown = type_damage_multiplier(atk_p, Some(atk_s), def_p, Some(def_s));
+
+        let letters: Vec<char> = breakdown.steps.iter().map(|s| s.step).collect();
+        assert_eq!(letters, "abcdefgh".chars().collect::<Vec<_>>());
+
+        let applied: Vec<bool> = breakdown.steps.iter().map(|s| s.applied).collect();
+        assert_eq!(
+            applied, expected,
+            "{}/{} attacking {}/{}",
+            atk_p.name, atk_s.name, def_p.name, def_s.name
+        );
+
+        let mut running_total = 1.0;
+        for step in &breakdown.steps {
+            if step.applied {
+                running_total += step.change;
+            }
+            assert!((step.running_total - running_total).abs() < 1e-6);
+        }
+        assert!((breakdown.total - running_total).abs() < 1e-6);
+    }
+
+    let super_effective =
+        type_damage_multiplier(&c.water, Some(&c.fighting), &c.fire, Some(&c.rock));
+    assert!((super_effective.multiplier - 2.125).abs() < 1e-6);
+    assert!(matches!(
+        super_effective.effectiveness,
+        Effectiveness::SuperEffective
+    ));
+
+    let not_very = type_damage_multiplier(&c.fire, Some(&c.bug), &c.water, Some(&c.rock));
+    assert!((not_very.multiplier - 0.325).abs() < 1e-6);
+    assert!(matches!(
+        not_very.effectiveness,
+        Effectiveness::NotVeryEffective
+    ));
+}
+
+/// The type damage multiplier exactly as steps 3a-4 of the fight algorithm document it
+#[allow(unused)]
+fn spec_type_multiplier(
+    atk_p: &PokemonType,
+    atk_s: Option<&PokemonType>,
+    def_p: &PokemonType,
+    def_s: Option<&PokemonType>,
+) -> f32 {
+    let mut mult: f32 = 1.0;
+
+    // a, b
+    if atk_p.is_strong_against(def_p) {
+        mult += 0.375;
+    }
+    if atk_p.is_weak_against(def_p) {
+        mult -= 0.225;
+    }
+    // c, d
+    if let Some(def_s) = def_s {
+        if atk_p.is_strong_against(def_s) {
+            mult += 0.375;
+        }
+        if atk_p.is_weak_against(def_s) {
+            mult -= 0.225;
+        }
+    }
+    // e, f
+    if let Some(atk_s) = atk_s {
+        if def_p.is_weak_against(atk_s) {
+            mult += 0.375;
+        }
+        if def_p.is_strong_against(atk_s) {
+            mult -= 0.225;
+        }
+    }
+    // g, h
+    if let (Some(atk_s), Some(def_s)) = (atk_s, def_s) {
+        if def_s.is_weak_against(atk_s) {
+            mult += 0.375;
+        }
+        if def_s.is_strong_against(atk_s) {
+            mult -= 0.225;
+        }
+    }
+
+    mult.clamp(0.1, 2.5)
+}
+
+/// Every single and dual typing of the attacker against every single and dual typing of the defender
+#[test]
+fn test_type_multiplier_matches_spec_for_every_typing() {
+    use crate::fight::pokemon_fight::type_damage_multiplier;
+
+    let c = chart();
+    let types = [&c.fire, &c.water, &c.grass, &c.rock, &c.normal];
+
+    let mut typings: Vec<(&PokemonType, Option<&PokemonType>)> = vec![];
+    for primary in types {
+        typings.push((primary, None));
+        for secondary in types.iter().filter(|t| t.name != primary.name) {
+            typings.push((primary, Some(*secondary)));
+        }
+    }
+    assert_eq!(typings.len(), 25);
+
+    for (atk_p, atk_s) in &typings {
+        for (def_p, def_s) in &typings {
+            let expected = spec_type_multiplier(atk_p, *atk_s, def_p, *def_s);
+            let actual = type_damage_multiplier(atk_p, *atk_s, def_p, *def_s).multiplier;
+
+            assert!(
+                (actual - expected).abs() < 1e-6,
+                "{}/{:?} attacking {}/{:?}: expected {expected}, got {actual}",
+                atk_p.name,
+                atk_s.map(|t| &t.name),
+                def_p.name,
+                def_s.map(|t| &t.name),
+            );
+        }
+    }
+
+    // hand-computed: Water beats Fire (a) and Rock (c), Fire beats Grass (f), Rock is weak against Grass (g)
+    let water_grass = type_damage_multiplier(&c.water, Some(&c.grass), &c.fire, Some(&c.rock));
+    assert!((water_grass.multiplier - 1.9).abs() < 1e-6);
+
+    // hand-computed: Fire is weak against Water (b) and Rock (d), Water is strong against the
+    // attacker's Rock (f), and Rock has no relationship with itself (g, h)
+    let fire_rock = type_damage_multiplier(&c.fire, Some(&c.rock), &c.water, Some(&c.rock));
+    assert!((fire_rock.multiplier - (1.0 - 0.225 - 0.225 - 0.225)).abs() < 1e-6);
+}
+
+/// Later fixtures finish first, so completion order is the reverse of schedule order
+#[tokio::test]
+async fn test_round_robin_respects_concurrency_cap() {
+    use crate::fight::batch::run_bounded;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // 4 trainers play 6 fixtures
+    const FIXTURES: usize = 6;
+    const CAP: usize = 2;
+
+    let in_flight = AtomicUsize::new(0);
+    let peak = AtomicUsize::new(0);
+    let (in_flight, peak) = (&in_flight, &peak);
+
+    let fights = (0..FIXTURES).map(|i| async move {
+        let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        peak.fetch_max(now, Ordering::SeqCst);
+        tokio::time::sleep(std::time::Duration::from_millis(5 * (FIXTURES - i) as u64)).await;
+        in_flight.fetch_sub(1, Ordering::SeqCst);
+        Ok(i)
+    });
+
+    let results = run_bounded(fights, CAP).await.unwrap();
+
+    assert_eq!(results, (0..FIXTURES).collect::<Vec<_>>());
+    assert_eq!(peak.load(Ordering::SeqCst), CAP);
+
+    // every first hit is a knockout, so no fight can end on a hit that leaves a fraction of HP
+    let c = chart();
+    let entries: Vec<_> = ["Red", "Blue", "Green", "Yellow"]
+        .iter()
+        .map(|name| {
+            let team = vec![fighter(&format!("{name}Water"), &c.water, 100, 5000, 50)];
+            (trainer(name, team), FightStrategy::StrongestType)
+        })
+        .collect();
+
+    let pairings = |max_concurrent_fights: usize| {
+        let entries = &entries;
+        async move {
+            let config = FightConfig {
+                max_concurrent_fights,
+                ..FightConfig::default()
+            };
+            crate::fight::round_robin::round_robin(entries, false, &config)
+                .await
+                .expect("A round robin of concrete teams should not touch the database")
+                .fixtures
+                .into_iter()
+                .map(|f| (f.challenger, f.contender))
+                .collect::<Vec<_>>()
+        }
+    };
+
+    let sequential = pairings(1).await;
+    assert_eq!(sequential.len(), FIXTURES);
+    assert_eq!(sequential[0], ("Red".to_string(), "Blue".to_string()));
+    assert_eq!(pairings(CAP).await, sequential);
+    assert_eq!(pairings(FIXTURES).await, sequential);
+}
+
+#[tokio::test]
+async fn test_trainer_fight_odds_dominant_team() {
+    use crate::fight::odds::trainer_fight_odds;
+
+    let c = chart();
+    let strong = trainer(
+        "Strong",
+        vec![
+            fighter("Titan", &c.normal, 1000, 200, 150),
+            fighter("Colossus", &c.normal, 1000, 200, 150),
+        ],
+    );
+    let weak = trainer(
+        "Weak",
+        vec![
+            fighter("Sprout", &c.normal, 50, 20, 10),
+            fighter("Pebble", &c.normal, 50, 20, 10),
+        ],
+    );
+
+    let odds = trainer_fight_odds(
+        &weak,
+        &strong,
+        FightStrategy::StrongestAtk,
+        FightStrategy::StrongestAtk,
+        100,
+        7,
+        &FightConfig::default(),
+    )
+    .await
+    .expect("Odds of concrete teams should not touch the database");
+
+    assert_eq!(odds.trials, 100);
+    assert!(odds.contender.win_rate >= 0.95);
+    assert!((odds.contender.win_rate + odds.challenger.win_rate - 1.0).abs() < 1e-6);
+    assert_eq!(
+        odds.contender.surviving_distribution.values().sum::<u32>(),
+        100
+    );
+    assert!(odds.contender.average_surviving > 1.0 && odds.contender.average_surviving <= 2.0);
+    assert_eq!(
+        odds.challenger.surviving_distribution.get(&0),
+        Some(&(100 - odds.challenger.wins))
+    );
+
+    for trials in [0, crate::fight::odds::MAX_TRIALS + 1] {
+        assert!(
+            trainer_fight_odds(
+                &weak,
+                &strong,
+                FightStrategy::StrongestAtk,
+                FightStrategy::StrongestAtk,
+                trials,
+                7,
+                &FightConfig::default(),
+            )
+            .await
+            .is_err()
+        );
+    }
+}
+#[test]
+fn test_no_status_condition_has_no_effect() {
+    use crate::fight::{config::FightConfig, status::StatusCondition};
+
+    let config = FightConfig::default();
+    let normal = ptype("Normal", &[], &[]);
+
+    assert_eq!(StatusCondition::default(), StatusCondition::None);
+    assert_eq!(
+        StatusCondition::inflicted_by(&normal, &config),
+        StatusCondition::None
+    );
+    assert_eq!(
+        StatusCondition::inflicted_by(&ptype("Fire", &[], &[]), &config),
+        StatusCondition::Burn
+    );
+    assert_eq!(StatusCondition::None.attack_multiplier(), 1.0);
+    assert_eq!(StatusCondition::None.residual_damage(400), None);
+    assert_eq!(StatusCondition::None.skip_chance(), 0.0);
+}
+
+/// Venom's hits on the Grass defender have a type advantage, so with a certain chance
+/// the first one poisons Leaf, which then loses 1/8 of its HP at the end of each of its turns
+#[tokio::test]
+async fn test_poison_ticks_shorten_seeded_fight() {
+    use crate::fight::FightEvent;
+    use crate::fight::pokemon_fight::process_fight_seeded;
+    use crate::fight::status::StatusCondition;
+
+    let c = chart();
+    let poison = ptype("Poison", &["Grass"], &[]);
+    let venom = fighter("Venom", &poison, 1000, 30, 60);
+    let leaf = fighter("Leaf", &c.grass, 400, 10, 40);
+
+    let hits = |log: &crate::fight::FightLog| {
+        log.log
+            .iter()
+            .filter(|e| matches!(e, FightEvent::Hit { .. }))
+            .count()
+    };
+
+    let plain = process_fight_seeded(&venom, &leaf, 3, &FightConfig::default())
+        .await
+        .unwrap();
+    assert!(
+        plain
+            .log
+            .iter()
+            .all(|e| !matches!(e, FightEvent::StatusDamage { .. }))
+    );
+
+    let config = FightConfig {
+        status_chance: 1.0,
+        ..FightConfig::default()
+    };
+    let poisoned = process_fight_seeded(&venom, &leaf, 3, &config)
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        &poisoned.log[1],
+        FightEvent::StatusApplied { pokemon, condition: StatusCondition::Poison } if pokemon == "Leaf"
+    ));
+
+    let ticks: Vec<_> = poisoned
+        .log
+        .iter()
+        .filter_map(|e| match e {
+            FightEvent::StatusDamage {
+                pokemon, damage, ..
+            } => Some((pokemon.clone(), *damage)),
+            _ => None,
+        })
+        .collect();
+    assert!(!ticks.is_empty());
+    assert!(ticks.iter().all(|t| *t == ("Leaf".to_string(), 50)));
+    assert!(hits(&poisoned) < hits(&plain));
+
+    // the same seed reproduces the same poisoned fight
+    let again = process_fight_seeded(&venom, &leaf, 3, &config)
+        .await
+        .unwrap();
+    assert_eq!(
+        serde_json::to_string(&again).unwrap(),
+        serde_json::to_string(&poisoned).unwrap()
+    );
+}
+#[tokio::test]
+async fn test_strategy_picks_reports_every_strategy() {
+    use crate::fight::strategy_picks;
+
+    let c = chart();
+    let team = vec![
+        pokemon("Striker", &c.normal, None, 200, 50),
+        pokemon("Wall", &c.normal, None, 50, 200),
+        pokemon("Balanced", &c.normal, None, 140, 140),
+    ];
+    let enemy = enemy(&c);
+
+    let picks = strategy_picks(&team, Some(&enemy)).await;
+    let pick = |strategy: &str| {
+        picks
+            .iter()
+            .find(|p| format!("{:?}", p.strategy) == strategy)
+            .and_then(|p| p.pokemon.clone())
+    };
+
+    assert_eq!(picks.len(), FightStrategy::ALL.len());
+    assert_eq!(pick("StrongestAtk").as_deref(), Some("Striker"));
+    assert_eq!(pick("StrongestDef").as_deref(), Some("Wall"));
+    assert_eq!(pick("StrongestSum").as_deref(), Some("Balanced"));
+    assert!(picks.iter().all(|p| p.pokemon.is_some()));
+}
+/// Both are tied on attack, Guard wins on its higher stat total in either team order
+/// Twin and Twain tie on everything, so the alphabetically first name wins
+#[tokio::test]
+async fn test_strongest_atk_tie_break_is_deterministic() {
+    let c = chart();
+    let guard = pokemon("Guard", &c.normal, None, 100, 150);
+    let brawler = pokemon("Brawler", &c.normal, None, 100, 50);
+
+    for team in [
+        vec![guard.clone(), brawler.clone()],
+        vec![brawler.clone(), guard.clone()],
+    ] {
+        let pick = FightStrategy::StrongestAtk
+            .choose_pokemon(&team, None, &[], &mut picker_rng())
+            .await
+            .unwrap();
+        assert_eq!(pick.name, "Guard");
+    }
+
+    let twin = pokemon("Twin", &c.normal, None, 100, 100);
+    let twain = pokemon("Twain", &c.normal, None, 100, 100);
+
+    for strategy in [
+        FightStrategy::StrongestAtk,
+        FightStrategy::StrongestDef,
+        FightStrategy::StrongestSum,
+    ] {
+        for team in [
+            vec![twin.clone(), twain.clone()],
+            vec![twain.clone(), twin.clone()],
+        ] {
+            let pick = strategy
+                .choose_pokemon(&team, None, &[], &mut picker_rng())
+                .await
+                .unwrap();
+            assert_eq!(pick.name, "Twain");
+        }
+    }
+}
+/// Wounded enters with 30 of its 100 HP, so the first hit is measured from 30
+#[tokio::test]
+async fn test_combatant_state_partial_hp() {
+    use crate::fight::FightEvent;
+    use crate::fight::pokemon_fight::{CombatantState, process_fight_with_rng};
+    use rand::{SeedableRng, rngs::StdRng};
+
+    let c = chart();
+    let striker = CombatantState::full(fighter("Striker", &c.normal, 100, 10, 90));
+    let wounded = CombatantState::new(fighter("Wounded", &c.normal, 100, 10, 10), 30);
+
+    assert_eq!(striker.current_hp, 100);
+    assert_eq!(wounded.pokemon.stats.hp, 100);
+    assert!(!wounded.is_fainted());
+
+    let mut rng = StdRng::seed_from_u64(11);
+    let log = process_fight_with_rng(&wounded, &striker, &FightConfig::default(), &mut rng)
+        .await
+        .expect("A fight of concrete pokemon should not touch the database");
+
+    match &log.log[0] {
+        FightEvent::Hit {
+            attacker,
+            damage,
+            hp_left,
+            attacker_hp,
+            ..
+        } => {
+            assert_eq!(attacker, "Striker");
+            assert_eq!(*attacker_hp, 100);
+            assert!((30 - *damage as i64 - *hp_left as i64).abs() <= 1);
+        }
+        e => panic!("The faster pokemon should hit first, got {e:?}"),
+    }
+
+    // Wounded's own hits report its HP counted down from 30, never from its base 100
+    assert!(log.log.iter().all(|e| match e {
+        FightEvent::Hit {
+            attacker,
+            attacker_hp,
+            ..
+        } if attacker == "Wounded" => *attacker_hp <= 30,
+        _ => true,
+    }));
+}
+/// Tired has 25 stamina: every attack costs 10 and regains 5, so it attacks at 25, 20, 15 and 10,
+/// then rests at 5 back up to 10
+#[tokio::test]
+async fn test_low_stamina_rests() {
+    use crate::fight::FightEvent;
+    use crate::fight::pokemon_fight::process_fight_seeded;
+
+    let c = chart();
+    let mut tired = fighter("Tired", &c.normal, 1000, 10, 90);
+    tired.stats.stamina = Some(25);
+    let sponge = fighter("Sponge", &c.normal, 1000, 10, 10);
+
+    let log = process_fight_seeded(&tired, &sponge, 5, &FightConfig::default())
+        .await
+        .unwrap();
+
+    let turns: Vec<_> = log
+        .log
+        .iter()
+        .filter_map(|e| match e {
+            FightEvent::Hit { attacker, .. } if attacker == "Tired" => Some("hit".to_string()),
+            FightEvent::Rest { pokemon, stamina } if pokemon == "Tired" => {
+                Some(format!("rest {stamina}"))
+            }
+            _ => None,
+        })
+        .take(6)
+        .collect();
+    assert_eq!(turns, vec!["hit", "hit", "hit", "hit", "rest 10", "hit"]);
+
+    // without a stamina stat nobody ever rests
+    assert!(
+        log.log
+            .iter()
+            .all(|e| !matches!(e, FightEvent::Rest { pokemon, .. } if pokemon == "Sponge"))
+    );
+    tired.stats.stamina = None;
+    let untired = process_fight_seeded(&tired, &sponge, 5, &FightConfig::default())
+        .await
+        .unwrap();
+    assert!(
+        untired
+            .log
+            .iter()
+            .all(|e| !matches!(e, FightEvent::Rest { .. }))
+    );
+}
+/// Every hit's `hp_fraction` is the defender's `hp_left` over its max HP, and is dropped on request
+#[tokio::test]
+async fn test_hit_hp_fraction_matches_hp_left() {
+    use crate::fight::FightEvent;
+    use crate::fight::pokemon_fight::process_fight_seeded;
+    use std::collections::HashMap;
+
+    let c = chart();
+    let tank = fighter("Tank", &c.normal, 300, 10, 50);
+    let glass = fighter("Glass", &c.normal, 80, 30, 60);
+    let max_hp: HashMap<_, _> = [("Tank", 300.0), ("Glass", 80.0)].into_iter().collect();
+
+    let log = process_fight_seeded(&tank, &glass, 7, &FightConfig::default())
+        .await
+        .unwrap();
+
+    let mut hits = 0;
+    for event in &log.log {
+        if let FightEvent::Hit {
+            defender,
+            hp_left,
+            hp_fraction,
+            ..
+        } = event
+        {
+            let fraction = hp_fraction.expect("The engine should always compute hp_fraction");
+            assert!((fraction - *hp_left as f32 / max_hp[defender.as_str()]).abs() < 1e-6);
+            assert!((0.0..=1.0).contains(&fraction));
+            hits += 1;
+        }
+    }
+    assert!(hits > 0);
+
+    let stripped = log.without_hp_fractions();
+    assert!(stripped.log.iter().all(|e| !matches!(
+        e,
+        FightEvent::Hit {
+            hp_fraction: Some(_),
+            ..
+        }
+    )));
+}
+/// With the same seed, a 1.5 damage scale makes every hit 1.5 times as strong,
+/// and a dual-typed attacker only gets half of the scale with the `Split` policy
+#[tokio::test]
+async fn test_damage_scale_multiplies_damage() {
+    use crate::fight::FightEvent;
+    use crate::fight::config::DualTypeScale;
+    use crate::fight::pokemon_fight::process_fight_seeded;
+
+    async fn first_hit(attacker: &Pokemon, defender: &Pokemon, config: &FightConfig) -> f32 {
+        let log = process_fight_seeded(attacker, defender, 9, config)
+            .await
+            .unwrap();
+        match &log.log[0] {
+            FightEvent::Hit { damage, .. } => *damage as f32,
+            e => panic!("The faster pokemon should hit first, got {e:?}"),
+        }
+    }
+
+    let c = chart();
+    let striker = fighter("Striker", &c.normal, 100000, 1000, 90);
+    let mut dual = pokemon("Dual", &c.normal, Some(&c.normal), 1000, 0);
+    dual.stats.hp = 100000;
+    dual.stats.agility = 90;
+    let sponge = fighter("Sponge", &c.normal, 100000, 10, 10);
+
+    let off = FightConfig::default();
+    let on = FightConfig {
+        damage_scale: Some(1.5),
+        ..FightConfig::default()
+    };
+    let split = FightConfig {
+        dual_type_scale: DualTypeScale::Split,
+        ..on.clone()
+    };
+
+    let base = first_hit(&striker, &sponge, &off).await;
+    assert!((first_hit(&striker, &sponge, &on).await - base * 1.5).abs() <= 1.0);
+    // a single-typed attacker is not affected by the secondary type policy
+    assert!((first_hit(&striker, &sponge, &split).await - base * 1.5).abs() <= 1.0);
+
+    let dual_base = first_hit(&dual, &sponge, &off).await;
+    assert!((first_hit(&dual, &sponge, &on).await - dual_base * 1.5).abs() <= 1.0);
+    assert!((first_hit(&dual, &sponge, &split).await - dual_base * 1.25).abs() <= 1.0);
+
+    // the scale ignores types, a Fire attacker gets the same scale as a Normal one
+    let flame = fighter("Flame", &c.fire, 100000, 1000, 90);
+    let flame_base = first_hit(&flame, &sponge, &off).await;
+    assert!((first_hit(&flame, &sponge, &on).await - flame_base * 1.5).abs() <= 1.0);
+}
+/// A champion that knocks out every opponent in one hit clears the whole team in (nearly) every trial,
+/// while a weakling that can't survive a hit never gets past the first opponent
+#[tokio::test]
+async fn test_gauntlet_odds_strong_pokemon_clears_team() {
+    use crate::fight::gauntlet::gauntlet_odds;
+
+    let c = chart();
+    let champion = fighter("Champion", &c.normal, 1000, 5000, 90);
+    let weakling = fighter("Weakling", &c.normal, 1, 1, 1);
+    let team = trainer(
+        "GauntletKeeper",
+        vec![
+            fighter("First", &c.normal, 100, 20, 50),
+            fighter("Second", &c.normal, 100, 20, 50),
+            fighter("Third", &c.normal, 100, 20, 50),
+        ],
+    );
+
+    let odds = gauntlet_odds(&champion, &team, 100, 1, &FightConfig::default())
+        .await
+        .unwrap();
+
+    assert_eq!(odds.team_size, 3);
+    assert_eq!(odds.max, 3);
+    assert_eq!(odds.median, 3.0);
+    assert!(odds.clear_depth[&3] >= 90, "{:?}", odds.clear_depth);
+    assert_eq!(odds.clear_depth.values().sum::<u32>(), 100);
+
+    let odds = gauntlet_odds(&weakling, &team, 10, 1, &FightConfig::default())
+        .await
+        .unwrap();
+    assert_eq!(odds.max, 0);
+    assert_eq!(odds.clear_depth[&0], 10);
+
+    assert!(
+        gauntlet_odds(&champion, &team, 0, 1, &FightConfig::default())
+            .await
+            .is_err()
+    );
+}
+/// Against a tanky defender, an extra point of attack shortens the long fight the most,
+/// so attack is the stat the matchup is most sensitive to
+#[tokio::test]
+async fn test_sensitivity_attack_matters_most_against_tank() {
+    use crate::fight::sensitivity::sensitivity;
+
+    let c = chart();
+    let mut attacker = fighter("Attacker", &c.normal, 1000, 20, 60);
+    attacker.stats.defense = 0;
+    let mut tank = fighter("Tank", &c.normal, 1500, 10, 40);
+    // takes 70% of the damage
+    tank.stats.defense = 100;
+
+    let report = sensitivity(&attacker, &tank, 200, 1, &FightConfig::default())
+        .await
+        .unwrap();
+
+    let change = |stat: &str| {
+        report
+            .stats
+            .iter()
+            .find(|s| s.stat == stat)
+            .map(|s| s.change)
+            .unwrap()
+    };
+
+    assert_eq!(report.stats.len(), 4);
+    assert!(change("atk") > 0.0);
+    assert!(
+        ["hp", "def", "agi"]
+            .iter()
+            .all(|s| change(s) < change("atk"))
+    );
+    assert_eq!(report.most_sensitive.as_deref(), Some("atk"));
+    // the pokemon itself is never changed
+    assert_eq!(attacker.stats.attack, 20);
+}
+/// Every trial lands in exactly one bucket, and every bucket is within the reported range
+#[tokio::test]
+async fn test_fight_length_distribution_counts_every_trial() {
+    use crate::fight::odds::fight_length_distribution;
+
+    let c = chart();
+    let a = fighter("LengthA", &c.normal, 300, 40, 60);
+    let b = fighter("LengthB", &c.fire, 300, 40, 50);
+
+    let distribution = fight_length_distribution(&a, &b, 200, 4, &FightConfig::default())
+        .await
+        .unwrap();
+
+    assert_eq!(distribution.trials, 200);
+    assert_eq!(distribution.turns.values().sum::<u32>(), 200);
+    assert!(
+        distribution
+            .turns
+            .keys()
+            .all(|turns| (distribution.min..=distribution.max).contains(turns))
+    );
+    assert!(distribution.min as f32 <= distribution.mean);
+    assert!(distribution.mean <= distribution.max as f32);
+
+    assert!(
+        fight_length_distribution(&a, &b, 1001, 4, &FightConfig::default())
+            .await
+            .is_err()
+    );
+}
+/// The highest stat total wins each type, a dual type counts for both of its types,
+/// and ties go to the alphabetically first name
+#[test]
+fn test_champions_pick_highest_stat_total() {
+    let c = chart();
+    let ember = pokemon("Ember", &c.fire, None, 60, 40);
+    let blaze = pokemon("Blaze", &c.fire, None, 90, 40);
+    let magma = pokemon("Magma", &c.rock, Some(&c.fire), 70, 40);
+    let pebble = pokemon("Pebble", &c.rock, None, 70, 40);
+
+    let champions = Pokemon::champions(&[ember, blaze, magma, pebble]);
+
+    assert_eq!(champions["Fire"].name, "Blaze");
+    // Magma and Pebble have the same total
+    assert_eq!(champions["Rock"].name, "Magma");
+    assert_eq!(champions.len(), 2);
+}
+/// Under the agility-weighted turn order the slower pokemon sometimes strikes first,
+/// while the default turn order always lets the faster one attack first
+#[tokio::test]
+async fn test_weighted_turn_order_lets_slower_pokemon_strike_first() {
+    use crate::fight::FightEvent;
+    use crate::fight::config::TurnOrder;
+    use crate::fight::pokemon_fight::process_fight_seeded;
+
+    let c = chart();
+    let fast = fighter("Fast", &c.normal, 200, 20, 70);
+    let slow = fighter("Slow", &c.normal, 200, 20, 30);
+
+    let weighted = FightConfig {
+        turn_order: TurnOrder::AgilityWeighted,
+        ..FightConfig::default()
+    };
+
+    let mut slow_first = [0, 0];
+    for (i, config) in [FightConfig::default(), weighted.clone()]
+        .iter()
+        .enumerate()
+    {
+        for seed in 0..50 {
+            let log = process_fight_seeded(&fast, &slow, seed, config)
+                .await
+                .unwrap();
+            match &log.log[0] {
+                FightEvent::Hit { attacker, .. } if attacker == "Slow" => slow_first[i] += 1,
+                _ => {}
+            }
+        }
+    }
+
+    assert_eq!(slow_first[0], 0);
+    // the slower pokemon has a 30% chance
+    assert!(slow_first[1] > 0 && slow_first[1] < 25, "{slow_first:?}");
+
+    // the same seed gives the same fight
+    let a = process_fight_seeded(&fast, &slow, 3, &weighted)
+        .await
+        .unwrap();
+    let b = process_fight_seeded(&fast, &slow, 3, &weighted)
+        .await
+        .unwrap();
+    assert_eq!(
+        serde_json::to_string(&a).unwrap(),
+        serde_json::to_string(&b).unwrap()
+    );
+}
+/// Deterministic fights repeat exactly, and every hit deals its expected damage
+#[tokio::test]
+async fn test_deterministic_fights_are_identical() {
+    use crate::fight::FightEvent;
+    use crate::fight::pokemon_fight::process_fight_deterministic;
+
+    let c = chart();
+    let a = fighter("SteadyA", &c.fire, 300, 40, 60);
+    let b = fighter("SteadyB", &c.grass, 300, 40, 50);
+
+    let first = process_fight_deterministic(&a, &b, &FightConfig::default())
+        .await
+        .unwrap();
+    let second = process_fight_deterministic(&a, &b, &FightConfig::default())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        serde_json::to_string(&first).unwrap(),
+        serde_json::to_string(&second).unwrap()
+    );
+
+    // no defense, so a hit is exactly the attack times the type multiplier
+    match &first.log[0] {
+        FightEvent::Hit {
+            attacker, damage, ..
+        } => {
+            assert_eq!(attacker, "SteadyA");
+            assert_eq!(*damage, 55);
+        }
+        e => panic!("The faster pokemon should hit first, got {e:?}"),
+    }
+}
+/// A trainer without pokemon is refused by the pre-flight check, naming the trainer,
+/// and the fight itself refuses it the same way instead of failing inside a strategy
+#[tokio::test]
+async fn test_empty_team_fails_preflight() {
+    let chart = chart();
+    let empty = trainer("Nobody", vec![]);
+    let ready = trainer(
+        "Ready",
+        vec![pokemon("Charmander", &chart.fire, None, 40, 10)],
+    );
+
+    let error = crate::fight::trainer_fight::check_fight_ready("Contender", &empty)
+        .await
+        .expect_err("An empty team should fail the pre-flight check")
+        .to_string();
+    assert_eq!(error, "Contender Nobody has no pokemon");
+
+    crate::fight::trainer_fight::check_fight_ready("Challenger", &ready)
+        .await
+        .expect("A team of concrete pokemon should pass the pre-flight check");
+
+    let error = crate::fight::trainer_fight::process_fight(
+        &ready,
+        &empty,
+        FightStrategy::Random,
+        FightStrategy::Random,
+        &FightConfig::default(),
+    )
+    .await
+    .expect_err("A fight against an empty team should not start")
+    .to_string();
+    assert_eq!(error, "Contender Nobody has no pokemon");
+}
+/// In a small chart, the type strong against every other type ranks first offensively,
+/// and the type weak against the most types ranks last defensively
+#[test]
+fn test_dominant_type_ranks_highest_offensively() {
+    use crate::pokemon::ranking::type_ranking;
+
+    let all_types = vec![
+        ptype("Dragon", &["Fire", "Water", "Grass"], &[]),
+        ptype("Fire", &["Grass"], &["Water", "Dragon"]),
+        ptype("Water", &["Fire"], &["Grass", "Dragon"]),
+        ptype("Grass", &["Water"], &["Fire", "Dragon"]),
+    ];
+
+    let dragon = type_ranking("Dragon", &all_types).expect("Dragon is in the chart");
+    assert_eq!(dragon.types, 4);
+    assert_eq!(dragon.offensive.count, 3);
+    assert_eq!(dragon.offensive.score, 1.0);
+    assert_eq!(dragon.offensive.percentile, 1.0);
+    assert_eq!(dragon.offensive.rank, 1);
+    assert_eq!(dragon.defensive.count, 0);
+    assert_eq!(dragon.defensive.rank, 1);
+
+    let fire = type_ranking("Fire", &all_types).expect("Fire is in the chart");
+    assert_eq!(fire.offensive.count, 1);
+    assert_eq!(fire.offensive.percentile, 0.0);
+    assert_eq!(fire.offensive.rank, 2);
+    assert_eq!(fire.defensive.count, 2);
+    assert_eq!(fire.defensive.rank, 2);
+
+    assert!(type_ranking("Fairy", &all_types).is_none());
+}
+/// With identical teams, the strength of the teams cancels out between the two orderings,
+/// so the whole difference comes from the second picker answering the first pick with a counter
+/// Hits are large, so every fight ends in a few knockouts
+#[tokio::test]
+async fn test_identical_teams_show_an_order_advantage() {
+    use crate::fight::odds::order_fairness;
+
+    let c = chart();
+    let team = || {
+        vec![
+            fighter("Flame", &c.fire, 200, 100, 50),
+            fighter("Wave", &c.water, 200, 100, 50),
+            fighter("Leaf", &c.grass, 200, 100, 50),
+        ]
+    };
+    let a = trainer("Red", team());
+    let b = trainer("Blue", team());
+
+    let fairness = order_fairness(
+        &a,
+        &b,
+        FightStrategy::StrongestType,
+        100,
+        3,
+        &FightConfig::default(),
+    )
+    .await
+    .expect("Fairness of concrete teams should not touch the database");
+
+    assert_eq!(fairness.a_first.contender.trainer, "Red");
+    assert_eq!(fairness.b_first.contender.trainer, "Blue");
+    assert_eq!(
+        fairness.second_pick_win_rate,
+        (fairness.a_first.challenger.wins + fairness.b_first.challenger.wins) as f32 / 200.0
+    );
+    assert!(
+        fairness.order_advantage > 10.0,
+        "{}",
+        fairness.order_advantage
+    );
+
+    assert!(
+        order_fairness(
+            &a,
+            &a,
+            FightStrategy::StrongestType,
+            10,
+            3,
+            &FightConfig::default()
+        )
+        .await
+        .is_err()
+    );
+}
+/// Random picks draw from the fight's seeded RNG, so the same seed reproduces the same fairness
+#[tokio::test]
+async fn test_seeded_random_fairness_is_reproducible() {
+    use crate::fight::odds::order_fairness;
+
+    let c = chart();
+    let team = || {
+        vec![
+            fighter("Flame", &c.fire, 200, 100, 50),
+            fighter("Wave", &c.water, 200, 100, 50),
+            fighter("Leaf", &c.grass, 200, 100, 50),
+        ]
+    };
+    let a = trainer("Red", team());
+    let b = trainer("Blue", team());
+
+    let config = FightConfig::default();
+    let run = || order_fairness(&a, &b, FightStrategy::Random, 50, 7, &config);
+    let first = run().await.unwrap();
+    let second = run().await.unwrap();
+
+    assert_eq!(
+        serde_json::to_value(&first).unwrap(),
+        serde_json::to_value(&second).unwrap()
+    );
+}
+/// The last entry of each series is the total damage the pokemon dealt in the log,
+/// and every series has one running total per turn
+#[tokio::test]
+async fn test_damage_timeline_ends_at_total_damage() {
+    use crate::fight::{FightEvent, pokemon_fight::process_fight_seeded};
+
+    let c = chart();
+    let a = fighter("TimelineA", &c.normal, 300, 40, 60);
+    let b = fighter("TimelineB", &c.fire, 300, 40, 50);
+
+    let log = process_fight_seeded(&a, &b, 11, &FightConfig::default())
+        .await
+        .unwrap();
+    let timeline = log.damage_timeline();
+
+    for name in ["TimelineA", "TimelineB"] {
+        let dealt: u32 = log
+            .log
+            .iter()
+            .filter_map(|event| match event {
+                FightEvent::Hit {
+                    attacker, damage, ..
+                } if attacker == name => Some(*damage),
+                _ => None,
+            })
+            .sum();
+
+        let series = &timeline[name];
+        assert_eq!(series.len(), log.turns());
+        assert_eq!(series.last().copied(), Some(dealt));
+        assert!(series.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+}
+
+/// The hero needs two hits for each easy opponent, even critical ones, so it takes a hit in every fight and carries the damage on,
+/// while the boss outlasts the hero whatever is rolled
+#[tokio::test]
+async fn test_campaign_carries_hp_until_the_hero_falls() {
+    use crate::fight::campaign::{Stage, StageLog, run_campaign};
+
+    let c = chart();
+    let hero = trainer("Hero", vec![fighter("HeroMon", &c.normal, 300, 100, 90)]);
+    let opponent = |name: &str, hp, attack| Stage::Fight {
+        opponent: trainer(
+            name,
+            vec![fighter(&format!("{name}Mon"), &c.normal, hp, attack, 10)],
+        ),
+        strategy: FightStrategy::StrongestSum,
+    };
+    let stages = [
+        opponent("EasyOne", 190, 40),
+        opponent("EasyTwo", 190, 40),
+        opponent("Boss", 1000, 250),
+        opponent("NeverReached", 10, 10),
+    ];
+
+    let campaign = run_campaign(
+        &hero,
+        FightStrategy::StrongestSum,
+        &stages,
+        0.0,
+        7,
+        &FightConfig::default(),
+    )
+    .await
+    .expect("A campaign of concrete teams should not touch the database");
+
+    assert!(!campaign.survived);
+    assert_eq!(campaign.fights_won, 2);
+    assert_eq!(campaign.stages.len(), 3);
+
+    let hp_left: Vec<u32> = campaign
+        .stages
+        .iter()
+        .map(|stage| match stage {
+            StageLog::Fight { hp_left, .. } => hp_left["HeroMon"],
+            StageLog::Heal => panic!("The campaign has no heal step"),
+        })
+        .collect();
+
+    assert!(hp_left[0] < 300);
+    assert!(hp_left[1] < hp_left[0]);
+    assert_eq!(hp_left[2], 0);
+
+    match &campaign.stages[2] {
+        StageLog::Fight { winner, .. } => assert_eq!(winner, "Boss"),
+        StageLog::Heal => unreachable!(),
+    }
+}
+
+/// The defense multiplier follows the documented formula, never turning damage into healing
+#[tokio::test]
+async fn test_defense_multiplier_matches_documented_formula() {
+    use crate::fight::FightEvent;
+    use crate::fight::pokemon_fight::{defense_multiplier, process_fight_deterministic};
+
+    assert_eq!(defense_multiplier(0), 1.0);
+    assert_eq!(defense_multiplier(250), 0.25);
+    assert_eq!(defense_multiplier(1000), 0.25);
+
+    let c = chart();
+    let attacker = fighter("Hammer", &c.normal, 100, 100, 90);
+
+    for (defense, expected) in [(250, 25), (0, 100)] {
+        let defender = pokemon("Wall", &c.normal, None, 10, defense);
+
+        let log = process_fight_deterministic(&attacker, &defender, &FightConfig::default())
+            .await
+            .unwrap();
+
+        match &log.log[0] {
+            FightEvent::Hit {
+                attacker, damage, ..
+            } => {
+                assert_eq!(attacker, "Hammer");
+                assert_eq!(*damage, expected);
+            }
+            e => panic!("The faster pokemon should hit first, got {e:?}"),
+        }
+    }
+}
+
+/// Fighting is the only secondary type that lets a Normal pokemon hit both Rock and Ice harder,
+/// while Water and Grass each cover one roster pokemon
+#[test]
+fn test_widest_coverage_secondary_ranks_first() {
+    use crate::fight::pokemon_fight::ResolvedPokemon;
+    use crate::pokemon::secondary::{SuggestionGoal, suggest_secondary};
+
+    let normal = ptype("Normal", &[], &[]);
+    let rock = ptype("Rock", &[], &["Fighting", "Water"]);
+    let ice = ptype("Ice", &[], &["Fighting"]);
+    let water = ptype("Water", &["Rock"], &["Grass"]);
+    let fighting = ptype("Fighting", &["Rock", "Ice"], &[]);
+    let grass = ptype("Grass", &["Water"], &[]);
+
+    let resolved = |name: &str, primary: &PokemonType| {
+        ResolvedPokemon::new(pokemon(name, primary, None, 50, 50), primary.clone(), None)
+    };
+
+    let me = resolved("Plain", &normal);
+    let roster = vec![
+        me.clone(),
+        resolved("Boulder", &rock),
+        resolved("Frost", &ice),
+        resolved("Wave", &water),
+    ];
+    let all_types = vec![normal.clone(), rock, ice, water, fighting, grass];
+
+    let ranked = suggest_secondary(&me, &all_types, &roster, SuggestionGoal::Offense).unwrap();
+
+    assert_eq!(ranked.roster_size, 3);
+    // the primary type is never suggested as the secondary type
+    assert_eq!(ranked.suggestions.len(), 5);
+    assert!(
+        ranked
+            .suggestions
+            .iter()
+            .all(|s| s.secondary_type != "Normal")
+    );
+
+    assert_eq!(ranked.suggestions[0].secondary_type, "Fighting");
+    assert_eq!(ranked.suggestions[0].coverage, 2);
+    assert!(
+        ranked.suggestions[1..]
+            .iter()
+            .all(|s| s.coverage < ranked.suggestions[0].coverage)
+    );
+
+    // a roster of only the pokemon itself leaves nothing to suggest against
+    assert!(suggest_secondary(&me, &all_types, &roster[..1], SuggestionGoal::Offense).is_err());
+}
+
+/// A fatal blow reports no HP left instead of a negative one, and so does a blow
+/// that leaves less than half a point, which then counts as fainted too
+#[tokio::test]
+async fn test_fatal_hit_reports_zero_hp_left() {
+    use crate::fight::FightEvent;
+    use crate::fight::pokemon_fight::process_fight_deterministic;
+
+    let c = chart();
+
+    // 1000 damage against 10 HP, and 99.7 damage (1 DEF) against 100 HP
+    for (attack, hp, defense) in [(1000, 10, 0), (100, 100, 1)] {
+        let attacker = fighter("Overkill", &c.normal, 100, attack, 90);
+        let mut defender = pokemon("Target", &c.normal, None, 10, defense);
+        defender.stats.hp = hp;
+
+        let log = process_fight_deterministic(&attacker, &defender, &FightConfig::default())
+            .await
+            .unwrap();
+
+        match &log.log[..] {
+            [
+                FightEvent::Hit {
+                    attacker, hp_left, ..
+                },
+                FightEvent::Fainted { pokemon },
+                FightEvent::PokemonWinner { .. },
+            ] => {
+                assert_eq!(attacker, "Overkill");
+                assert_eq!(*hp_left, 0);
+                assert_eq!(pokemon, "Target");
+            }
+            events => panic!("The first hit should end the fight, got {events:?}"),
+        }
+    }
+}
+
+/// A fight simulated without a seed still draws from one, and echoes it so it can be replayed exactly
+#[tokio::test]
+async fn test_unseeded_fight_replays_from_its_seed() {
+    use crate::fight::pokemon_fight::{process_fight, process_fight_seeded};
+
+    let c = chart();
+    let contender = pokemon("WaterMon", &c.water, None, 60, 40);
+    let challenger = pokemon("FireMon", &c.fire, Some(&c.rock), 70, 30);
+    let config = FightConfig::default();
+
+    let first = process_fight_seeded(&contender, &challenger, 42, &config)
+        .await
+        .unwrap();
+    let second = process_fight_seeded(&contender, &challenger, 42, &config)
+        .await
+        .unwrap();
+
+    assert_eq!(first.seed, Some(42));
+    assert_eq!(
+        serde_json::to_string(&first).unwrap(),
+        serde_json::to_string(&second).unwrap()
+    );
+
+    let unseeded = process_fight(&contender, &challenger, &config)
+        .await
+        .unwrap();
+    let seed = unseeded
+        .seed
+        .expect("Every pokemon fight should echo its seed");
+
+    let replayed = process_fight_seeded(&contender, &challenger, seed, &config)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        serde_json::to_string(&unseeded).unwrap(),
+        serde_json::to_string(&replayed).unwrap()
+    );
+}
+
+#[tokio::test]
+async fn test_every_strategy_picks_nothing_from_an_empty_team() {
+    let chart = chart();
+    let enemy = pokemon("Enemy", &chart.fire, None, 50, 50);
+    let enemy_team = vec![enemy.clone()];
+
+    for strategy in FightStrategy::ALL {
+        assert!(
+            strategy
+                .choose_pokemon(&[], None, &[], &mut picker_rng())
+                .await
+                .is_none()
+        );
+        assert!(
+            strategy
+                .choose_pokemon(&[], Some(&enemy), &enemy_team, &mut picker_rng())
+                .await
+                .is_none()
+        );
+    }
+}
+
+/// Solo outclasses both of Duo's pokemon, and Duo's first pokemon outclasses Weakling
+#[tokio::test]
+async fn test_trainer_fight_ends_with_one_winner() {
+    use crate::fight::trainer_fight::process_fight_with_rng;
+    use crate::fight::{FightEvent, FightLog};
+    use rand::{SeedableRng, rngs::StdRng};
+
+    let c = chart();
+    let winners = |log: &FightLog| -> Vec<(String, Vec<String>)> {
+        log.log
+            .iter()
+            .filter_map(|e| match e {
+                FightEvent::Winner {
+                    trainer,
+                    pokemon_left,
+                } => Some((trainer.clone(), pokemon_left.clone())),
+                _ => None,
+            })
+            .collect()
+    };
+
+    let solo = trainer("Solo", vec![fighter("Titan", &c.normal, 500, 300, 200)]);
+    let duo = trainer(
+        "Duo",
+        vec![
+            fighter("Mite", &c.normal, 50, 10, 50),
+            fighter("Gnat", &c.normal, 40, 5, 40),
+        ],
+    );
+
+    let log = process_fight_with_rng(
+        &solo,
+        &duo,
+        FightStrategy::StrongestAtk,
+        FightStrategy::StrongestAtk,
+        &FightConfig::default(),
+        &mut StdRng::seed_from_u64(3),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        winners(&log),
+        vec![("Solo".to_string(), vec!["Titan".to_string()])]
+    );
+    assert!(matches!(log.log.last(), Some(FightEvent::Winner { .. })));
+
+    let weakling = trainer("Weakling", vec![fighter("Runt", &c.normal, 30, 5, 10)]);
+    let pair = trainer(
+        "Pair",
+        vec![
+            fighter("Bruiser", &c.normal, 300, 200, 100),
+            fighter("Backup", &c.normal, 100, 50, 50),
+        ],
+    );
+
+    let log = process_fight_with_rng(
+        &weakling,
+        &pair,
+        FightStrategy::StrongestAtk,
+        FightStrategy::StrongestAtk,
+        &FightConfig::default(),
+        &mut StdRng::seed_from_u64(3),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        winners(&log),
+        vec![(
+            "Pair".to_string(),
+            vec!["Bruiser".to_string(), "Backup".to_string()]
+        )]
+    );
+    assert!(matches!(log.log.last(), Some(FightEvent::Winner { .. })));
+}
+
+/// Every run is won by exactly one pokemon, and the same seed gives the same summary
+#[tokio::test]
+async fn test_fight_batch_counts_every_run() {
+    use crate::fight::odds::{MAX_BATCH_RUNS, fight_batch};
+
+    let c = chart();
+    let a = fighter("BatchA", &c.normal, 300, 40, 60);
+    let b = fighter("BatchB", &c.fire, 300, 40, 50);
+    let config = FightConfig::default();
+
+    let summary = fight_batch(&a, &b, 500, 9, &config).await.unwrap();
+
+    assert_eq!(summary.runs, 500);
+    assert_eq!(summary.contender_wins + summary.challenger_wins, 500);
+    assert!((summary.contender_win_percentage - summary.contender_wins as f32 / 5.0).abs() < 0.01);
+    assert!(summary.average_turns >= 2.0);
+    assert!(summary.average_winner_hp > 0.0 && summary.average_winner_hp <= 300.0);
+
+    let again = fight_batch(&a, &b, 500, 9, &config).await.unwrap();
+    assert_eq!(again.contender_wins, summary.contender_wins);
+    assert_eq!(again.average_turns, summary.average_turns);
+
+    for runs in [0, MAX_BATCH_RUNS + 1] {
+        assert!(fight_batch(&a, &b, runs, 9, &config).await.is_err());
+    }
+}
+
+/// The streamed events are the events of the same seeded fight's log, in order
+#[tokio::test]
+async fn test_streamed_fight_matches_its_log() {
+    use crate::fight::pokemon_fight::{CombatantState, process_fight_with_hp_seeded, stream_fight};
+
+    let c = chart();
+    let contender = CombatantState::full(fighter("StreamA", &c.water, 200, 40, 60));
+    let challenger = CombatantState::full(fighter("StreamB", &c.fire, 200, 40, 50));
+    let config = FightConfig::default();
+
+    let log = process_fight_with_hp_seeded(&contender, &challenger, 12, &config)
+        .await
+        .unwrap();
+
+    let mut events = stream_fight(&contender, &challenger, 12, &config)
+        .await
+        .unwrap();
+    let mut streamed = vec![];
+    while let Some(event) = events.recv().await {
+        streamed.push(serde_json::to_value(event).unwrap());
+    }
+
+    assert!(!streamed.is_empty());
+    assert_eq!(
+        serde_json::Value::from(streamed),
+        serde_json::to_value(&log.log).unwrap()
+    );
+}
+
+/// The crit is rolled after the random multiplier, so with the same seed
+/// a forced crit only changes the damage, by the crit multiplier
+#[test]
+fn test_forced_crit_deals_one_and_a_half_times_the_damage() {
+    use crate::fight::FightEvent;
+    use crate::fight::pokemon_fight::{CRIT_MULTIPLIER, ResolvedPokemon, process_fight_resolved};
+    use rand::{SeedableRng, rngs::StdRng};
+
+    let c = chart();
+    let attacker = ResolvedPokemon::new(
+        fighter("Critter", &c.normal, 1000, 100, 90),
+        c.normal.clone(),
+        None,
+    );
+    let defender = ResolvedPokemon::new(
+        fighter("Dummy", &c.normal, 1000, 10, 10),
+        c.normal.clone(),
+        None,
+    );
+
+    let first_hit = |crit_chance| {
+        let config = FightConfig {
+            crit_chance,
+            ..FightConfig::default()
+        };
+        let log = process_fight_resolved(
+            &attacker,
+            &defender,
+            1000,
+            1000,
+            &config,
+            &mut StdRng::seed_from_u64(5),
+        );
+
+        match &log.log[0] {
+            FightEvent::Hit { damage, crit, .. } => (*damage, *crit),
+            other => panic!("Expected a hit, got {:?}", other),
+        }
+    };
+
+    let (normal, normal_crit) = first_hit(0.0);
+    let (critical, critical_crit) = first_hit(1.0);
+
+    assert!(!normal_crit);
+    assert!(critical_crit);
+    assert!((critical as f32 - normal as f32 * CRIT_MULTIPLIER).abs() <= 1.0);
+}
+
+/// Each doubling of the attacker's level makes its first hit deal more damage,
+/// and stats without a level are at the default level
+#[tokio::test]
+async fn test_doubling_level_increases_damage() {
+    use crate::fight::FightEvent;
+    use crate::fight::pokemon_fight::process_fight_deterministic;
+    use crate::pokemon::stats::{DEFAULT_LEVEL, PokemonStats};
+
+    let c = chart();
+    let defender = fighter("Target", &c.normal, 1000, 10, 10);
+
+    let mut damages = vec![];
+    for level in [25, 50, 100] {
+        let mut attacker = fighter("Climber", &c.normal, 100, 60, 90);
+        attacker.stats.level = level;
+
+        let log = process_fight_deterministic(&attacker, &defender, &FightConfig::default())
+            .await
+            .unwrap();
+
+        match &log.log[0] {
+            FightEvent::Hit {
+                attacker, damage, ..
+            } if attacker == "Climber" => damages.push(*damage),
+            other => panic!("Expected a hit from Climber, got {:?}", other),
+        }
+    }
+
+    assert_eq!(damages[1], 60);
+    assert!(damages[0] < damages[1]);
+    assert!(damages[1] < damages[2]);
+
+    let stats: PokemonStats =
+        serde_json::from_str(r#"{"hp": 10, "atk": 10, "def": 10, "agi": 10}"#).unwrap();
+    assert_eq!(stats.level, DEFAULT_LEVEL);
+    assert_eq!(stats.level_multiplier(), 1.0);
+}
+
+#[tokio::test]
+async fn test_paralysis_skips_turns_without_damage() {
+    use crate::fight::FightEvent;
+    use crate::fight::pokemon_fight::process_fight_seeded;
+    use crate::fight::status::StatusCondition;
+
+    let c = chart();
+    let spark = fighter("Spark", &c.electric, 1000, 10, 60);
+    let wave = fighter("Wave", &c.water, 1000, 10, 40);
+
+    let config = FightConfig {
+        status_chance: 1.0,
+        ..FightConfig::default()
+    };
+    let log = process_fight_seeded(&spark, &wave, 3, &config)
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        &log.log[1],
+        FightEvent::StatusApplied { pokemon, condition: StatusCondition::Paralysis } if pokemon == "Wave"
+    ));
+
+    let skips: Vec<_> = log
+        .log
+        .iter()
+        .filter_map(|e| match e {
+            FightEvent::Paralyzed { pokemon } => Some(pokemon.clone()),
+            _ => None,
+        })
+        .collect();
+    assert!(!skips.is_empty());
+    assert!(skips.iter().all(|p| p == "Wave"));
+
+    // paralysis only costs turns, never HP
+    assert!(
+        log.log
+            .iter()
+            .all(|e| !matches!(e, FightEvent::StatusDamage { .. }))
+    );
+}
+
+/// Glass is the frailest, Tank has the most HP and defense together, Bulky the most HP alone
+#[tokio::test]
+async fn test_lowest_hp_and_most_durable_picks() {
+    let c = chart();
+    let with = |name: &str, hp: u32, attack: u32, defense: u32| {
+        let mut pokemon = pokemon(name, &c.normal, None, attack, defense);
+        pokemon.stats.hp = hp;
+        pokemon
+    };
+    let team = vec![
+        with("Bulky", 200, 60, 50),
+        with("Glass", 40, 200, 30),
+        with("Tank", 150, 10, 120),
+    ];
+    let enemy = pokemon("Enemy", &c.fire, None, 50, 50);
+
+    for enemy in [None, Some(&enemy)] {
+        let enemy_team: Vec<Pokemon> = enemy.into_iter().cloned().collect();
+        let pick = |strategy: FightStrategy| {
+            let team = &team;
+            let enemy_team = &enemy_team;
+            async move {
+                strategy
+                    .choose_pokemon(team, enemy, enemy_team, &mut picker_rng())
+                    .await
+                    .map(|p| p.name)
+            }
+        };
+
+        assert_eq!(
+            pick(FightStrategy::LowestHp).await.as_deref(),
+            Some("Glass")
+        );
+        assert_eq!(
+            pick(FightStrategy::MostDurable).await.as_deref(),
+            Some("Tank")
+        );
+    }
+
+    // a tie on HP goes to the lowest stat total
+    let tied = vec![with("Frail", 40, 10, 10), with("Glass", 40, 200, 30)];
+    let pick = FightStrategy::LowestHp
+        .choose_pokemon(&tied, None, &[], &mut picker_rng())
+        .await;
+    assert_eq!(pick.map(|p| p.name).as_deref(), Some("Frail"));
+}
+
+#[test]
+fn test_fight_strategy_from_str() {
+    assert!(matches!(
+        "LowestHp".parse::<FightStrategy>(),
+        Ok(FightStrategy::LowestHp)
+    ));
+    assert!(matches!(
+        "MostDurable".parse::<FightStrategy>(),
+        Ok(FightStrategy::MostDurable)
+    ));
+    assert_eq!("Defensive".parse::<FightStrategy>().err(), Some(()));
+    assert_eq!("lowesthp".parse::<FightStrategy>().err(), Some(()));
+}
+
+/// Cannon (Bug) is weak against both enemy types and Icicle (Ice) against Fire,
+/// only the much weaker Plain (Normal) has no weaknesses
+#[tokio::test]
+async fn test_safest_switch_prefers_a_weak_safe_pokemon() {
+    let c = chart();
+    let team = vec![
+        pokemon("Cannon", &c.bug, None, 300, 100),
+        pokemon("Icicle", &c.ice, None, 150, 150),
+        pokemon("Plain", &c.normal, None, 20, 20),
+    ];
+    let enemy = enemy(&c);
+
+    let pick = FightStrategy::SafestSwitch
+        .choose_pokemon(
+            &team,
+            Some(&enemy),
+            std::slice::from_ref(&enemy),
+            &mut picker_rng(),
+        )
+        .await;
+    assert_eq!(pick.map(|p| p.name).as_deref(), Some("Plain"));
+
+    // the first pick falls back to the strongest sum
+    let first = FightStrategy::SafestSwitch
+        .choose_pokemon(&team, None, &[], &mut picker_rng())
+        .await;
+    assert_eq!(first.map(|p| p.name).as_deref(), Some("Cannon"));
+
+    // without a safe pokemon the one weak against fewer types is chosen
+    let risky = &team[..2];
+    let pick = FightStrategy::SafestSwitch
+        .choose_pokemon(
+            risky,
+            Some(&enemy),
+            std::slice::from_ref(&enemy),
+            &mut picker_rng(),
+        )
+        .await;
+    assert_eq!(pick.map(|p| p.name).as_deref(), Some("Icicle"));
+
+    assert!(matches!(
+        "SafestSwitch".parse::<FightStrategy>(),
+        Ok(FightStrategy::SafestSwitch)
+    ));
+}
+
+/// Titan outclasses both of Pair's pokemon
+#[tokio::test]
+async fn test_fight_log_reports_its_outcome() {
+    use crate::fight::pokemon_fight::process_fight_deterministic;
+    use crate::fight::trainer_fight::process_fight_with_rng;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    let c = chart();
+    let titan = fighter("Titan", &c.normal, 1000, 200, 60);
+    let minnow = fighter("Minnow", &c.normal, 100, 10, 40);
+
+    let log = process_fight_deterministic(&titan, &minnow, &FightConfig::default())
+        .await
+        .unwrap();
+    assert_eq!(log.winner.as_deref(), Some("Titan"));
+    assert_eq!(log.total_rounds as usize, log.turns());
+    assert!(log.total_rounds > 0);
+    assert!(matches!(log.winner_hp_left, Some(hp) if hp > 0 && hp <= 1000));
+
+    let json = serde_json::to_value(&log).unwrap();
+    assert_eq!(json["winner"], "Titan");
+    assert_eq!(json["winner_hp_left"], log.winner_hp_left.unwrap());
+
+    let solo = trainer("Solo", vec![titan]);
+    let pair = trainer(
+        "Pair",
+        vec![minnow, fighter("Guppy", &c.normal, 100, 10, 40)],
+    );
+    let mut rng = StdRng::seed_from_u64(1);
+    let log = process_fight_with_rng(
+        &pair,
+        &solo,
+        FightStrategy::StrongestSum,
+        FightStrategy::StrongestSum,
+        &FightConfig::default(),
+        &mut rng,
+    )
+    .await
+    .unwrap();
+    assert_eq!(log.winner.as_deref(), Some("Solo"));
+    assert_eq!(log.total_rounds as usize, log.turns());
+    assert!(log.winner_hp_left.is_some());
+}
+
+#[tokio::test]
+async fn test_tournament_standings_add_up() {
+    use crate::fight::round_robin::{MAX_TRAINERS, round_robin};
+
+    let c = chart();
+    let entries: Vec<_> = [("Ash", 200), ("Misty", 40), ("Brock", 20)]
+        .iter()
+        .map(|(name, attack)| {
+            let team = vec![
+                fighter(&format!("{name}One"), &c.normal, 100, *attack, 50),
+                fighter(&format!("{name}Two"), &c.normal, 100, *attack, 50),
+            ];
+            (trainer(name, team), FightStrategy::StrongestSum)
+        })
+        .collect();
+
+    let league = round_robin(&entries, false, &FightConfig::default())
+        .await
+        .unwrap();
+
+    assert_eq!(league.fixtures.len(), 3);
+    assert_eq!(league.table.iter().map(|s| s.wins).sum::<usize>(), 3);
+    assert_eq!(league.table.iter().map(|s| s.losses).sum::<usize>(), 3);
+    assert_eq!(
+        league.table.iter().map(|s| s.pokemon_left).sum::<usize>(),
+        league
+            .fixtures
+            .iter()
+            .map(|f| f.pokemon_left)
+            .sum::<usize>()
+    );
+    assert!(
+        league
+            .fixtures
+            .iter()
+            .all(|f| (1..=2).contains(&f.pokemon_left))
+    );
+
+    // the strongest team wins both its fixtures
+    assert_eq!(league.table[0].trainer, "Ash");
+    assert_eq!(league.table[0].wins, 2);
+
+    let crowd: Vec<_> = (0..=MAX_TRAINERS)
+        .map(|i| {
+            (
+                trainer(&format!("T{i}"), vec![]),
+                FightStrategy::StrongestSum,
+            )
+        })
+        .collect();
+    let error = round_robin(&crowd, false, &FightConfig::default())
+        .await
+        .expect_err("too many trainers should be refused");
+    assert!(error.to_string().contains("at most 16 trainers"));
+}
+
+#[tokio::test]
+async fn test_zero_accuracy_always_misses() {
+    use crate::fight::FightEvent;
+    use crate::fight::pokemon_fight::process_fight_seeded;
+
+    let c = chart();
+    let mut blind = fighter("Blind", &c.normal, 100, 200, 80);
+    blind.stats.accuracy = 0;
+    let steady = fighter("Steady", &c.normal, 100, 30, 20);
+
+    for seed in 0..10 {
+        let log = process_fight_seeded(&blind, &steady, seed, &FightConfig::default())
+            .await
+            .unwrap();
+
+        // the faster pokemon swings first and misses every time, never dealing damage
+        assert!(matches!(
+            &log.log[0],
+            FightEvent::Missed { attacker, defender } if attacker == "Blind" && defender == "Steady"
+        ));
+        assert!(log.log.iter().all(|e| !matches!(
+            e,
+            FightEvent::Hit { attacker, .. } if attacker == "Blind"
+        )));
+        assert_eq!(log.winner.as_deref(), Some("Steady"));
+
+        // a missed turn still passes the turn to the opponent
+        let blind_turns = log
+            .log
+            .iter()
+            .filter(|e| matches!(e, FightEvent::Missed { .. }))
+            .count();
+        let steady_turns = log
+            .log
+            .iter()
+            .filter(|e| matches!(e, FightEvent::Hit { .. }))
+            .count();
+        assert_eq!(blind_turns, steady_turns);
+        assert_eq!(log.turns(), blind_turns + steady_turns);
+    }
+
+    // accuracy is out of range above 100, and a pokemon that can never hit is refused
+    blind.stats.accuracy = 101;
+    assert!(blind.stats.validate().is_err());
+    blind.stats.accuracy = 0;
+    assert!(blind.stats.validate().is_err());
+}
+
+/// Without randomness every roll is `0.5`, so two pokemon with less than 50 accuracy never hit each other,
+/// the fight stops at the turn limit as a draw instead of running forever
+#[tokio::test]
+async fn test_fight_of_pokemon_that_never_hit_ends_at_turn_limit() {
+    use crate::fight::FightEvent;
+    use crate::fight::pokemon_fight::{MAX_FIGHT_TURNS, process_fight_deterministic};
+
+    let c = chart();
+    let mut left = fighter("Left", &c.normal, 100, 50, 60);
+    let mut right = fighter("Right", &c.normal, 100, 50, 40);
+    left.stats.accuracy = 40;
+    right.stats.accuracy = 1;
+
+    let log = process_fight_deterministic(&left, &right, &FightConfig::default())
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        log.log.last(),
+        Some(FightEvent::TurnLimit { turns }) if *turns == MAX_FIGHT_TURNS
+    ));
+    assert_eq!(log.turns(), MAX_FIGHT_TURNS as usize);
+    assert_eq!(log.winner, None);
+}
@@ -0,0 +1,144 @@
+#[allow(unused_imports)]
+use crate::idempotency::{IdempotencyCache, IdempotencyKey};
+#[allow(unused_imports)]
+use crate::json::{ApiError, JsonResult, JsonStatus};
+#[allow(unused_imports)]
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[allow(unused_imports)]
+use std::time::Duration;
+
+/// Behaves like a create endpoint: the first call succeeds, later calls fail as duplicates
+#[allow(unused)]
+async fn create_once(created: &AtomicUsize) -> JsonResult<'static> {
+    if created.fetch_add(1, Ordering::SeqCst) > 0 {
+        return Err(ApiError::conflict("Pokemon already exists"));
+    }
+
+    Ok(JsonStatus::data_owned(vec!["created"]))
+}
+
+#[allow(unused)]
+fn to_json(result: JsonResult<'static>) -> String {
+    let status = match result {
+        Ok(status) => status,
+        Err(error) => JsonStatus::from(error),
+    };
+    serde_json::to_string(&status).unwrap()
+}
+
+#[tokio::test]
+async fn test_same_key_replays_result() {
+    let cache = IdempotencyCache::new(Duration::from_secs(60));
+    let created = AtomicUsize::new(0);
+    let key = IdempotencyKey::new(Some("POST /api/pokemons abc".to_string()));
+
+    let first = cache.run(&key, create_once(&created)).await;
+    assert!(first.is_ok());
+    let second = cache.run(&key, create_once(&created)).await;
+    assert!(second.is_ok());
+
+    assert_eq!(created.load(Ordering::SeqCst), 1);
+    assert_eq!(
+        to_json(cache.run(&key, create_once(&created)).await),
+        r#"{"status":"Ok","data":["created"]}"#
+    );
+}
+
+#[tokio::test]
+async fn test_without_key_runs_every_time() {
+    let cache = IdempotencyCache::new(Duration::from_secs(60));
+    let created = AtomicUsize::new(0);
+    let key = IdempotencyKey::new(None);
+
+    assert!(cache.run(&key, create_once(&created)).await.is_ok());
+    assert!(cache.run(&key, create_once(&created)).await.is_err());
+}
+
+#[tokio::test]
+async fn test_expired_key_runs_again() {
+    let cache = IdempotencyCache::new(Duration::ZERO);
+    let created = AtomicUsize::new(0);
+    let key = IdempotencyKey::new(Some("POST /api/pokemons abc".to_string()));
+
+    assert!(cache.run(&key, create_once(&created)).await.is_ok());
+    assert!(cache.run(&key, create_once(&created)).await.is_err());
+}
+
+#[tokio::test]
+async fn test_concurrent_retry_is_refused_while_in_flight() {
+    use rocket::http::Status;
+    use rocket::tokio::sync::oneshot;
+
+    let cache = IdempotencyCache::new(Duration::from_secs(60));
+    let created = AtomicUsize::new(0);
+    let key = IdempotencyKey::new(Some("POST /api/pokemons abc".to_string()));
+    let (finish, finished) = oneshot::channel::<()>();
+
+    // the first request is still running when the retry arrives
+    let first = cache.run(&key, async {
+        finished.await.unwrap();
+        create_once(&created).await
+    });
+    let retry = async {
+        let result = cache.run(&key, create_once(&created)).await;
+        finish.send(()).unwrap();
+        result
+    };
+
+    let (first, retry) = rocket::tokio::join!(first, retry);
+
+    assert!(first.is_ok());
+    assert_eq!(retry.err().map(|e| e.code()), Some(Status::Conflict));
+    assert_eq!(created.load(Ordering::SeqCst), 1);
+
+    // once the first request is done, retries get its result
+    assert_eq!(
+        to_json(cache.run(&key, create_once(&created)).await),
+        r#"{"status":"Ok","data":["created"]}"#
+    );
+}
+
+#[tokio::test]
+async fn test_failed_request_frees_its_key() {
+    let cache = IdempotencyCache::new(Duration::from_secs(60));
+    let key = IdempotencyKey::new(Some("POST /api/pokemons abc".to_string()));
+
+    let failed = cache
+        .run(&key, async {
+            Err(ApiError::internal("The database is down"))
+        })
+        .await;
+    assert!(failed.is_err());
+
+    let created = AtomicUsize::new(0);
+    assert!(cache.run(&key, create_once(&created)).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_key_reused_with_another_body_is_refused() {
+    use rocket::http::Status;
+
+    let cache = IdempotencyCache::new(Duration::from_secs(60));
+    let created = AtomicUsize::new(0);
+    let key = |body: &str| {
+        IdempotencyKey::new(Some("POST /api/pokemons abc".to_string())).with_body(&body)
+    };
+
+    assert!(
+        cache
+            .run(&key("Pikachu"), create_once(&created))
+            .await
+            .is_ok()
+    );
+
+    let reused = cache.run(&key("Bulbasaur"), create_once(&created)).await;
+    assert_eq!(reused.err().map(|e| e.code()), Some(Status::BadRequest));
+    assert_eq!(created.load(Ordering::SeqCst), 1);
+
+    assert!(
+        cache
+            .run(&key("Pikachu"), create_once(&created))
+            .await
+            .is_ok()
+    );
+}
@@ -145,3 +145,93 @@ fn test_into_raw_json() {
     assert!(json_str.contains("\"field1\":\"raw json test\""));
     assert!(json_str.contains("\"field2\":400"));
 }
+
+#[test]
+fn test_http_codes() {
+    use rocket::http::Status as HttpStatus;
+
+    assert_eq!(JsonStatus::ok(None::<String>).code(), HttpStatus::Ok);
+    assert_eq!(JsonStatus::error("bad").code(), HttpStatus::BadRequest);
+    assert_eq!(
+        JsonStatus::error("taken")
+            .with_code(HttpStatus::Conflict)
+            .code(),
+        HttpStatus::Conflict
+    );
+
+    // the code isn't part of the body
+    let json = serde_json::to_string(&JsonStatus::error("missing").with_code(HttpStatus::NotFound))
+        .unwrap();
+    assert_eq!(json, "{\"status\":{\"Error\":\"missing\"},\"data\":[]}");
+}
+
+/// Fails with the `ApiError` variant named `kind`
+#[allow(unused)]
+#[rocket::get("/<kind>")]
+fn fail_with(kind: &str) -> crate::json::JsonResult<'static> {
+    use crate::json::ApiError;
+
+    Err(match kind {
+        "not_found" => ApiError::not_found("Trainer not found"),
+        "bad_request" => ApiError::bad_request("Invalid strategy"),
+        "conflict" => ApiError::conflict("Trainer already exists"),
+        "too_large" => ApiError::payload_too_large("Too many items"),
+        _ => ApiError::internal("The database is down"),
+    })
+}
+
+#[test]
+fn test_api_error_codes() {
+    use rocket::http::Status as HttpStatus;
+    use rocket::local::blocking::Client;
+
+    let rocket = rocket::build().mount("/", rocket::routes![fail_with]);
+    let client = Client::tracked(rocket).expect("Failed to create client");
+
+    for (kind, code, message) in [
+        ("not_found", HttpStatus::NotFound, "Trainer not found"),
+        ("bad_request", HttpStatus::BadRequest, "Invalid strategy"),
+        ("conflict", HttpStatus::Conflict, "Trainer already exists"),
+        ("too_large", HttpStatus::PayloadTooLarge, "Too many items"),
+        ("internal", HttpStatus::InternalServerError, "The database is down"),
+    ] {
+        let response = client.get(format!("/{kind}")).dispatch();
+        assert_eq!(response.status(), code, "{kind}");
+
+        // the body keeps the envelope of every response
+        let json: serde_json::Value =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(json["status"]["Error"], message);
+        assert_eq!(json["data"], serde_json::json!([]));
+    }
+}
+
+#[test]
+fn test_api_error_from_anyhow() {
+    use crate::database::error::NotFound;
+    use crate::json::ApiError;
+
+    let not_found = anyhow::Error::new(NotFound {
+        kind: "Trainer".to_string(),
+        identifier: "Ash".to_string(),
+    });
+    assert!(matches!(
+        ApiError::from_anyhow(not_found),
+        ApiError::NotFound(_)
+    ));
+
+    let database_down = anyhow::Error::new(std::io::Error::from(
+        std::io::ErrorKind::ConnectionRefused,
+    ))
+    .context("while connecting to the database");
+    assert!(matches!(
+        ApiError::from_anyhow(database_down),
+        ApiError::Internal(_)
+    ));
+
+    let bad_input = anyhow::anyhow!("A pokemon can't fight itself");
+    assert!(matches!(
+        ApiError::from_anyhow(bad_input),
+        ApiError::BadRequest(_)
+    ));
+}
@@ -0,0 +1,38 @@
+#[allow(unused_imports)]
+use log::{Level, Log, Metadata};
+
+#[allow(unused_imports)]
+use crate::logging::ReloadableLogger;
+
+#[allow(unused)]
+fn debug_line(target: &str) -> Metadata<'_> {
+    Metadata::builder()
+        .level(Level::Debug)
+        .target(target)
+        .build()
+}
+
+#[test]
+fn test_set_log_filter() {
+    let logger = ReloadableLogger::new("info").unwrap();
+
+    assert!(!logger.enabled(&debug_line("pokemon_simulator::database::get")));
+    assert!(logger.enabled(&Metadata::builder().level(Level::Info).build()));
+
+    logger
+        .set_filter("info,pokemon_simulator::database=debug")
+        .unwrap();
+
+    assert!(logger.enabled(&debug_line("pokemon_simulator::database::get")));
+    assert!(!logger.enabled(&debug_line("pokemon_simulator::fight")));
+
+    // an invalid filter keeps the current one
+    assert!(logger.set_filter("info,pokemon_simulator=loud").is_err());
+    assert_eq!(logger.filter(), "info,pokemon_simulator::database=debug");
+    assert!(logger.enabled(&debug_line("pokemon_simulator::database::get")));
+
+    logger.set_filter("warn").unwrap();
+
+    assert!(!logger.enabled(&debug_line("pokemon_simulator::database::get")));
+    assert!(!logger.enabled(&Metadata::builder().level(Level::Info).build()));
+}
@@ -3,6 +3,14 @@ mod json;
 
 mod database;
 
+mod fight;
+
+mod idempotency;
+
+mod config;
+
+mod logging;
+
 // TODO: Add mock database impl to test this on?
 // mod pokemon;
 
@@ -1,22 +1,56 @@
+use rocket::State;
+
 use crate::{
+    config::ApiConfig,
     database::{
+        DbRepr,
         delete::DbDelete,
-        get::DbGet,
+        get::{DEFAULT_PAGE_LIMIT, DbGet},
         link::DbLink,
         promise::{MaybePromise, Promised},
         put::DbPut,
+        suggest,
+        update::DbUpdate,
     },
-    json::{self, JsonResult, JsonStatus},
+    fight::{self, FightStrategy},
+    idempotency::{IdempotencyCache, IdempotencyKey},
+    json::{self, ApiError, JsonResult, JsonStatus},
     pokemon::Pokemon,
     trainer::{self, Trainer},
 };
 
-/// Endpoint for getting a list of all trainers
-#[get("/trainers")]
-pub async fn get_trainers<'a>() -> JsonResult<'a> {
+/// Endpoint for getting a page of the list of all trainers, ordered by name, with their teams resolved
+/// `limit` defaults to 50 and is capped, the page comes with the total number of trainers
+#[get("/trainers?<skip>&<limit>")]
+pub async fn get_trainers<'a>(
+    skip: Option<u64>,
+    limit: Option<u64>,
+    api_config: &State<ApiConfig>,
+) -> JsonResult<'a> {
     info!("Request to /api/trainers");
-    let trainers = Trainer::get_all().await.map_err(JsonStatus::from_anyhow)?;
-    Ok(JsonStatus::data_owned(trainers))
+    let page = Trainer::get_page_with_teams(skip.unwrap_or(0), limit.unwrap_or(DEFAULT_PAGE_LIMIT))
+        .await
+        .map_err(ApiError::from_anyhow)?;
+    api_config.check_page_len(page.items.len())?;
+    Ok(JsonStatus::data_owned(page))
+}
+
+/// Endpoint for counting the trainers, without listing them.
+#[get("/trainers/count")]
+pub async fn count_trainers<'a>() -> JsonResult<'a> {
+    info!("Request to /api/trainers/count");
+    let count = Trainer::count().await.map_err(ApiError::from_anyhow)?;
+    Ok(JsonStatus::data_owned(count))
+}
+
+/// Endpoint for the number of Pokemon a trainer owns, without fetching its team.
+#[get("/trainers/<trainer_name>/team_size")]
+pub async fn get_team_size<'a>(trainer_name: String) -> JsonResult<'a> {
+    info!("Request to /api/trainers/{}/team_size", trainer_name);
+    let team_size = Trainer::count_linked_by_id(&trainer::Relationship::Owns, &trainer_name)
+        .await
+        .map_err(ApiError::from_anyhow)?;
+    Ok(JsonStatus::data_owned(team_size))
 }
 
 /// Endpoint for getting a list of all Pokemon owned by a trainer.
@@ -24,75 +58,146 @@ pub async fn get_trainers<'a>() -> JsonResult<'a> {
 pub async fn get_trainer_pokemons<'a>(trainer_name: String) -> JsonResult<'a> {
     info!("Request to /api/trainer_pokemons/{}", trainer_name);
 
-    let mut trainer = match Trainer::get_first(&trainer_name).await {
-        Ok(trainer) => trainer,
-        Err(_) => return Err(JsonStatus::error("Trainer not found")),
-    };
+    let mut trainer = suggest::find::<Trainer>("Trainer", &trainer_name).await?;
 
     // resolve the trainer's pokemons
     for p in &mut trainer.team {
-        *p = MaybePromise::from_concrete(
-            p.clone().resolve().await.map_err(JsonStatus::from_anyhow)?,
-        );
+        *p = MaybePromise::from_concrete(p.clone().resolve().await.map_err(ApiError::from_anyhow)?);
     }
 
     Ok(JsonStatus::data_owned(trainer.team))
 }
 
+/// Endpoint for comparing what every strategy would pick from a trainer's team
+/// against the same enemy pokemon, or as the opening pick if `vs` is not given.
+#[get("/trainers/<trainer_name>/strategy_picks?<vs>")]
+pub async fn get_strategy_picks<'a>(trainer_name: String, vs: Option<String>) -> JsonResult<'a> {
+    info!("Request to /api/trainers/{}/strategy_picks", trainer_name);
+
+    let trainer = suggest::find::<Trainer>("Trainer", &trainer_name).await?;
+
+    if trainer.team.is_empty() {
+        return Err(ApiError::bad_request("Trainer has no pokemon to pick from"));
+    }
+
+    let enemy = match vs {
+        Some(vs) => Some(suggest::find::<Pokemon>("Pokemon", &vs).await?),
+        None => None,
+    };
+
+    let team = futures::future::try_join_all(trainer.team.iter().map(|p| p.clone().resolve()))
+        .await
+        .map_err(ApiError::from_anyhow)?;
+
+    let picks = fight::strategy_picks(&team, enemy.as_ref()).await;
+
+    Ok(JsonStatus::data_owned(picks))
+}
+
 /// Endpoint for creating a new trainer.
 #[post("/trainer_pokemons/<trainer_name>")]
-pub async fn create_trainer<'a>(trainer_name: String) -> JsonResult<'a> {
+pub async fn create_trainer<'a>(
+    trainer_name: String,
+    key: IdempotencyKey,
+    idempotency: &State<IdempotencyCache>,
+) -> JsonResult<'a> {
     info!("Request to /api/trainer_pokemons/{}", trainer_name);
 
-    if trainer_name.len() > 30 {
-        return Err(JsonStatus::error("Name is too long"));
-    }
+    idempotency
+        .run(&key, async move {
+            if trainer_name.len() > 30 {
+                return Err(ApiError::bad_request("Name is too long"));
+            }
 
-    if trainer_name.is_empty() {
-        return Err(JsonStatus::error("Name cannot be empty"));
-    }
+            if trainer_name.is_empty() {
+                return Err(ApiError::bad_request("Name cannot be empty"));
+            }
 
-    // do not allow duplicate trainers
-    if Trainer::get_first(&trainer_name).await.is_ok() {
-        return Err(JsonStatus::error("Trainer already exists"));
-    }
+            // do not allow duplicate trainers
+            if Trainer::exists(&trainer_name)
+                .await
+                .map_err(ApiError::from_anyhow)?
+            {
+                return Err(ApiError::conflict("Trainer already exists"));
+            }
 
-    let trainer = Trainer {
-        name: trainer_name,
-        team: vec![],
-    };
+            let trainer = Trainer {
+                name: trainer_name,
+                team: vec![],
+                strategy: None,
+            };
 
-    trainer
-        .put_self_only() // no need for relationships since the team is empty
-        .await
-        .map_err(JsonStatus::from_anyhow)?;
+            trainer
+                .put_self_only() // no need for relationships since the team is empty
+                .await
+                .map_err(ApiError::from_anyhow)?;
 
-    Ok(JsonStatus::new_empty(json::Status::Ok))
+            Ok(JsonStatus::new_empty(json::Status::Ok))
+        })
+        .await
 }
 
 /// Endpoint for deleting a trainer.
 #[delete("/trainer_pokemons/<trainer_name>")]
-pub async fn delete_trainer<'a>(trainer_name: String) -> JsonResult<'a> {
+pub async fn delete_trainer<'a>(
+    trainer_name: String,
+    key: IdempotencyKey,
+    idempotency: &State<IdempotencyCache>,
+) -> JsonResult<'a> {
     info!("Request to /api/trainer_pokemons/{}", trainer_name);
 
-    let mut trainer = match Trainer::get_first(&trainer_name).await {
-        Ok(trainer) => trainer,
-        Err(_) => return Err(JsonStatus::error("Trainer not found")),
-    };
+    idempotency
+        .run(&key, async move {
+            let mut trainer = suggest::find::<Trainer>("Trainer", &trainer_name).await?;
 
-    // remove all links first
-    for p in trainer.team.clone() {
-        trainer
-            .unlink_from(&p, &trainer::Relationship::Owns)
-            .await
-            .map_err(JsonStatus::from_anyhow)?;
-    }
+            // remove all links first
+            for p in trainer.team.clone() {
+                trainer
+                    .unlink_from(&p, &trainer::Relationship::Owns)
+                    .await
+                    .map_err(ApiError::from_anyhow)?;
+            }
+
+            Trainer::delete(&trainer.name)
+                .await
+                .map_err(ApiError::from_anyhow)?;
 
-    Trainer::delete(&trainer.name)
+            Ok(JsonStatus::new_empty(json::Status::Ok))
+        })
         .await
-        .map_err(JsonStatus::from_anyhow)?;
+}
+
+/// Endpoint for setting the strategy a trainer fights with when no strategy is given.
+#[put("/trainer_pokemons/<trainer_name>/strategy/<strategy>")]
+pub async fn set_trainer_strategy<'a>(
+    trainer_name: String,
+    strategy: String,
+    key: IdempotencyKey,
+    idempotency: &State<IdempotencyCache>,
+) -> JsonResult<'a> {
+    info!(
+        "Request to /api/trainer_pokemons/{}/strategy/{}",
+        trainer_name, strategy
+    );
+
+    idempotency
+        .run(&key, async move {
+            let strategy: FightStrategy = strategy
+                .parse()
+                .map_err(|_| ApiError::bad_request("Invalid strategy"))?;
+
+            let mut trainer = suggest::find::<Trainer>("Trainer", &trainer_name).await?;
 
-    Ok(JsonStatus::new_empty(json::Status::Ok))
+            trainer.strategy = Some(strategy);
+
+            trainer
+                .update(trainer.get_raw_identifier())
+                .await
+                .map_err(ApiError::from_anyhow)?;
+
+            Ok(JsonStatus::new_empty(json::Status::Ok))
+        })
+        .await
 }
 
 /// Endpoint for adding a Pokemon to a trainer's team.
@@ -100,42 +205,47 @@ pub async fn delete_trainer<'a>(trainer_name: String) -> JsonResult<'a> {
 pub async fn add_pokemon_to_trainer<'a>(
     trainer_name: String,
     pokemon_name: String,
+    key: IdempotencyKey,
+    idempotency: &State<IdempotencyCache>,
 ) -> JsonResult<'a> {
     info!(
         "Request to /api/trainer_pokemons/{}/{}",
         trainer_name, pokemon_name
     );
 
-    let mut trainer = match Trainer::get_first(&trainer_name).await {
-        Ok(trainer) => trainer,
-        Err(_) => return Err(JsonStatus::error("Trainer not found")),
-    };
+    idempotency
+        .run(&key, async move {
+            let mut trainer = suggest::find::<Trainer>("Trainer", &trainer_name).await?;
 
-    let pokemon = match Pokemon::get_first(&pokemon_name).await {
-        Ok(pokemon) => pokemon,
-        Err(_) => return Err(JsonStatus::error("Pokemon not found")),
-    };
+            let pokemon = suggest::find::<Pokemon>("Pokemon", &pokemon_name).await?;
 
-    for p in &trainer.team {
-        if p.ident() == pokemon.name {
-            return Err(JsonStatus::error("Pokemon already in team"));
-        }
-    }
+            for p in &trainer.team {
+                if p.ident() == pokemon.name {
+                    return Err(ApiError::conflict("Pokemon already in team"));
+                }
+            }
 
-    // do not allow more than 6 pokemon in a team
-    if trainer.team.len() >= 6 {
-        return Err(JsonStatus::error("Team is full"));
-    }
+            // do not allow more than 6 pokemon in a team,
+            // counted in the database so no team member gets resolved
+            let team_size = trainer
+                .link_count(&trainer::Relationship::Owns)
+                .await
+                .map_err(ApiError::from_anyhow)?;
+            if team_size >= trainer::MAX_TEAM_SIZE as u64 {
+                return Err(ApiError::conflict("Team is full"));
+            }
 
-    trainer
-        .link_to(
-            &MaybePromise::from_promise(pokemon.as_promise()),
-            &trainer::Relationship::Owns,
-        )
-        .await
-        .map_err(JsonStatus::from_anyhow)?;
+            trainer
+                .link_to(
+                    &MaybePromise::from_promise(pokemon.as_promise()),
+                    &trainer::Relationship::Owns,
+                )
+                .await
+                .map_err(ApiError::from_anyhow)?;
 
-    Ok(JsonStatus::new_empty(json::Status::Ok))
+            Ok(JsonStatus::new_empty(json::Status::Ok))
+        })
+        .await
 }
 
 /// Endpoint for removing a Pokemon from a trainer's team.
@@ -143,34 +253,37 @@ pub async fn add_pokemon_to_trainer<'a>(
 pub async fn remove_pokemon_from_trainer<'a>(
     trainer_name: String,
     pokemon_name: String,
+    key: IdempotencyKey,
+    idempotency: &State<IdempotencyCache>,
 ) -> JsonResult<'a> {
     info!(
         "Request to /api/trainer_pokemons/{}/{}",
         trainer_name, pokemon_name
     );
 
-    let mut trainer = match Trainer::get_first(&trainer_name).await {
-        Ok(trainer) => trainer,
-        Err(_) => return Err(JsonStatus::error("Trainer not found")),
-    };
+    idempotency
+        .run(&key, async move {
+            let mut trainer = suggest::find::<Trainer>("Trainer", &trainer_name).await?;
 
-    let mut unlink_pokemon = None;
-    for p in &trainer.team {
-        if p.ident() == pokemon_name {
-            unlink_pokemon = Some(p.clone());
-            break;
-        }
-    }
+            let mut unlink_pokemon = None;
+            for p in &trainer.team {
+                if p.ident() == pokemon_name {
+                    unlink_pokemon = Some(p.clone());
+                    break;
+                }
+            }
 
-    match unlink_pokemon {
-        Some(p) => {
-            let rel = trainer::Relationship::Owns;
-            trainer
-                .unlink_from(&p, &rel)
-                .await
-                .map_err(JsonStatus::from_anyhow)?;
-            Ok(JsonStatus::new_empty(json::Status::Ok))
-        }
-        None => Err(JsonStatus::error("Pokemon not found in team")),
-    }
+            match unlink_pokemon {
+                Some(p) => {
+                    let rel = trainer::Relationship::Owns;
+                    trainer
+                        .unlink_from(&p, &rel)
+                        .await
+                        .map_err(ApiError::from_anyhow)?;
+                    Ok(JsonStatus::new_empty(json::Status::Ok))
+                }
+                None => Err(ApiError::not_found("Pokemon not found in team")),
+            }
+        })
+        .await
 }
@@ -7,12 +7,22 @@ use anyhow::Result;
 
 use crate::{
     database::{
-        AsDbString, DbRepr, delete::DbDelete, get::DbGet, link::DbLink, promise::MaybePromise,
-        put::DbPut, sanitize,
+        AsDbString, DbHandle, DbQuery, DbRepr, DbValue, QueryExecutor,
+        delete::DbDelete,
+        get::{DbGet, Page, check_page_limit},
+        link::DbLink,
+        promise::MaybePromise,
+        put::DbPut,
+        sanitize,
+        update::DbUpdate,
     },
+    fight::FightStrategy,
     pokemon::Pokemon,
 };
 
+/// The maximum number of pokemon in a trainer's team
+pub const MAX_TEAM_SIZE: usize = 6;
+
 /// Represents a Pokémon trainer with a name and a team of Pokémon
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Trainer {
@@ -20,6 +30,120 @@ pub struct Trainer {
     pub name: String,
     /// The team of Pokemon owned by the trainer
     pub team: Vec<MaybePromise<Pokemon>>,
+    /// The strategy the trainer prefers to fight with, `None` if they never picked one
+    #[serde(default)]
+    pub strategy: Option<FightStrategy>,
+}
+
+impl Trainer {
+    /// The strategy the trainer fights with when none is given, `StrongestSum` if they never picked one
+    pub fn strategy(&self) -> FightStrategy {
+        self.strategy.clone().unwrap_or(FightStrategy::StrongestSum)
+    }
+
+    /// Reads the preferred strategy of a trainer node,
+    /// trainers stored before strategies were kept have no strategy field
+    fn strategy_from_node(node: &neo4rs::Node) -> Option<FightStrategy> {
+        node.get::<String>("strategy")
+            .ok()
+            .and_then(|strategy| strategy.parse().ok())
+    }
+
+    /// Gets all trainers with their teams resolved, using a single query
+    /// instead of one query per trainer and team member
+    pub async fn get_all_with_teams() -> Result<Vec<Trainer>> {
+        Self::get_all_with_teams_on(&DbHandle::connect().await?).await
+    }
+
+    /// Same as `get_all_with_teams`, running the query on the given executor
+    pub async fn get_all_with_teams_on(executor: &impl QueryExecutor) -> Result<Vec<Trainer>> {
+        let query = DbQuery::new(
+            "MATCH (t:Trainer) \
+            OPTIONAL MATCH (t)-[:Owns]->(p:Pokemon)-[:PrimaryType]->(pt:PokemonType) \
+            OPTIONAL MATCH (p)-[:SecondaryType]->(st:PokemonType) \
+            RETURN t, p, pt, st ORDER BY t.name;",
+        );
+
+        debug!("GetAll With Teams Query: {:?}", query);
+
+        let trainers = Self::query_with_teams(executor, query).await?;
+
+        debug!("GetAll With Teams Result Count: {}", trainers.len());
+
+        Ok(trainers)
+    }
+
+    /// Same as `get_all_with_teams`, but only gets at most `limit` trainers, ordered by name,
+    /// after skipping the first `skip`, along with the total number of trainers
+    /// `limit` must be between 1 and `MAX_PAGE_LIMIT`
+    pub async fn get_page_with_teams(skip: u64, limit: u64) -> Result<Page<Trainer>> {
+        check_page_limit(limit)?;
+
+        let total = Self::count().await?;
+
+        // the page is cut before the teams are matched, so it counts trainers rather than rows
+        let query = DbQuery::new(
+            "MATCH (t:Trainer) WITH t ORDER BY t.name SKIP $skip LIMIT $limit \
+            OPTIONAL MATCH (t)-[:Owns]->(p:Pokemon)-[:PrimaryType]->(pt:PokemonType) \
+            OPTIONAL MATCH (p)-[:SecondaryType]->(st:PokemonType) \
+            RETURN t, p, pt, st ORDER BY t.name;",
+        )
+        .param("skip", skip)
+        .param("limit", limit);
+
+        debug!("GetPage With Teams Query: {:?}", query);
+
+        Ok(Page {
+            total,
+            skip,
+            limit,
+            items: Self::query_with_teams(&DbHandle::connect().await?, query).await?,
+            warnings: vec![],
+        })
+    }
+
+    /// Runs a query returning a trainer `t`, one of its pokemon `p` with the types `pt` and `st` per row,
+    /// ordered by trainer, and groups the rows into trainers
+    async fn query_with_teams(
+        executor: &impl QueryExecutor,
+        query: DbQuery,
+    ) -> Result<Vec<Trainer>> {
+        let mut trainers: Vec<Trainer> = vec![];
+
+        // rows are ordered by trainer, one row per owned pokemon
+        // (or a single row with no pokemon for an empty team)
+        for row in executor.fetch_all(query).await? {
+            let trainer_node = row.get::<neo4rs::Node>("t")?;
+            let name = trainer_node.get::<String>("name")?;
+
+            if trainers.last().is_none_or(|t| t.name != name) {
+                trainers.push(Trainer {
+                    name,
+                    team: vec![],
+                    strategy: Self::strategy_from_node(&trainer_node),
+                });
+            }
+
+            let pokemon_node = row.get::<Option<neo4rs::Node>>("p")?;
+            let primary_type = row.get::<Option<neo4rs::Node>>("pt")?;
+
+            if let Some(pokemon_node) = pokemon_node
+                && let Some(primary_type) = primary_type
+            {
+                let secondary_type = row.get::<Option<neo4rs::Node>>("st")?;
+                let pokemon = Pokemon::from_db_nodes(&pokemon_node, primary_type, secondary_type)?;
+
+                // trainers was pushed to above
+                trainers
+                    .last_mut()
+                    .unwrap()
+                    .team
+                    .push(MaybePromise::from_concrete(pokemon));
+            }
+        }
+
+        Ok(trainers)
+    }
 }
 
 impl DbRepr for Trainer {
@@ -36,8 +160,21 @@ impl DbRepr for Trainer {
 }
 
 impl DbPut for Trainer {
-    fn put_args(&self) -> String {
-        format!("{{name: '{}'}}", sanitize(&self.name))
+    fn put_args(&self) -> Vec<(&'static str, DbValue)> {
+        let mut args = vec![("name", self.name.as_str().into())];
+
+        if let Some(strategy) = &self.strategy {
+            args.push(("strategy", strategy.name().into()));
+        }
+
+        args
+    }
+}
+
+/// Updates the preferred strategy, the team is changed through its relationships
+impl DbUpdate for Trainer {
+    fn update_args(&self) -> Vec<(&'static str, DbValue)> {
+        vec![("strategy", self.strategy.as_ref().map(|strategy| strategy.name()).into())]
     }
 }
 
@@ -92,12 +229,15 @@ impl DbGet for Trainer {
     {
         Box::pin(async move {
             let name = node.get::<String>("name")?;
+            let strategy = Self::strategy_from_node(&node);
 
-            let team =
-                Trainer::get_linked_by_id(&Relationship::Owns, format!("'{}'", sanitize(&name)))
-                    .await?;
+            let team = Trainer::get_linked_by_id(&Relationship::Owns, &name).await?;
 
-            Ok(Trainer { name, team })
+            Ok(Trainer {
+                name,
+                team,
+                strategy,
+            })
         })
     }
 